@@ -0,0 +1,264 @@
+use std::sync::Arc;
+
+use egui::{Button, ComboBox, DragValue, Grid, ScrollArea, TextEdit, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{self, Job};
+use crate::data::LogStream;
+use crate::eval::{self, Expr, Progress, Timing};
+use crate::library::UserExpr;
+use crate::plot::{Config, ERROR_RED};
+use crate::util;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    #[default]
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    pub const ALL: [Self; 6] = [
+        Self::Less,
+        Self::LessEq,
+        Self::Greater,
+        Self::GreaterEq,
+        Self::Equal,
+        Self::NotEqual,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Less => "<",
+            Self::LessEq => "<=",
+            Self::Greater => ">",
+            Self::GreaterEq => ">=",
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+        }
+    }
+
+    fn holds(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Self::Less => value < threshold,
+            Self::LessEq => value <= threshold,
+            Self::Greater => value > threshold,
+            Self::GreaterEq => value >= threshold,
+            Self::Equal => value == threshold,
+            Self::NotEqual => value != threshold,
+        }
+    }
+}
+
+/// The expression is violating the rule while the comparison with the threshold holds, e.g.
+/// `ams_umin_true < 3.0`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlarmRule {
+    pub name: String,
+    pub expr: String,
+    pub comparison: Comparison,
+    pub threshold: f64,
+}
+
+impl AlarmRule {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            expr: String::new(),
+            comparison: Comparison::Less,
+            threshold: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub rule: String,
+    /// time in seconds
+    pub start: f64,
+    /// time in seconds
+    pub end: f64,
+    /// the value furthest beyond the threshold
+    pub extreme: f64,
+}
+
+/// State of the alarm window.
+#[derive(Default)]
+pub struct AlarmScan {
+    pub violations: Option<Vec<Violation>>,
+    pub errors: Vec<String>,
+    pub job: Option<Job<(Vec<Violation>, Vec<String>)>>,
+}
+
+impl AlarmScan {
+    /// Scans the session for violations of the rules on a worker thread.
+    pub fn start(&mut self, cfg: &Config, streams: &Arc<[LogStream]>) {
+        let rules = cfg.alarm_rules.clone();
+        let library = cfg.expr_library.clone();
+        let timing = cfg.timing();
+        let streams = Arc::clone(streams);
+        self.job = Some(Job::spawn(move |progress| {
+            scan(&rules, &library, &timing, &streams, progress)
+        }));
+    }
+}
+
+pub enum AlarmAction {
+    Scan,
+    /// jump to the violation at the index
+    JumpTo(usize),
+}
+
+/// Finds all intervals in which a rule is violated, sorted by their start. Rules that can't be
+/// evaluated are reported as errors. Stops early if the evaluation is cancelled.
+pub fn scan(
+    rules: &[AlarmRule],
+    library: &[UserExpr],
+    timing: &Timing,
+    streams: &Arc<[LogStream]>,
+    progress: &Progress,
+) -> (Vec<Violation>, Vec<String>) {
+    let mut violations = Vec::new();
+    let mut errors = Vec::new();
+    for rule in rules.iter().take_while(|_| !progress.is_cancelled()) {
+        let expr = Expr::new("time", &rule.expr);
+        let values = match eval::eval(&expr, Arc::clone(streams), library, timing, progress) {
+            Ok(v) => v,
+            Err(e) => {
+                let error = e.y.or(e.x).map(|e| e.to_string());
+                let error = error.unwrap_or_else(|| "invalid expression".to_string());
+                errors.push(format!("{}: {error}", rule.name));
+                continue;
+            }
+        };
+
+        let further = |a: f64, b: f64| match rule.comparison {
+            Comparison::Less | Comparison::LessEq => a < b,
+            Comparison::Greater | Comparison::GreaterEq => a > b,
+            Comparison::Equal | Comparison::NotEqual => false,
+        };
+        let mut current: Option<Violation> = None;
+        for p in values.iter() {
+            let violated = rule.comparison.holds(p.y, rule.threshold);
+            match (&mut current, violated) {
+                (None, true) => {
+                    current = Some(Violation {
+                        rule: rule.name.clone(),
+                        start: p.x,
+                        end: p.x,
+                        extreme: p.y,
+                    });
+                }
+                (Some(v), true) => {
+                    v.end = p.x;
+                    if further(p.y, v.extreme) {
+                        v.extreme = p.y;
+                    }
+                }
+                (Some(_), false) => violations.extend(current.take()),
+                (None, false) => (),
+            }
+        }
+        violations.extend(current);
+    }
+
+    violations.sort_by(|a, b| a.start.total_cmp(&b.start));
+    (violations, errors)
+}
+
+pub fn alarms_dialog(
+    ui: &mut Ui,
+    scan: &AlarmScan,
+    rules: &mut Vec<AlarmRule>,
+) -> Option<AlarmAction> {
+    let mut action = None;
+
+    ui.heading("Rules");
+    let mut i = 0;
+    while i < rules.len() {
+        let r = &mut rules[i];
+        let removed = ui
+            .horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut r.name).desired_width(100.0));
+                ui.add(
+                    TextEdit::singleline(&mut r.expr)
+                        .code_editor()
+                        .desired_width(200.0),
+                );
+                ComboBox::from_id_source(("comparison", i))
+                    .width(40.0)
+                    .selected_text(r.comparison.name())
+                    .show_ui(ui, |ui| {
+                        for c in Comparison::ALL {
+                            ui.selectable_value(&mut r.comparison, c, c.name());
+                        }
+                    });
+                ui.add(DragValue::new(&mut r.threshold).speed(0.01));
+                ui.button("🗙").clicked()
+            })
+            .inner;
+        if removed {
+            rules.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    if ui.button(" + ").clicked() {
+        rules.push(AlarmRule::new(format!("{}.", rules.len() + 1)));
+    }
+
+    ui.separator();
+
+    let can_scan = !rules.is_empty() && scan.job.is_none();
+    if ui.add_enabled(can_scan, Button::new("Scan")).clicked() {
+        action = Some(AlarmAction::Scan);
+    }
+    if let Some(job) = &scan.job {
+        app::job_progress(ui, job);
+    }
+    for e in scan.errors.iter() {
+        ui.colored_label(ERROR_RED, e);
+    }
+
+    let Some(violations) = &scan.violations else {
+        return action;
+    };
+    ui.separator();
+    if violations.is_empty() {
+        ui.label("No violations found");
+        return action;
+    }
+
+    ScrollArea::vertical()
+        .max_height(400.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            Grid::new("violations")
+                .striped(true)
+                .num_columns(3)
+                .show(ui, |ui| {
+                    ui.strong("Rule");
+                    ui.strong("Time");
+                    ui.strong("Extreme");
+                    ui.end_row();
+
+                    for (i, v) in violations.iter().enumerate() {
+                        ui.label(&v.rule);
+                        let start = util::format_time(v.start);
+                        let end = util::format_time(v.end);
+                        let time = ui.link(format!("{start} - {end}"));
+                        if time.on_hover_text("Jump to the violation").clicked() {
+                            action = Some(AlarmAction::JumpTo(i));
+                        }
+                        ui.monospace(format!("{:.3}", v.extreme));
+                        ui.end_row();
+                    }
+                });
+        });
+
+    action
+}
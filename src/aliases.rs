@@ -0,0 +1,107 @@
+use egui::{Button, TextEdit, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::eval::{self, Expr};
+
+/// Readable name of a channel with a cryptic firmware name, e.g. `dc_link_voltage` for `l_uzk`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChannelAlias {
+    pub name: String,
+    pub alias: String,
+}
+
+pub fn alias_of<'a>(table: &'a [ChannelAlias], name: &str) -> Option<&'a str> {
+    (table.iter())
+        .find(|c| c.name == name && !c.alias.is_empty())
+        .map(|c| c.alias.as_str())
+}
+
+/// The alias of the channel, or the name itself if it has none.
+pub fn display_name<'a>(table: &'a [ChannelAlias], name: &'a str) -> &'a str {
+    alias_of(table, name).unwrap_or(name)
+}
+
+/// Replaces aliases that are used as identifiers with the names of their channels.
+pub fn resolve(table: &[ChannelAlias], input: &str) -> String {
+    if table.is_empty() {
+        return input.to_string();
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find(eval::is_ident_char) {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|c| !eval::is_ident_char(c)).unwrap_or(rest.len());
+        let ident = &rest[..end];
+        let channel = (table.iter())
+            .find(|c| c.alias == ident && !ident.starts_with(|c: char| c.is_ascii_digit()))
+            .map(|c| c.name.as_str());
+        output.push_str(channel.unwrap_or(ident));
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+pub fn resolve_expr(table: &[ChannelAlias], expr: &Expr) -> Expr {
+    Expr::new(resolve(table, &expr.x), resolve(table, &expr.y))
+}
+
+/// Returns true if the aliases used in expressions changed, so the plots have to be evaluated
+/// again.
+pub fn channel_aliases_sidebar(
+    ui: &mut Ui,
+    table: &mut Vec<ChannelAlias>,
+    new: &mut ChannelAlias,
+    in_exprs: &mut bool,
+) -> bool {
+    let mut changed = ui
+        .checkbox(in_exprs, "Use aliases in expressions")
+        .on_hover_text("Aliases can be used instead of the channel names")
+        .changed();
+
+    let mut i = 0;
+    while i < table.len() {
+        let c = &mut table[i];
+        let (edited, removed) = ui
+            .horizontal(|ui| {
+                let name = ui.add(TextEdit::singleline(&mut c.name).desired_width(110.0));
+                let alias = ui.add(TextEdit::singleline(&mut c.alias).desired_width(110.0));
+                (name.changed() || alias.changed(), ui.button("🗙").clicked())
+            })
+            .inner;
+        changed |= *in_exprs && (edited || removed);
+        if removed {
+            table.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut new.name)
+                .hint_text("channel")
+                .desired_width(110.0),
+        );
+        ui.add(
+            TextEdit::singleline(&mut new.alias)
+                .hint_text("alias")
+                .desired_width(110.0),
+        );
+
+        let valid = !new.name.trim().is_empty() && !new.alias.trim().is_empty();
+        if ui.add_enabled(valid, Button::new("Add")).clicked() {
+            let mut c = std::mem::take(new);
+            c.name = c.name.trim().to_string();
+            c.alias = c.alias.trim().to_string();
+            table.push(c);
+            changed |= *in_exprs;
+        }
+    });
+
+    changed
+}
@@ -0,0 +1,49 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use crate::laps::LapTrigger;
+use crate::markers::{EventMarker, SyncMarker};
+use crate::plot::Config;
+
+pub const ANNOTATIONS_EXTENSION: &str = "json";
+
+/// Annotations of a session, that can be shared independently of the plot config. Missing fields
+/// are defaulted, so sets exported by older versions can still be imported.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnnotationSet {
+    pub lap_trigger: LapTrigger,
+    pub sync_markers: Vec<SyncMarker>,
+    pub event_markers: Vec<EventMarker>,
+}
+
+impl AnnotationSet {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            lap_trigger: config.lap_trigger.clone(),
+            sync_markers: config.sync_markers.clone(),
+            event_markers: config.event_markers.clone(),
+        }
+    }
+
+    pub fn apply(self, config: &mut Config) {
+        config.lap_trigger = self.lap_trigger;
+        config.sync_markers = self.sync_markers;
+        config.event_markers = self.event_markers;
+    }
+}
+
+pub fn save(path: &Path, annotations: &AnnotationSet) -> anyhow::Result<()> {
+    let string = serde_json::to_string_pretty(annotations)?;
+    std::fs::write(path, string).with_context(|| format!("writing {}", path.display()))?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> anyhow::Result<AnnotationSet> {
+    let string =
+        std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let annotations = serde_json::from_str(&string)?;
+    Ok(annotations)
+}
@@ -1,22 +1,58 @@
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::thread::JoinHandle;
+use std::time::Instant;
 
 use chrono::{Duration, Local, TimeZone};
 use egui::{
-    menu, Align2, CentralPanel, Color32, Key, Modifiers, RichText, TopBottomPanel, Ui, Vec2, Window,
+    menu, Align2, Button, CentralPanel, Checkbox, Color32, DragValue, Grid, Key, Modifiers,
+    ProgressBar, RichText, ScrollArea, TopBottomPanel, Ui, Vec2, Window,
 };
 use egui_extras::{Column, TableBuilder};
 use egui_plot::PlotPoint;
 use serde::{Deserialize, Serialize};
 
-use crate::data::LogStream;
-use crate::eval::{self, Expr, ExprError};
-use crate::fs::{ErrorFile, Files, SelectableFile, SelectableFiles};
-use crate::plot::{self, Config};
+use crate::alarms::{self, AlarmAction, AlarmScan};
+use crate::aliases;
+use crate::audio::{self, AudioExport};
+use crate::collab::{self, Collab, CollabRole};
+use crate::compare::RunB;
+use crate::config_diff::{self, ConfigImport, ImportMode};
+use crate::data::{LogStream, RateSegment, SanityError};
+use crate::energy::{self, EnergySummary};
+use crate::eval::{self, Expr, ExprError, Progress, Timing};
+use crate::export::{self, ExportFormat, SeriesExport};
+use crate::faults::{self, FaultOccurrence};
+use crate::find::Find;
+use crate::fs::{
+    ErrorFile, FileLoad, Files, SelectableFile, SelectableFiles, MISSING_START_WARNING,
+};
+use crate::image_export::{self, ImageExport};
+use crate::inspector::{self, Inspector};
+use crate::laps::Lap;
+use crate::library::UserExpr;
+use crate::parquet;
+use crate::plot::{self, Config, HoverReadout, SplitView};
+use crate::power::{self, PowerAction, PowerCheck};
+use crate::recovery::{self, Recovery};
+use crate::regression::{self, RegressionAction, RegressionTest};
+use crate::sanity_rules;
+use crate::scoreboard::{self, Scoreboard, ScoreboardAction};
+use crate::sectors;
+use crate::series_cache::{self, RecentSeries, SeriesCache};
+use crate::session::SessionMeta;
+use crate::side_series::SideSeries;
+use crate::spill::{SpillStore, SpilledSeries};
+use crate::theme::Theme;
+use crate::toasts::Toasts;
+use crate::track::Track;
 use crate::util;
+use crate::watch::{self, DirWatch};
 
 const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const FILE_LOAD_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
 
 #[derive(Default, Serialize, Deserialize)]
 #[serde(default)]
@@ -24,19 +60,120 @@ pub struct PlotApp {
     pub config: Config,
     pub files: Option<Files>,
     #[serde(skip)]
+    pub file_load: Option<FileLoad>,
+    #[serde(skip)]
     pub selectable_files: Option<SelectableFiles>,
     #[serde(skip)]
     pub data: Option<PlotData>,
+    #[serde(skip)]
+    pub series_export: Option<SeriesExport>,
+    #[serde(skip)]
+    pub audio_export: Option<AudioExport>,
+    #[serde(skip)]
+    pub inspector: Option<Inspector>,
+    #[serde(skip)]
+    pub config_import: Option<ConfigImport>,
+    #[serde(skip)]
+    pub toasts: Toasts,
+    #[serde(skip)]
+    pub faults: Option<Vec<FaultOccurrence>>,
+    #[serde(skip)]
+    pub regression: Option<RegressionTest>,
+    #[serde(skip)]
+    pub scoreboard: Option<Scoreboard>,
+    #[serde(skip)]
+    pub alarms: Option<AlarmScan>,
+    #[serde(skip)]
+    pub power_check: Option<PowerCheck>,
+    #[serde(skip)]
+    pub energy: Option<EnergySummary>,
+    #[serde(skip)]
+    pub image_export: Option<ImageExport>,
+    #[serde(skip)]
+    pub recovery: Option<Recovery>,
+    #[serde(skip)]
+    pub last_snapshot: Option<Instant>,
+    #[serde(skip)]
+    pub dir_watch: DirWatch,
+    #[serde(skip)]
+    pub collab: Option<Collab>,
+    #[serde(skip)]
+    pub show_source_files: bool,
+    #[serde(skip)]
+    pub show_sanity_rules: bool,
+    #[serde(skip)]
+    pub show_sectors: bool,
 }
 
 pub struct PlotData {
+    /// the enabled samples of [`PlotData::all_streams`]
     pub streams: Arc<[LogStream]>,
+    /// all concatenated files
+    pub all_streams: Arc<[LogStream]>,
+    /// indexed by stream and source file
+    pub enabled_sources: Vec<Vec<bool>>,
+    /// source files that aren't enabled, updated with [`PlotApp::apply_source_selection`]
+    pub disabled_files: HashSet<PathBuf>,
     pub plots: Vec<Vec<PlotValues>>,
+    pub warnings: Vec<String>,
+    /// indexed by stream and entry
+    pub channels: Vec<Vec<ChannelInfo>>,
+    /// detected sample periods, indexed by stream
+    pub rates: Vec<Vec<RateSegment>>,
+    pub track: Option<Track>,
+    pub laps: Vec<Lap>,
+    pub series_cache: Option<SeriesCache>,
+    pub session: SessionMeta,
+    /// values of the other x expression of each tab, by tab id
+    pub x_stash: HashMap<u64, Vec<(Expr, PlotValues)>>,
+    pub spill: SpillStore,
+    /// second run that line plots are compared with
+    pub run_b: Option<RunB>,
+    /// derived channels the streams were last built with
+    pub derived: Vec<UserExpr>,
+    pub derived_errors: Vec<String>,
+    /// time ranges in seconds that violate the power limit, shaded in time plots
+    pub power_violations: Vec<(f64, f64)>,
+    pub side_series: SideSeries,
+    pub find: Find,
+    /// changes whenever the streams are rebuilt
+    pub revision: u64,
+    pub recent_series: RecentSeries,
+    /// plots whose expression was edited, by tab id and plot index, evaluated once the
+    /// expression wasn't changed for a moment
+    pub edited: Vec<(u64, usize, Instant)>,
+}
+
+impl PlotData {
+    /// Evaluates the expression, or takes the values from the recently evaluated series.
+    pub fn start_job(&self, expr: &Expr, cfg: &Config) -> PlotValues {
+        let cache = self.series_cache.clone().filter(|_| cfg.cache_series);
+        let data = Arc::clone(&self.streams);
+        let expr = Expr::new(self.session.resolve(&expr.x), self.session.resolve(&expr.y));
+        let expr = aliases::resolve_expr(cfg.expr_aliases(), &expr);
+        let library = cfg.expr_library.clone();
+        let timing = cfg.timing();
+        let key = RecentSeries::key(self.revision, &expr, &library, &timing);
+        if let Some(values) = self.recent_series.load(key) {
+            return PlotValues::Result(Ok(values.to_vec()));
+        }
+        let recent = Some((self.recent_series.clone(), key));
+        PlotValues::Job(Job::start(expr, data, library, timing, cache, recent))
+    }
+}
+
+pub struct ChannelInfo {
+    pub sparkline: Vec<f32>,
+    pub constant: bool,
 }
 
 pub enum PlotValues {
     Job(Job),
     Result(Result<Vec<PlotPoint>, Box<ExprError>>),
+    /// moved to disk to stay within the memory budget
+    Spilled(SpilledSeries),
+    /// the evaluation was cancelled before it finished
+    Cancelled,
 }
 
 impl PlotValues {
@@ -66,21 +203,90 @@ impl PlotValues {
     }
 }
 
-pub struct Job {
-    handle: JoinHandle<Result<Vec<PlotPoint>, Box<ExprError>>>,
+/// Work on a worker thread that reports its progress and can be cancelled, by default the
+/// evaluation of a plot.
+pub struct Job<T = Result<Vec<PlotPoint>, Box<ExprError>>> {
+    handle: JoinHandle<T>,
+    progress: Arc<Progress>,
+}
+
+impl<T: Send + 'static> Job<T> {
+    /// Runs `f` on a worker thread, it should return early once the progress is cancelled.
+    pub fn spawn(f: impl FnOnce(&Progress) -> T + Send + 'static) -> Self {
+        let progress = Arc::new(Progress::default());
+        let job_progress = Arc::clone(&progress);
+        let handle = std::thread::spawn(move || f(&job_progress));
+        Self { handle, progress }
+    }
 }
 
 impl Job {
-    pub fn start(expr: Expr, data: Arc<[LogStream]>) -> Self {
-        let handle = std::thread::spawn(move || eval::eval(&expr, data));
-        Self { handle }
+    /// Evaluates the expression on a worker thread. If a cache is passed, cached values are
+    /// used when present and newly evaluated values are stored. The values are also stored in
+    /// the recent series under the key, if passed.
+    pub fn start(
+        expr: Expr,
+        data: Arc<[LogStream]>,
+        library: Vec<UserExpr>,
+        timing: Timing,
+        cache: Option<SeriesCache>,
+        recent: Option<(RecentSeries, u64)>,
+    ) -> Self {
+        Self::spawn(move |job_progress| {
+            let cached = cache
+                .as_ref()
+                .and_then(|c| c.load(&expr, &library, &timing));
+            if let Some(values) = cached {
+                if let Some((recent, key)) = &recent {
+                    recent.store(*key, &values);
+                }
+                return Ok(values);
+            }
+            let res = eval::eval(&expr, data, &library, &timing, job_progress);
+            if job_progress.is_cancelled() {
+                return res;
+            }
+            if let (Some(cache), Ok(values)) = (&cache, &res) {
+                _ = cache.store(&expr, &library, &timing, values);
+            }
+            if let (Some((recent, key)), Ok(values)) = (&recent, &res) {
+                recent.store(*key, values);
+            }
+            res
+        })
+    }
+}
+
+impl<T> Job<T> {
+    /// Takes the result out of a finished job, results of cancelled jobs are dropped.
+    pub fn poll(job: &mut Option<Self>) -> Option<T> {
+        if !job.as_ref().is_some_and(Self::is_done) {
+            return None;
+        }
+        let job = job.take()?;
+        let cancelled = job.is_cancelled();
+        let res = job.join();
+        (!cancelled).then_some(res)
     }
 
     pub fn is_done(&self) -> bool {
         self.handle.is_finished()
     }
 
-    pub fn join(self) -> Result<Vec<PlotPoint>, Box<ExprError>> {
+    pub fn progress(&self) -> f32 {
+        self.progress.fraction()
+    }
+
+    /// Asks the worker thread to stop, the job is done shortly after.
+    pub fn cancel(&self) {
+        self.progress.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.progress.is_cancelled()
+    }
+
+    pub fn join(self) -> T {
         self.handle.join().expect("failed to join worker thread")
     }
 }
@@ -90,10 +296,14 @@ impl eframe::App for PlotApp {
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 
-    fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.config.theme.apply(ctx, frame.info().system_theme);
         if ctx.input_mut(|i| i.consume_key(Modifiers::CTRL, Key::O)) {
             self.open_dir_dialog();
         }
+        if self.data.is_some() && ctx.input_mut(|i| i.consume_key(Modifiers::CTRL, Key::E)) {
+            self.image_export = Some(ImageExport::default());
+        }
         if ctx.input_mut(|i| i.consume_key(Modifiers::CTRL | Modifiers::SHIFT, Key::O)) {
             if let Some(files) = &self.files {
                 self.try_open_dir(files.dir.clone());
@@ -119,6 +329,254 @@ impl eframe::App for PlotApp {
                         }
                         ui.close_menu();
                     }
+                    let sources = ui.add_enabled(self.data.is_some(), Button::new("Source files"));
+                    if sources
+                        .on_hover_text("Enable or disable individual concatenated files")
+                        .clicked()
+                    {
+                        self.show_source_files = true;
+                        ui.close_menu();
+                    }
+                    let run_b = ui.add_enabled(self.data.is_some(), Button::new("Open run B"));
+                    if run_b
+                        .on_hover_text("Compare the line plots with another session")
+                        .clicked()
+                    {
+                        self.open_run_b_dialog();
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    for format in [ExportFormat::Csv, ExportFormat::Parquet] {
+                        let text = format!("Export {}", format.name());
+                        let export = ui.add_enabled(self.data.is_some(), Button::new(text));
+                        if export.clicked() {
+                            if let Some(data) = &self.data {
+                                let tab = &self.config.tabs[self.config.selected_tab];
+                                let range = tab.x_bounds.clone();
+                                let export = SeriesExport::new(format, &data.streams, range);
+                                self.series_export = Some(export);
+                            }
+                            ui.close_menu();
+                        }
+                    }
+
+                    let wav = ui
+                        .add_enabled(self.data.is_some(), Button::new("Export wav"))
+                        .on_hover_text("Export a channel as audio, to listen for vibrations");
+                    if wav.clicked() {
+                        let tab = &self.config.tabs[self.config.selected_tab];
+                        self.audio_export = Some(AudioExport::new(tab.x_bounds.clone()));
+                        ui.close_menu();
+                    }
+                    let export = ui.add_enabled(self.data.is_some(), Button::new("Export image"));
+                    if export.clicked() {
+                        self.image_export = Some(ImageExport::default());
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Open project").clicked() {
+                        self.open_project_dialog();
+                        ui.close_menu();
+                    }
+                    let save = ui.add_enabled(self.files.is_some(), Button::new("Save project"));
+                    if save.clicked() {
+                        self.save_project_dialog();
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Export config").clicked() {
+                        self.export_config_dialog();
+                        ui.close_menu();
+                    }
+                    if ui.button("Import config").clicked() {
+                        self.import_config_dialog();
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Export annotations").clicked() {
+                        self.export_annotations_dialog();
+                        ui.close_menu();
+                    }
+                    if ui.button("Import annotations").clicked() {
+                        self.import_annotations_dialog();
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Export reference").clicked() {
+                        self.export_reference_dialog();
+                        ui.close_menu();
+                    }
+                    if ui.button("Inspect file").clicked() {
+                        self.inspect_file_dialog();
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("View", |ui| {
+                    for t in Theme::ALL {
+                        ui.radio_value(&mut self.config.theme, t, t.name());
+                    }
+                    ui.separator();
+
+                    ui.label("Split view");
+                    for s in SplitView::ALL {
+                        ui.radio_value(&mut self.config.split_view, s, s.name());
+                    }
+                    let split = self.config.split_view != SplitView::Off;
+                    ui.add_enabled(
+                        split,
+                        Checkbox::new(&mut self.config.link_split_x_axes, "Link x-axes of panes"),
+                    );
+                });
+
+                ui.menu_button("Tools", |ui| {
+                    let faults = ui.add_enabled(self.data.is_some(), Button::new("Faults"));
+                    if faults.clicked() {
+                        if let Some(data) = &self.data {
+                            let dictionary = &self.config.fault_dictionary;
+                            self.faults = Some(faults::detect(&data.streams, dictionary));
+                        }
+                        ui.close_menu();
+                    }
+
+                    let regression =
+                        ui.add_enabled(self.data.is_some(), Button::new("Regression test"));
+                    if regression.clicked() {
+                        self.regression = Some(RegressionTest::default());
+                        ui.close_menu();
+                    }
+
+                    let scoreboard = ui.add_enabled(self.data.is_some(), Button::new("Scoreboard"));
+                    if scoreboard.clicked() {
+                        self.scoreboard = Some(Scoreboard::default());
+                        ui.close_menu();
+                    }
+
+                    let alarms =
+                        ui.add_enabled(self.data.is_some(), Button::new("Threshold alarms"));
+                    if alarms.clicked() {
+                        self.alarms = Some(AlarmScan::default());
+                        ui.close_menu();
+                    }
+
+                    let power = ui.add_enabled(self.data.is_some(), Button::new("Power limit"));
+                    if power.clicked() {
+                        self.power_check = Some(PowerCheck::default());
+                        ui.close_menu();
+                    }
+
+                    let sectors = ui.add_enabled(self.data.is_some(), Button::new("Sector times"));
+                    if sectors.clicked() {
+                        self.show_sectors = true;
+                        ui.close_menu();
+                    }
+
+                    let energy = ui.add_enabled(self.data.is_some(), Button::new("Energy summary"));
+                    if energy.clicked() {
+                        let tab = &self.config.tabs[self.config.selected_tab];
+                        self.energy = Some(EnergySummary::new(tab.x_bounds.clone()));
+                        ui.close_menu();
+                    }
+
+                    if ui
+                        .button("Sanity rules")
+                        .on_hover_text("Expected ranges of channels, checked when opening files")
+                        .clicked()
+                    {
+                        self.show_sanity_rules = true;
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    let current = self.collab.as_ref().map_or(CollabRole::Off, |c| c.role);
+                    if let Some(role) = collab::role_menu(ui, current) {
+                        self.collab = None;
+                        if role != CollabRole::Off {
+                            match Collab::start(role) {
+                                Ok(c) => self.collab = Some(c),
+                                Err(e) => self
+                                    .toasts
+                                    .error("Error starting review mode", e.to_string()),
+                            }
+                        }
+                    }
+                    let resample = ui
+                        .horizontal(|ui| {
+                            ui.label("Resample rate");
+                            let rate = DragValue::new(&mut self.config.resample_rate)
+                                .range(0..=1000)
+                                .suffix(" Hz");
+                            ui.add(rate)
+                        })
+                        .inner
+                        .on_hover_text(
+                            "Evaluate all streams on a fixed time grid, 0 to evaluate at the \
+                            samples of the first stream",
+                        );
+                    let resample_changed =
+                        resample.drag_stopped() || resample.changed() && !resample.dragged();
+                    if let (true, Some(data)) = (resample_changed, &mut self.data) {
+                        data.plots = plot::start_jobs(&self.config, data);
+                        data.x_stash.clear();
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Gap threshold");
+                        let periods = DragValue::new(&mut self.config.gap_periods)
+                            .range(0..=1000)
+                            .suffix(" periods");
+                        ui.add(periods)
+                    })
+                    .inner
+                    .on_hover_text(
+                        "Break line plots where no samples were logged for longer than this many \
+                        sample periods, 0 to always connect the samples",
+                    );
+                    ui.checkbox(&mut self.config.shade_gaps, "Shade gaps");
+                    ui.checkbox(&mut self.config.link_x_axes, "Link x-axes")
+                        .on_hover_text("Keep the same time window when switching tabs");
+                    ui.menu_button("Hover readout", |ui| {
+                        ui.label("Show the values of all line plots at the hovered time");
+                        for r in HoverReadout::ALL {
+                            ui.radio_value(&mut self.config.hover_readout, r, r.name());
+                        }
+                    });
+                    ui.checkbox(&mut self.config.watch_dir, "Watch directory")
+                        .on_hover_text("Offer to append log files written to the opened directory");
+                    ui.checkbox(&mut self.config.unit_warnings, "Check units")
+                        .on_hover_text("Warn about adding or comparing values of different units");
+                    ui.horizontal(|ui| {
+                        ui.label("Memory budget");
+                        ui.add(
+                            DragValue::new(&mut self.config.memory_budget)
+                                .speed(16)
+                                .suffix(" MiB"),
+                        );
+                    })
+                    .response
+                    .on_hover_text(
+                        "Evaluated series of the least recently shown tabs are moved to disk \
+                        when they exceed the budget, 0 for no limit",
+                    );
+                    ui.checkbox(&mut self.config.cache_series, "Cache evaluated series")
+                        .on_hover_text("Reopening the same files shows cached plots immediately");
+                    if ui.button("Clear series cache").clicked() {
+                        if let Err(e) = series_cache::clear() {
+                            self.toasts
+                                .error("Error clearing series cache", e.to_string());
+                        }
+                        ui.close_menu();
+                    }
                 });
 
                 ui.add_space(40.0);
@@ -136,14 +594,68 @@ impl eframe::App for PlotApp {
 
                     for p in files.items.iter() {
                         let text = p.strip_prefix(prefix).unwrap().display().to_string();
-                        ui.label(RichText::new(text).strong());
+                        if self
+                            .data
+                            .as_ref()
+                            .is_some_and(|d| d.disabled_files.contains(p))
+                        {
+                            ui.label(RichText::new(text).strikethrough().weak());
+                        } else {
+                            ui.label(RichText::new(text).strong());
+                        }
+                    }
+                }
+
+                if let Some(collab) = &self.collab {
+                    ui.add_space(20.0);
+                    match collab.role {
+                        CollabRole::Broadcast => ui.label("📡 Broadcasting"),
+                        _ => ui.label("👁 Following"),
+                    };
+                }
+
+                if let Some(data) = &self.data {
+                    if !data.warnings.is_empty() {
+                        ui.add_space(20.0);
+                        ui.colored_label(Color32::YELLOW, "⚠")
+                            .on_hover_text(data.warnings.join("\n"));
                     }
                 }
             });
         });
 
+        self.poll_file_load();
+        if self.file_load.is_some() {
+            ctx.request_repaint_after(FILE_LOAD_POLL_INTERVAL);
+        }
+        if let (Some(collab), Some(_)) = (&mut self.collab, &self.data) {
+            let res = match collab.role {
+                CollabRole::Follow => {
+                    ctx.request_repaint_after(collab::FOLLOW_POLL_INTERVAL);
+                    collab.follow(&mut self.config)
+                }
+                _ => collab.broadcast(&self.config),
+            };
+            if let Err(e) = res {
+                self.toasts.error("Error in review mode", e.to_string());
+                self.collab = None;
+            }
+        }
+        if let (true, Some(files), None) = (self.config.watch_dir, &self.files, &self.file_load) {
+            self.dir_watch.poll(files);
+            ctx.request_repaint_after(watch::WATCH_INTERVAL);
+        }
+
         CentralPanel::default().show(ctx, |ui| {
-            if self.selectable_files.is_some() {
+            if let Some(load) = &self.file_load {
+                let parsed = load.parsed.load(Ordering::Relaxed);
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(format!("Loading files {parsed}/{}", load.total));
+                });
+                let progress = parsed as f32 / load.total.max(1) as f32;
+                ui.add(ProgressBar::new(progress).desired_width(300.0));
+            } else if self.selectable_files.is_some() {
                 ui.label("...");
             } else if let Some(data) = &mut self.data {
                 plot::keybindings(ui, data, &mut self.config);
@@ -165,21 +677,547 @@ impl eframe::App for PlotApp {
                 .show(ctx, |ui| select_files_dialog(ui, files));
 
             match r {
-                Some(r) if open => {
-                    if let Some(true) = r.inner {
+                Some(r) if open => match r.inner.flatten() {
+                    Some(SelectFilesAction::Open) => {
                         let files = self.selectable_files.take().unwrap();
                         self.concat_and_show(files);
                     }
-                }
+                    Some(SelectFilesAction::ExportReport) => self.export_sanity_report_dialog(),
+                    None => (),
+                },
                 _ => self.selectable_files = None,
             }
         }
 
+        if let (Some(export), Some(data)) = (&mut self.series_export, &self.data) {
+            let mut open = true;
+            let r = Window::new(format!("Export {}", export.format.name()))
+                .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+                .default_size(Vec2::new(400.0, 600.0))
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| series_export_dialog(ui, export, data));
+
+            match r {
+                Some(r) if open => {
+                    if let Some(true) = r.inner {
+                        let ext = export.format.extension();
+                        let dialog = rfd::FileDialog::new()
+                            .add_filter(ext, &[ext])
+                            .set_file_name(format!("export.{ext}"));
+                        if let Some(path) = dialog.save_file() {
+                            let range = export.range();
+                            let (streams, selected) = (&data.streams, &export.selected);
+                            let offsets = &self.config.stream_offsets;
+                            let result = match export.format {
+                                ExportFormat::Csv => {
+                                    export::export_csv(&path, streams, offsets, selected, range)
+                                }
+                                ExportFormat::Parquet => parquet::export_parquet(
+                                    &path, streams, offsets, selected, range,
+                                ),
+                            };
+                            match result {
+                                Ok(()) => self.series_export = None,
+                                Err(e) => {
+                                    let summary =
+                                        format!("Error exporting {}", export.format.name());
+                                    let details = format!("{}: {e}", path.display());
+                                    self.toasts.error(summary, details);
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => self.series_export = None,
+            }
+        }
+
+        if let (Some(export), Some(data)) = (&mut self.audio_export, &self.data) {
+            let mut open = true;
+            let r = Window::new("Export wav")
+                .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    audio::audio_export_dialog(ui, export, &data.streams)
+                });
+
+            match r {
+                Some(r) if open => {
+                    if let (Some(true), Some((s, e))) = (r.inner, export.channel) {
+                        let name = &data.streams[s].entries[e].name;
+                        let dialog = rfd::FileDialog::new()
+                            .add_filter("wav", &["wav"])
+                            .set_file_name(format!("{name}.wav"));
+                        if let Some(path) = dialog.save_file() {
+                            let offset = self.config.stream_offsets.get(s).copied().unwrap_or(0);
+                            let (range, speed) = (export.range(), export.speed);
+                            let stream = &data.streams[s];
+                            match audio::export_wav(&path, stream, e, offset, range, speed) {
+                                Ok(()) => self.audio_export = None,
+                                Err(e) => {
+                                    let details = format!("{}: {e}", path.display());
+                                    self.toasts.error("Error exporting wav", details);
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => self.audio_export = None,
+            }
+        }
+
+        if let Some(inspector) = &mut self.inspector {
+            let mut open = true;
+            Window::new("Inspector")
+                .default_size(Vec2::new(900.0, 700.0))
+                .open(&mut open)
+                .show(ctx, |ui| inspector::inspector_dialog(ui, inspector));
+
+            if !open {
+                self.inspector = None;
+            }
+        }
+
+        if let Some(import) = &mut self.config_import {
+            let mut open = true;
+            let r = Window::new("Import config")
+                .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    config_diff::config_diff_dialog(ui, import, &self.config)
+                });
+
+            match r.and_then(|r| r.inner.flatten()) {
+                Some(true) => {
+                    let import = self.config_import.take().unwrap();
+                    match import.mode {
+                        ImportMode::Replace => self.apply_config(import.config),
+                        ImportMode::Merge => self.merge_config(import.config),
+                    }
+                }
+                Some(false) => self.config_import = None,
+                None if !open => self.config_import = None,
+                None => (),
+            }
+        }
+
+        if let (Some(occurrences), Some(data)) = (&self.faults, &mut self.data) {
+            let mut open = true;
+            let r = Window::new("Faults")
+                .default_size(Vec2::new(600.0, 400.0))
+                .open(&mut open)
+                .show(ctx, |ui| faults::faults_dialog(ui, occurrences));
+
+            if let Some(i) = r.and_then(|r| r.inner.flatten()) {
+                plot::add_fault_tab(data, &mut self.config, &occurrences[i]);
+            }
+            if !open {
+                self.faults = None;
+            }
+        }
+
+        if let Some(test) = &mut self.regression {
+            if let Some(results) = Job::poll(&mut test.job) {
+                test.results = Some(results);
+            }
+            if test.job.is_some() {
+                ctx.request_repaint_after(plot::JOB_POLL_INTERVAL);
+            }
+            let mut open = true;
+            let checks = &mut self.config.validation_checks;
+            let r = Window::new("Regression test")
+                .default_size(Vec2::new(700.0, 500.0))
+                .open(&mut open)
+                .show(ctx, |ui| regression::regression_dialog(ui, test, checks));
+            if let (false, Some(job)) = (open, &test.job) {
+                job.cancel();
+            }
+
+            match r.and_then(|r| r.inner.flatten()) {
+                Some(RegressionAction::PickAfter) => self.pick_regression_session_dialog(),
+                Some(RegressionAction::Run) => {
+                    if let Some(data) = &self.data {
+                        test.start(&self.config, &data.streams);
+                    }
+                }
+                None => (),
+            }
+            if !open {
+                self.regression = None;
+            }
+        }
+
+        if let Some(board) = &mut self.scoreboard {
+            if let Some(results) = Job::poll(&mut board.job) {
+                board.results = Some(results);
+            }
+            if board.job.is_some() {
+                ctx.request_repaint_after(plot::JOB_POLL_INTERVAL);
+            }
+            let mut open = true;
+            let metrics = &mut self.config.score_metrics;
+            let current_name = (self.files.as_ref())
+                .and_then(|f| f.dir.file_name())
+                .map_or("Opened".into(), |n| n.to_string_lossy());
+            let r = Window::new("Scoreboard")
+                .default_size(Vec2::new(700.0, 400.0))
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    scoreboard::scoreboard_dialog(ui, board, metrics, &current_name)
+                });
+            if let (false, Some(job)) = (open, &board.job) {
+                job.cancel();
+            }
+
+            match r.and_then(|r| r.inner.flatten()) {
+                Some(ScoreboardAction::AddSession) => self.add_scoreboard_session_dialog(),
+                Some(ScoreboardAction::Run) => {
+                    if let Some(data) = &self.data {
+                        board.start(&self.config, data);
+                    }
+                }
+                None => (),
+            }
+            if !open {
+                self.scoreboard = None;
+            }
+        }
+
+        if let Some(alarms) = &mut self.alarms {
+            if let Some((violations, errors)) = Job::poll(&mut alarms.job) {
+                alarms.violations = Some(violations);
+                alarms.errors = errors;
+            }
+            if alarms.job.is_some() {
+                ctx.request_repaint_after(plot::JOB_POLL_INTERVAL);
+            }
+            let mut open = true;
+            let rules = &mut self.config.alarm_rules;
+            let r = Window::new("Threshold alarms")
+                .default_size(Vec2::new(600.0, 400.0))
+                .open(&mut open)
+                .show(ctx, |ui| alarms::alarms_dialog(ui, alarms, rules));
+            if let (false, Some(job)) = (open, &alarms.job) {
+                job.cancel();
+            }
+
+            match r.and_then(|r| r.inner.flatten()) {
+                Some(AlarmAction::Scan) => {
+                    if let Some(data) = &self.data {
+                        alarms.start(&self.config, &data.streams);
+                    }
+                }
+                Some(AlarmAction::JumpTo(i)) => {
+                    if let Some(v) = alarms.violations.as_ref().and_then(|v| v.get(i)) {
+                        plot::jump_to(&mut self.config, v.start, v.end);
+                    }
+                }
+                None => (),
+            }
+            if !open {
+                self.alarms = None;
+            }
+        }
+
+        if let Some(check) = &mut self.power_check {
+            if let (Some(res), Some(data)) = (Job::poll(&mut check.job), &mut self.data) {
+                data.power_violations = match &res {
+                    Ok(report) => (report.violations.iter())
+                        .map(|v| (v.start, v.end))
+                        .collect(),
+                    Err(_) => Vec::new(),
+                };
+                (check.report, check.error) = match res {
+                    Ok(report) => (Some(report), None),
+                    Err(e) => (None, Some(e)),
+                };
+            }
+            if check.job.is_some() {
+                ctx.request_repaint_after(plot::JOB_POLL_INTERVAL);
+            }
+            let mut open = true;
+            let limit = &mut self.config.power_limit;
+            let r = Window::new("Power limit")
+                .default_size(Vec2::new(400.0, 400.0))
+                .open(&mut open)
+                .show(ctx, |ui| power::power_limit_dialog(ui, check, limit));
+            if let (false, Some(job)) = (open, &check.job) {
+                job.cancel();
+            }
+
+            match r.and_then(|r| r.inner.flatten()) {
+                Some(PowerAction::Check) => {
+                    if let Some(data) = &self.data {
+                        check.start(&self.config, &data.streams);
+                    }
+                }
+                Some(PowerAction::JumpTo(i)) => {
+                    let report = check.report.as_ref();
+                    if let Some(v) = report.and_then(|r| r.violations.get(i)) {
+                        plot::jump_to(&mut self.config, v.start, v.end);
+                    }
+                }
+                None => (),
+            }
+            if !open {
+                self.power_check = None;
+                if let Some(data) = &mut self.data {
+                    data.power_violations.clear();
+                }
+            }
+        }
+
+        if let Some(summary) = &mut self.energy {
+            if let Some(res) = Job::poll(&mut summary.job) {
+                (summary.report, summary.error) = match res {
+                    Ok(report) => (Some(report), None),
+                    Err(e) => (None, Some(e)),
+                };
+            }
+            if summary.job.is_some() {
+                ctx.request_repaint_after(plot::JOB_POLL_INTERVAL);
+            }
+            let mut open = true;
+            let limit = &mut self.config.power_limit;
+            let endurance = &mut self.config.endurance_distance;
+            let r = Window::new("Energy summary")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    energy::energy_dialog(ui, summary, limit, endurance)
+                });
+
+            if let (false, Some(job)) = (open, &summary.job) {
+                job.cancel();
+            }
+
+            if let (Some(true), Some(data)) = (r.and_then(|r| r.inner), &self.data) {
+                summary.start(&self.config, &data.streams);
+            }
+            if !open {
+                self.energy = None;
+            }
+        }
+
+        if let Some(export) = &mut self.image_export {
+            let mut open = true;
+            let r = Window::new("Export image")
+                .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| image_export::image_export_dialog(ui, export));
+
+            match r.and_then(|r| r.inner.flatten()) {
+                Some(true) => {
+                    self.export_image_dialog(ctx);
+                    self.image_export = None;
+                }
+                Some(false) => self.image_export = None,
+                None if !open => self.image_export = None,
+                None => (),
+            }
+        }
+
+        if let Some(recovery) = &self.recovery {
+            let r = Window::new("Recover session")
+                .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| recovery::recovery_dialog(ui, recovery));
+
+            if let Some(restore) = r.and_then(|r| r.inner.flatten()) {
+                let recovery = self.recovery.take().unwrap();
+                if let Err(e) = recovery::discard() {
+                    self.toasts
+                        .error("Error removing recovery data", e.to_string());
+                }
+                if let (true, Some(state)) = (restore, recovery.state) {
+                    self.apply_config(state.config);
+                    if let Some(files) = state.files {
+                        self.try_open_files(files, false);
+                    }
+                }
+            }
+        }
+
+        if self.config.watch_dir && !self.dir_watch.new_files.is_empty() {
+            let r = Window::new("New log files")
+                .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| watch::new_files_dialog(ui, &self.dir_watch));
+
+            match r.and_then(|r| r.inner.flatten()) {
+                Some(true) => {
+                    if let Some(mut files) = self.files.clone() {
+                        files.items.append(&mut self.dir_watch.new_files);
+                        files.items.sort();
+                        self.try_open_files(files, false);
+                    }
+                }
+                Some(false) => self.dir_watch.ignore(),
+                None => (),
+            }
+        }
+
+        if let (true, Some(data), Some(files)) =
+            (self.show_source_files, &mut self.data, &self.files)
+        {
+            let mut open = true;
+            let r = Window::new("Source files")
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| source_files_dialog(ui, data, &files.dir));
+
+            self.show_source_files = open;
+            if r.and_then(|r| r.inner) == Some(true) {
+                self.apply_source_selection();
+            }
+        }
+
+        if let (true, Some(data)) = (self.show_sectors, &self.data) {
+            let mut open = true;
+            let sectors = &mut self.config.sectors;
+            Window::new("Sector times")
+                .open(&mut open)
+                .default_size(Vec2::new(500.0, 500.0))
+                .show(ctx, |ui| {
+                    sectors::sectors_dialog(ui, sectors, &data.laps, data.track.as_ref())
+                });
+            self.show_sectors = open;
+        }
+
+        if self.show_sanity_rules {
+            let mut open = true;
+            let rules = &mut self.config.sanity_rules;
+            let r = Window::new("Sanity rules")
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| sanity_rules::sanity_rules_dialog(ui, rules));
+
+            self.show_sanity_rules = open;
+            // update the results in the file selection dialog
+            if let (Some(true), Some(files)) = (r.and_then(|r| r.inner), &mut self.selectable_files)
+            {
+                for f in files.by_header.iter_mut().flatten() {
+                    f.rule_checks = sanity_rules::check_all(rules, &f.stream);
+                }
+            }
+        }
+
+        let snapshot_due =
+            (self.last_snapshot).is_none_or(|t| t.elapsed() >= recovery::SNAPSHOT_INTERVAL);
+        if snapshot_due {
+            recovery::snapshot(&self.config, self.files.as_ref());
+            self.last_snapshot = Some(Instant::now());
+        }
+
+        self.toasts.show(ctx);
+
         self.detect_files_being_dropped(ctx);
     }
 }
 
-pub fn select_files_dialog(ui: &mut Ui, opened_files: &mut SelectableFiles) -> bool {
+/// Progress bar of the job with a button to cancel it.
+pub fn job_progress<T>(ui: &mut Ui, job: &Job<T>) {
+    ui.horizontal(|ui| {
+        let width = ui.available_width() - ui.spacing().interact_size.x;
+        let bar = ProgressBar::new(job.progress())
+            .desired_width(width)
+            .show_percentage();
+        ui.add(bar);
+        if ui.button("🗙").on_hover_text("Cancel evaluation").clicked() {
+            job.cancel();
+        }
+    });
+}
+
+pub fn series_export_dialog(ui: &mut Ui, export: &mut SeriesExport, data: &PlotData) -> bool {
+    ui.horizontal(|ui| {
+        if ui.button("Select all").clicked() {
+            export.select_all(true);
+        }
+        if ui.button("Deselect all").clicked() {
+            export.select_all(false);
+        }
+    });
+
+    ScrollArea::vertical()
+        .max_height(400.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            for (s, selected) in data.streams.iter().zip(export.selected.iter_mut()) {
+                for (e, sel) in s.entries.iter().zip(selected.iter_mut()) {
+                    ui.checkbox(sel, &e.name);
+                }
+                ui.add_space(10.0);
+            }
+        });
+
+    ui.add_space(10.0);
+
+    match &export.visible_range {
+        Some(range) => {
+            let start = util::format_time(*range.start());
+            let end = util::format_time(*range.end());
+            let text = format!("Only visible range ({start} - {end})");
+            ui.checkbox(&mut export.only_visible, text);
+        }
+        None => {
+            ui.add_enabled(false, egui::Checkbox::new(&mut false, "Only visible range"));
+        }
+    }
+
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| ui.button("Export").clicked()).inner
+}
+
+/// Returns whether the selection changed.
+fn source_files_dialog(ui: &mut Ui, data: &mut PlotData, dir: &Path) -> bool {
+    let mut changed = false;
+    let streams = data.all_streams.iter().zip(data.enabled_sources.iter_mut());
+    for (i, (s, enabled)) in streams.enumerate() {
+        ui.strong(format!("Stream {} ({})", i + 1, s.format));
+        Grid::new(("source_files", i))
+            .striped(true)
+            .num_columns(3)
+            .show(ui, |ui| {
+                let num_enabled = enabled.iter().filter(|e| **e).count();
+                for (source, e) in s.sources.iter().zip(enabled.iter_mut()) {
+                    let path = source.path.strip_prefix(dir).unwrap_or(&source.path);
+                    // at least one file of each stream stays enabled
+                    let can_toggle = !*e || num_enabled > 1;
+                    let checkbox = egui::Checkbox::new(e, path.display().to_string());
+                    changed |= ui.add_enabled(can_toggle, checkbox).changed();
+                    ui.label(format!("{} samples", source.samples.len()));
+                    let time = |i: usize| util::format_time(s.time[i] as f64 / 1000.0);
+                    if source.samples.is_empty() {
+                        ui.label("");
+                    } else {
+                        let (start, end) = (source.samples.start, source.samples.end - 1);
+                        ui.label(format!("{} - {}", time(start), time(end)));
+                    }
+                    ui.end_row();
+                }
+            });
+        ui.add_space(10.0);
+    }
+    changed
+}
+
+pub enum SelectFilesAction {
+    Open,
+    ExportReport,
+}
+
+pub fn select_files_dialog(
+    ui: &mut Ui,
+    opened_files: &mut SelectableFiles,
+) -> Option<SelectFilesAction> {
     let common_prefix = opened_files.dir.as_path();
 
     for (i, group) in opened_files.by_header.iter_mut().enumerate() {
@@ -193,7 +1231,21 @@ pub fn select_files_dialog(ui: &mut Ui, opened_files: &mut SelectableFiles) -> b
 
     ui.add_space(20.0);
 
-    ui.horizontal(|ui| ui.button("Ok").clicked()).inner
+    if opened_files.missing_start_times() {
+        ui.colored_label(Color32::YELLOW, MISSING_START_WARNING);
+        ui.add_space(20.0);
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button("Ok").clicked() {
+            return Some(SelectFilesAction::Open);
+        }
+        let export = ui
+            .button("Export report")
+            .on_hover_text("Save the failed sanity checks of all files as csv");
+        export.clicked().then_some(SelectFilesAction::ExportReport)
+    })
+    .inner
 }
 
 enum MoveDirection {
@@ -253,7 +1305,7 @@ fn select_files_table(ui: &mut Ui, files: &mut Vec<SelectableFile>, common_prefi
                     });
                     row.col(|ui| {
                         ui.horizontal_centered(|ui| {
-                            ui.label(f.stream.version.to_string());
+                            ui.label(f.stream.format.to_string());
                         });
                     });
                     row.col(|ui| {
@@ -263,9 +1315,26 @@ fn select_files_table(ui: &mut Ui, files: &mut Vec<SelectableFile>, common_prefi
                         });
                     });
                     row.col(|ui| {
-                        ui.horizontal_centered(|ui| match &f.sanity_check {
-                            Ok(_) => ui.label("ok"),
-                            Err(e) => ui.colored_label(Color32::YELLOW, &e.0),
+                        ui.horizontal_centered(|ui| {
+                            match &f.sanity_check {
+                                Ok(_) => ui.label("ok"),
+                                Err(e) => ui.colored_label(Color32::YELLOW, &e.0),
+                            };
+                            if !f.rule_checks.is_empty() {
+                                rule_checks_label(ui, &f.rule_checks);
+                            }
+                            let corrupted = &f.read_warnings.corrupted;
+                            if !corrupted.is_empty() {
+                                let text = format!("⚠ {} corrupted blocks", corrupted.len());
+                                let details = corrupted.iter().map(|b| b.to_string());
+                                ui.colored_label(Color32::YELLOW, text)
+                                    .on_hover_text(details.collect::<Vec<_>>().join("\n"));
+                            }
+                            if let Some(truncated) = &f.read_warnings.truncated {
+                                let text = format!("⚠ recovered {} samples", truncated.recovered);
+                                ui.colored_label(Color32::YELLOW, text)
+                                    .on_hover_text(truncated.to_string());
+                            }
                         });
                     });
                     row.col(|ui| {
@@ -322,6 +1391,24 @@ fn select_files_table(ui: &mut Ui, files: &mut Vec<SelectableFile>, common_prefi
     }
 }
 
+/// Number of passed rules, with the result of each rule on hover.
+fn rule_checks_label(ui: &mut Ui, checks: &[(String, Result<(), SanityError>)]) {
+    let passed = checks.iter().filter(|(_, r)| r.is_ok()).count();
+    let text = format!("rules: {passed}/{} ok", checks.len());
+    let label = match passed == checks.len() {
+        true => ui.label(text),
+        false => ui.colored_label(Color32::YELLOW, text),
+    };
+    label.on_hover_ui(|ui| {
+        for (rule, result) in checks {
+            match result {
+                Ok(()) => ui.label(format!("{rule}: ok")),
+                Err(e) => ui.colored_label(Color32::YELLOW, format!("{rule}: {}", e.0)),
+            };
+        }
+    });
+}
+
 fn error_files_table(ui: &mut Ui, files: &[ErrorFile], common_prefix: &Path) {
     TableBuilder::new(ui)
         .column(Column::exact(400.0)) // file name
@@ -357,13 +1444,24 @@ fn error_files_table(ui: &mut Ui, files: &[ErrorFile], common_prefix: &Path) {
 
 impl PlotApp {
     pub fn new(context: &eframe::CreationContext) -> Self {
-        let mut app = context
-            .storage
-            .and_then(|s| eframe::get_value::<PlotApp>(s, eframe::APP_KEY))
-            .unwrap_or_default();
+        let stored = context.storage.and_then(|s| s.get_string(eframe::APP_KEY));
+        let mut app = match stored.map(|s| ron::from_str::<PlotApp>(&s)) {
+            Some(Ok(app)) => app,
+            Some(Err(e)) => {
+                let mut app = PlotApp::default();
+                let details = format!("{e}\n\nThe default config is used instead.");
+                app.toasts.error("Error loading the stored config", details);
+                app
+            }
+            None => PlotApp::default(),
+        };
 
-        if let Some(f) = app.files.clone() {
-            app.try_open_files(f, false);
+        // don't reopen the files automatically after a crash, they might have caused it
+        app.recovery = recovery::load();
+        if app.recovery.is_none() {
+            if let Some(f) = app.files.clone() {
+                app.try_open_files(f, false);
+            }
         }
         app
     }
@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use egui::{Button, Checkbox, ComboBox, DragValue, ScrollArea, Ui};
+
+use crate::data::LogStream;
+use crate::util;
+
+/// in Hz
+const AUDIO_SAMPLE_RATE: u32 = 44_100;
+/// longer exports are refused, they would take hundreds of megabytes
+const MAX_AUDIO_SECONDS: f64 = 600.0;
+/// headroom below full scale after normalizing
+const PEAK_LEVEL: f64 = 0.9;
+
+/// State of the audio export dialog.
+pub struct AudioExport {
+    /// stream and entry index
+    pub channel: Option<(usize, usize)>,
+    /// log seconds played per second of audio
+    pub speed: f64,
+    /// x-range of the active plot when the dialog was opened
+    pub visible_range: Option<RangeInclusive<f64>>,
+    pub only_visible: bool,
+}
+
+impl AudioExport {
+    pub fn new(visible_range: Option<RangeInclusive<f64>>) -> Self {
+        Self {
+            channel: None,
+            speed: 1.0,
+            only_visible: visible_range.is_some(),
+            visible_range,
+        }
+    }
+
+    pub fn range(&self) -> Option<RangeInclusive<f64>> {
+        self.visible_range.clone().filter(|_| self.only_visible)
+    }
+}
+
+/// Resamples the channel to audio, linearly interpolating between its samples, and writes it as
+/// a mono 16 bit wav file. The offset of the channel is removed and it's normalized to the peak.
+pub fn export_wav(
+    path: &Path,
+    stream: &LogStream,
+    entry: usize,
+    offset: i64,
+    range: Option<RangeInclusive<f64>>,
+    speed: f64,
+) -> io::Result<()> {
+    let time = |i: usize| (stream.time[i] as i64 + offset) as f64 / 1000.0;
+    let (Some(first), Some(last)) = (stream.time.first(), stream.time.last()) else {
+        return Err(io::Error::other("the channel has no samples"));
+    };
+    let full = (*first as i64 + offset) as f64 / 1000.0..=(*last as i64 + offset) as f64 / 1000.0;
+    let range = match range {
+        Some(r) => r.start().max(*full.start())..=r.end().min(*full.end()),
+        None => full,
+    };
+    let seconds = (range.end() - range.start()) / speed;
+    if seconds > MAX_AUDIO_SECONDS {
+        return Err(io::Error::other(format!(
+            "the audio would be {} long, narrow the range or increase the speed",
+            util::format_time(seconds)
+        )));
+    }
+
+    let e = &stream.entries[entry];
+    let num_samples = (seconds * AUDIO_SAMPLE_RATE as f64) as usize;
+    let mut cursor = 0;
+    let mut samples = Vec::with_capacity(num_samples);
+    for k in 0..num_samples {
+        let t = range.start() + k as f64 * speed / AUDIO_SAMPLE_RATE as f64;
+        while cursor + 1 < stream.len() && time(cursor + 1) <= t {
+            cursor += 1;
+        }
+        let v = match cursor + 1 < stream.len() {
+            true => {
+                let (t0, t1) = (time(cursor), time(cursor + 1));
+                let (v0, v1) = (e.get_f64(cursor), e.get_f64(cursor + 1));
+                let factor = ((t - t0) / (t1 - t0)).clamp(0.0, 1.0);
+                v0 + factor * (v1 - v0)
+            }
+            false => e.get_f64(cursor),
+        };
+        samples.push(v);
+    }
+
+    let finite = || samples.iter().filter(|v| v.is_finite());
+    let count = finite().count().max(1);
+    let mean = finite().sum::<f64>() / count as f64;
+    let peak = finite().map(|v| (v - mean).abs()).fold(0.0, f64::max);
+    let gain = match peak > 0.0 {
+        true => PEAK_LEVEL / peak,
+        false => 0.0,
+    };
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_wav_header(&mut writer, samples.len())?;
+    for v in samples {
+        let v = if v.is_finite() {
+            (v - mean) * gain
+        } else {
+            0.0
+        };
+        let v = (v * i16::MAX as f64).round() as i16;
+        writer.write_all(&v.to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+/// RIFF header of mono 16 bit PCM.
+fn write_wav_header(writer: &mut impl Write, num_samples: usize) -> io::Result<()> {
+    let data_len = (num_samples * 2) as u32;
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_len).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    // PCM, mono
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&AUDIO_SAMPLE_RATE.to_le_bytes())?;
+    writer.write_all(&(AUDIO_SAMPLE_RATE * 2).to_le_bytes())?;
+    // block align and bits per sample
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())
+}
+
+/// Returns true if the channel should be exported.
+pub fn audio_export_dialog(ui: &mut Ui, export: &mut AudioExport, streams: &[LogStream]) -> bool {
+    let selected = (export.channel)
+        .and_then(|(s, e)| streams.get(s)?.entries.get(e))
+        .map_or("Select channel", |e| e.name.as_str());
+    ComboBox::from_id_source("audio_channel")
+        .selected_text(selected)
+        .width(250.0)
+        .show_ui(ui, |ui| {
+            ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for (s, stream) in streams.iter().enumerate() {
+                    for (e, entry) in stream.entries.iter().enumerate() {
+                        ui.selectable_value(&mut export.channel, Some((s, e)), &entry.name);
+                    }
+                }
+            });
+        });
+
+    ui.horizontal(|ui| {
+        ui.label("Speed");
+        let speed = DragValue::new(&mut export.speed)
+            .range(0.01..=100.0)
+            .speed(0.01)
+            .suffix("×");
+        ui.add(speed)
+            .on_hover_text("Playing faster raises the pitch, e.g. to hear slow oscillations");
+    });
+    ui.label(format!("Exported at {AUDIO_SAMPLE_RATE} Hz"));
+
+    match &export.visible_range {
+        Some(range) => {
+            let start = util::format_time(*range.start());
+            let end = util::format_time(*range.end());
+            let text = format!("Only visible range ({start} - {end})");
+            ui.checkbox(&mut export.only_visible, text);
+        }
+        None => {
+            ui.add_enabled(false, Checkbox::new(&mut false, "Only visible range"));
+        }
+    }
+
+    ui.add_space(10.0);
+
+    ui.add_enabled(export.channel.is_some(), Button::new("Export"))
+        .clicked()
+}
@@ -0,0 +1,140 @@
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use egui::Ui;
+use serde::{Deserialize, Serialize};
+
+use crate::plot::Config;
+
+pub const COLLAB_PORT: u16 = 47353;
+pub const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// the view is sent again after this interval even if it didn't change, so instances that start
+/// following later catch up
+const RESEND_INTERVAL: Duration = Duration::from_secs(1);
+const MAGIC: &str = "s3plot-review-v1";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CollabRole {
+    #[default]
+    Off,
+    Broadcast,
+    Follow,
+}
+
+impl CollabRole {
+    pub const ALL: [Self; 3] = [Self::Off, Self::Broadcast, Self::Follow];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Broadcast => "Broadcast",
+            Self::Follow => "Follow",
+        }
+    }
+}
+
+/// The part of the view that is shared with the other instances.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SharedView {
+    magic: String,
+    pub tab: usize,
+    /// tabs are matched by name first, since the instances might have different tabs
+    pub tab_name: String,
+    /// time in seconds
+    pub cursor: Option<f64>,
+}
+
+/// Review mode, one instance broadcasts its selected tab and time cursor over UDP to the LAN and
+/// the other instances follow it.
+#[derive(Debug)]
+pub struct Collab {
+    pub role: CollabRole,
+    socket: UdpSocket,
+    last_sent: Option<(SharedView, Instant)>,
+}
+
+impl Collab {
+    pub fn start(role: CollabRole) -> std::io::Result<Self> {
+        let socket = match role {
+            CollabRole::Follow => UdpSocket::bind((Ipv4Addr::UNSPECIFIED, COLLAB_PORT))?,
+            _ => {
+                let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+                socket.set_broadcast(true)?;
+                socket
+            }
+        };
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            role,
+            socket,
+            last_sent: None,
+        })
+    }
+
+    /// Sends the view if it changed or wasn't sent for a while.
+    pub fn broadcast(&mut self, cfg: &Config) -> std::io::Result<()> {
+        let view = SharedView {
+            magic: MAGIC.into(),
+            tab: cfg.selected_tab,
+            tab_name: (cfg.tabs.get(cfg.selected_tab)).map_or(String::new(), |t| t.name.clone()),
+            cursor: cfg.cursor,
+        };
+        if let Some((last, sent)) = &self.last_sent {
+            if *last == view && sent.elapsed() < RESEND_INTERVAL {
+                return Ok(());
+            }
+        }
+
+        let msg = serde_json::to_vec(&view).map_err(std::io::Error::other)?;
+        self.socket
+            .send_to(&msg, (Ipv4Addr::BROADCAST, COLLAB_PORT))?;
+        self.last_sent = Some((view, Instant::now()));
+        Ok(())
+    }
+
+    /// Applies the last view received since the previous call.
+    pub fn follow(&mut self, cfg: &mut Config) -> std::io::Result<()> {
+        let mut latest = None;
+        let mut buf = [0; 1024];
+        loop {
+            match self.socket.recv_from(&mut buf) {
+                Ok((len, _)) => {
+                    let view = serde_json::from_slice::<SharedView>(&buf[..len]).ok();
+                    latest = view.filter(|v| v.magic == MAGIC).or(latest);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        let Some(view) = latest else {
+            return Ok(());
+        };
+
+        let tab = (cfg.tabs.iter())
+            .position(|t| t.name == view.tab_name)
+            .or((view.tab < cfg.tabs.len()).then_some(view.tab));
+        if let Some(tab) = tab {
+            cfg.selected_tab = tab;
+        }
+        cfg.cursor = view.cursor;
+        Ok(())
+    }
+}
+
+/// Returns the newly selected role.
+pub fn role_menu(ui: &mut Ui, current: CollabRole) -> Option<CollabRole> {
+    let mut selected = None;
+    ui.menu_button("Review mode", |ui| {
+        ui.label(
+            "Share the selected tab and cursor with other instances in the LAN (experimental)",
+        );
+        for role in CollabRole::ALL {
+            if ui.radio(current == role, role.name()).clicked() && current != role {
+                selected = Some(role);
+                ui.close_menu();
+            }
+        }
+    });
+    selected
+}
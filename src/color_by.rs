@@ -0,0 +1,77 @@
+use egui::{Color32, Rect, Sense, Ui, Vec2};
+use egui_plot::PlotPoint;
+
+use crate::eval::Expr;
+use crate::heatmap;
+use crate::plot::{TabConfig, XyMode};
+
+/// Number of distinct colors a line is drawn with.
+const COLOR_BUCKETS: usize = 32;
+const COLOR_BAR_SIZE: Vec2 = Vec2::new(120.0, 10.0);
+
+/// The expression the values to color the line plot by are evaluated with.
+pub fn color_expr(tab_cfg: &TabConfig, plot: usize) -> Option<Expr> {
+    let p = &tab_cfg.plots[plot];
+    if p.color_by.trim().is_empty() || p.xy_mode != XyMode::Off {
+        return None;
+    }
+    Some(Expr::new(&tab_cfg.plot_expr(p).x, &p.color_by))
+}
+
+/// Splits the line into runs of points with the same color, the bucket of the color value at the
+/// x value of each point. Runs share their boundary point, so the line stays connected. Points
+/// without a color value are in runs without a bucket.
+pub fn colored_runs(
+    line: Vec<PlotPoint>,
+    colors: &[PlotPoint],
+    (min, max): (f64, f64),
+) -> Vec<(Vec<PlotPoint>, Option<usize>)> {
+    let bucket = |x: f64| {
+        let i = colors.partition_point(|v| v.x <= x).checked_sub(1)?;
+        let v = colors[i].y;
+        if !v.is_finite() {
+            return None;
+        }
+        let norm = if max > min {
+            (v - min) / (max - min)
+        } else {
+            0.5
+        };
+        let b = (norm * (COLOR_BUCKETS - 1) as f64).round() as usize;
+        Some(b.min(COLOR_BUCKETS - 1))
+    };
+
+    let mut runs: Vec<(Vec<PlotPoint>, Option<usize>)> = Vec::new();
+    for p in line {
+        let b = bucket(p.x);
+        match runs.last_mut() {
+            Some((points, last)) if *last == b => points.push(p),
+            Some((points, _)) => {
+                let start = *points.last().unwrap();
+                runs.push((vec![start, p], b));
+            }
+            None => runs.push((vec![p], b)),
+        }
+    }
+    runs
+}
+
+pub fn bucket_color(bucket: usize) -> Color32 {
+    heatmap::colormap(bucket as f32 / (COLOR_BUCKETS - 1) as f32)
+}
+
+/// A row of the color legend with the color expression and its range.
+pub fn color_legend(ui: &mut Ui, name: &str, color_by: &str, (min, max): (f64, f64)) {
+    ui.horizontal(|ui| {
+        ui.label(format!("{name}: {color_by}"));
+        ui.weak(format!("{min:.3}"));
+        let (resp, painter) = ui.allocate_painter(COLOR_BAR_SIZE, Sense::hover());
+        let width = resp.rect.width() / COLOR_BUCKETS as f32;
+        for b in 0..COLOR_BUCKETS {
+            let min = resp.rect.left_top() + Vec2::new(b as f32 * width, 0.0);
+            let rect = Rect::from_min_size(min, Vec2::new(width, resp.rect.height()));
+            painter.rect_filled(rect, 0.0, bucket_color(b));
+        }
+        ui.weak(format!("{max:.3}"));
+    });
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::app::{Job, PlotValues};
+use crate::data::LogStream;
+use crate::eval::{Expr, Timing};
+use crate::library::UserExpr;
+use crate::session::SessionMeta;
+
+/// A second, independently loaded session that line plots are compared with. The plots of all
+/// tabs are evaluated for it when they are first shown.
+pub struct RunB {
+    pub dir: PathBuf,
+    pub streams: Arc<[LogStream]>,
+    pub session: SessionMeta,
+    /// seconds added to the x values of run B, to align it with the opened run
+    pub offset: f64,
+    /// by the resolved x and y expression
+    values: HashMap<(String, String), PlotValues>,
+}
+
+impl RunB {
+    pub fn new(dir: PathBuf, streams: Arc<[LogStream]>) -> Self {
+        let session = SessionMeta::load(&dir, &streams);
+        Self {
+            dir,
+            streams,
+            session,
+            offset: 0.0,
+            values: HashMap::new(),
+        }
+    }
+
+    /// Starts the evaluation if the expression wasn't requested before.
+    pub fn request(&mut self, expr: &Expr, library: &[UserExpr]) {
+        let key = self.key(expr);
+        if !self.values.contains_key(&key) {
+            let expr = Expr::new(&key.0, &key.1);
+            let data = Arc::clone(&self.streams);
+            let job = Job::start(expr, data, library.to_vec(), Timing::default(), None, None);
+            self.values.insert(key, PlotValues::Job(job));
+        }
+    }
+
+    pub fn get(&self, expr: &Expr) -> Option<&PlotValues> {
+        self.values.get(&self.key(expr))
+    }
+
+    fn key(&self, expr: &Expr) -> (String, String) {
+        (self.session.resolve(&expr.x), self.session.resolve(&expr.y))
+    }
+
+    /// Collects the results of finished jobs, returns true if some are still running.
+    pub fn poll_jobs(&mut self) -> bool {
+        let mut pending = false;
+        for values in self.values.values_mut() {
+            if let PlotValues::Job(j) = values {
+                if j.is_done() {
+                    let job = std::mem::replace(values, PlotValues::empty());
+                    *values = PlotValues::Result(job.into_job().unwrap().join());
+                } else {
+                    pending = true;
+                }
+            }
+        }
+        pending
+    }
+
+    /// Drops all values, e.g. when the expression library changed.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
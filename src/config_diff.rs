@@ -0,0 +1,319 @@
+use egui::{Color32, Grid, RichText, ScrollArea, Ui};
+
+use crate::eval::Expr;
+use crate::plot::{Config, NamedPlot, TabConfig};
+
+const ADDED_GREEN: Color32 = Color32::from_rgb(0x56, 0xc0, 0x56);
+const REMOVED_RED: Color32 = Color32::from_rgb(0xf0, 0x56, 0x56);
+const CHANGED_YELLOW: Color32 = Color32::from_rgb(0xe0, 0xc0, 0x40);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImportMode {
+    /// the imported config replaces the current one
+    #[default]
+    Replace,
+    /// the imported tabs are appended after the current ones
+    Merge,
+}
+
+impl ImportMode {
+    pub const ALL: [Self; 2] = [Self::Replace, Self::Merge];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Replace => "Replace",
+            Self::Merge => "Merge",
+        }
+    }
+}
+
+pub struct ConfigImport {
+    pub config: Config,
+    pub mode: ImportMode,
+    pub diff: ConfigDiff,
+}
+
+impl ConfigImport {
+    pub fn new(current: &Config, config: Config) -> Self {
+        let diff = ConfigDiff::new(current, &config);
+        Self {
+            config,
+            mode: ImportMode::Replace,
+            diff,
+        }
+    }
+
+    fn set_mode(&mut self, current: &Config, mode: ImportMode) {
+        self.mode = mode;
+        self.diff = match mode {
+            ImportMode::Replace => ConfigDiff::new(current, &self.config),
+            ImportMode::Merge => ConfigDiff::merged(current, &self.config),
+        };
+    }
+}
+
+/// Appends the imported tabs after the current ones, tabs with a name that is already taken are
+/// numbered. Returns the number of appended tabs.
+pub fn merge_tabs(current: &mut Config, imported: Vec<TabConfig>) -> usize {
+    let names = merged_names(&current.tabs, &imported);
+    let count = imported.len();
+    for (mut tab, name) in imported.into_iter().zip(names) {
+        tab.name = name;
+        // the ids have to be unique, e.g. when importing an export of the current config
+        tab.id = rand::random();
+        current.tabs.push(tab);
+    }
+    count
+}
+
+/// Names of the imported tabs after they are appended to the current ones.
+fn merged_names(current: &[TabConfig], imported: &[TabConfig]) -> Vec<String> {
+    let mut taken = current.iter().map(|t| t.name.clone()).collect::<Vec<_>>();
+    let mut names = Vec::with_capacity(imported.len());
+    for t in imported.iter() {
+        let mut name = t.name.clone();
+        let mut n = 2;
+        while taken.contains(&name) {
+            name = format!("{} ({n})", t.name);
+            n += 1;
+        }
+        taken.push(name.clone());
+        names.push(name);
+    }
+    names
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Unchanged,
+    Added,
+    Removed,
+    Changed,
+}
+
+impl Change {
+    fn color(&self) -> Option<Color32> {
+        match self {
+            Change::Unchanged => None,
+            Change::Added => Some(ADDED_GREEN),
+            Change::Removed => Some(REMOVED_RED),
+            Change::Changed => Some(CHANGED_YELLOW),
+        }
+    }
+}
+
+/// Differences between two configs, tabs and plots are matched by name.
+pub struct ConfigDiff {
+    pub tabs: Vec<TabDiff>,
+}
+
+pub struct TabDiff {
+    pub name: String,
+    pub change: Change,
+    pub plots: Vec<PlotDiff>,
+}
+
+pub struct PlotDiff {
+    pub name: String,
+    pub change: Change,
+    pub current: Option<Expr>,
+    pub imported: Option<Expr>,
+}
+
+impl ConfigDiff {
+    pub fn new(current: &Config, imported: &Config) -> Self {
+        let mut tabs = Vec::new();
+        for t in current.tabs.iter() {
+            let other = imported.tabs.iter().find(|o| o.name == t.name);
+            tabs.push(TabDiff::new(&t.name, Some(t), other));
+        }
+        for t in imported.tabs.iter() {
+            if !current.tabs.iter().any(|c| c.name == t.name) {
+                tabs.push(TabDiff::new(&t.name, None, Some(t)));
+            }
+        }
+        Self { tabs }
+    }
+
+    /// Differences of merging the imported tabs, the current tabs stay unchanged.
+    pub fn merged(current: &Config, imported: &Config) -> Self {
+        let mut tabs = (current.tabs.iter())
+            .map(|t| TabDiff::new(&t.name, Some(t), Some(t)))
+            .collect::<Vec<_>>();
+        let names = merged_names(&current.tabs, &imported.tabs);
+        for (t, name) in imported.tabs.iter().zip(names.iter()) {
+            tabs.push(TabDiff::new(name, None, Some(t)));
+        }
+        Self { tabs }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tabs.iter().all(|t| t.change == Change::Unchanged)
+    }
+}
+
+impl TabDiff {
+    fn new(name: &str, current: Option<&TabConfig>, imported: Option<&TabConfig>) -> Self {
+        let empty = Vec::new();
+        let current_plots = current.map_or(&empty, |t| &t.plots);
+        let imported_plots = imported.map_or(&empty, |t| &t.plots);
+
+        let mut plots = Vec::new();
+        for p in current_plots.iter() {
+            let other = imported_plots.iter().find(|o| o.name == p.name);
+            plots.push(PlotDiff::new(&p.name, Some(p), other));
+        }
+        for p in imported_plots.iter() {
+            if !current_plots.iter().any(|c| c.name == p.name) {
+                plots.push(PlotDiff::new(&p.name, None, Some(p)));
+            }
+        }
+
+        let change = match (current, imported) {
+            (Some(_), None) => Change::Removed,
+            (None, Some(_)) => Change::Added,
+            _ if plots.iter().any(|p| p.change != Change::Unchanged) => Change::Changed,
+            _ => Change::Unchanged,
+        };
+
+        Self {
+            name: name.to_string(),
+            change,
+            plots,
+        }
+    }
+}
+
+impl PlotDiff {
+    fn new(name: &str, current: Option<&NamedPlot>, imported: Option<&NamedPlot>) -> Self {
+        let change = match (current, imported) {
+            (Some(_), None) => Change::Removed,
+            (None, Some(_)) => Change::Added,
+            (Some(c), Some(i)) if c.expr != i.expr => Change::Changed,
+            _ => Change::Unchanged,
+        };
+
+        Self {
+            name: name.to_string(),
+            change,
+            current: current.map(|p| p.expr.clone()),
+            imported: imported.map(|p| p.expr.clone()),
+        }
+    }
+}
+
+/// Shows the current and the imported config side by side, returns `Some(true)` if the import
+/// should be applied and `Some(false)` if it was cancelled.
+pub fn config_diff_dialog(
+    ui: &mut Ui,
+    import: &mut ConfigImport,
+    current: &Config,
+) -> Option<bool> {
+    ui.horizontal(|ui| {
+        let mut mode = import.mode;
+        for m in ImportMode::ALL {
+            ui.radio_value(&mut mode, m, m.name());
+        }
+        if mode != import.mode {
+            import.set_mode(current, mode);
+        }
+    });
+    match import.mode {
+        ImportMode::Replace => ui.label("The current config is replaced by the imported one."),
+        ImportMode::Merge => ui.label(
+            "The imported tabs are appended after the current ones, everything else of the \
+            current config is kept.",
+        ),
+    };
+    ui.add_space(10.0);
+
+    let diff = &import.diff;
+    if diff.is_empty() {
+        ui.label("The imported config contains no changes to tabs or expressions.");
+    }
+
+    ScrollArea::vertical()
+        .max_height(500.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            Grid::new("config_diff")
+                .num_columns(2)
+                .min_col_width(300.0)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.heading("Current");
+                    ui.heading("Imported");
+                    ui.end_row();
+
+                    for t in diff.tabs.iter() {
+                        let current = t.change != Change::Added;
+                        let imported = t.change != Change::Removed;
+                        diff_cell(ui, current, t.change, |ui, color| {
+                            ui.label(colored(RichText::new(&t.name).strong(), color));
+                        });
+                        diff_cell(ui, imported, t.change, |ui, color| {
+                            ui.label(colored(RichText::new(&t.name).strong(), color));
+                        });
+                        ui.end_row();
+
+                        for p in t.plots.iter() {
+                            diff_cell(ui, p.current.is_some(), p.change, |ui, color| {
+                                plot_label(ui, &p.name, p.current.as_ref(), color);
+                            });
+                            diff_cell(ui, p.imported.is_some(), p.change, |ui, color| {
+                                plot_label(ui, &p.name, p.imported.as_ref(), color);
+                            });
+                            ui.end_row();
+                        }
+                    }
+                });
+        });
+
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        if ui.button("Apply").clicked() {
+            return Some(true);
+        }
+        if ui.button("Cancel").clicked() {
+            return Some(false);
+        }
+        None
+    })
+    .inner
+}
+
+fn diff_cell(
+    ui: &mut Ui,
+    present: bool,
+    change: Change,
+    add_contents: impl FnOnce(&mut Ui, Option<Color32>),
+) {
+    if present {
+        ui.vertical(|ui| add_contents(ui, change.color()));
+    } else {
+        ui.label("");
+    }
+}
+
+fn plot_label(ui: &mut Ui, name: &str, expr: Option<&Expr>, color: Option<Color32>) {
+    let Some(expr) = expr else {
+        return;
+    };
+    ui.label(colored(RichText::new(format!("  {name}")), color));
+    ui.label(colored(
+        RichText::new(format!("    x: {}", expr.x)).monospace(),
+        color,
+    ));
+    ui.label(colored(
+        RichText::new(format!("    y: {}", expr.y)).monospace(),
+        color,
+    ));
+}
+
+fn colored(text: RichText, color: Option<Color32>) -> RichText {
+    match color {
+        Some(c) => text.color(c),
+        None => text,
+    }
+}
@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use egui::{Button, Checkbox, Color32, DragValue, TextEdit, TextStyle, Ui};
+use egui_plot::{Line, LineStyle, PlotPoints, PlotUi};
+use serde::{Deserialize, Serialize};
+
+use crate::eval;
+
+/// number of points a function is sampled at
+const FUNCTION_SAMPLES: usize = 500;
+const DEFAULT_CURVE_COLOR: Color32 = Color32::from_gray(0x90);
+const CURVE_WIDTH: f32 = 2.0;
+
+/// A static curve drawn on top of the plots of a tab, e.g. the maximum motor torque over speed.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReferenceCurve {
+    pub name: String,
+    pub points: Vec<[f64; 2]>,
+    pub color: Color32,
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+/// A curve defined as a function of `x`, sampled over a range.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurveFunction {
+    pub expr: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Default for CurveFunction {
+    fn default() -> Self {
+        Self {
+            expr: String::new(),
+            start: 0.0,
+            end: 100.0,
+        }
+    }
+}
+
+impl CurveFunction {
+    fn sample(&self) -> cods::Result<Vec<[f64; 2]>> {
+        let step = (self.end - self.start) / (FUNCTION_SAMPLES - 1) as f64;
+        let xs = (0..FUNCTION_SAMPLES)
+            .map(|i| self.start + i as f64 * step)
+            .collect::<Vec<_>>();
+        let ys = eval::eval_fn(&self.expr, &xs)?;
+        Ok(xs.into_iter().zip(ys).map(|(x, y)| [x, y]).collect())
+    }
+}
+
+#[derive(Default)]
+pub struct NewCurve {
+    pub name: String,
+    pub function: CurveFunction,
+    pub error: Option<String>,
+}
+
+/// Reads the points of a csv file with an x and a y column, separated by commas or semicolons.
+/// The first line is skipped if it doesn't contain numbers, since it's usually a header.
+pub fn import_csv(path: &Path) -> Result<Vec<[f64; 2]>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut points = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.split([',', ';']).map(|f| f.trim().parse::<f64>());
+        match (fields.next(), fields.next()) {
+            (Some(Ok(x)), Some(Ok(y))) => points.push([x, y]),
+            _ if i == 0 => (),
+            _ => return Err(format!("line {}: expected two numbers: `{line}`", i + 1)),
+        }
+    }
+    if points.is_empty() {
+        return Err("the file contains no points".into());
+    }
+    Ok(points)
+}
+
+/// Draws the visible curves dashed, so they stand apart from the logged data.
+pub fn draw_curves(ui: &mut PlotUi, curves: &[ReferenceCurve]) {
+    for c in curves.iter().filter(|c| !c.hidden) {
+        let line = Line::new(PlotPoints::from(c.points.clone()))
+            .name(&c.name)
+            .color(c.color)
+            .width(CURVE_WIDTH)
+            .style(LineStyle::dashed_loose());
+        ui.line(line);
+    }
+}
+
+pub fn reference_curves_sidebar(ui: &mut Ui, curves: &mut Vec<ReferenceCurve>, new: &mut NewCurve) {
+    let mut i = 0;
+    while i < curves.len() {
+        let c = &mut curves[i];
+        let removed = ui
+            .horizontal(|ui| {
+                let mut visible = !c.hidden;
+                if ui.add(Checkbox::without_text(&mut visible)).changed() {
+                    c.hidden = !visible;
+                }
+                ui.color_edit_button_srgba(&mut c.color);
+                ui.add(TextEdit::singleline(&mut c.name).desired_width(150.0));
+                ui.button("🗙").clicked()
+            })
+            .inner;
+        if removed {
+            curves.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    ui.separator();
+
+    ui.add(
+        TextEdit::singleline(&mut new.name)
+            .hint_text("name")
+            .desired_width(150.0),
+    );
+    ui.add(
+        TextEdit::singleline(&mut new.function.expr)
+            .font(TextStyle::Monospace)
+            .hint_text("f(x), e.g. min(230, 60000 / x)")
+            .desired_width(f32::INFINITY),
+    );
+    ui.horizontal(|ui| {
+        let f = &mut new.function;
+        ui.label("x");
+        ui.add(DragValue::new(&mut f.start).speed(1.0));
+        ui.label("to");
+        ui.add(DragValue::new(&mut f.end).speed(1.0));
+
+        let valid = !f.expr.trim().is_empty() && f.start < f.end;
+        if ui.add_enabled(valid, Button::new("Add")).clicked() {
+            match f.sample() {
+                Ok(points) => {
+                    let name = match new.name.trim() {
+                        "" => f.expr.trim().to_string(),
+                        name => name.to_string(),
+                    };
+                    curves.push(ReferenceCurve {
+                        name,
+                        points,
+                        color: DEFAULT_CURVE_COLOR,
+                        hidden: false,
+                    });
+                    *new = NewCurve::default();
+                }
+                Err(e) => new.error = Some(e.to_string()),
+            }
+        }
+    });
+
+    if ui.button("Import csv…").clicked() {
+        let dialog = rfd::FileDialog::new().add_filter("csv", &["csv", "txt"]);
+        if let Some(path) = dialog.pick_file() {
+            match import_csv(&path) {
+                Ok(points) => {
+                    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                    let name = match new.name.trim() {
+                        "" => stem.to_string(),
+                        name => name.to_string(),
+                    };
+                    curves.push(ReferenceCurve {
+                        name,
+                        points,
+                        color: DEFAULT_CURVE_COLOR,
+                        hidden: false,
+                    });
+                    new.name.clear();
+                    new.error = None;
+                }
+                Err(e) => new.error = Some(format!("{}: {e}", path.display())),
+            }
+        }
+    }
+
+    if let Some(e) = &new.error {
+        ui.colored_label(ui.visuals().error_fg_color, e);
+    }
+}
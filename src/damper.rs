@@ -0,0 +1,116 @@
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::{LogStream, RateSegment};
+
+pub const CORNERS: [&str; 4] = ["FL", "FR", "RL", "RR"];
+
+/// Spring displacement channels, in mm, used to compute the damper velocity histograms of a tab.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DamperConfig {
+    /// in the order of [`CORNERS`]
+    pub channels: [String; 4],
+    /// width of the speed buckets in mm/s
+    pub bucket: f64,
+    /// in mm/s, faster samples are counted in the outermost buckets
+    pub max_speed: f64,
+}
+
+impl Default for DamperConfig {
+    fn default() -> Self {
+        Self {
+            channels: CORNERS.map(|c| format!("spring_{}", c.to_lowercase())),
+            bucket: 10.0,
+            max_speed: 200.0,
+        }
+    }
+}
+
+impl Hash for DamperConfig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.channels.hash(state);
+        self.bucket.to_bits().hash(state);
+        self.max_speed.to_bits().hash(state);
+    }
+}
+
+/// Share of the samples per damper speed bucket of one corner. Positive velocities are bump
+/// (compression), assuming the displacement increases when the spring is compressed.
+pub struct DamperHistogram {
+    /// center of the bucket in mm/s and share of the samples in percent
+    pub buckets: Vec<(f64, f64)>,
+    /// in percent
+    pub bump: f64,
+    /// in percent
+    pub rebound: f64,
+}
+
+/// Computes the histogram of all four corners, in the order of [`CORNERS`].
+pub fn histograms(
+    streams: &[LogStream],
+    cfg: &DamperConfig,
+) -> Vec<Result<DamperHistogram, String>> {
+    (cfg.channels.iter())
+        .map(|c| histogram(streams, c, cfg))
+        .collect()
+}
+
+fn histogram(
+    streams: &[LogStream],
+    channel: &str,
+    cfg: &DamperConfig,
+) -> Result<DamperHistogram, String> {
+    let (s, e) = find_channel(streams, channel)?;
+    if cfg.bucket <= 0.0 || cfg.max_speed < cfg.bucket {
+        return Err("the max speed has to be at least one positive bucket".into());
+    }
+
+    let stream = &streams[s];
+    let displacement = |i| stream.entries[e].get_f64(i);
+    let half_buckets = (cfg.max_speed / cfg.bucket).ceil() as i64;
+    let mut counts = vec![0usize; 2 * half_buckets as usize];
+    let mut bump = 0;
+    let mut rebound = 0;
+    let segments = stream.sample_periods();
+    for i in 1..stream.len() {
+        let dt = RateSegment::step(&segments, &stream.time, i) / 1000.0;
+        let velocity = (displacement(i) - displacement(i - 1)) / dt;
+        if dt <= 0.0 || !velocity.is_finite() {
+            continue;
+        }
+
+        let bucket = (velocity / cfg.bucket).floor() as i64;
+        let bucket = bucket.clamp(-half_buckets, half_buckets - 1);
+        counts[(bucket + half_buckets) as usize] += 1;
+        if velocity > 0.0 {
+            bump += 1;
+        } else if velocity < 0.0 {
+            rebound += 1;
+        }
+    }
+
+    let total = counts.iter().sum::<usize>();
+    if total == 0 {
+        return Err(format!("channel `{channel}` has no samples"));
+    }
+    let percent = |count: usize| count as f64 / total as f64 * 100.0;
+    let buckets = (counts.iter().enumerate())
+        .map(|(i, &count)| {
+            let center = (i as i64 - half_buckets) as f64 * cfg.bucket + cfg.bucket / 2.0;
+            (center, percent(count))
+        })
+        .collect();
+
+    Ok(DamperHistogram {
+        buckets,
+        bump: percent(bump),
+        rebound: percent(rebound),
+    })
+}
+
+fn find_channel(streams: &[LogStream], name: &str) -> Result<(usize, usize), String> {
+    (streams.iter().enumerate())
+        .find_map(|(i, s)| Some((i, s.entries.iter().position(|e| e.name == name)?)))
+        .ok_or_else(|| format!("channel `{name}` not found"))
+}
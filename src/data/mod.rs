@@ -1,21 +1,44 @@
+use std::ops::Range;
+use std::path::PathBuf;
 use std::string::FromUtf8Error;
 use std::{fmt, io};
 
 use chrono::NaiveDateTime;
 
-pub use crate::data::read::read_file;
-pub use crate::data::sanity::sanity_check;
+pub use crate::data::motec::parse_motec;
+pub use crate::data::raw::{read_raw, RawField, RawFile};
+pub use crate::data::read::{parse_file, ReadWarnings};
+pub use crate::data::sanity::{sanity_check, sanity_failures};
 
+mod motec;
+mod raw;
 mod read;
 mod sanity;
 
-#[derive(Debug)]
+/// Number of samples of which the median sample period is compared to detect rate changes.
+const RATE_BLOCK: usize = 64;
+/// Relative change of the median sample period that starts a new [`RateSegment`].
+const RATE_TOLERANCE: f64 = 0.1;
+/// Sample intervals longer than this multiple of the median are gaps and don't count towards the
+/// period of a segment.
+const GAP_FACTOR: f64 = 2.0;
+
+#[derive(Clone, Debug)]
 pub struct LogStream {
-    pub version: Version,
+    pub format: LogFormat,
     pub start: Option<NaiveDateTime>,
     /// time in ms
     pub time: Vec<u32>,
     pub entries: Vec<DataEntry>,
+    /// files the samples were read from, empty for a single parsed file
+    pub sources: Vec<SourceFile>,
+}
+
+/// Samples of a concatenated [`LogStream`] that were read from one file.
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub samples: Range<usize>,
 }
 
 impl LogStream {
@@ -23,13 +46,26 @@ impl LogStream {
         self.time.len()
     }
 
+    /// Value of the entry at the last sample before or at `time`, `NaN` if there are no samples.
+    pub fn value_at(&self, entry: usize, time: u32) -> f64 {
+        if self.time.is_empty() {
+            return f64::NAN;
+        }
+        let i = self.time.partition_point(|&t| t <= time);
+        self.entries[entry].get_f64(i.saturating_sub(1))
+    }
+
     pub fn header_matches(&self, other: &Self) -> bool {
         if self.entries.len() != other.entries.len() {
             return false;
         }
 
         for (a, b) in self.entries.iter().zip(other.entries.iter()) {
-            if !a.kind.matches(&b.kind) {
+            if !a.kind.matches(&b.kind)
+                || a.scale != b.scale
+                || a.offset != b.offset
+                || a.labels != b.labels
+            {
                 return false;
             }
         }
@@ -44,18 +80,145 @@ impl LogStream {
         }
     }
 
+    /// Adds `offset` ms to the time of each sample. Returns `false` and leaves the time
+    /// unchanged if a shifted time doesn't fit into a `u32`.
+    pub fn shift_time(&mut self, offset: u32) -> bool {
+        let max = self.time.iter().max().copied().unwrap_or(0);
+        if max.checked_add(offset).is_none() {
+            return false;
+        }
+        for t in self.time.iter_mut() {
+            *t += offset;
+        }
+        true
+    }
+
     pub fn extend(&mut self, other: &Self) {
         self.time.extend_from_slice(&other.time);
         for (e, o) in self.entries.iter_mut().zip(other.entries.iter()) {
             e.kind.extend(&o.kind);
         }
     }
+
+    /// Copy of the stream with only the samples of the sources that are `enabled`, indexed like
+    /// [`LogStream::sources`].
+    pub fn select_sources(&self, enabled: &[bool]) -> Self {
+        let sources = (self.sources.iter().zip(enabled))
+            .filter(|(_, e)| **e)
+            .map(|(s, _)| s)
+            .collect::<Vec<_>>();
+        let len = sources.iter().map(|s| s.samples.len()).sum();
+
+        let mut stream = Self {
+            format: self.format,
+            start: self.start,
+            time: Vec::with_capacity(len),
+            entries: (self.entries.iter())
+                .map(|e| DataEntry {
+                    name: e.name.clone(),
+                    unit: e.unit.clone(),
+                    scale: e.scale,
+                    offset: e.offset,
+                    kind: e.kind.empty(len),
+                    labels: e.labels.clone(),
+                })
+                .collect(),
+            sources: Vec::with_capacity(sources.len()),
+        };
+        for s in sources {
+            let start = stream.len();
+            stream.time.extend_from_slice(&self.time[s.samples.clone()]);
+            for (e, o) in stream.entries.iter_mut().zip(self.entries.iter()) {
+                e.kind.extend_range(&o.kind, s.samples.clone());
+            }
+            stream.sources.push(SourceFile {
+                path: s.path.clone(),
+                samples: start..stream.len(),
+            });
+        }
+        stream
+    }
+
+    /// Detects the sample period from the time vector. The logger can change its rate mid-file,
+    /// so the stream is split into segments wherever the median period of a block of samples
+    /// differs from the current segment. Streams with less than two samples have no segments.
+    pub fn sample_periods(&self) -> Vec<RateSegment> {
+        let deltas = (self.time.windows(2))
+            .map(|w| w[1].saturating_sub(w[0]))
+            .collect::<Vec<_>>();
+
+        let mut segments: Vec<(usize, u32)> = Vec::new();
+        for (i, block) in deltas.chunks(RATE_BLOCK).enumerate() {
+            let mut sorted = block.to_vec();
+            sorted.sort_unstable();
+            let median = sorted[sorted.len() / 2];
+            let same = segments.last().is_some_and(|&(_, current)| {
+                (median as f64 - current as f64).abs() <= current as f64 * RATE_TOLERANCE
+            });
+            if !same {
+                segments.push((i * RATE_BLOCK, median));
+            }
+        }
+
+        // the median of integer ms deltas is rounded, use the mean without gaps instead
+        let ends = (segments.iter().skip(1).map(|(start, _)| *start)).chain([deltas.len()]);
+        (segments.iter().zip(ends))
+            .map(|(&(start, median), end)| {
+                let max = median as f64 * GAP_FACTOR;
+                let (sum, count) = (deltas[start..end].iter())
+                    .filter(|&&d| d as f64 <= max)
+                    .fold((0.0, 0), |(sum, count), &d| (sum + d as f64, count + 1));
+                let period = if count == 0 {
+                    median as f64
+                } else {
+                    sum / count as f64
+                };
+                RateSegment { start, period }
+            })
+            .collect()
+    }
+}
+
+/// Part of a [`LogStream`] that is sampled at the same rate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateSegment {
+    /// index of the first sample
+    pub start: usize,
+    /// in ms
+    pub period: f64,
+}
+
+impl RateSegment {
+    /// in Hz, `None` if the samples have the same time
+    pub fn rate(&self) -> Option<f64> {
+        (self.period > 0.0).then(|| 1000.0 / self.period)
+    }
+
+    /// Period of the segment that contains the sample at `index`.
+    pub fn period_at(segments: &[Self], index: usize) -> Option<f64> {
+        let i = segments.partition_point(|s| s.start <= index);
+        Some(segments.get(i.checked_sub(1)?)?.period)
+    }
+
+    /// Time step in ms from the sample at `index - 1` to the one at `index`. This is the detected
+    /// period, so the jitter of the ms timestamps doesn't add noise to rate dependent values,
+    /// unless the step is a gap.
+    pub fn step(segments: &[Self], time: &[u32], index: usize) -> f64 {
+        let delta = time[index].saturating_sub(time[index - 1]) as f64;
+        match Self::period_at(segments, index - 1) {
+            Some(period) if delta <= period * GAP_FACTOR => period,
+            _ => delta,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Version {
     V1,
     V2,
+    V3,
+    /// with CRC-32 checksums
+    V4,
 }
 
 impl fmt::Display for Version {
@@ -63,14 +226,120 @@ impl fmt::Display for Version {
         match self {
             Version::V1 => write!(f, "v1"),
             Version::V2 => write!(f, "v2"),
+            Version::V3 => write!(f, "v3"),
+            Version::V4 => write!(f, "v4"),
         }
     }
 }
 
-#[derive(Debug)]
+/// Format of the file a [`LogStream`] was read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    S3lg(Version),
+    /// MoTeC `.ld` file of the previous data logger
+    Motec,
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogFormat::S3lg(version) => write!(f, "{version}"),
+            LogFormat::Motec => write!(f, "MoTeC"),
+        }
+    }
+}
+
+/// Byte order of the numbers in an s3lg file, the newer firmware writes little endian.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endian {
+    #[default]
+    Big,
+    Little,
+}
+
+impl fmt::Display for Endian {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endian::Big => write!(f, "big endian"),
+            Endian::Little => write!(f, "little endian"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct DataEntry {
     pub name: String,
+    /// empty if the file doesn't specify one
+    pub unit: String,
+    /// physical value = raw value * scale + offset
+    pub scale: f64,
+    pub offset: f64,
     pub kind: EntryKind,
+    /// names of the codes of an [`EntryKind::Enum`], empty for other kinds
+    pub labels: Vec<String>,
+}
+
+/// Name of the code of an enum channel, `None` if the value isn't a known code.
+pub fn enum_label(labels: &[String], value: f64) -> Option<&str> {
+    let code = (value.fract() == 0.0 && value >= 0.0).then_some(value as usize)?;
+    labels.get(code).map(String::as_str)
+}
+
+impl DataEntry {
+    /// Physical value of the sample at `index`, with the scale and offset applied.
+    pub fn get_f64(&self, index: usize) -> f64 {
+        let raw = self.kind.get_f64(index);
+        if self.scale == 1.0 && self.offset == 0.0 {
+            raw
+        } else {
+            raw * self.scale + self.offset
+        }
+    }
+
+    pub fn unit(&self) -> Option<&str> {
+        (!self.unit.is_empty()).then_some(self.unit.as_str())
+    }
+
+    /// Whether all of the first `len` values are equal.
+    pub fn is_constant(&self, len: usize) -> bool {
+        if len == 0 {
+            return true;
+        }
+        let first = self.kind.get_f64(0);
+        (1..len).all(|i| self.kind.get_f64(i) == first)
+    }
+
+    /// Low resolution preview of the values, made up of the averages of `points` equally sized
+    /// chunks, normalized to `0.0..=1.0`.
+    pub fn sparkline(&self, len: usize, points: usize) -> Vec<f32> {
+        if len == 0 || points == 0 {
+            return Vec::new();
+        }
+
+        let chunk_size = len.div_ceil(points);
+        let mut values = Vec::with_capacity(points);
+        let mut start = 0;
+        while start < len {
+            let end = (start + chunk_size).min(len);
+            let sum = (start..end).map(|i| self.kind.get_f64(i)).sum::<f64>();
+            values.push(sum / (end - start) as f64);
+            start = end;
+        }
+
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let range = max - min;
+        values
+            .into_iter()
+            .map(|v| {
+                if range > 0.0 && range.is_finite() {
+                    ((v - min) / range) as f32
+                } else {
+                    0.5
+                }
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -89,9 +358,29 @@ pub enum EntryKind {
 
     F32(Vec<f32>),
     F64(Vec<f64>),
+
+    /// codes of the labels in [`DataEntry::labels`], e.g. states of a state machine
+    Enum(Vec<u8>),
 }
 
 impl EntryKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntryKind::Bool(_) => "bool",
+            EntryKind::U8(_) => "u8",
+            EntryKind::U16(_) => "u16",
+            EntryKind::U32(_) => "u32",
+            EntryKind::U64(_) => "u64",
+            EntryKind::I8(_) => "i8",
+            EntryKind::I16(_) => "i16",
+            EntryKind::I32(_) => "i32",
+            EntryKind::I64(_) => "i64",
+            EntryKind::F32(_) => "f32",
+            EntryKind::F64(_) => "f64",
+            EntryKind::Enum(_) => "enum",
+        }
+    }
+
     pub fn reserve(&mut self, additional: usize) {
         match self {
             EntryKind::Bool(v) => v.reserve(additional),
@@ -105,6 +394,7 @@ impl EntryKind {
             EntryKind::I64(v) => v.reserve(additional),
             EntryKind::F32(v) => v.reserve(additional),
             EntryKind::F64(v) => v.reserve(additional),
+            EntryKind::Enum(v) => v.reserve(additional),
         }
     }
 
@@ -122,9 +412,46 @@ impl EntryKind {
                 | (EntryKind::I64(_), EntryKind::I64(_))
                 | (EntryKind::F32(_), EntryKind::F32(_))
                 | (EntryKind::F64(_), EntryKind::F64(_))
+                | (EntryKind::Enum(_), EntryKind::Enum(_))
         )
     }
 
+    /// Empty values of the same kind, with space for `capacity` samples.
+    pub fn empty(&self, capacity: usize) -> Self {
+        match self {
+            EntryKind::Bool(_) => EntryKind::Bool(Vec::with_capacity(capacity)),
+            EntryKind::U8(_) => EntryKind::U8(Vec::with_capacity(capacity)),
+            EntryKind::U16(_) => EntryKind::U16(Vec::with_capacity(capacity)),
+            EntryKind::U32(_) => EntryKind::U32(Vec::with_capacity(capacity)),
+            EntryKind::U64(_) => EntryKind::U64(Vec::with_capacity(capacity)),
+            EntryKind::I8(_) => EntryKind::I8(Vec::with_capacity(capacity)),
+            EntryKind::I16(_) => EntryKind::I16(Vec::with_capacity(capacity)),
+            EntryKind::I32(_) => EntryKind::I32(Vec::with_capacity(capacity)),
+            EntryKind::I64(_) => EntryKind::I64(Vec::with_capacity(capacity)),
+            EntryKind::F32(_) => EntryKind::F32(Vec::with_capacity(capacity)),
+            EntryKind::F64(_) => EntryKind::F64(Vec::with_capacity(capacity)),
+            EntryKind::Enum(_) => EntryKind::Enum(Vec::with_capacity(capacity)),
+        }
+    }
+
+    pub fn extend_range(&mut self, other: &Self, range: Range<usize>) {
+        match (self, other) {
+            (EntryKind::Bool(a), EntryKind::Bool(b)) => a.extend_from_slice(&b[range]),
+            (EntryKind::U8(a), EntryKind::U8(b)) => a.extend_from_slice(&b[range]),
+            (EntryKind::U16(a), EntryKind::U16(b)) => a.extend_from_slice(&b[range]),
+            (EntryKind::U32(a), EntryKind::U32(b)) => a.extend_from_slice(&b[range]),
+            (EntryKind::U64(a), EntryKind::U64(b)) => a.extend_from_slice(&b[range]),
+            (EntryKind::I8(a), EntryKind::I8(b)) => a.extend_from_slice(&b[range]),
+            (EntryKind::I16(a), EntryKind::I16(b)) => a.extend_from_slice(&b[range]),
+            (EntryKind::I32(a), EntryKind::I32(b)) => a.extend_from_slice(&b[range]),
+            (EntryKind::I64(a), EntryKind::I64(b)) => a.extend_from_slice(&b[range]),
+            (EntryKind::F32(a), EntryKind::F32(b)) => a.extend_from_slice(&b[range]),
+            (EntryKind::F64(a), EntryKind::F64(b)) => a.extend_from_slice(&b[range]),
+            (EntryKind::Enum(a), EntryKind::Enum(b)) => a.extend_from_slice(&b[range]),
+            _ => (),
+        }
+    }
+
     pub fn extend(&mut self, other: &Self) {
         match (self, other) {
             (EntryKind::Bool(a), EntryKind::Bool(b)) => a.extend_from_slice(b),
@@ -138,6 +465,7 @@ impl EntryKind {
             (EntryKind::I64(a), EntryKind::I64(b)) => a.extend_from_slice(b),
             (EntryKind::F32(a), EntryKind::F32(b)) => a.extend_from_slice(b),
             (EntryKind::F64(a), EntryKind::F64(b)) => a.extend_from_slice(b),
+            (EntryKind::Enum(a), EntryKind::Enum(b)) => a.extend_from_slice(b),
             _ => (),
         }
     }
@@ -155,6 +483,7 @@ impl EntryKind {
             EntryKind::I64(v) => v[index] as f64,
             EntryKind::F32(v) => v[index] as f64,
             EntryKind::F64(v) => v[index],
+            EntryKind::Enum(v) => v[index] as f64,
         }
     }
 }
@@ -167,6 +496,7 @@ pub enum Error {
     UnknownVersion(u16),
     UnknownDatatype(u8),
     InvalidTimestamp(i64),
+    Motec(String),
 }
 
 impl std::error::Error for Error {}
@@ -183,6 +513,7 @@ impl fmt::Display for Error {
             Self::UnknownVersion(version) => write!(f, "Unknown version: {version}"),
             Self::UnknownDatatype(code) => write!(f, "Unknown datatype code: {code}"),
             Self::InvalidTimestamp(timestamp) => write!(f, "Invalid unix timestamp: {timestamp}"),
+            Self::Motec(error) => write!(f, "Invalid MoTeC file: {error}"),
         }
     }
 }
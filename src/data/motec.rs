@@ -0,0 +1,308 @@
+use chrono::NaiveDateTime;
+
+use super::{DataEntry, EntryKind, Error, LogFormat, LogStream};
+
+const LD_MARKER: u32 = 0x40;
+const LD_DATE_TIME_FORMAT: &str = "%d/%m/%Y %H:%M:%S";
+const CHANNEL_META_SIZE: usize = 124;
+
+/// Parses a MoTeC `.ld` file from memory. The channels are logged at individual frequencies, so
+/// they are resampled to the highest one, holding the last value. Channels with an unknown
+/// datatype are skipped.
+pub fn parse_motec(bytes: &[u8]) -> Result<LogStream, Error> {
+    let marker = le_u32(bytes, 0)?;
+    if marker != LD_MARKER {
+        return Err(Error::Motec(format!("invalid marker: {marker:#x}")));
+    }
+    let mut meta_ptr = le_u32(bytes, 8)? as usize;
+    let date = le_string(bytes, 94, 16)?;
+    let time = le_string(bytes, 126, 16)?;
+    let start = NaiveDateTime::parse_from_str(&format!("{date} {time}"), LD_DATE_TIME_FORMAT).ok();
+
+    let mut channels = Vec::new();
+    // the channels are a linked list, limit the length in case it is cyclic
+    while meta_ptr != 0 && channels.len() < bytes.len() / CHANNEL_META_SIZE {
+        let channel = parse_channel(bytes, meta_ptr)?;
+        meta_ptr = le_u32(bytes, meta_ptr + 4)? as usize;
+        channels.extend(channel);
+    }
+
+    let max_freq = channels.iter().map(|c| c.freq).max().unwrap_or(1) as u64;
+    let num_samples = (channels.iter())
+        .map(|c| (c.kind.len() as u64 * max_freq).div_ceil(c.freq as u64))
+        .max()
+        .unwrap_or(0);
+    // a sample of the fastest channel takes at least 2 bytes, more samples than bytes means the
+    // length or frequency of a channel is corrupted
+    if num_samples > bytes.len() as u64 {
+        return Err(Error::Motec(format!(
+            "{num_samples} samples at {max_freq} Hz don't fit into the file"
+        )));
+    }
+    let time = (0..num_samples)
+        .map(|i| (i * 1000 / max_freq) as u32)
+        .collect::<Vec<_>>();
+
+    let entries = (channels.into_iter())
+        .map(|c| DataEntry {
+            name: c.name,
+            unit: c.unit,
+            scale: c.scale,
+            offset: c.offset,
+            kind: c.kind.resample(c.freq as u64, &time),
+            labels: Vec::new(),
+        })
+        .collect();
+
+    Ok(LogStream {
+        format: LogFormat::Motec,
+        start,
+        time,
+        entries,
+        sources: Vec::new(),
+    })
+}
+
+struct Channel {
+    name: String,
+    unit: String,
+    /// in Hz
+    freq: u16,
+    scale: f64,
+    offset: f64,
+    kind: EntryKind,
+}
+
+/// Returns `None` if the datatype or frequency isn't supported.
+fn parse_channel(bytes: &[u8], ptr: usize) -> Result<Option<Channel>, Error> {
+    let data_ptr = le_u32(bytes, ptr + 8)? as usize;
+    let len = le_u32(bytes, ptr + 12)? as usize;
+    let type_class = le_u16(bytes, ptr + 18)?;
+    let type_size = le_u16(bytes, ptr + 20)?;
+    let freq = le_u16(bytes, ptr + 22)?;
+    let shift = le_u16(bytes, ptr + 24)? as i16 as f64;
+    let mul = le_u16(bytes, ptr + 26)? as i16 as f64;
+    let scale = le_u16(bytes, ptr + 28)? as i16 as f64;
+    let dec = le_u16(bytes, ptr + 30)? as i16;
+    let name = le_string(bytes, ptr + 32, 32)?;
+    let unit = le_string(bytes, ptr + 72, 12)?;
+
+    // channels without samples would be shorter than the time column after resampling
+    if freq == 0 || len == 0 {
+        return Ok(None);
+    }
+    let data = (bytes.get(data_ptr..))
+        .and_then(|d| d.get(..len * type_size as usize))
+        .ok_or_else(|| Error::Motec(format!("data of channel `{name}` is out of bounds")))?;
+
+    macro_rules! le {
+        ($ty:ty) => {
+            (data.chunks_exact(std::mem::size_of::<$ty>()))
+                .map(|b| <$ty>::from_le_bytes(b.try_into().unwrap()))
+                .collect::<Vec<_>>()
+        };
+    }
+    let kind = match (type_class, type_size) {
+        (0 | 3 | 5, 2) => EntryKind::I16(le!(i16)),
+        (0 | 3 | 5, 4) => EntryKind::I32(le!(i32)),
+        (7, 2) => EntryKind::F32(le!(u16).into_iter().map(f16_to_f32).collect()),
+        (7, 4) => EntryKind::F32(le!(f32)),
+        _ => return Ok(None),
+    };
+
+    // physical value = (raw / scale * 10^-dec + shift) * mul
+    let scale = if scale == 0.0 { 1.0 } else { scale };
+    let mul = if mul == 0.0 { 1.0 } else { mul };
+    Ok(Some(Channel {
+        name: channel_name(&name),
+        unit,
+        freq,
+        scale: mul / (scale * 10f64.powi(dec as i32)),
+        offset: shift * mul,
+        kind,
+    }))
+}
+
+/// Channel names are used as identifiers in expressions, e.g. `Engine RPM` becomes `Engine_RPM`.
+fn channel_name(name: &str) -> String {
+    (name.chars())
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exp = ((bits >> 10) & 0x1f) as i32;
+    let frac = (bits & 0x3ff) as f32;
+    match exp {
+        0 => sign * frac * 2f32.powi(-24),
+        0x1f if frac == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + frac / 1024.0) * 2f32.powi(exp - 15),
+    }
+}
+
+impl EntryKind {
+    fn len(&self) -> usize {
+        match self {
+            EntryKind::Bool(v) => v.len(),
+            EntryKind::U8(v) => v.len(),
+            EntryKind::U16(v) => v.len(),
+            EntryKind::U32(v) => v.len(),
+            EntryKind::U64(v) => v.len(),
+            EntryKind::I8(v) => v.len(),
+            EntryKind::I16(v) => v.len(),
+            EntryKind::I32(v) => v.len(),
+            EntryKind::I64(v) => v.len(),
+            EntryKind::F32(v) => v.len(),
+            EntryKind::F64(v) => v.len(),
+            EntryKind::Enum(v) => v.len(),
+        }
+    }
+
+    /// Samples values logged at `freq` at the times in ms, holding the last value.
+    fn resample(self, freq: u64, time: &[u32]) -> Self {
+        fn resample<T: Copy>(values: Vec<T>, freq: u64, time: &[u32]) -> Vec<T> {
+            let Some(last) = values.len().checked_sub(1) else {
+                return Vec::new();
+            };
+            (time.iter())
+                .map(|&t| values[((t as u64 * freq / 1000) as usize).min(last)])
+                .collect()
+        }
+        match self {
+            EntryKind::Bool(v) => EntryKind::Bool(resample(v, freq, time)),
+            EntryKind::U8(v) => EntryKind::U8(resample(v, freq, time)),
+            EntryKind::U16(v) => EntryKind::U16(resample(v, freq, time)),
+            EntryKind::U32(v) => EntryKind::U32(resample(v, freq, time)),
+            EntryKind::U64(v) => EntryKind::U64(resample(v, freq, time)),
+            EntryKind::I8(v) => EntryKind::I8(resample(v, freq, time)),
+            EntryKind::I16(v) => EntryKind::I16(resample(v, freq, time)),
+            EntryKind::I32(v) => EntryKind::I32(resample(v, freq, time)),
+            EntryKind::I64(v) => EntryKind::I64(resample(v, freq, time)),
+            EntryKind::F32(v) => EntryKind::F32(resample(v, freq, time)),
+            EntryKind::F64(v) => EntryKind::F64(resample(v, freq, time)),
+            EntryKind::Enum(v) => EntryKind::Enum(resample(v, freq, time)),
+        }
+    }
+}
+
+fn field(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8], Error> {
+    (bytes.get(offset..))
+        .and_then(|b| b.get(..len))
+        .ok_or_else(|| Error::Motec(format!("unexpected end of file at {offset:#x}")))
+}
+
+fn le_u16(bytes: &[u8], offset: usize) -> Result<u16, Error> {
+    Ok(u16::from_le_bytes(
+        field(bytes, offset, 2)?.try_into().unwrap(),
+    ))
+}
+
+fn le_u32(bytes: &[u8], offset: usize) -> Result<u32, Error> {
+    Ok(u32::from_le_bytes(
+        field(bytes, offset, 4)?.try_into().unwrap(),
+    ))
+}
+
+/// Zero terminated string of a fixed size field.
+fn le_string(bytes: &[u8], offset: usize, len: usize) -> Result<String, Error> {
+    let bytes = field(bytes, offset, len)?;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
+    Ok(String::from_utf8_lossy(&bytes[..end]).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST_CHANNEL: usize = 0x100;
+    const DATA: usize = FIRST_CHANNEL + 2 * CHANNEL_META_SIZE;
+
+    fn put(bytes: &mut [u8], offset: usize, data: &[u8]) {
+        bytes[offset..offset + data.len()].copy_from_slice(data);
+    }
+
+    /// Minimal `.ld` file with two `i16` channels, each given by its name, frequency and values.
+    fn ld_file(channels: [(&str, u16, &[i16]); 2]) -> Vec<u8> {
+        let data_len = channels.iter().map(|(_, _, v)| v.len() * 2).sum::<usize>();
+        let mut bytes = vec![0; DATA + data_len];
+        put(&mut bytes, 0, &LD_MARKER.to_le_bytes());
+        put(&mut bytes, 8, &(FIRST_CHANNEL as u32).to_le_bytes());
+        put(&mut bytes, 94, b"16/10/2026");
+        put(&mut bytes, 126, b"12:30:00");
+
+        let mut data_ptr = DATA;
+        for (i, (name, freq, values)) in channels.into_iter().enumerate() {
+            let ptr = FIRST_CHANNEL + i * CHANNEL_META_SIZE;
+            let next = match i {
+                0 => ptr + CHANNEL_META_SIZE,
+                _ => 0,
+            };
+            put(&mut bytes, ptr + 4, &(next as u32).to_le_bytes());
+            put(&mut bytes, ptr + 8, &(data_ptr as u32).to_le_bytes());
+            put(&mut bytes, ptr + 12, &(values.len() as u32).to_le_bytes());
+            put(&mut bytes, ptr + 20, &2u16.to_le_bytes());
+            put(&mut bytes, ptr + 22, &freq.to_le_bytes());
+            put(&mut bytes, ptr + 26, &1u16.to_le_bytes());
+            put(&mut bytes, ptr + 28, &1u16.to_le_bytes());
+            put(&mut bytes, ptr + 32, name.as_bytes());
+            for v in values {
+                put(&mut bytes, data_ptr, &v.to_le_bytes());
+                data_ptr += 2;
+            }
+        }
+        bytes
+    }
+
+    fn values(entry: &DataEntry) -> &[i16] {
+        match &entry.kind {
+            EntryKind::I16(v) => v,
+            _ => panic!("expected i16 entry"),
+        }
+    }
+
+    #[test]
+    fn minimal_ld() {
+        let bytes = ld_file([("Engine RPM", 10, &[1, 2, 3, 4]), ("Gear", 5, &[7, 8])]);
+        let stream = parse_motec(&bytes).unwrap();
+
+        assert_eq!(stream.format, LogFormat::Motec);
+        assert_eq!(stream.start.unwrap().to_string(), "2026-10-16 12:30:00");
+        assert_eq!(stream.time, [0, 100, 200, 300]);
+        assert_eq!(stream.entries[0].name, "Engine_RPM");
+        assert_eq!(values(&stream.entries[0]), [1, 2, 3, 4]);
+        assert_eq!(stream.entries[1].name, "Gear");
+        assert_eq!(values(&stream.entries[1]), [7, 7, 8, 8]);
+    }
+
+    #[test]
+    fn empty_channel() {
+        let bytes = ld_file([("Engine RPM", 10, &[1, 2, 3]), ("Gear", 10, &[])]);
+        let stream = parse_motec(&bytes).unwrap();
+
+        assert_eq!(stream.time, [0, 100, 200]);
+        assert_eq!(stream.entries.len(), 1);
+        assert_eq!(stream.entries[0].name, "Engine_RPM");
+    }
+
+    #[test]
+    fn invalid_marker() {
+        let mut bytes = ld_file([("a", 10, &[1]), ("b", 10, &[2])]);
+        bytes[0] = 0;
+        assert!(parse_motec(&bytes).is_err());
+    }
+
+    #[test]
+    fn data_out_of_bounds() {
+        let mut bytes = ld_file([("a", 10, &[1]), ("b", 10, &[2])]);
+        bytes.truncate(DATA + 2);
+        assert!(parse_motec(&bytes).is_err());
+    }
+
+    #[test]
+    fn too_many_samples() {
+        let bytes = ld_file([("fast", u16::MAX, &[1]), ("slow", 1, &[1, 2, 3])]);
+        assert!(parse_motec(&bytes).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use std::io::{Cursor, Read};
+
+use super::read::{
+    block_has_crc, read_f64, read_i64, read_string, read_u16, read_u32, read_u8, read_version,
+    sample_layout, CRC_SIZE,
+};
+use super::{Endian, EntryKind, Error};
+
+/// Undecoded file contents with the byte layout of the header and the samples.
+#[derive(Debug)]
+pub struct RawFile {
+    pub bytes: Vec<u8>,
+    pub header: Vec<RawField>,
+    /// fields of a single sample, offsets are relative to the start of the sample
+    pub sample_fields: Vec<RawField>,
+    pub data_offset: usize,
+    pub sample_size: usize,
+    /// samples per CRC block, 0 for files without checksums
+    pub block_len: usize,
+    pub endian: Endian,
+    /// error that stopped decoding the header
+    pub error: Option<Error>,
+}
+
+#[derive(Debug)]
+pub struct RawField {
+    pub name: String,
+    pub offset: usize,
+    pub len: usize,
+    /// bit of a packed bool
+    pub bit: Option<u8>,
+    pub kind: Option<EntryKind>,
+}
+
+impl RawField {
+    fn new(name: impl Into<String>, offset: usize, len: usize) -> Self {
+        Self {
+            name: name.into(),
+            offset,
+            len,
+            bit: None,
+            kind: None,
+        }
+    }
+
+    fn with_kind(mut self, kind: EntryKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Formats the value of this field, `bytes` has to start at the offset of the field.
+    pub fn format_value(&self, bytes: &[u8], endian: Endian) -> String {
+        let Some(bytes) = bytes.get(..self.len) else {
+            return "<eof>".into();
+        };
+        let Some(kind) = &self.kind else {
+            return match std::str::from_utf8(bytes) {
+                Ok(s) => format!("{s:?}"),
+                Err(_) => format!("{bytes:02x?}"),
+            };
+        };
+
+        macro_rules! num {
+            ($ty:ty) => {{
+                let mut buf = [0; std::mem::size_of::<$ty>()];
+                buf.copy_from_slice(bytes);
+                match endian {
+                    Endian::Big => <$ty>::from_be_bytes(buf).to_string(),
+                    Endian::Little => <$ty>::from_le_bytes(buf).to_string(),
+                }
+            }};
+        }
+        match kind {
+            EntryKind::Bool(_) => {
+                let bit = self.bit.unwrap_or(0);
+                (bytes[0] & (1 << bit) != 0).to_string()
+            }
+            EntryKind::U8(_) | EntryKind::Enum(_) => num!(u8),
+            EntryKind::U16(_) => num!(u16),
+            EntryKind::U32(_) => num!(u32),
+            EntryKind::U64(_) => num!(u64),
+            EntryKind::I8(_) => num!(i8),
+            EntryKind::I16(_) => num!(i16),
+            EntryKind::I32(_) => num!(i32),
+            EntryKind::I64(_) => num!(i64),
+            EntryKind::F32(_) => num!(f32),
+            EntryKind::F64(_) => num!(f64),
+        }
+    }
+}
+
+impl RawFile {
+    pub fn num_samples(&self) -> usize {
+        self.sample_count().0
+    }
+
+    /// Bytes after the last complete sample.
+    pub fn trailing_bytes(&self) -> usize {
+        self.sample_count().1
+    }
+
+    /// Mirrors the block splitting of [`super::parse_file`].
+    fn sample_count(&self) -> (usize, usize) {
+        if self.error.is_some() || self.sample_size == 0 {
+            return (0, 0);
+        }
+        let len = self.bytes.len().saturating_sub(self.data_offset);
+        if self.block_len == 0 {
+            return (len / self.sample_size, len % self.sample_size);
+        }
+
+        let block_size = (self.block_len.saturating_mul(self.sample_size)).saturating_add(CRC_SIZE);
+        let full_blocks = len / block_size * self.block_len;
+        let last = len % block_size;
+        match block_has_crc(last, block_size, self.sample_size) {
+            true => (full_blocks + (last - CRC_SIZE) / self.sample_size, 0),
+            false => (
+                full_blocks + last / self.sample_size,
+                last % self.sample_size,
+            ),
+        }
+    }
+
+    /// Offset of the sample in the file, skipping the checksums of the blocks before it.
+    pub fn sample_offset(&self, index: usize) -> usize {
+        let checksums = match self.block_len {
+            0 => 0,
+            block_len => index / block_len * CRC_SIZE,
+        };
+        self.data_offset + index * self.sample_size + checksums
+    }
+
+    pub fn sample(&self, index: usize) -> &[u8] {
+        let start = self.sample_offset(index);
+        &self.bytes[start..start + self.sample_size]
+    }
+}
+
+/// Decodes as much of the header as possible, errors are stored inside the [`RawFile`] so the
+/// already decoded part can still be inspected.
+pub fn read_raw(bytes: Vec<u8>) -> RawFile {
+    let mut header = Vec::new();
+    let mut entries = Vec::new();
+    let mut endian = Endian::Big;
+    let result = decode_header(&bytes, &mut header, &mut entries, &mut endian);
+
+    let (data_offset, block_len, error) = match result {
+        Ok((offset, block_len)) => (offset, block_len, None),
+        Err(e) => {
+            let offset = header.last().map_or(0, |f| f.offset + f.len);
+            (offset, 0, Some(e))
+        }
+    };
+
+    let (layout, sample_size) = sample_layout(entries.iter().map(|(_, kind)| kind));
+    let mut sample_fields = Vec::with_capacity(entries.len() + 1);
+    sample_fields.push(RawField::new("time", 0, 4).with_kind(EntryKind::U32(Vec::new())));
+    for ((name, kind), (offset, bit)) in entries.into_iter().zip(layout) {
+        let mut field = RawField::new(name, offset, kind.size() as usize);
+        field.bit = matches!(kind, EntryKind::Bool(_)).then_some(bit);
+        sample_fields.push(field.with_kind(kind));
+    }
+
+    RawFile {
+        bytes,
+        header,
+        sample_fields,
+        data_offset,
+        sample_size,
+        block_len,
+        endian,
+        error,
+    }
+}
+
+fn decode_header(
+    bytes: &[u8],
+    header: &mut Vec<RawField>,
+    entries: &mut Vec<(String, EntryKind)>,
+    endian: &mut Endian,
+) -> Result<(usize, usize), Error> {
+    let mut reader = Cursor::new(bytes);
+
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    header.push(RawField::new("magic", 0, 4));
+    if &magic != b"s3lg" {
+        return Err(Error::InvalidMagic(magic));
+    }
+
+    let (version, file_endian) = read_version(&mut reader)?;
+    header.push(RawField::new("version", 4, 2).with_kind(EntryKind::U16(Vec::new())));
+    *endian = file_endian;
+    let endian = file_endian;
+
+    let num_entries = read_u16(&mut reader, endian)?;
+    header.push(RawField::new("num entries", 6, 2).with_kind(EntryKind::U16(Vec::new())));
+
+    if version >= 2 {
+        read_i64(&mut reader, endian)?;
+        let field = RawField::new("start timestamp", 8, 8).with_kind(EntryKind::I64(Vec::new()));
+        header.push(field);
+    }
+
+    for i in 0..num_entries {
+        let offset = reader.position() as usize;
+        let code = read_u8(&mut reader)?;
+        let field = RawField::new(format!("entry {i} datatype"), offset, 1);
+        header.push(field.with_kind(EntryKind::U8(Vec::new())));
+        let kind = EntryKind::try_from(code)?;
+
+        let name_len = read_u8(&mut reader)?;
+        let field = RawField::new(format!("entry {i} name length"), offset + 1, 1);
+        header.push(field.with_kind(EntryKind::U8(Vec::new())));
+
+        let name = read_string(&mut reader, name_len as usize)?;
+        header.push(RawField::new(
+            format!("entry {i} name"),
+            offset + 2,
+            name_len as usize,
+        ));
+
+        if version >= 3 {
+            let offset = reader.position() as usize;
+            let unit_len = read_u8(&mut reader)?;
+            let field = RawField::new(format!("entry {i} unit length"), offset, 1);
+            header.push(field.with_kind(EntryKind::U8(Vec::new())));
+
+            read_string(&mut reader, unit_len as usize)?;
+            let field = RawField::new(format!("entry {i} unit"), offset + 1, unit_len as usize);
+            header.push(field);
+
+            let offset = offset + 1 + unit_len as usize;
+            read_f64(&mut reader, endian)?;
+            let field = RawField::new(format!("entry {i} scale"), offset, 8);
+            header.push(field.with_kind(EntryKind::F64(Vec::new())));
+            read_f64(&mut reader, endian)?;
+            let field = RawField::new(format!("entry {i} offset"), offset + 8, 8);
+            header.push(field.with_kind(EntryKind::F64(Vec::new())));
+        }
+
+        if let EntryKind::Enum(_) = kind {
+            let offset = reader.position() as usize;
+            let num_labels = read_u8(&mut reader)?;
+            let field = RawField::new(format!("entry {i} num labels"), offset, 1);
+            header.push(field.with_kind(EntryKind::U8(Vec::new())));
+            for j in 0..num_labels {
+                let offset = reader.position() as usize;
+                let label_len = read_u8(&mut reader)?;
+                let field = RawField::new(format!("entry {i} label {j} length"), offset, 1);
+                header.push(field.with_kind(EntryKind::U8(Vec::new())));
+                read_string(&mut reader, label_len as usize)?;
+                let field = RawField::new(
+                    format!("entry {i} label {j}"),
+                    offset + 1,
+                    label_len as usize,
+                );
+                header.push(field);
+            }
+        }
+
+        entries.push((name, kind));
+    }
+
+    let mut block_len = 0;
+    if version >= 4 {
+        let offset = reader.position() as usize;
+        block_len = read_u32(&mut reader, endian)? as usize;
+        let field = RawField::new("crc block length", offset, 4);
+        header.push(field.with_kind(EntryKind::U32(Vec::new())));
+    }
+
+    Ok((reader.position() as usize, block_len))
+}
@@ -1,11 +1,14 @@
-use std::io::{self, Read, Seek, SeekFrom};
+use std::fmt;
+use std::io::Read;
+use std::ops::Range;
 
 use chrono::DateTime;
 
-use super::{DataEntry, EntryKind, Error, LogStream, Version};
+use super::{DataEntry, Endian, EntryKind, Error, LogFormat, LogStream, Version};
+use crate::util;
 
 impl EntryKind {
-    fn size(&self) -> u8 {
+    pub(super) fn size(&self) -> u8 {
         match self {
             Self::Bool(_) => 1,
             Self::U8(_) => 1,
@@ -18,6 +21,7 @@ impl EntryKind {
             Self::I64(_) => 8,
             Self::F32(_) => 4,
             Self::F64(_) => 8,
+            Self::Enum(_) => 1,
         }
     }
 }
@@ -38,19 +42,104 @@ impl TryFrom<u8> for EntryKind {
             8 => Self::I64(Vec::new()),
             9 => Self::F32(Vec::new()),
             10 => Self::F64(Vec::new()),
+            11 => Self::Enum(Vec::new()),
             _ => return Err(Error::UnknownDatatype(value)),
         };
         Ok(data_type)
     }
 }
 
-struct BoolContext {
-    bit_fields: u8,
-    mask: u8,
+/// Size of the CRC-32 after each block of samples in v4 files.
+pub(super) const CRC_SIZE: usize = 4;
+/// Latest s3lg version.
+const MAX_VERSION: u16 = 4;
+/// CRC-32 (IEEE) lookup table of the reflected polynomial.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Problems of a file that could be read anyway.
+#[derive(Debug, Default)]
+pub struct ReadWarnings {
+    pub truncated: Option<Truncated>,
+    pub corrupted: Vec<CorruptedBlock>,
+}
+
+impl ReadWarnings {
+    pub fn is_empty(&self) -> bool {
+        self.truncated.is_none() && self.corrupted.is_empty()
+    }
 }
 
-pub fn read_file(reader: &mut (impl Read + Seek)) -> Result<LogStream, Error> {
-    let stream_len = reader.len()?;
+/// The end of a file was cut off, e.g. because the logger lost power.
+#[derive(Debug)]
+pub struct Truncated {
+    /// complete samples before the partial one
+    pub recovered: usize,
+    /// size of the partial sample
+    pub trailing_bytes: usize,
+}
+
+impl fmt::Display for Truncated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "file is truncated, recovered {} samples", self.recovered)?;
+        if self.trailing_bytes > 0 {
+            write!(
+                f,
+                ", dropped a partial sample of {} bytes",
+                self.trailing_bytes
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Block of samples whose checksum didn't match, the samples are left out of the stream.
+#[derive(Debug)]
+pub struct CorruptedBlock {
+    /// indices of the samples in the file
+    pub samples: Range<usize>,
+    /// time in ms of the last valid sample before the block
+    pub after: Option<u32>,
+}
+
+impl fmt::Display for CorruptedBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Range { start, end } = self.samples;
+        write!(f, "samples {start}..{end} failed the CRC check")?;
+        if let Some(after) = self.after {
+            write!(f, " after {}", util::format_time(after as f64 / 1000.0))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a file from memory. The samples are decoded record by record from the byte slice,
+/// using the sample layout computed from the header. A partial sample at the end of the file is
+/// dropped and reported as [`Truncated`].
+///
+/// Since v4 the samples are split into blocks of a length given at the end of the header, each
+/// followed by the big endian CRC-32 of its bytes. A block length larger than the number of
+/// samples checks the whole file at once. Blocks with a wrong checksum are reported as
+/// [`CorruptedBlock`]s and masked, which shows up as a gap in the plots.
+pub fn parse_file(bytes: &[u8]) -> Result<(LogStream, ReadWarnings), Error> {
+    let mut reader = bytes;
 
     let mut magic = [0; 4];
     reader.read_exact(&mut magic)?;
@@ -58,18 +147,21 @@ pub fn read_file(reader: &mut (impl Read + Seek)) -> Result<LogStream, Error> {
         return Err(Error::InvalidMagic(magic));
     }
 
-    let version = match read_u16(reader)? {
+    let (version, endian) = read_version(&mut reader)?;
+    let version = match version {
         1 => Version::V1,
         2 => Version::V2,
+        3 => Version::V3,
+        4 => Version::V4,
         v => return Err(Error::UnknownVersion(v)),
     };
 
-    let num_entries = read_u16(reader)?;
+    let num_entries = read_u16(&mut reader, endian)?;
 
     let start = match version {
         Version::V1 => None,
-        Version::V2 => {
-            let unix_timestamp = read_i64(reader)?;
+        Version::V2 | Version::V3 | Version::V4 => {
+            let unix_timestamp = read_i64(&mut reader, endian)?;
             let date_time = DateTime::from_timestamp(unix_timestamp, 0)
                 .ok_or(Error::InvalidTimestamp(unix_timestamp))?
                 .naive_utc();
@@ -78,120 +170,327 @@ pub fn read_file(reader: &mut (impl Read + Seek)) -> Result<LogStream, Error> {
     };
 
     let mut log_file = LogStream {
-        version,
+        format: LogFormat::S3lg(version),
         start,
         time: Vec::new(),
         entries: Vec::with_capacity(num_entries as usize),
+        sources: Vec::new(),
     };
 
-    let mut pos: u64 = 8;
     for _ in 0..num_entries {
-        let code = read_u8(reader)?;
+        let code = read_u8(&mut reader)?;
         let kind = EntryKind::try_from(code)?;
-        let name_len = read_u8(reader)?;
-        let name = read_string(reader, name_len as usize)?;
+        let name_len = read_u8(&mut reader)?;
+        let name = read_string(&mut reader, name_len as usize)?;
         let name = name.replace('.', "_");
 
-        log_file.entries.push(DataEntry { name, kind });
+        let (unit, scale, offset) = match version {
+            Version::V1 | Version::V2 => (String::new(), 1.0, 0.0),
+            Version::V3 | Version::V4 => {
+                let unit_len = read_u8(&mut reader)?;
+                let unit = read_string(&mut reader, unit_len as usize)?;
+                let scale = read_f64(&mut reader, endian)?;
+                let offset = read_f64(&mut reader, endian)?;
+                (unit, scale, offset)
+            }
+        };
+
+        // enum entries are followed by the names of their codes
+        let mut labels = Vec::new();
+        if let EntryKind::Enum(_) = kind {
+            let num_labels = read_u8(&mut reader)?;
+            for _ in 0..num_labels {
+                let label_len = read_u8(&mut reader)?;
+                labels.push(read_string(&mut reader, label_len as usize)?);
+            }
+        }
 
-        pos += 2 + name_len as u64;
+        log_file.entries.push(DataEntry {
+            name,
+            unit,
+            scale,
+            offset,
+            kind,
+            labels,
+        });
     }
 
-    // preallocate data arrays
-    let mut data_entry_size = 4;
-    for e in log_file.entries.iter() {
-        data_entry_size += e.kind.size() as u64;
-    }
-    let num_data_entries = (stream_len - pos) / data_entry_size;
-    log_file.time.reserve(num_data_entries as usize);
-    for e in log_file.entries.iter_mut() {
-        e.kind.reserve(num_data_entries as usize);
+    let block_len = match version {
+        Version::V4 => read_u32(&mut reader, endian)? as usize,
+        _ => 0,
+    };
+
+    let (layout, sample_size) = sample_layout(log_file.entries.iter().map(|e| &e.kind));
+    log_file.reserve(reader.len() / sample_size);
+    let mut warnings = ReadWarnings::default();
+    if block_len == 0 {
+        let samples = reader.chunks_exact(sample_size);
+        warnings.truncated =
+            Some(samples.remainder().len())
+                .filter(|len| *len > 0)
+                .map(|trailing_bytes| Truncated {
+                    recovered: samples.len(),
+                    trailing_bytes,
+                });
+        decode_samples(&mut log_file, &layout, endian, samples);
+        return Ok((log_file, warnings));
     }
 
-    let mut bool_ctx = None;
-    for _ in 0..num_data_entries {
-        log_file.time.push(read_u32(reader)?);
+    let block_size = block_len
+        .saturating_mul(sample_size)
+        .saturating_add(CRC_SIZE);
+    let mut index = 0;
+    for block in reader.chunks(block_size) {
+        if !block_has_crc(block.len(), block_size, sample_size) {
+            let samples = block.chunks_exact(sample_size);
+            warnings.truncated = Some(Truncated {
+                recovered: log_file.len() + samples.len(),
+                trailing_bytes: samples.remainder().len(),
+            });
+            decode_samples(&mut log_file, &layout, endian, samples);
+            break;
+        }
 
-        for e in log_file.entries.iter_mut() {
-            let mut is_bool_entry = false;
+        let (samples, crc) = block.split_at(block.len() - CRC_SIZE);
+        let count = samples.len() / sample_size;
+        let crc = crc.try_into().unwrap();
+        let crc = match endian {
+            Endian::Big => u32::from_be_bytes(crc),
+            Endian::Little => u32::from_le_bytes(crc),
+        };
+        if crc32(samples) == crc {
+            let samples = samples.chunks_exact(sample_size);
+            decode_samples(&mut log_file, &layout, endian, samples);
+        } else {
+            warnings.corrupted.push(CorruptedBlock {
+                samples: index..index + count,
+                after: log_file.time.last().copied(),
+            });
+        }
+        index += count;
+    }
 
-            match &mut e.kind {
-                EntryKind::Bool(v) => {
-                    let ctx = match &mut bool_ctx {
-                        Some(ctx) => ctx,
-                        None => {
-                            let bit_fields = read_u8(reader)?;
-                            bool_ctx.insert(BoolContext {
-                                bit_fields,
-                                mask: 1,
-                            })
-                        }
-                    };
-
-                    let masked = ctx.bit_fields & ctx.mask;
-                    v.push(masked != 0);
-
-                    if ctx.mask >= 0x80 {
-                        bool_ctx = None;
-                    } else {
-                        ctx.mask <<= 1;
-                    }
+    Ok((log_file, warnings))
+}
 
-                    is_bool_entry = true;
-                }
-                EntryKind::U8(v) => v.push(read_u8(reader)?),
-                EntryKind::U16(v) => v.push(read_u16(reader)?),
-                EntryKind::U32(v) => v.push(read_u32(reader)?),
-                EntryKind::U64(v) => v.push(read_u64(reader)?),
-                EntryKind::I8(v) => v.push(read_i8(reader)?),
-                EntryKind::I16(v) => v.push(read_i16(reader)?),
-                EntryKind::I32(v) => v.push(read_i32(reader)?),
-                EntryKind::I64(v) => v.push(read_i64(reader)?),
-                EntryKind::F32(v) => v.push(read_f32(reader)?),
-                EntryKind::F64(v) => v.push(read_f64(reader)?),
+fn decode_samples<'a>(
+    log_file: &mut LogStream,
+    layout: &[(usize, u8)],
+    endian: Endian,
+    samples: impl Iterator<Item = &'a [u8]>,
+) {
+    macro_rules! num {
+        ($ty:ty, $bytes:expr) => {{
+            let bytes = $bytes[..std::mem::size_of::<$ty>()].try_into().unwrap();
+            match endian {
+                Endian::Big => <$ty>::from_be_bytes(bytes),
+                Endian::Little => <$ty>::from_le_bytes(bytes),
             }
+        }};
+    }
+    for sample in samples {
+        log_file.time.push(num!(u32, sample));
 
-            if !is_bool_entry {
-                bool_ctx = None;
+        for (e, &(offset, bit)) in log_file.entries.iter_mut().zip(layout.iter()) {
+            let bytes = &sample[offset..];
+            match &mut e.kind {
+                EntryKind::Bool(v) => v.push(bytes[0] & (1 << bit) != 0),
+                EntryKind::U8(v) => v.push(bytes[0]),
+                EntryKind::U16(v) => v.push(num!(u16, bytes)),
+                EntryKind::U32(v) => v.push(num!(u32, bytes)),
+                EntryKind::U64(v) => v.push(num!(u64, bytes)),
+                EntryKind::I8(v) => v.push(bytes[0] as i8),
+                EntryKind::I16(v) => v.push(num!(i16, bytes)),
+                EntryKind::I32(v) => v.push(num!(i32, bytes)),
+                EntryKind::I64(v) => v.push(num!(i64, bytes)),
+                EntryKind::F32(v) => v.push(num!(f32, bytes)),
+                EntryKind::F64(v) => v.push(num!(f64, bytes)),
+                EntryKind::Enum(v) => v.push(bytes[0]),
             }
         }
     }
+}
+
+/// Whether a block of `len` bytes ends with a checksum, only the last block can be shorter.
+pub(super) fn block_has_crc(len: usize, block_size: usize, sample_size: usize) -> bool {
+    len == block_size || len > CRC_SIZE && (len - CRC_SIZE).is_multiple_of(sample_size)
+}
 
-    Ok(log_file)
+fn crc32(bytes: &[u8]) -> u32 {
+    let crc = (bytes.iter()).fold(!0u32, |crc, b| {
+        CRC_TABLE[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8)
+    });
+    !crc
 }
 
-impl<T: Seek> SeekUtils for T {}
-pub trait SeekUtils: Seek {
-    fn len(&mut self) -> io::Result<u64> {
-        let pos = self.stream_position()?;
-        let len = self.seek(SeekFrom::End(0))?;
-        self.seek(SeekFrom::Start(pos))?;
-        Ok(len)
+/// Byte offset inside a sample and bit of each entry, and the size of a whole sample. The sample
+/// starts with the `u32` time, consecutive bools are packed into bit fields of up to 8 bits.
+pub(super) fn sample_layout<'a>(
+    kinds: impl ExactSizeIterator<Item = &'a EntryKind>,
+) -> (Vec<(usize, u8)>, usize) {
+    let mut layout = Vec::with_capacity(kinds.len());
+    let mut offset = 4;
+    let mut bit = None;
+    for kind in kinds {
+        match kind {
+            EntryKind::Bool(_) => {
+                let b = match bit {
+                    Some(b) => b,
+                    None => {
+                        offset += 1;
+                        0
+                    }
+                };
+                layout.push((offset - 1, b));
+                bit = if b >= 7 { None } else { Some(b + 1) };
+            }
+            _ => {
+                layout.push((offset, 0));
+                offset += kind.size() as usize;
+                bit = None;
+            }
+        }
+    }
+    (layout, offset)
+}
+
+/// Reads the version, which also determines the byte order of the file. Files of the little
+/// endian firmware start with e.g. `04 00`, which is far beyond any big endian version.
+pub(super) fn read_version(reader: &mut impl Read) -> Result<(u16, Endian), Error> {
+    let mut buf = [0; 2];
+    reader.read_exact(&mut buf)?;
+    let big = u16::from_be_bytes(buf);
+    let little = u16::from_le_bytes(buf);
+    if (1..=MAX_VERSION).contains(&big) {
+        Ok((big, Endian::Big))
+    } else if (1..=MAX_VERSION).contains(&little) {
+        Ok((little, Endian::Little))
+    } else {
+        Err(Error::UnknownVersion(big))
     }
 }
 
+pub(super) fn read_u8(reader: &mut impl Read) -> Result<u8, Error> {
+    let mut buf = [0; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
 macro_rules! impl_read_num {
     ($ident:ident, $ty:ty) => {
-        fn $ident(reader: &mut impl Read) -> Result<$ty, Error> {
+        pub(super) fn $ident(reader: &mut impl Read, endian: Endian) -> Result<$ty, Error> {
             let mut buf = [0; std::mem::size_of::<$ty>()];
             reader.read_exact(&mut buf)?;
-            Ok(<$ty>::from_be_bytes(buf))
+            match endian {
+                Endian::Big => Ok(<$ty>::from_be_bytes(buf)),
+                Endian::Little => Ok(<$ty>::from_le_bytes(buf)),
+            }
         }
     };
 }
-impl_read_num!(read_u8, u8);
 impl_read_num!(read_u16, u16);
 impl_read_num!(read_u32, u32);
-impl_read_num!(read_u64, u64);
-impl_read_num!(read_i8, i8);
-impl_read_num!(read_i16, i16);
-impl_read_num!(read_i32, i32);
 impl_read_num!(read_i64, i64);
-impl_read_num!(read_f32, f32);
 impl_read_num!(read_f64, f64);
 
-fn read_string(reader: &mut impl Read, len: usize) -> Result<String, Error> {
+pub(super) fn read_string(reader: &mut impl Read, len: usize) -> Result<String, Error> {
     let mut buf = vec![0; len];
     reader.read_exact(&mut buf)?;
     Ok(String::from_utf8(buf)?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// v4 file with a single `u16` entry, the samples are split into blocks of `block_len`.
+    fn v4_file(samples: &[(u32, u16)], block_len: usize) -> Vec<u8> {
+        let mut bytes = b"s3lg".to_vec();
+        bytes.extend(4u16.to_be_bytes());
+        bytes.extend(1u16.to_be_bytes());
+        bytes.extend(1_700_000_000i64.to_be_bytes());
+        bytes.extend([2, 3]);
+        bytes.extend(b"rpm");
+        bytes.push(0);
+        bytes.extend(1.0f64.to_be_bytes());
+        bytes.extend(0.0f64.to_be_bytes());
+        bytes.extend((block_len as u32).to_be_bytes());
+
+        for block in samples.chunks(block_len) {
+            let data = (block.iter())
+                .flat_map(|(t, v)| t.to_be_bytes().into_iter().chain(v.to_be_bytes()))
+                .collect::<Vec<_>>();
+            bytes.extend(&data);
+            bytes.extend(crc32(&data).to_be_bytes());
+        }
+        bytes
+    }
+
+    fn samples() -> Vec<(u32, u16)> {
+        (0..5).map(|i| (i * 10, 1000 + i as u16)).collect()
+    }
+
+    fn values(stream: &LogStream) -> &[u16] {
+        match &stream.entries[0].kind {
+            EntryKind::U16(v) => v,
+            _ => panic!("expected u16 entry"),
+        }
+    }
+
+    const HEADER_SIZE: usize = 4 + 2 + 2 + 8 + 2 + 3 + 1 + 8 + 8 + 4;
+    const BLOCK_SIZE: usize = 2 * 6 + CRC_SIZE;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn valid_v4() {
+        let (stream, warnings) = parse_file(&v4_file(&samples(), 2)).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(stream.format, LogFormat::S3lg(Version::V4));
+        assert_eq!(stream.entries[0].name, "rpm");
+        assert_eq!(stream.time, [0, 10, 20, 30, 40]);
+        assert_eq!(values(&stream), [1000, 1001, 1002, 1003, 1004]);
+    }
+
+    #[test]
+    fn block_longer_than_file() {
+        let (stream, warnings) = parse_file(&v4_file(&samples(), 100)).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(stream.len(), 5);
+    }
+
+    #[test]
+    fn corrupted_block() {
+        let mut bytes = v4_file(&samples(), 2);
+        bytes[HEADER_SIZE + BLOCK_SIZE + 5] ^= 0xff;
+        let (stream, warnings) = parse_file(&bytes).unwrap();
+
+        assert!(warnings.truncated.is_none());
+        assert_eq!(warnings.corrupted.len(), 1);
+        assert_eq!(warnings.corrupted[0].samples, 2..4);
+        assert_eq!(warnings.corrupted[0].after, Some(10));
+        assert_eq!(stream.time, [0, 10, 40]);
+        assert_eq!(values(&stream), [1000, 1001, 1004]);
+    }
+
+    #[test]
+    fn truncated_block() {
+        let mut bytes = v4_file(&samples(), 2);
+        bytes.truncate(HEADER_SIZE + BLOCK_SIZE + 8);
+        let (stream, warnings) = parse_file(&bytes).unwrap();
+
+        assert!(warnings.corrupted.is_empty());
+        let truncated = warnings.truncated.unwrap();
+        assert_eq!(truncated.recovered, 3);
+        assert_eq!(truncated.trailing_bytes, 2);
+        assert_eq!(stream.time, [0, 10, 20]);
+    }
+
+    #[test]
+    fn truncated_header() {
+        assert!(parse_file(&v4_file(&samples(), 2)[..HEADER_SIZE - 2]).is_err());
+    }
+}
@@ -1,44 +1,67 @@
+use std::fmt::Display;
+
 use super::{DataEntry, EntryKind, SanityError};
 
-pub fn sanity_check(entries: &[DataEntry]) -> Result<(), SanityError> {
-    for e in entries {
-        let r = match &e.kind {
-            EntryKind::Bool(_) => Ok(()),
-            EntryKind::U8(v) => check_all(v, &e.name, sanity_check_u8),
-            EntryKind::U16(v) => check_all(v, &e.name, sanity_check_u16),
-            EntryKind::U32(v) => check_all(v, &e.name, sanity_check_u32),
-            EntryKind::U64(v) => check_all(v, &e.name, sanity_check_u64),
-            EntryKind::I8(v) => check_all(v, &e.name, sanity_check_i8),
-            EntryKind::I16(v) => check_all(v, &e.name, sanity_check_i16),
-            EntryKind::I32(v) => check_all(v, &e.name, sanity_check_i32),
-            EntryKind::I64(v) => check_all(v, &e.name, sanity_check_i64),
-            EntryKind::F32(v) => check_all(v, &e.name, sanity_check_f32),
-            EntryKind::F64(v) => check_all(v, &e.name, sanity_check_f64),
-        };
+/// First sample of a channel that failed the sanity check.
+#[derive(Debug)]
+pub struct SanityFailure {
+    pub channel: String,
+    pub index: usize,
+    /// the raw value
+    pub value: String,
+    /// e.g. `max` or `nan`
+    pub reason: &'static str,
+}
 
-        r?;
+pub fn sanity_check(entries: &[DataEntry]) -> Result<(), SanityError> {
+    match entries.iter().find_map(first_failure) {
+        Some(f) => Err(SanityError(format!("'{}' is {}", f.channel, f.reason))),
+        None => Ok(()),
     }
-    Ok(())
 }
 
-fn check_all<T: Copy>(
+/// The first failing sample of every channel.
+pub fn sanity_failures(entries: &[DataEntry]) -> Vec<SanityFailure> {
+    entries.iter().filter_map(first_failure).collect()
+}
+
+fn first_failure(e: &DataEntry) -> Option<SanityFailure> {
+    let (index, value, reason) = match &e.kind {
+        EntryKind::Bool(_) | EntryKind::Enum(_) => None,
+        EntryKind::U8(v) => find_failure(v, sanity_check_u8),
+        EntryKind::U16(v) => find_failure(v, sanity_check_u16),
+        EntryKind::U32(v) => find_failure(v, sanity_check_u32),
+        EntryKind::U64(v) => find_failure(v, sanity_check_u64),
+        EntryKind::I8(v) => find_failure(v, sanity_check_i8),
+        EntryKind::I16(v) => find_failure(v, sanity_check_i16),
+        EntryKind::I32(v) => find_failure(v, sanity_check_i32),
+        EntryKind::I64(v) => find_failure(v, sanity_check_i64),
+        EntryKind::F32(v) => find_failure(v, sanity_check_f32),
+        EntryKind::F64(v) => find_failure(v, sanity_check_f64),
+    }?;
+
+    Some(SanityFailure {
+        channel: e.name.clone(),
+        index,
+        value,
+        reason,
+    })
+}
+
+fn find_failure<T: Copy + Display>(
     values: &[T],
-    name: &str,
-    check: impl Fn(T, &str) -> Result<(), SanityError>,
-) -> Result<(), SanityError> {
-    for entry in values {
-        check(*entry, name)?;
-    }
-    Ok(())
+    check: impl Fn(T) -> Option<&'static str>,
+) -> Option<(usize, String, &'static str)> {
+    (values.iter().enumerate()).find_map(|(i, v)| check(*v).map(|r| (i, v.to_string(), r)))
 }
 
 macro_rules! impl_sanity_check_unsigned_int {
     ($ident:ident, $ty:ty) => {
-        fn $ident(val: $ty, name: &str) -> Result<(), SanityError> {
+        fn $ident(val: $ty) -> Option<&'static str> {
             if val == <$ty>::MAX {
-                return Err(SanityError(format!("'{name}' is max")));
+                return Some("max");
             }
-            Ok(())
+            None
         }
     };
 }
@@ -49,14 +72,14 @@ impl_sanity_check_unsigned_int!(sanity_check_u64, u64);
 
 macro_rules! impl_sanity_check_signed_int {
     ($ident:ident, $ty:ty) => {
-        fn $ident(val: $ty, name: &str) -> Result<(), SanityError> {
+        fn $ident(val: $ty) -> Option<&'static str> {
             if val == <$ty>::MIN {
-                return Err(SanityError(format!("'{name}' is min")));
+                return Some("min");
             }
             if val == <$ty>::MAX {
-                return Err(SanityError(format!("'{name}' is max")));
+                return Some("max");
             }
-            Ok(())
+            None
         }
     };
 }
@@ -67,14 +90,14 @@ impl_sanity_check_signed_int!(sanity_check_i64, i64);
 
 macro_rules! Impl_sanity_check_float {
     ($ident:ident, $ty:ty) => {
-        fn $ident(val: $ty, name: &str) -> Result<(), SanityError> {
+        fn $ident(val: $ty) -> Option<&'static str> {
             if val.is_nan() {
-                return Err(SanityError(format!("'{name}' is nan")));
+                return Some("nan");
             }
             if val.is_infinite() {
-                return Err(SanityError(format!("'{name}' is infinite")));
+                return Some("infinite");
             }
-            Ok(())
+            None
         }
     };
 }
@@ -0,0 +1,274 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use egui::{Button, Checkbox, DragValue, Grid, TextEdit, Ui};
+use egui_plot::PlotPoint;
+
+use crate::app::{self, Job};
+use crate::data::LogStream;
+use crate::eval::{self, Expr, Progress, Timing};
+use crate::library::UserExpr;
+use crate::plot::{Config, ERROR_RED};
+use crate::power::PowerLimit;
+use crate::units::{self, ChannelUnit};
+use crate::util;
+
+/// Channel of the energy meter, compared with the integrated power if present.
+const ENERGY_METER_CHANNEL: &str = "energy_total";
+/// Length of the Formula Student endurance in km.
+pub const DEFAULT_ENDURANCE_DISTANCE: f64 = 22.0;
+
+pub struct EnergyReport {
+    /// in seconds
+    pub duration: f64,
+    /// in kWh, drawn from the accumulator
+    pub consumed: f64,
+    /// in kWh, fed back into the accumulator
+    pub regen: f64,
+    /// in kW
+    pub average_power: f64,
+    /// in m, if the driven distance is known
+    pub distance: Option<f64>,
+    /// change of the energy meter channel and its unit
+    pub meter: Option<(f64, Option<String>)>,
+}
+
+impl EnergyReport {
+    /// in kWh
+    pub fn net(&self) -> f64 {
+        self.consumed - self.regen
+    }
+
+    /// Net energy scaled to the endurance distance in km, in kWh.
+    pub fn projected(&self, endurance_distance: f64) -> Option<f64> {
+        let km = self.distance.filter(|d| *d > 0.0)? / 1000.0;
+        Some(self.net() / km * endurance_distance)
+    }
+}
+
+/// State of the energy window.
+pub struct EnergySummary {
+    /// x-range of the active plot when the window was opened
+    pub visible_range: Option<RangeInclusive<f64>>,
+    pub only_visible: bool,
+    pub report: Option<EnergyReport>,
+    pub error: Option<String>,
+    pub job: Option<Job<Result<EnergyReport, String>>>,
+}
+
+impl EnergySummary {
+    pub fn new(visible_range: Option<RangeInclusive<f64>>) -> Self {
+        Self {
+            visible_range,
+            only_visible: false,
+            report: None,
+            error: None,
+            job: None,
+        }
+    }
+
+    pub fn range(&self) -> Option<RangeInclusive<f64>> {
+        self.visible_range.clone().filter(|_| self.only_visible)
+    }
+
+    /// Computes the summary on a worker thread.
+    pub fn start(&mut self, cfg: &Config, streams: &Arc<[LogStream]>) {
+        let limit = cfg.power_limit.clone();
+        let library = cfg.expr_library.clone();
+        let timing = cfg.timing();
+        let streams = Arc::clone(streams);
+        let channel_units = cfg.channel_units.clone();
+        let range = self.range();
+        self.job = Some(Job::spawn(move |progress| {
+            summarize(
+                &limit,
+                &library,
+                &timing,
+                &streams,
+                &channel_units,
+                range,
+                progress,
+            )
+        }));
+    }
+}
+
+/// Integrates the power of the accumulator over the range, or all samples if there is none. A
+/// positive current discharges the accumulator.
+pub fn summarize(
+    limit: &PowerLimit,
+    library: &[UserExpr],
+    timing: &Timing,
+    streams: &Arc<[LogStream]>,
+    channel_units: &[ChannelUnit],
+    range: Option<RangeInclusive<f64>>,
+    progress: &Progress,
+) -> Result<EnergyReport, String> {
+    let eval_time = |y: &str| {
+        let expr = Expr::new("time", y);
+        eval::eval(&expr, Arc::clone(streams), library, timing, progress)
+            .map(|values| in_range(values, range.as_ref()))
+    };
+
+    let y = format!("({}) * ({}) / 1000", limit.current, limit.voltage);
+    let power = eval_time(&y).map_err(|e| match e.y.or(e.x) {
+        Some(e) => e.to_string(),
+        None => "invalid expression".to_string(),
+    })?;
+    let (Some(first), Some(last)) = (power.first(), power.last()) else {
+        return Err("no samples in the range".to_string());
+    };
+    let duration = last.x - first.x;
+
+    let mut consumed = 0.0;
+    let mut regen = 0.0;
+    for w in power.windows(2) {
+        let (p0, p1) = (w[0].y, w[1].y);
+        if !p0.is_finite() || !p1.is_finite() {
+            continue;
+        }
+        // in kWh
+        let energy = (p0 + p1) / 2.0 * (w[1].x - w[0].x) / 3600.0;
+        if energy > 0.0 {
+            consumed += energy;
+        } else {
+            regen -= energy;
+        }
+    }
+
+    let distance = (eval_time(eval::DISTANCE).ok())
+        .and_then(|d| Some(d.last()?.y - d.first()?.y))
+        .filter(|d| d.is_finite());
+    let meter = (eval_time(ENERGY_METER_CHANNEL).ok())
+        .and_then(|e| Some(e.last()?.y - e.first()?.y))
+        .filter(|e| e.is_finite())
+        .map(|e| {
+            let unit = units::channel_unit(streams, channel_units, ENERGY_METER_CHANNEL);
+            (e, unit)
+        });
+
+    Ok(EnergyReport {
+        duration,
+        consumed,
+        regen,
+        average_power: match duration > 0.0 {
+            true => (consumed - regen) * 3600.0 / duration,
+            false => f64::NAN,
+        },
+        distance,
+        meter,
+    })
+}
+
+fn in_range(values: Vec<PlotPoint>, range: Option<&RangeInclusive<f64>>) -> Vec<PlotPoint> {
+    match range {
+        Some(r) => values.into_iter().filter(|p| r.contains(&p.x)).collect(),
+        None => values,
+    }
+}
+
+/// Returns true if the summary should be computed.
+pub fn energy_dialog(
+    ui: &mut Ui,
+    summary: &mut EnergySummary,
+    limit: &mut PowerLimit,
+    endurance_distance: &mut f64,
+) -> bool {
+    Grid::new("energy_inputs").num_columns(2).show(ui, |ui| {
+        ui.label("Current");
+        let current = TextEdit::singleline(&mut limit.current)
+            .code_editor()
+            .hint_text("in A");
+        ui.add(current);
+        ui.end_row();
+
+        ui.label("Voltage");
+        let voltage = TextEdit::singleline(&mut limit.voltage)
+            .code_editor()
+            .hint_text("in V");
+        ui.add(voltage);
+        ui.end_row();
+
+        ui.label("Endurance");
+        ui.add(DragValue::new(endurance_distance).speed(0.1).suffix(" km"));
+        ui.end_row();
+    });
+
+    match &summary.visible_range {
+        Some(range) => {
+            let start = util::format_time(*range.start());
+            let end = util::format_time(*range.end());
+            let text = format!("Only visible range ({start} - {end})");
+            ui.checkbox(&mut summary.only_visible, text);
+        }
+        None => {
+            ui.add_enabled(false, Checkbox::new(&mut false, "Only visible range"));
+        }
+    }
+
+    ui.separator();
+
+    let valid = !limit.current.trim().is_empty() && !limit.voltage.trim().is_empty();
+    let can_compute = valid && summary.job.is_none();
+    let compute = ui
+        .add_enabled(can_compute, Button::new("Compute"))
+        .clicked();
+    if let Some(job) = &summary.job {
+        app::job_progress(ui, job);
+    }
+    if let Some(e) = &summary.error {
+        ui.colored_label(ERROR_RED, e);
+    }
+
+    let Some(report) = &summary.report else {
+        return compute;
+    };
+    ui.separator();
+    Grid::new("energy_summary")
+        .striped(true)
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Duration");
+            ui.monospace(util::format_time(report.duration));
+            ui.end_row();
+
+            ui.label("Consumed");
+            ui.monospace(format!("{:.3} kWh", report.consumed));
+            ui.end_row();
+
+            ui.label("Regenerated");
+            ui.monospace(format!("{:.3} kWh", report.regen));
+            ui.end_row();
+
+            ui.label("Net");
+            ui.monospace(format!("{:.3} kWh", report.net()));
+            ui.end_row();
+
+            ui.label("Average power");
+            ui.monospace(format!("{:.2} kW", report.average_power));
+            ui.end_row();
+
+            if let Some(distance) = report.distance {
+                ui.label("Distance");
+                ui.monospace(format!("{:.2} km", distance / 1000.0));
+                ui.end_row();
+            }
+            if let Some(projected) = report.projected(*endurance_distance) {
+                ui.label("Projected endurance")
+                    .on_hover_text("Net energy per distance, scaled to the endurance length");
+                ui.monospace(format!("{projected:.2} kWh"));
+                ui.end_row();
+            }
+            if let Some((energy, unit)) = &report.meter {
+                ui.label("Energy meter")
+                    .on_hover_text(format!("Change of `{ENERGY_METER_CHANNEL}`"));
+                match unit {
+                    Some(unit) => ui.monospace(format!("{energy:.3} {unit}")),
+                    None => ui.monospace(format!("{energy:.3}")),
+                };
+                ui.end_row();
+            }
+        });
+
+    compute
+}
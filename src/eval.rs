@@ -1,12 +1,15 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use cods::{Asts, Checker, Context, Funs, Ident, IdentSpan, Span, Stack, Val, VarRef};
 use egui_plot::PlotPoint;
 use serde::{Deserialize, Serialize};
 
-use crate::data::LogStream;
+use crate::data::{self, DataEntry, EntryKind, LogStream, RateSegment, SanityError};
+use crate::interpolation::{self, ChannelInterpolation, Interpolation};
+use crate::library::UserExpr;
 
-#[derive(Default, Clone, Serialize, Deserialize)]
+#[derive(Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Expr {
     pub x: String,
     pub y: String,
@@ -27,7 +30,105 @@ pub struct ExprError {
     pub y: Option<cods::Error>,
 }
 
-pub fn eval(expr: &Expr, data: Arc<[LogStream]>) -> Result<Vec<PlotPoint>, Box<ExprError>> {
+/// Number of samples that are processed between progress updates and cancellation checks.
+const PROGRESS_STRIDE: usize = 4096;
+
+/// Builtin identifier of the driven distance in meters, unless a channel has this name.
+pub const DISTANCE: &str = "distance";
+/// Channel with the driven distance in meters, logged by some firmwares.
+const DRIVEN_CHANNEL: &str = "driven";
+/// Wheel speeds in m/s, whose mean is integrated if there is no driven distance channel.
+const WHEEL_SPEED_CHANNELS: [&str; 4] = [
+    "wheel_speed_fl",
+    "wheel_speed_fr",
+    "wheel_speed_rl",
+    "wheel_speed_rr",
+];
+
+/// Shared between an evaluation and the ui, to report the number of processed samples and to
+/// cancel the evaluation.
+#[derive(Debug, Default)]
+pub struct Progress {
+    done: AtomicUsize,
+    total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    /// From `0.0` to `1.0`, over all passes of the evaluation including library expressions.
+    pub fn fraction(&self) -> f32 {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0.0;
+        }
+        self.done.load(Ordering::Relaxed) as f32 / total as f32
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn add_total(&self, samples: usize) {
+        self.total.fetch_add(samples, Ordering::Relaxed);
+    }
+}
+
+/// How the samples of the streams are aligned during evaluation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct Timing {
+    /// in ms by stream index, added to the time of each stream to line up loggers with offset
+    /// clocks
+    pub offsets: Vec<i64>,
+    /// in ms, all streams are resampled onto a fixed time grid over the first stream with this
+    /// interval, `0` to evaluate at the samples of the first stream
+    pub resample_interval: u32,
+    /// how channels are evaluated between their samples, linear if not listed
+    pub interpolation: Vec<ChannelInterpolation>,
+}
+
+impl Timing {
+    fn offset(&self, stream: usize) -> i64 {
+        self.offsets.get(stream).copied().unwrap_or(0)
+    }
+
+    /// Number of times the expressions are evaluated at.
+    fn num_samples(&self, data: &[LogStream]) -> usize {
+        match (self.resample_interval, data[0].time.as_slice()) {
+            (0, time) => time.len(),
+            (_, []) => 0,
+            (interval, [first, .., last]) => ((last - first) / interval) as usize + 1,
+            (_, [_]) => 1,
+        }
+    }
+
+    /// Times the expressions are evaluated at, in ms.
+    fn sample_times(&self, data: &[LogStream]) -> Vec<i64> {
+        let offset = self.offset(0);
+        match self.resample_interval {
+            0 => data[0].time.iter().map(|t| *t as i64 + offset).collect(),
+            interval => {
+                let start = data[0].time.first().map_or(0, |t| *t as i64 + offset);
+                (0..self.num_samples(data) as i64)
+                    .map(|i| start + i * interval as i64)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Returns partial values if the evaluation is cancelled, check [`Progress::is_cancelled`]
+/// before using them.
+pub fn eval(
+    expr: &Expr,
+    data: Arc<[LogStream]>,
+    library: &[UserExpr],
+    timing: &Timing,
+    progress: &Progress,
+) -> Result<Vec<PlotPoint>, Box<ExprError>> {
     let mut ctx_x = Context::default();
     let mut ctx_y = Context::default();
 
@@ -36,8 +137,36 @@ pub fn eval(expr: &Expr, data: Arc<[LogStream]>) -> Result<Vec<PlotPoint>, Box<E
     let mut vars_x = Vec::with_capacity(num_vars);
     let mut vars_y = Vec::with_capacity(num_vars);
 
-    let asts_x = parse(&data, &mut ctx_x, &mut vars_x, &expr.x);
-    let asts_y = parse(&data, &mut ctx_y, &mut vars_y, &expr.y);
+    progress.add_total(timing.num_samples(&data));
+    let derived = eval_library(&data, timing, library, &[&expr.x, &expr.y], progress);
+
+    let mut filters_x = Vec::new();
+    let mut filters_y = Vec::new();
+    let input_x = expand_filters(&data, timing, &derived, progress, &mut filters_x, &expr.x);
+    let input_y = expand_filters(&data, timing, &derived, progress, &mut filters_y, &expr.y);
+
+    let asts_x = input_x.and_then(|input| {
+        parse(
+            &data,
+            timing,
+            &derived,
+            &mut ctx_x,
+            &mut vars_x,
+            &filters_x,
+            &input,
+        )
+    });
+    let asts_y = input_y.and_then(|input| {
+        parse(
+            &data,
+            timing,
+            &derived,
+            &mut ctx_y,
+            &mut vars_y,
+            &filters_y,
+            &input,
+        )
+    });
 
     let ((funs_x, asts_x), (funs_y, asts_y)) = match (asts_x, asts_y) {
         (Ok(x), Ok(y)) => (x, y),
@@ -55,34 +184,13 @@ pub fn eval(expr: &Expr, data: Arc<[LogStream]>) -> Result<Vec<PlotPoint>, Box<E
     stack_x.resize(vars_x.len());
     stack_y.resize(vars_y.len());
 
-    let mut lerp_values = Vec::with_capacity(data.len() - 1);
-    for d in data.iter().skip(1) {
-        lerp_values.push((0, &d.time[0..1]));
-    }
-    for (i, &time) in data[0].time.iter().enumerate() {
-        for (j, d) in data.iter().skip(1).enumerate() {
-            let mut d_index = 0;
-            while let Some(&t) = d.time.get(d_index) {
-                if t == time || t > time && d_index == 0 {
-                    lerp_values[j] = (d_index, &d.time[d_index..d_index + 1]);
-                } else if t > time {
-                    lerp_values[j] = (d_index - 1, &d.time[d_index - 1..d_index + 1]);
-                } else if d_index + 1 == d.len() {
-                    lerp_values[j] = (d_index, &d.time[d_index..d_index + 1]);
-                } else {
-                    d_index += 1;
-                    continue;
-                }
-                break;
-            }
-        }
-
+    for_each_sample(&data, timing, progress, |i, time, lerp_values| {
         for (var, id) in vars_x.iter() {
-            let val = get_value(&data, *id, i, time, &lerp_values);
+            let val = get_value(&data, &filters_x, &derived, *id, i, time, lerp_values);
             stack_x.set(var, val);
         }
         for (var, id) in vars_y.iter() {
-            let val = get_value(&data, *id, i, time, &lerp_values);
+            let val = get_value(&data, &filters_y, &derived, *id, i, time, lerp_values);
             stack_y.set(var, val);
         }
 
@@ -94,21 +202,299 @@ pub fn eval(expr: &Expr, data: Arc<[LogStream]>) -> Result<Vec<PlotPoint>, Box<E
                 values.push(PlotPoint::new(x, y));
             }
         };
+    });
+
+    Ok(values)
+}
+
+/// Evaluates the derived channels at the samples of the first stream and appends them to it, so
+/// they can be used like the channels of the files. Derived channels can use the library and the
+/// ones defined before them. Returns a message for every channel that couldn't be added or has
+/// values that fail the sanity check.
+pub fn derive_channels(
+    streams: &mut [LogStream],
+    channels: &[UserExpr],
+    library: &[UserExpr],
+    timing: Timing,
+) -> Vec<String> {
+    if streams.is_empty() {
+        return Vec::new();
     }
+    let timing = Timing {
+        resample_interval: 0,
+        ..timing
+    };
+    let progress = Progress::default();
+
+    let mut errors = Vec::new();
+    for c in channels.iter() {
+        let exists = (streams.iter().flat_map(|s| s.entries.iter())).any(|e| e.name == c.name);
+        if exists || c.name == "time" {
+            errors.push(format!(
+                "`{}`: a channel with this name already exists",
+                c.name
+            ));
+            continue;
+        }
 
+        let derived = eval_library(streams, &timing, library, &[&c.expr], &progress);
+        let values = match eval_series(streams, &timing, &derived, &c.expr, &progress) {
+            Ok(values) => values,
+            Err(e) => {
+                errors.push(format!("`{}`: {e}", c.name));
+                continue;
+            }
+        };
+        let entry = DataEntry {
+            name: c.name.clone(),
+            unit: String::new(),
+            scale: 1.0,
+            offset: 0.0,
+            kind: EntryKind::F64(values),
+            labels: Vec::new(),
+        };
+        if let Err(SanityError(e)) = data::sanity_check(std::slice::from_ref(&entry)) {
+            errors.push(e);
+        }
+        streams[0].entries.push(entry);
+    }
+    errors
+}
+
+/// Calls `f` with the index and time of every sample time, and for each stream the index of the
+/// sample before and the factor to interpolate towards the next one. Stops early if the
+/// evaluation is cancelled.
+fn for_each_sample(
+    data: &[LogStream],
+    timing: &Timing,
+    progress: &Progress,
+    mut f: impl FnMut(usize, i64, &[(usize, f64)]),
+) {
+    let times = timing.sample_times(data);
+    let mut cursors = vec![0; data.len()];
+    let mut lerp_values = vec![(0, 0.0); data.len()];
+    for (i, &time) in times.iter().enumerate() {
+        if i % PROGRESS_STRIDE == 0 && i > 0 {
+            if progress.is_cancelled() {
+                return;
+            }
+            progress.done.fetch_add(PROGRESS_STRIDE, Ordering::Relaxed);
+        }
+
+        for (s, d) in data.iter().enumerate() {
+            lerp_values[s] = if s == 0 && timing.resample_interval == 0 {
+                (i, 0.0)
+            } else {
+                lerp_position(d, timing.offset(s), &mut cursors[s], time)
+            };
+        }
+
+        f(i, time, &lerp_values);
+    }
+    let reported = times.len().saturating_sub(1) / PROGRESS_STRIDE * PROGRESS_STRIDE;
+    progress
+        .done
+        .fetch_add(times.len() - reported, Ordering::Relaxed);
+}
+
+/// Advances the cursor to the last sample at or before the time and returns it with the factor
+/// to interpolate towards the next sample. Sample times are monotonic, so the cursor only ever
+/// moves forward and all streams are merged in a single pass.
+fn lerp_position(stream: &LogStream, offset: i64, cursor: &mut usize, time: i64) -> (usize, f64) {
+    let time_at = |k: usize| stream.time[k] as i64 + offset;
+    while *cursor + 1 < stream.len() && time_at(*cursor + 1) <= time {
+        *cursor += 1;
+    }
+
+    let c = *cursor;
+    if c + 1 >= stream.len() || time_at(c) >= time {
+        return (c, 0.0);
+    }
+    let (time0, time1) = (time_at(c), time_at(c + 1));
+    (c, (time - time0) as f64 / (time1 - time0) as f64)
+}
+
+/// A library expression evaluated at every sample time.
+struct Derived {
+    name: String,
+    values: Vec<f64>,
+}
+
+/// Evaluates the library expressions the inputs depend on, directly or through other library
+/// expressions. Expressions that fail to evaluate are left out, so using them is reported as an
+/// unknown identifier. Channels take precedence over library expressions with the same name,
+/// which take precedence over the builtin [`DISTANCE`].
+fn eval_library(
+    data: &[LogStream],
+    timing: &Timing,
+    library: &[UserExpr],
+    inputs: &[&str],
+    progress: &Progress,
+) -> Vec<Derived> {
+    let is_channel = |name: &str| {
+        name == "time" || (data.iter().flat_map(|s| s.entries.iter())).any(|e| e.name == name)
+    };
+
+    let mut needed = vec![false; library.len()];
+    for i in (0..library.len()).rev() {
+        let name = &library[i].name;
+        let used = inputs.iter().any(|input| references(input, name))
+            || (i + 1..library.len()).any(|j| needed[j] && references(&library[j].expr, name));
+        needed[i] = used && !is_channel(name);
+    }
+
+    let num_needed = needed.iter().filter(|n| **n).count();
+    progress.add_total(num_needed * timing.num_samples(data));
+
+    let mut derived = Vec::new();
+    let uses_distance = inputs.iter().any(|input| references(input, DISTANCE))
+        || (library.iter().zip(needed.iter())).any(|(u, n)| *n && references(&u.expr, DISTANCE));
+    let distance_defined = is_channel(DISTANCE) || library.iter().any(|u| u.name == DISTANCE);
+    if uses_distance && !distance_defined {
+        derived.extend(eval_distance(data, timing, progress));
+    }
+    for (u, _) in library.iter().zip(needed).filter(|(_, needed)| *needed) {
+        if let Ok(values) = eval_series(data, timing, &derived, &u.expr, progress) {
+            let name = u.name.clone();
+            derived.push(Derived { name, values });
+        }
+    }
+    derived
+}
+
+/// The driven distance at every sample time, from the driven distance channel if present,
+/// otherwise by integrating the mean of the available wheel speeds.
+fn eval_distance(data: &[LogStream], timing: &Timing, progress: &Progress) -> Option<Derived> {
+    let has_channel =
+        |name: &str| (data.iter().flat_map(|s| s.entries.iter())).any(|e| e.name == name);
+    progress.add_total(timing.num_samples(data));
+    if has_channel(DRIVEN_CHANNEL) {
+        let values = eval_series(data, timing, &[], DRIVEN_CHANNEL, progress).ok()?;
+        let name = DISTANCE.to_string();
+        return Some(Derived { name, values });
+    }
+
+    let wheels = (WHEEL_SPEED_CHANNELS.iter())
+        .filter(|c| has_channel(c))
+        .copied()
+        .collect::<Vec<_>>();
+    if wheels.is_empty() {
+        return None;
+    }
+    let input = format!("({}) / {}", wheels.join(" + "), wheels.len());
+    let speed = eval_series(data, timing, &[], &input, progress).ok()?;
+
+    let times = timing.sample_times(data);
+    let mut values = Vec::with_capacity(speed.len());
+    let mut sum = 0.0;
+    for (i, &v) in speed.iter().enumerate() {
+        if let Some(prev) = i.checked_sub(1) {
+            let dt = (times[i] - times[prev]) as f64 / 1000.0;
+            let v0 = speed[prev];
+            // skip invalid samples instead of poisoning the rest of the distance
+            if v.is_finite() && v0.is_finite() {
+                sum += (v0 + v) / 2.0 * dt;
+            }
+        }
+        values.push(sum);
+    }
+    let name = DISTANCE.to_string();
+    Some(Derived { name, values })
+}
+
+/// Evaluates an expression of the single variable `x` at every value, `NaN` where evaluation
+/// fails.
+pub fn eval_fn(input: &str, xs: &[f64]) -> cods::Result<Vec<f64>> {
+    let mut ctx = Context::default();
+    ctx.idents.push("x");
+    let tokens = ctx.lex(input)?;
+    let items = ctx.group(tokens)?;
+    let csts = ctx.parse(items)?;
+
+    let mut checker = Checker::default();
+    let ident = IdentSpan::new(Ident(0), Span::pos(0, 0));
+    let var = ctx.def_var(
+        &mut checker.scopes,
+        ident,
+        cods::DataType::Float,
+        true,
+        false,
+    );
+    let asts = ctx.check_with(&mut checker, csts)?;
+    if !ctx.errors.is_empty() {
+        return Err(ctx.errors.remove(0));
+    }
+
+    let mut stack = Stack::default();
+    stack.resize(1);
+    let values = xs.iter().map(|&x| {
+        stack.set(&var, Val::Float(x));
+        cods::eval_with(&mut stack, &checker.funs, &asts)
+            .ok()
+            .and_then(cast_float)
+            .unwrap_or(f64::NAN)
+    });
+    Ok(values.collect())
+}
+
+/// Evaluates the input at every sample time, `NaN` where evaluation fails.
+fn eval_series(
+    data: &[LogStream],
+    timing: &Timing,
+    derived: &[Derived],
+    input: &str,
+    progress: &Progress,
+) -> cods::Result<Vec<f64>> {
+    let mut ctx = Context::default();
+    let mut vars = Vec::new();
+    let mut filters = Vec::new();
+    let input = expand_filters(data, timing, derived, progress, &mut filters, input)?;
+    let (funs, asts) = parse(data, timing, derived, &mut ctx, &mut vars, &filters, &input)?;
+
+    let mut stack = Stack::default();
+    stack.resize(vars.len());
+
+    let mut values = Vec::with_capacity(timing.num_samples(data));
+    for_each_sample(data, timing, progress, |i, time, lerp_values| {
+        for (var, id) in vars.iter() {
+            let val = get_value(data, &filters, derived, *id, i, time, lerp_values);
+            stack.set(var, val);
+        }
+        let val = cods::eval_with(&mut stack, &funs, &asts)
+            .ok()
+            .and_then(cast_float);
+        values.push(val.unwrap_or(f64::NAN));
+    });
     Ok(values)
 }
 
+#[derive(Clone, Copy)]
+enum VarId {
+    Entry(usize, usize, Interpolation),
+    Time,
+    Filter(usize),
+    Derived(usize),
+}
+
 fn parse(
     data: &[LogStream],
+    timing: &Timing,
+    derived: &[Derived],
     ctx: &mut Context,
-    vars: &mut Vec<(VarRef, (usize, usize))>,
+    vars: &mut Vec<(VarRef, VarId)>,
+    filters: &[Filter],
     input: &str,
 ) -> cods::Result<(Funs, Asts)> {
     for v in data.iter().flat_map(|g| g.entries.iter()) {
         ctx.idents.push(&v.name);
     }
     ctx.idents.push("time");
+    for f in filters.iter() {
+        ctx.idents.push(&f.ident);
+    }
+    for d in derived.iter() {
+        ctx.idents.push(&d.name);
+    }
 
     let tokens = ctx.lex(input)?;
     let items = ctx.group(tokens)?;
@@ -126,7 +512,8 @@ fn parse(
                 true,
                 false,
             );
-            vars.push((inner, (i, j)));
+            let mode = interpolation::of_entry(&timing.interpolation, &group.entries[j]);
+            vars.push((inner, VarId::Entry(i, j, mode)));
 
             id += 1;
         }
@@ -139,7 +526,31 @@ fn parse(
         true,
         false,
     );
-    vars.push((inner, (data.len(), 0)));
+    vars.push((inner, VarId::Time));
+
+    for k in 0..filters.len() {
+        let ident = IdentSpan::new(Ident(vars.len()), Span::pos(0, 0));
+        let inner = ctx.def_var(
+            &mut checker.scopes,
+            ident,
+            cods::DataType::Float,
+            true,
+            false,
+        );
+        vars.push((inner, VarId::Filter(k)));
+    }
+
+    for k in 0..derived.len() {
+        let ident = IdentSpan::new(Ident(vars.len()), Span::pos(0, 0));
+        let inner = ctx.def_var(
+            &mut checker.scopes,
+            ident,
+            cods::DataType::Float,
+            true,
+            false,
+        );
+        vars.push((inner, VarId::Derived(k)));
+    }
 
     let asts = ctx.check_with(&mut checker, csts)?;
     if !ctx.errors.is_empty() {
@@ -159,27 +570,258 @@ fn cast_float(val: Val) -> Option<f64> {
 
 fn get_value(
     data: &[LogStream],
-    id: (usize, usize),
+    filters: &[Filter],
+    derived: &[Derived],
+    id: VarId,
     index: usize,
-    time: u32,
-    lerp_values: &[(usize, &[u32])],
+    time: i64,
+    lerp_values: &[(usize, f64)],
+) -> Val {
+    match id {
+        VarId::Entry(s, e, mode) => {
+            let entry = &data[s].entries[e];
+            sample_value(s, |i| entry.get_f64(i), lerp_values, mode)
+        }
+        VarId::Time => Val::Float(time as f64 / 1000.0),
+        VarId::Filter(k) => {
+            let f = &filters[k];
+            match f.stream {
+                Some(s) => sample_value(s, |i| f.values[i], lerp_values, Interpolation::Linear),
+                None => Val::Float(f.values[index]),
+            }
+        }
+        VarId::Derived(k) => Val::Float(derived[k].values[index]),
+    }
+}
+
+fn sample_value(
+    stream: usize,
+    value: impl Fn(usize) -> f64,
+    lerp_values: &[(usize, f64)],
+    mode: Interpolation,
 ) -> Val {
-    if id.0 == 0 {
-        Val::Float(data[id.0].entries[id.1].kind.get_f64(index))
-    } else if id.0 < data.len() {
-        match lerp_values[id.0 - 1] {
-            (index, [_time]) => Val::Float(data[id.0].entries[id.1].kind.get_f64(index)),
-            (index, [time0, time1]) => {
-                let range = time1 - time0;
-                let pos = time - time0;
-                let factor = pos as f64 / range as f64;
-                let val0 = data[id.0].entries[id.1].kind.get_f64(index);
-                let val1 = data[id.0].entries[id.1].kind.get_f64(index + 1);
-                Val::Float(val0 + factor * (val1 - val0))
+    let (index, factor) = lerp_values[stream];
+    if factor == 0.0 {
+        return Val::Float(value(index));
+    }
+    match mode {
+        Interpolation::Linear => {
+            let val0 = value(index);
+            let val1 = value(index + 1);
+            Val::Float(val0 + factor * (val1 - val0))
+        }
+        Interpolation::Hold => Val::Float(value(index)),
+        Interpolation::Nearest if factor < 0.5 => Val::Float(value(index)),
+        Interpolation::Nearest => Val::Float(value(index + 1)),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum FilterKind {
+    Lowpass,
+    MovAvg,
+}
+
+const FILTER_FUNS: [(&str, FilterKind); 2] = [
+    ("lowpass", FilterKind::Lowpass),
+    ("movavg", FilterKind::MovAvg),
+];
+
+pub const FILTER_SIGNATURES: [&str; 2] = [
+    "lowpass(signal, cutoff_hz) -> float",
+    "movavg(signal, window) -> float",
+];
+
+/// A filtered signal, that is computed once over the whole stream before evaluation.
+struct Filter {
+    ident: String,
+    /// stream whose samples the values are at, `None` for the sample times of the evaluation
+    stream: Option<usize>,
+    values: Vec<f64>,
+}
+
+/// Replaces calls to the series level filter functions `lowpass(signal, cutoff_hz)` and
+/// `movavg(signal, window)` with identifiers referring to precomputed [`Filter`]s. The
+/// identifiers have the same length as the replaced calls, so error spans still match the input.
+/// Calls that can't be expanded are left untouched and reported by the parser.
+///
+/// A channel is filtered at its own samples. Any other signal, e.g. `a - b` or a library
+/// expression, is evaluated at the sample times first, errors in it are returned.
+fn expand_filters(
+    data: &[LogStream],
+    timing: &Timing,
+    derived: &[Derived],
+    progress: &Progress,
+    filters: &mut Vec<Filter>,
+    input: &str,
+) -> cods::Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut i = 0;
+    while let Some(c) = input[i..].chars().next() {
+        let ident_start = !input[..i].ends_with(is_ident_char);
+        if ident_start {
+            let call = parse_filter_call(data, timing, derived, progress, &input[i..])?;
+            if let Some((len, mut filter)) = call {
+                let call_len = input[i..i + len].chars().count();
+                filter.ident = format!("_{}", filters.len());
+                while filter.ident.len() < call_len {
+                    filter.ident.push('_');
+                }
+                output.push_str(&filter.ident);
+                filters.push(filter);
+                i += len;
+                continue;
             }
-            _ => Val::Float(f64::NAN),
         }
-    } else {
-        Val::Float(time as f64 / 1000.0)
+
+        output.push(c);
+        i += c.len_utf8();
+    }
+    Ok(output)
+}
+
+fn parse_filter_call(
+    data: &[LogStream],
+    timing: &Timing,
+    derived: &[Derived],
+    progress: &Progress,
+    input: &str,
+) -> cods::Result<Option<(usize, Filter)>> {
+    let Some(call) = split_filter_call(input) else {
+        return Ok(None);
+    };
+    let (call_len, kind, signal, param) = call;
+    let valid = match kind {
+        FilterKind::Lowpass => param > 0.0,
+        FilterKind::MovAvg => param >= 1.0,
+    };
+    if !valid {
+        return Ok(None);
+    }
+    let filter = |x: &[f64], dt: &dyn Fn(usize) -> f64| match kind {
+        FilterKind::Lowpass => lowpass(x, dt, param),
+        FilterKind::MovAvg => movavg(x, param.round() as usize),
+    };
+
+    let channel = data.iter().enumerate().find_map(|(i, s)| {
+        let j = s.entries.iter().position(|e| e.name == signal)?;
+        Some((i, j))
+    });
+    let (stream, values) = match channel {
+        Some((stream, entry)) => {
+            let s = &data[stream];
+            let x = (0..s.len())
+                .map(|i| s.entries[entry].get_f64(i))
+                .collect::<Vec<_>>();
+            let segments = s.sample_periods();
+            let dt = |i| RateSegment::step(&segments, &s.time, i);
+            (Some(stream), filter(&x, &dt))
+        }
+        None => {
+            progress.add_total(timing.num_samples(data));
+            let x = eval_series(data, timing, derived, signal, progress)?;
+            let times = timing.sample_times(data);
+            let dt = |i: usize| (times[i] - times[i - 1]) as f64;
+            (None, filter(&x, &dt))
+        }
+    };
+
+    let filter = Filter {
+        ident: String::new(),
+        stream,
+        values,
+    };
+    Ok(Some((call_len, filter)))
+}
+
+/// Length, kind, signal and parameter of a filter call at the start of the input.
+fn split_filter_call(input: &str) -> Option<(usize, FilterKind, &str, f64)> {
+    let (name, kind) = FILTER_FUNS.iter().find(|(n, _)| input.starts_with(n))?;
+    let rest = input[name.len()..].trim_start();
+    let args = rest.strip_prefix('(')?;
+    let args_len = closing_paren(args)?;
+    // the parameter is a number, so the last comma separates it from the signal
+    let (signal, param) = args[..args_len].rsplit_once(',')?;
+    let param = param.trim().parse::<f64>().ok()?;
+
+    let call_len = input.len() - args.len() + args_len + 1;
+    if input[..call_len].contains('\n') {
+        return None;
+    }
+    Some((call_len, *kind, signal.trim(), param))
+}
+
+/// Position of the parenthesis that closes an already opened one.
+fn closing_paren(input: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in input.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Whether the expression input contains `ident` as a whole identifier.
+pub fn references(input: &str, ident: &str) -> bool {
+    input.match_indices(ident).any(|(i, _)| {
+        let before = input[..i].chars().next_back();
+        let after = input[i + ident.len()..].chars().next();
+        !before.is_some_and(is_ident_char) && !after.is_some_and(is_ident_char)
+    })
+}
+
+pub fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// First order low-pass filter, `dt` is the time step in ms from the previous to the sample at
+/// the index.
+fn lowpass(x: &[f64], dt: &dyn Fn(usize) -> f64, cutoff_hz: f64) -> Vec<f64> {
+    let rc = 1.0 / (2.0 * std::f64::consts::PI * cutoff_hz);
+
+    let mut values = Vec::with_capacity(x.len());
+    let mut prev = None;
+    for (i, &x) in x.iter().enumerate() {
+        let y = match prev {
+            Some(prev) => {
+                let dt = dt(i) / 1000.0;
+                let alpha = dt / (rc + dt);
+                prev + alpha * (x - prev)
+            }
+            None => x,
+        };
+        values.push(y);
+        prev = Some(y);
+    }
+    values
+}
+
+/// Centered moving average over `window` samples, the window is truncated at the edges.
+fn movavg(x: &[f64], window: usize) -> Vec<f64> {
+    let len = x.len();
+    let before = (window - 1) / 2;
+    let after = window / 2;
+
+    let mut values = Vec::with_capacity(len);
+    let mut sum = 0.0;
+    let mut start = 0;
+    let mut end = 0;
+    for i in 0..len {
+        let new_start = i.saturating_sub(before);
+        let new_end = (i + after + 1).min(len);
+        while end < new_end {
+            sum += x[end];
+            end += 1;
+        }
+        while start < new_start {
+            sum -= x[start];
+            start += 1;
+        }
+        values.push(sum / (end - start) as f64);
     }
+    values
 }
@@ -0,0 +1,163 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use crate::data::LogStream;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Parquet => "Parquet",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+/// Settings of the export dialog of the selected entries, in any [`ExportFormat`].
+pub struct SeriesExport {
+    pub format: ExportFormat,
+    /// selected entries, indexed by stream and entry
+    pub selected: Vec<Vec<bool>>,
+    /// x-range of the active plot when the dialog was opened
+    pub visible_range: Option<RangeInclusive<f64>>,
+    pub only_visible: bool,
+}
+
+impl SeriesExport {
+    pub fn new(
+        format: ExportFormat,
+        streams: &[LogStream],
+        visible_range: Option<RangeInclusive<f64>>,
+    ) -> Self {
+        Self {
+            format,
+            selected: streams
+                .iter()
+                .map(|s| vec![true; s.entries.len()])
+                .collect(),
+            only_visible: visible_range.is_some(),
+            visible_range,
+        }
+    }
+
+    pub fn range(&self) -> Option<RangeInclusive<f64>> {
+        self.visible_range.clone().filter(|_| self.only_visible)
+    }
+
+    pub fn select_all(&mut self, selected: bool) {
+        for s in self.selected.iter_mut() {
+            s.fill(selected);
+        }
+    }
+}
+
+pub fn export_csv(
+    path: &Path,
+    streams: &[LogStream],
+    offsets: &[i64],
+    selected: &[Vec<bool>],
+    range: Option<RangeInclusive<f64>>,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_csv(&mut writer, streams, offsets, selected, range)?;
+    writer.flush()
+}
+
+/// Writes the selected entries as csv, one row per sample of the first stream. The time column
+/// is in seconds, samples of other streams are taken from their last sample at or before the
+/// time of the row. If a `range` is passed only rows inside of it are written.
+pub fn write_csv(
+    writer: &mut impl Write,
+    streams: &[LogStream],
+    offsets: &[i64],
+    selected: &[Vec<bool>],
+    range: Option<RangeInclusive<f64>>,
+) -> io::Result<()> {
+    write!(writer, "time")?;
+    for (s, sel) in streams.iter().zip(selected) {
+        for (e, _) in s.entries.iter().zip(sel).filter(|(_, s)| **s) {
+            write!(writer, ",")?;
+            write_field(writer, &e.name)?;
+        }
+    }
+    writeln!(writer)?;
+
+    for (time, indices) in row_indices(streams, offsets, range) {
+        write!(writer, "{}", time as f64 / 1000.0)?;
+        for ((s, sel), index) in streams.iter().zip(selected).zip(indices) {
+            for (e, _) in s.entries.iter().zip(sel).filter(|(_, s)| **s) {
+                match index {
+                    Some(index) => write!(writer, ",{}", e.get_f64(index))?,
+                    None => write!(writer, ",")?,
+                }
+            }
+        }
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+/// Time in ms and the sample index of each stream of every row. One row per sample of the first
+/// stream, the samples of other streams are the last ones at or before the time of the row. The
+/// times of the streams are shifted by their offsets, like in [`crate::eval::Timing`].
+pub fn row_indices(
+    streams: &[LogStream],
+    offsets: &[i64],
+    range: Option<RangeInclusive<f64>>,
+) -> Vec<(i64, Vec<Option<usize>>)> {
+    let Some(first) = streams.first() else {
+        return Vec::new();
+    };
+    let offset = |s: usize| offsets.get(s).copied().unwrap_or(0);
+
+    let mut cursors = vec![0; streams.len()];
+    let mut rows = Vec::new();
+    for (i, &time) in first.time.iter().enumerate() {
+        let time = time as i64 + offset(0);
+        if range
+            .as_ref()
+            .is_some_and(|r| !r.contains(&(time as f64 / 1000.0)))
+        {
+            continue;
+        }
+
+        let indices = (streams.iter().enumerate())
+            .map(|(j, s)| {
+                if j == 0 {
+                    return Some(i);
+                }
+                let time_at = |k: usize| s.time[k] as i64 + offset(j);
+                let c = &mut cursors[j];
+                while *c + 1 < s.len() && time_at(*c + 1) <= time {
+                    *c += 1;
+                }
+                (*c < s.len() && time_at(*c) <= time).then_some(*c)
+            })
+            .collect();
+        rows.push((time, indices));
+    }
+    rows
+}
+
+pub fn write_field(writer: &mut impl Write, field: &str) -> io::Result<()> {
+    if field.contains([',', '"', '\n']) {
+        write!(writer, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        write!(writer, "{field}")
+    }
+}
@@ -0,0 +1,113 @@
+use egui::{Grid, ScrollArea, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::data::LogStream;
+use crate::util;
+
+/// Entry of the fault dictionary, mapping a fault channel to the channels that are relevant
+/// when investigating it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FaultDefinition {
+    pub name: String,
+    pub channel: String,
+    pub related: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FaultOccurrence {
+    pub name: String,
+    pub channel: String,
+    pub related: Vec<String>,
+    /// time in seconds
+    pub start: f64,
+    /// time in seconds
+    pub end: f64,
+}
+
+/// Finds all intervals in which a fault channel is non-zero. Channels listed in the dictionary
+/// are fault channels, as well as all other channels containing "fault" in their name.
+pub fn detect(streams: &[LogStream], dictionary: &[FaultDefinition]) -> Vec<FaultOccurrence> {
+    let mut occurrences = Vec::new();
+    for s in streams.iter() {
+        for e in s.entries.iter() {
+            let def = dictionary.iter().find(|d| d.channel == e.name);
+            let (name, related) = match def {
+                Some(d) => (d.name.clone(), d.related.clone()),
+                None if e.name.to_lowercase().contains("fault") => (e.name.clone(), Vec::new()),
+                None => continue,
+            };
+
+            let mut start = None;
+            for (i, &time) in s.time.iter().enumerate() {
+                let active = e.get_f64(i) != 0.0;
+                let secs = time as f64 / 1000.0;
+                match (start, active) {
+                    (None, true) => start = Some(secs),
+                    (Some(s), false) => {
+                        occurrences.push(FaultOccurrence {
+                            name: name.clone(),
+                            channel: e.name.clone(),
+                            related: related.clone(),
+                            start: s,
+                            end: secs,
+                        });
+                        start = None;
+                    }
+                    _ => (),
+                }
+            }
+
+            if let (Some(start), Some(&last)) = (start, s.time.last()) {
+                occurrences.push(FaultOccurrence {
+                    name,
+                    channel: e.name.clone(),
+                    related,
+                    start,
+                    end: last as f64 / 1000.0,
+                });
+            }
+        }
+    }
+
+    occurrences.sort_by(|a, b| a.start.total_cmp(&b.start));
+    occurrences
+}
+
+/// Lists the fault occurrences, returns the index of the occurrence a tab should be created for.
+pub fn faults_dialog(ui: &mut Ui, occurrences: &[FaultOccurrence]) -> Option<usize> {
+    if occurrences.is_empty() {
+        ui.label("No faults found");
+        return None;
+    }
+
+    let mut create_tab = None;
+    ScrollArea::vertical()
+        .max_height(500.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            Grid::new("faults")
+                .striped(true)
+                .num_columns(4)
+                .show(ui, |ui| {
+                    ui.strong("Fault");
+                    ui.strong("Channel");
+                    ui.strong("Time");
+                    ui.label("");
+                    ui.end_row();
+
+                    for (i, o) in occurrences.iter().enumerate() {
+                        ui.label(&o.name);
+                        ui.monospace(&o.channel);
+                        let start = util::format_time(o.start);
+                        let end = util::format_time(o.end);
+                        ui.label(format!("{start} - {end}"));
+                        if ui.button("Create tab").clicked() {
+                            create_tab = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+    create_tab
+}
@@ -0,0 +1,149 @@
+use std::f64::consts::PI;
+
+use egui_plot::PlotPoint;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FftWindow {
+    Rectangular,
+    #[default]
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl FftWindow {
+    pub const ALL: [Self; 4] = [Self::Rectangular, Self::Hann, Self::Hamming, Self::Blackman];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rectangular => "Rectangular",
+            Self::Hann => "Hann",
+            Self::Hamming => "Hamming",
+            Self::Blackman => "Blackman",
+        }
+    }
+
+    fn weight(&self, i: usize, n: usize) -> f64 {
+        let x = 2.0 * PI * i as f64 / (n - 1) as f64;
+        match self {
+            Self::Rectangular => 1.0,
+            Self::Hann => 0.5 - 0.5 * x.cos(),
+            Self::Hamming => 0.54 - 0.46 * x.cos(),
+            Self::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn from_angle(angle: f64) -> Self {
+        Self {
+            re: angle.cos(),
+            im: angle.sin(),
+        }
+    }
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            re: self.re - other.re,
+            im: self.im - other.im,
+        }
+    }
+
+    fn mul(self, other: Self) -> Self {
+        Self {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+
+    fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+}
+
+/// Single sided magnitude spectrum of the y-values. The points are expected to be uniformly
+/// sampled with x-values in seconds, so the resulting x-values are in Hz. The mean is removed
+/// so the DC component doesn't dominate the spectrum, non-finite values are treated as zero.
+pub fn spectrum(points: &[PlotPoint], window: FftWindow) -> Vec<PlotPoint> {
+    let n = points.len();
+    if n < 2 {
+        return Vec::new();
+    }
+    let duration = points[n - 1].x - points[0].x;
+    if duration <= 0.0 || !duration.is_finite() {
+        return Vec::new();
+    }
+    let sample_rate = (n - 1) as f64 / duration;
+
+    let finite = || points.iter().map(|p| p.y).filter(|y| y.is_finite());
+    let mean = finite().sum::<f64>() / finite().count().max(1) as f64;
+
+    let len = n.next_power_of_two();
+    let mut buf = vec![Complex::default(); len];
+    let mut window_sum = 0.0;
+    for (i, p) in points.iter().enumerate() {
+        let w = window.weight(i, n);
+        window_sum += w;
+        if p.y.is_finite() {
+            buf[i].re = (p.y - mean) * w;
+        }
+    }
+
+    fft(&mut buf);
+
+    (0..=len / 2)
+        .map(|k| {
+            let freq = k as f64 * sample_rate / len as f64;
+            let magnitude = 2.0 * buf[k].abs() / window_sum;
+            PlotPoint::new(freq, magnitude)
+        })
+        .collect()
+}
+
+/// In-place iterative radix-2 FFT, the length of `buf` has to be a power of two.
+fn fft(buf: &mut [Complex]) {
+    let n = buf.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buf.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let step = Complex::from_angle(-2.0 * PI / len as f64);
+        for start in (0..n).step_by(len) {
+            let mut w = Complex { re: 1.0, im: 0.0 };
+            for k in 0..len / 2 {
+                let u = buf[start + k];
+                let v = buf[start + k + len / 2].mul(w);
+                buf[start + k] = u.add(v);
+                buf[start + k + len / 2] = u.sub(v);
+                w = w.mul(step);
+            }
+        }
+        len <<= 1;
+    }
+}
@@ -0,0 +1,124 @@
+use egui::{Button, Key, RichText, TextEdit, Ui};
+use egui_plot::PlotPoint;
+
+use crate::app::{PlotData, PlotValues};
+use crate::eval::Expr;
+use crate::plot::{Config, ERROR_RED};
+use crate::series_cache::RecentSeries;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Next,
+    Prev,
+}
+
+/// Searches the times a boolean expression becomes true, e.g. `brake_pedal > 0.9 && gas > 0.1`.
+/// The expression is evaluated once and searched again until it, the streams, the library or the
+/// timing change.
+#[derive(Default)]
+pub struct Find {
+    pub input: String,
+    /// key of the input and data revision the values were evaluated with, see [`RecentSeries::key`]
+    evaluated: Option<(u64, PlotValues)>,
+    /// search to run once the evaluation finished
+    pending: Option<(Direction, f64)>,
+    message: Option<String>,
+}
+
+/// Time of the next or previous sample where the values become non-zero, relative to `from`. The
+/// values are inactive before the first sample, so it is an edge if it is already non-zero.
+pub fn find_edge(values: &[PlotPoint], from: f64, direction: Direction) -> Option<f64> {
+    let active = |p: &PlotPoint| p.y.is_finite() && p.y != 0.0;
+    let first = (values.first()).filter(|p| active(p)).map(|p| p.x);
+    let mut edges = first.into_iter().chain(
+        (values.windows(2))
+            .filter(|w| !active(&w[0]) && active(&w[1]))
+            .map(|w| w[1].x),
+    );
+    match direction {
+        Direction::Next => edges.find(|x| *x > from),
+        Direction::Prev => edges.rev().find(|x| *x < from),
+    }
+}
+
+/// Returns the time that was found. `from` is the time the search starts at, e.g. the cursor.
+pub fn find_sidebar(ui: &mut Ui, data: &mut PlotData, cfg: &Config, from: f64) -> Option<f64> {
+    let mut find = std::mem::take(&mut data.find);
+    let mut search = None;
+    ui.horizontal(|ui| {
+        let input = TextEdit::singleline(&mut find.input)
+            .hint_text("brake_pedal > 0.9 && gas > 0.1")
+            .code_editor()
+            .desired_width(180.0);
+        let resp = ui.add(input);
+        if resp.changed() {
+            find.message = None;
+        }
+        if resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+            search = Some(Direction::Next);
+        }
+        let valid = !find.input.trim().is_empty();
+        let prev = ui.add_enabled(valid, Button::new("◀"));
+        if prev
+            .on_hover_text("Previous time it becomes true")
+            .clicked()
+        {
+            search = Some(Direction::Prev);
+        }
+        let next = ui.add_enabled(valid, Button::new("▶"));
+        if next.on_hover_text("Next time it becomes true").clicked() {
+            search = Some(Direction::Next);
+        }
+    });
+
+    if let Some(direction) = search.filter(|_| !find.input.trim().is_empty()) {
+        let expr = Expr::new("time", find.input.trim());
+        let key = RecentSeries::key(data.revision, &expr, &cfg.expr_library, &cfg.timing());
+        let outdated = match &find.evaluated {
+            Some((k, _)) => *k != key,
+            None => true,
+        };
+        if outdated {
+            let values = data.start_job(&expr, cfg);
+            find.evaluated = Some((key, values));
+        }
+        find.pending = Some((direction, from));
+        find.message = None;
+    }
+
+    let mut found = None;
+    if let Some((_, values)) = &mut find.evaluated {
+        if let PlotValues::Job(j) = values {
+            if j.is_done() {
+                let job = std::mem::replace(values, PlotValues::empty());
+                *values = PlotValues::Result(job.into_job().unwrap().join());
+            } else if find.pending.is_some() {
+                ui.spinner();
+                ui.ctx().request_repaint();
+            }
+        }
+        match (&*values, find.pending) {
+            (PlotValues::Result(Ok(d)), Some((direction, from))) => {
+                found = find_edge(d, from, direction);
+                if found.is_none() {
+                    find.message = Some("No match".to_string());
+                }
+                find.pending = None;
+            }
+            (PlotValues::Result(Err(e)), Some(_)) => {
+                find.message = Some(match e.y.as_ref().or(e.x.as_ref()) {
+                    Some(e) => e.to_string(),
+                    None => "invalid expression".to_string(),
+                });
+                find.pending = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(message) = &find.message {
+        ui.label(RichText::new(message).color(ERROR_RED));
+    }
+
+    data.find = find;
+    found
+}
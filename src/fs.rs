@@ -1,15 +1,42 @@
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write as _;
-use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufWriter, Write as _};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::thread::JoinHandle;
 
+use anyhow::Context as _;
 use egui::{Align2, Color32, Context, Id, LayerId, Order, Pos2, Rect, TextStyle, Vec2};
+use ron::ser::PrettyConfig;
 use serde::{Deserialize, Serialize};
 
-use crate::app::{Job, PlotData, PlotValues};
-use crate::data::{self, LogStream, SanityError};
-use crate::PlotApp;
+use crate::annotations::{self, AnnotationSet, ANNOTATIONS_EXTENSION};
+use crate::app::{ChannelInfo, PlotData};
+use crate::compare::RunB;
+use crate::config_diff::{self, ConfigImport};
+use crate::data::{self, LogStream, ReadWarnings, SanityError};
+use crate::eval;
+use crate::find::Find;
+use crate::image_export;
+use crate::inspector::Inspector;
+use crate::laps;
+use crate::plot::{self, Config, TabConfig};
+use crate::project::{self, PROJECT_EXTENSION};
+use crate::reference::{self, Reference, REFERENCE_EXTENSION};
+use crate::sanity_rules::{self, SanityRule};
+use crate::scoreboard::ScoreSession;
+use crate::series_cache::{RecentSeries, SeriesCache};
+use crate::session::SessionMeta;
+use crate::side_series::SideSeries;
+use crate::spill::SpillStore;
+use crate::track;
+use crate::{util, PlotApp};
+
+const SPARKLINE_POINTS: usize = 40;
+
+pub const MISSING_START_WARNING: &str = "Some of the selected files have no start timestamp, so \
+    they can't be aligned and are assumed to start with the earliest file that has one.";
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct Files {
@@ -24,12 +51,36 @@ pub struct SelectableFiles {
     pub with_error: Vec<ErrorFile>,
 }
 
+impl SelectableFiles {
+    /// Whether some of the selected files have a start timestamp and others, e.g. v1 files,
+    /// don't.
+    pub fn missing_start_times(&self) -> bool {
+        let mut has_start = (self.by_header.iter().flatten())
+            .filter(|f| f.selected)
+            .map(|f| f.stream.start.is_some());
+        match has_start.next() {
+            Some(first) => has_start.any(|s| s != first),
+            None => false,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SelectableFile {
     pub selected: bool,
     pub file: PathBuf,
     pub stream: LogStream,
     pub sanity_check: Result<(), SanityError>,
+    /// results of the configured [`SanityRule`]s, with their descriptions
+    pub rule_checks: Vec<(String, Result<(), SanityError>)>,
+    /// truncation and corrupted blocks
+    pub read_warnings: ReadWarnings,
+}
+
+impl SelectableFile {
+    pub fn passed_checks(&self) -> bool {
+        self.sanity_check.is_ok() && self.rule_checks.iter().all(|(_, r)| r.is_ok())
+    }
 }
 
 #[derive(Debug)]
@@ -38,6 +89,41 @@ pub struct ErrorFile {
     pub error: data::Error,
 }
 
+/// Files being parsed on worker threads.
+pub struct FileLoad {
+    pub total: usize,
+    pub parsed: Arc<AtomicUsize>,
+    always_show_dialog: bool,
+    handle: JoinHandle<SelectableFiles>,
+}
+
+impl FileLoad {
+    /// Starts parsing the files, the selection dialog is shown afterwards if `always_show_dialog`
+    /// is set, some files have errors, or fail a sanity check.
+    pub fn start(files: Files, always_show_dialog: bool, rules: Vec<SanityRule>) -> Self {
+        let total = files.items.len();
+        let parsed = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&parsed);
+        let handle = std::thread::spawn(move || open_files(files, &counter, &rules));
+        Self {
+            total,
+            parsed,
+            always_show_dialog,
+            handle,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    pub fn join(self) -> SelectableFiles {
+        self.handle
+            .join()
+            .expect("failed to join file loading thread")
+    }
+}
+
 impl PlotApp {
     pub fn open_dir_dialog(&mut self) {
         if let Some(path) = rfd::FileDialog::new().pick_folder() {
@@ -45,6 +131,253 @@ impl PlotApp {
         }
     }
 
+    pub fn export_config_dialog(&mut self) {
+        let dialog = rfd::FileDialog::new()
+            .add_filter("ron", &["ron"])
+            .set_file_name("config.ron");
+        if let Some(path) = dialog.save_file() {
+            let res = ron::ser::to_string_pretty(&self.config, PrettyConfig::default())
+                .map_err(|e| e.to_string())
+                .and_then(|s| std::fs::write(&path, s).map_err(|e| e.to_string()));
+            if let Err(e) = res {
+                self.toasts
+                    .error("Error exporting config", format!("{}: {e}", path.display()));
+            }
+        }
+    }
+
+    pub fn import_config_dialog(&mut self) {
+        let dialog = rfd::FileDialog::new().add_filter("ron", &["ron"]);
+        if let Some(path) = dialog.pick_file() {
+            let res = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|s| ron::from_str::<Config>(&s).map_err(|e| e.to_string()));
+            match res {
+                Ok(config) => self.config_import = Some(ConfigImport::new(&self.config, config)),
+                Err(e) => {
+                    let details = format!("{}: {e}", path.display());
+                    self.toasts.error("Error importing config", details);
+                }
+            }
+        }
+    }
+
+    pub fn export_sanity_report_dialog(&mut self) {
+        let Some(files) = &self.selectable_files else {
+            return;
+        };
+        let dialog = rfd::FileDialog::new()
+            .add_filter("csv", &["csv"])
+            .set_file_name("sanity_report.csv");
+        if let Some(path) = dialog.save_file() {
+            let res = std::fs::File::create(&path).and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                sanity_rules::write_report(&mut writer, files, &self.config.sanity_rules)?;
+                writer.flush()
+            });
+            if let Err(e) = res {
+                self.toasts.error(
+                    "Error exporting sanity report",
+                    format!("{}: {e}", path.display()),
+                );
+            }
+        }
+    }
+
+    pub fn export_annotations_dialog(&mut self) {
+        let dialog = rfd::FileDialog::new()
+            .add_filter("annotations", &[ANNOTATIONS_EXTENSION])
+            .set_file_name(format!("annotations.{ANNOTATIONS_EXTENSION}"));
+        if let Some(path) = dialog.save_file() {
+            let annotations = AnnotationSet::from_config(&self.config);
+            if let Err(e) = annotations::save(&path, &annotations) {
+                self.toasts
+                    .error("Error exporting annotations", format!("{e:#}"));
+            }
+        }
+    }
+
+    pub fn export_reference_dialog(&mut self) {
+        let dialog = rfd::FileDialog::new()
+            .add_filter("reference", &[REFERENCE_EXTENSION])
+            .set_file_name(format!("reference.{REFERENCE_EXTENSION}"));
+        if let Some(path) = dialog.save_file() {
+            let streams = self.data.as_ref().map_or(&[][..], |d| &d.streams);
+            let library = &self.config.expr_library;
+            let reference = Reference::new(streams, library, &self.config.channel_units);
+            if let Err(e) = reference::save(&path, &reference) {
+                self.toasts
+                    .error("Error exporting reference", format!("{e:#}"));
+            }
+        }
+    }
+
+    pub fn import_annotations_dialog(&mut self) {
+        let dialog = rfd::FileDialog::new().add_filter("annotations", &[ANNOTATIONS_EXTENSION]);
+        if let Some(path) = dialog.pick_file() {
+            match annotations::load(&path) {
+                Ok(annotations) => {
+                    annotations.apply(&mut self.config);
+                    if let Some(data) = &mut self.data {
+                        let trigger = &self.config.lap_trigger;
+                        data.laps = laps::detect(&data.streams, data.track.as_ref(), trigger);
+                    }
+                }
+                Err(e) => self
+                    .toasts
+                    .error("Error importing annotations", format!("{e:#}")),
+            }
+        }
+    }
+
+    pub fn pick_regression_session_dialog(&mut self) {
+        let Some(test) = &mut self.regression else {
+            return;
+        };
+        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+            match load_session(dir.clone()) {
+                Ok(streams) => {
+                    test.after_dir = Some(dir);
+                    test.after = Some(streams);
+                    test.results = None;
+                    test.error = None;
+                }
+                Err(e) => test.error = Some(format!("{e:#}")),
+            }
+        }
+    }
+
+    pub fn open_run_b_dialog(&mut self) {
+        let Some(data) = &mut self.data else {
+            return;
+        };
+        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+            match load_session(dir.clone()) {
+                Ok(streams) => data.run_b = Some(RunB::new(dir, streams)),
+                Err(e) => self.toasts.error("Error opening run B", format!("{e:#}")),
+            }
+        }
+    }
+
+    pub fn add_scoreboard_session_dialog(&mut self) {
+        let Some(board) = &mut self.scoreboard else {
+            return;
+        };
+        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+            match load_session(dir.clone()) {
+                Ok(streams) => {
+                    board.sessions.push(ScoreSession { dir, streams });
+                    board.invalidate();
+                    board.error = None;
+                }
+                Err(e) => board.error = Some(format!("{e:#}")),
+            }
+        }
+    }
+
+    pub fn export_image_dialog(&mut self, ctx: &Context) {
+        let (Some(export), Some(data)) = (&self.image_export, &self.data) else {
+            return;
+        };
+        let Some(figure) = plot::tab_figure(data, &self.config, export.width as usize) else {
+            let details = "Only time plots can be exported as images";
+            self.toasts.error("Error exporting image", details);
+            return;
+        };
+
+        let ext = export.format.extension();
+        let name = format!("{}.{ext}", figure.title);
+        let dialog = rfd::FileDialog::new()
+            .add_filter(export.format.name(), &[ext])
+            .set_file_name(name);
+        if let Some(path) = dialog.save_file() {
+            if let Err(e) = image_export::export(ctx, &path, &figure, export) {
+                self.toasts.error("Error exporting image", format!("{e:#}"));
+            }
+        }
+    }
+
+    pub fn apply_config(&mut self, config: Config) {
+        self.config = config;
+        if self.config.tabs.is_empty() {
+            self.config.tabs.push(TabConfig::named("Tab 1".into()));
+        }
+        self.config.selected_tab = self.config.selected_tab.min(self.config.tabs.len() - 1);
+        self.config.split_tab = self.config.split_tab.min(self.config.tabs.len() - 1);
+        if let Some(data) = &mut self.data {
+            if data.derived != self.config.derived_channels {
+                rebuild_streams(data, &self.config);
+                return;
+            }
+            data.plots = plot::start_jobs(&self.config, data);
+            data.x_stash.clear();
+            data.laps = laps::detect(&data.streams, data.track.as_ref(), &self.config.lap_trigger);
+        }
+    }
+
+    /// Appends the tabs of the config after the current ones, the plots of the current tabs are
+    /// kept.
+    pub fn merge_config(&mut self, config: Config) {
+        let count = config_diff::merge_tabs(&mut self.config, config.tabs);
+        if let Some(data) = &mut self.data {
+            let new_tabs = &self.config.tabs[self.config.tabs.len() - count..];
+            for t in new_tabs.iter() {
+                let values = (t.plots.iter())
+                    .map(|p| data.start_job(&t.plot_expr(p), &self.config))
+                    .collect();
+                data.plots.push(values);
+            }
+        }
+    }
+
+    pub fn save_project_dialog(&mut self) {
+        let Some(files) = &self.files else {
+            return;
+        };
+        let mut dialog = rfd::FileDialog::new()
+            .add_filter("s3plot project", &[PROJECT_EXTENSION])
+            .set_file_name(format!("analysis.{PROJECT_EXTENSION}"));
+        if let Some(dir) = util::common_parent_dir(files.items.iter()) {
+            dialog = dialog.set_directory(dir);
+        }
+        if let Some(path) = dialog.save_file() {
+            if let Err(e) = project::save(&path, &self.config, files) {
+                self.toasts.error("Error saving project", format!("{e:#}"));
+            }
+        }
+    }
+
+    pub fn open_project_dialog(&mut self) {
+        let dialog = rfd::FileDialog::new().add_filter("s3plot project", &[PROJECT_EXTENSION]);
+        if let Some(path) = dialog.pick_file() {
+            self.open_project(&path);
+        }
+    }
+
+    pub fn open_project(&mut self, path: &Path) {
+        match project::load(path) {
+            Ok(project) => {
+                self.apply_config(project.config);
+                project.view.apply(&mut self.config);
+                self.try_open_files(project.files, false);
+            }
+            Err(e) => self.toasts.error("Error opening project", format!("{e:#}")),
+        }
+    }
+
+    pub fn inspect_file_dialog(&mut self) {
+        let dialog = rfd::FileDialog::new().add_filter("s3lg", &["s3lg"]);
+        if let Some(path) = dialog.pick_file() {
+            match Inspector::open(path.clone()) {
+                Ok(inspector) => self.inspector = Some(inspector),
+                Err(e) => {
+                    let details = format!("{}: {e}", path.display());
+                    self.toasts.error("Error inspecting file", details);
+                }
+            }
+        }
+    }
+
     pub fn detect_files_being_dropped(&mut self, ctx: &Context) {
         // Preview hovering files
         if !ctx.input(|i| i.raw.hovered_files.is_empty()) {
@@ -101,30 +434,68 @@ impl PlotApp {
         }
 
         // Collect dropped files
-        if !ctx.input(|i| i.raw.dropped_files.is_empty()) {
-            if let Some(p) = ctx.input(|i| i.raw.dropped_files.first().and_then(|f| f.path.clone()))
-            {
-                self.try_open_dir(p);
-            }
+        let dropped = ctx.input(|i| {
+            (i.raw.dropped_files.iter())
+                .filter_map(|f| f.path.clone())
+                .collect::<Vec<_>>()
+        });
+        match dropped.as_slice() {
+            [] => (),
+            [p] if p.extension().is_some_and(|e| e == PROJECT_EXTENSION) => self.open_project(p),
+            [p] if p.is_dir() => self.try_open_dir(p.clone()),
+            _ => match collect_dropped_files(&dropped) {
+                Ok(files) if !files.items.is_empty() => self.try_open_files(files, true),
+                Ok(_) => self.toasts.error("No log files were dropped", ""),
+                Err(e) => self
+                    .toasts
+                    .error("Error opening dropped files", e.to_string()),
+            },
         }
     }
 
     pub fn try_open_dir(&mut self, dir: PathBuf) {
-        if let Ok(files) = find_files(dir) {
-            self.selectable_files = Some(open_files(files));
+        let rules = self.config.sanity_rules.clone();
+        match find_files(dir.clone()) {
+            Ok(files) => self.file_load = Some(FileLoad::start(files, true, rules)),
+            Err(e) => {
+                let details = format!("{}: {e}", dir.display());
+                self.toasts.error("Error opening directory", details);
+            }
         }
     }
 
+    /// Parses the files in the background, see [`PlotApp::poll_file_load`].
     pub fn try_open_files(&mut self, files: Files, always_show_dialog: bool) {
-        let selectable_files = open_files(files);
+        let rules = self.config.sanity_rules.clone();
+        self.file_load = Some(FileLoad::start(files, always_show_dialog, rules));
+    }
+
+    /// Shows the parsed files once all of them are done.
+    pub fn poll_file_load(&mut self) {
+        if !self.file_load.as_ref().is_some_and(FileLoad::is_done) {
+            return;
+        }
+        let load = self.file_load.take().unwrap();
+        let always_show_dialog = load.always_show_dialog;
+        let selectable_files = load.join();
 
         let all_succeeded = selectable_files.with_error.is_empty();
         let sanity_check_passed = selectable_files
             .by_header
             .iter()
-            .all(|g| g.iter().all(|f| f.sanity_check.is_ok()));
-
-        if all_succeeded && sanity_check_passed && !always_show_dialog {
+            .all(|g| g.iter().all(SelectableFile::passed_checks));
+
+        let missing_start_times = selectable_files.missing_start_times();
+        // show the read warnings
+        let read_warnings =
+            (selectable_files.by_header.iter().flatten()).any(|f| !f.read_warnings.is_empty());
+
+        if all_succeeded
+            && sanity_check_passed
+            && !missing_start_times
+            && !read_warnings
+            && !always_show_dialog
+        {
             self.concat_and_show(selectable_files);
         } else {
             self.selectable_files = Some(selectable_files);
@@ -132,27 +503,27 @@ impl PlotApp {
     }
 
     pub fn concat_and_show(&mut self, selectable_files: SelectableFiles) {
-        let mut streams = Vec::with_capacity(selectable_files.by_header.len());
-        let mut files = Vec::new();
-        for group in selectable_files.by_header.into_iter() {
-            let additional = group.iter().skip(1).map(|s| s.stream.len()).sum();
-            let mut group_iter = group.into_iter().filter(|f| f.selected);
-
-            let mut first = match group_iter.next() {
-                Some(f) => f,
-                None => continue,
-            };
-            first.stream.reserve(additional);
-            files.push(first.file);
-
-            for s in group_iter {
-                first.stream.extend(&s.stream);
-                files.push(s.file);
+        let mut warnings = Vec::new();
+        if selectable_files.missing_start_times() {
+            warnings.push(MISSING_START_WARNING.to_string());
+        }
+        for f in selectable_files
+            .by_header
+            .iter()
+            .flatten()
+            .filter(|f| f.selected)
+        {
+            let name = f
+                .file
+                .strip_prefix(&selectable_files.dir)
+                .unwrap_or(&f.file);
+            for block in f.read_warnings.corrupted.iter() {
+                warnings.push(format!("{}: {block}", name.display()));
             }
-
-            streams.push(first.stream);
         }
 
+        let (streams, files, align_warnings) = concat_streams(selectable_files.by_header);
+        warnings.extend(align_warnings);
         let files = Files {
             dir: selectable_files.dir,
             items: files,
@@ -163,34 +534,204 @@ impl PlotApp {
             self.files = None;
             self.data = None;
         } else {
-            let mut lowest_delta = (0, 0);
-            for (i, s) in streams.iter().enumerate() {
-                let delta = s.time.windows(2).take(20).map(|w| w[1] - w[0]).sum::<u32>()
-                    / std::cmp::min(20, s.time.len() as u32);
-                if delta < lowest_delta.1 {
-                    lowest_delta = (i, delta);
-                }
-            }
+            let session = SessionMeta::load(&files.dir, &streams);
+            self.files = Some(files);
+            let enabled_sources = (streams.iter())
+                .map(|s| vec![true; s.sources.len()])
+                .collect();
+            let streams: Arc<[LogStream]> = streams.into();
+            let mut data = PlotData {
+                all_streams: Arc::clone(&streams),
+                enabled_sources,
+                disabled_files: HashSet::new(),
+                streams,
+                plots: Vec::new(),
+                warnings,
+                channels: Vec::new(),
+                rates: Vec::new(),
+                track: None,
+                laps: Vec::new(),
+                series_cache: None,
+                session,
+                x_stash: HashMap::new(),
+                spill: SpillStore::new(),
+                run_b: None,
+                derived: Vec::new(),
+                derived_errors: Vec::new(),
+                power_violations: Vec::new(),
+                side_series: SideSeries::default(),
+                find: Find::default(),
+                revision: 0,
+                recent_series: RecentSeries::default(),
+                edited: Vec::new(),
+            };
+            update_streams(&mut data, &self.config);
+            self.data = Some(data);
+        }
+    }
+
+    pub fn apply_source_selection(&mut self) {
+        if let Some(data) = &mut self.data {
+            data.disabled_files = (data.all_streams.iter().zip(data.enabled_sources.iter()))
+                .flat_map(|(s, enabled)| s.sources.iter().zip(enabled.iter()))
+                .filter(|(_, e)| !**e)
+                .map(|(s, _)| s.path.clone())
+                .collect();
+            rebuild_streams(data, &self.config);
+        }
+    }
+}
 
-            streams.swap(0, lowest_delta.0);
+/// Rebuilds the streams from the enabled source files and the derived channels without parsing
+/// the files again, and restarts the plots.
+pub fn rebuild_streams(data: &mut PlotData, cfg: &Config) {
+    let all_enabled = data.enabled_sources.iter().flatten().all(|e| *e);
+    data.streams = if all_enabled {
+        Arc::clone(&data.all_streams)
+    } else {
+        (data.all_streams.iter().zip(data.enabled_sources.iter()))
+            .map(|(s, enabled)| s.select_sources(enabled))
+            .collect()
+    };
+    data.x_stash.clear();
+    update_streams(data, cfg);
+}
 
-            self.files = Some(files);
-            self.data = Some({
-                let streams = streams.into();
-                let plots = (self.config.tabs.iter())
-                    .map(|t| {
-                        t.plots
-                            .iter()
-                            .map(|p| {
-                                PlotValues::Job(Job::start(p.expr.clone(), Arc::clone(&streams)))
-                            })
-                            .collect()
-                    })
-                    .collect();
-                PlotData { streams, plots }
+/// Adds the derived channels, then recomputes everything that is derived from the streams and
+/// restarts the plots.
+fn update_streams(data: &mut PlotData, cfg: &Config) {
+    data.revision += 1;
+    data.derived = cfg.derived_channels.clone();
+    data.derived_errors = Vec::new();
+    if !cfg.derived_channels.is_empty() {
+        let mut streams = data.streams.to_vec();
+        let library = &cfg.expr_library;
+        let timing = cfg.timing();
+        data.derived_errors =
+            eval::derive_channels(&mut streams, &cfg.derived_channels, library, timing);
+        data.streams = streams.into();
+    }
+
+    let streams = &data.streams;
+    data.channels = (streams.iter())
+        .map(|s| {
+            (s.entries.iter())
+                .map(|e| ChannelInfo {
+                    sparkline: e.sparkline(s.len(), SPARKLINE_POINTS),
+                    constant: e.is_constant(s.len()),
+                })
+                .collect()
+        })
+        .collect();
+    data.rates = streams.iter().map(LogStream::sample_periods).collect();
+    data.track = track::reconstruct(streams);
+    data.laps = laps::detect(streams, data.track.as_ref(), &cfg.lap_trigger);
+    // the cache is keyed by the files, so each selection has its own
+    let files = (streams.iter().flat_map(|s| s.sources.iter()))
+        .map(|s| s.path.clone())
+        .collect::<Vec<_>>();
+    data.series_cache = SeriesCache::new(&files, &cfg.derived_channels);
+    data.plots = plot::start_jobs(cfg, data);
+}
+
+/// Reads all files of the directory and concatenates them like [`PlotApp::concat_and_show`],
+/// without user interaction. Files that fail the sanity check are skipped.
+pub fn load_session(dir: PathBuf) -> anyhow::Result<Arc<[LogStream]>> {
+    let files = find_files(dir.clone()).with_context(|| format!("reading {}", dir.display()))?;
+    let selectable_files = open_files(files, &AtomicUsize::new(0), &[]);
+    let (streams, _, _) = concat_streams(selectable_files.by_header);
+    if streams.is_empty() {
+        anyhow::bail!("no valid log files found in {}", dir.display());
+    }
+    Ok(streams.into())
+}
+
+/// Concatenates the selected files of each header group into one stream. The streams are
+/// ordered and aligned, so they can be evaluated together. Also returns warnings about streams
+/// that couldn't be aligned.
+fn concat_streams(
+    by_header: Vec<Vec<SelectableFile>>,
+) -> (Vec<LogStream>, Vec<PathBuf>, Vec<String>) {
+    let mut streams = Vec::with_capacity(by_header.len());
+    let mut files = Vec::new();
+    for group in by_header.into_iter() {
+        let additional = group.iter().skip(1).map(|s| s.stream.len()).sum();
+        let mut group_iter = group.into_iter().filter(|f| f.selected);
+
+        let mut first = match group_iter.next() {
+            Some(f) => f,
+            None => continue,
+        };
+        first.stream.reserve(additional);
+        first.stream.sources.push(data::SourceFile {
+            path: first.file.clone(),
+            samples: 0..first.stream.len(),
+        });
+        files.push(first.file);
+
+        for s in group_iter {
+            let start = first.stream.len();
+            first.stream.extend(&s.stream);
+            first.stream.sources.push(data::SourceFile {
+                path: s.file.clone(),
+                samples: start..first.stream.len(),
             });
+            files.push(s.file);
         }
+
+        streams.push(first.stream);
     }
+
+    let mut warnings = Vec::new();
+    if !streams.is_empty() {
+        let mut lowest_delta = (0, u32::MAX);
+        for (i, s) in streams.iter().enumerate() {
+            let deltas = s.time.windows(2).take(20).map(|w| w[1] - w[0]);
+            // streams with less than two samples have no sample rate
+            let Some(delta) = (deltas.clone().sum::<u32>()).checked_div(deltas.count() as u32)
+            else {
+                continue;
+            };
+            if delta < lowest_delta.1 {
+                lowest_delta = (i, delta);
+            }
+        }
+
+        streams.swap(0, lowest_delta.0);
+        warnings = align_start_times(&mut streams);
+    }
+
+    (streams, files, warnings)
+}
+
+/// Aligns streams with a start timestamp to the earliest one. Streams without one (v1) can't be
+/// aligned and keep their relative time, which effectively starts them at the earliest start.
+/// The same goes for streams that start too long after the earliest one, which are returned as
+/// warnings.
+fn align_start_times(streams: &mut [LogStream]) -> Vec<String> {
+    let Some(earliest) = streams.iter().filter_map(|s| s.start).min() else {
+        return Vec::new();
+    };
+
+    let mut warnings = Vec::new();
+    for s in streams.iter_mut() {
+        let Some(start) = s.start else {
+            continue;
+        };
+        let offset = (start - earliest).num_milliseconds();
+        let aligned = u32::try_from(offset).is_ok_and(|offset| s.shift_time(offset));
+        if aligned {
+            s.start = Some(earliest);
+        } else {
+            let name = (s.sources.first())
+                .and_then(|s| s.path.file_name())
+                .map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+            warnings.push(format!(
+                "{name}: starts {start}, too long after {earliest} to align its time"
+            ));
+        }
+    }
+    warnings
 }
 
 fn find_files(dir: PathBuf) -> Result<Files, data::Error> {
@@ -202,7 +743,7 @@ fn find_files(dir: PathBuf) -> Result<Files, data::Error> {
             continue;
         }
 
-        if path.extension().map_or(false, |e| e == "s3lg") {
+        if is_log_file(&path) {
             items.push(path);
         }
     }
@@ -212,11 +753,71 @@ fn find_files(dir: PathBuf) -> Result<Files, data::Error> {
     Ok(Files { dir, items })
 }
 
-fn open_files(files: Files) -> SelectableFiles {
+/// Collects the log files of dropped files and directories, only the files directly inside of
+/// directories are used. The directory of the list is the common ancestor of all paths.
+fn collect_dropped_files(paths: &[PathBuf]) -> Result<Files, data::Error> {
+    let mut items = Vec::new();
+    let mut dirs = Vec::new();
+    for p in paths.iter() {
+        if p.is_dir() {
+            items.extend(find_files(p.clone())?.items);
+            dirs.push(p.as_path());
+        } else if is_log_file(p) {
+            items.push(p.clone());
+            dirs.extend(p.parent());
+        }
+    }
+    items.sort();
+    items.dedup();
+
+    let dir = (dirs.iter().skip(1))
+        .fold(dirs.first().map(|d| d.to_path_buf()), |common, d| {
+            common.map(|c| common_ancestor(&c, d))
+        })
+        .unwrap_or_default();
+    Ok(Files { dir, items })
+}
+
+pub fn is_log_file(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "s3lg" || e == "ld")
+}
+
+fn common_ancestor(a: &Path, b: &Path) -> PathBuf {
+    (a.components().zip(b.components()))
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a)
+        .collect()
+}
+
+/// Parses the files in parallel and groups them by header, keeping the order of the files.
+/// `parsed` is incremented after each file.
+fn open_files(files: Files, parsed: &AtomicUsize, rules: &[SanityRule]) -> SelectableFiles {
+    let num_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let num_threads = num_threads.clamp(1, files.items.len().max(1));
+    let mut opened_files = std::thread::scope(|s| {
+        let workers = (0..num_threads)
+            .map(|t| {
+                let items = &files.items;
+                s.spawn(move || {
+                    (items.iter().enumerate().skip(t).step_by(num_threads))
+                        .map(|(i, f)| {
+                            let opened_file = open_file(f, rules);
+                            parsed.fetch_add(1, Ordering::Relaxed);
+                            (i, opened_file)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        (workers.into_iter())
+            .flat_map(|w| w.join().expect("failed to join file parsing thread"))
+            .collect::<Vec<_>>()
+    });
+    opened_files.sort_by_key(|(i, _)| *i);
+
     let mut by_header: Vec<Vec<SelectableFile>> = Vec::new();
     let mut with_error = Vec::new();
-    'outer: for f in files.items.iter() {
-        let opened_file = open_file(f);
+    'outer: for (_, opened_file) in opened_files.into_iter() {
         match opened_file {
             Ok(selectable_file) => {
                 for group in by_header.iter_mut() {
@@ -238,21 +839,30 @@ fn open_files(files: Files) -> SelectableFiles {
     }
 }
 
-fn open_file(path: &Path) -> Result<SelectableFile, ErrorFile> {
-    let result = File::open(path).map_err(From::from).and_then(|f| {
-        let mut reader = BufReader::new(f);
-        data::read_file(&mut reader)
-    });
+fn open_file(path: &Path, rules: &[SanityRule]) -> Result<SelectableFile, ErrorFile> {
+    // reading the whole file at once is a lot faster than decoding value by value from a reader
+    let is_motec = path.extension().is_some_and(|e| e == "ld");
+    let result = std::fs::read(path)
+        .map_err(From::from)
+        .and_then(|bytes| match is_motec {
+            true => data::parse_motec(&bytes).map(|stream| (stream, ReadWarnings::default())),
+            false => data::parse_file(&bytes),
+        });
 
     result
-        .map(|stream| {
+        .map(|(stream, read_warnings)| {
             let sanity_check = data::sanity_check(&stream.entries);
-            SelectableFile {
-                selected: sanity_check.is_ok(),
+            let rule_checks = sanity_rules::check_all(rules, &stream);
+            let mut file = SelectableFile {
+                selected: false,
                 file: path.to_path_buf(),
                 stream,
                 sanity_check,
-            }
+                rule_checks,
+                read_warnings,
+            };
+            file.selected = file.passed_checks();
+            file
         })
         .map_err(|error| ErrorFile {
             file: path.to_path_buf(),
@@ -0,0 +1,233 @@
+use egui::{Color32, ColorImage};
+use egui_plot::PlotPoint;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_HEATMAP_BINS: usize = 50;
+pub const MAX_HEATMAP_BINS: usize = 256;
+
+/// What the cells of a heatmap tab are colored by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CellValue {
+    /// number of samples in the cell
+    #[default]
+    Count,
+    /// mean of the second expression of the tab over the samples in the cell
+    Mean,
+}
+
+impl CellValue {
+    pub const ALL: [Self; 2] = [Self::Count, Self::Mean];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Count => "Count",
+            Self::Mean => "Mean of 2nd",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HeatmapConfig {
+    /// number of cells along each axis
+    pub bins: usize,
+    pub value: CellValue,
+}
+
+impl Default for HeatmapConfig {
+    fn default() -> Self {
+        Self {
+            bins: DEFAULT_HEATMAP_BINS,
+            value: CellValue::Count,
+        }
+    }
+}
+
+/// Colors of the density colormap, from low to high density.
+const COLORMAP: [Color32; 5] = [
+    Color32::from_rgb(0x44, 0x01, 0x54),
+    Color32::from_rgb(0x3b, 0x52, 0x8b),
+    Color32::from_rgb(0x21, 0x90, 0x8d),
+    Color32::from_rgb(0x5d, 0xc9, 0x63),
+    Color32::from_rgb(0xfd, 0xe7, 0x25),
+];
+
+/// Two-dimensional histogram of points.
+pub struct Density {
+    pub image: ColorImage,
+    pub min: PlotPoint,
+    pub max: PlotPoint,
+}
+
+impl Density {
+    pub fn center(&self) -> PlotPoint {
+        PlotPoint::new(
+            0.5 * (self.min.x + self.max.x),
+            0.5 * (self.min.y + self.max.y),
+        )
+    }
+
+    pub fn size(&self) -> [f32; 2] {
+        [
+            (self.max.x - self.min.x) as f32,
+            (self.max.y - self.min.y) as f32,
+        ]
+    }
+}
+
+/// Bins the points into a `bins` x `bins` grid spanning `min..max` and colors each cell by its
+/// logarithmic point count. Empty cells are transparent.
+pub fn density(points: &[PlotPoint], bins: usize, min: PlotPoint, max: PlotPoint) -> Density {
+    let mut counts = vec![0u32; bins * bins];
+    let width = max.x - min.x;
+    let height = max.y - min.y;
+    if width > 0.0 && height > 0.0 {
+        for p in points.iter() {
+            let x = ((p.x - min.x) / width * bins as f64).floor();
+            let y = ((p.y - min.y) / height * bins as f64).floor();
+            if !(0.0..bins as f64).contains(&x) || !(0.0..bins as f64).contains(&y) {
+                continue;
+            }
+            // image rows start at the top
+            let row = bins - 1 - y as usize;
+            counts[row * bins + x as usize] += 1;
+        }
+    }
+
+    let max_count = counts.iter().copied().max().unwrap_or(0);
+    let scale = ((max_count + 1) as f32).ln();
+    let pixels = counts
+        .iter()
+        .map(|&c| {
+            if c == 0 {
+                Color32::TRANSPARENT
+            } else {
+                colormap(((c + 1) as f32).ln() / scale)
+            }
+        })
+        .collect();
+
+    Density {
+        image: ColorImage {
+            size: [bins, bins],
+            pixels,
+        },
+        min,
+        max,
+    }
+}
+
+/// A [`Density`] over the bounds of the points, with the value of every cell.
+pub struct Heatmap {
+    pub density: Density,
+    /// by row from the bottom and column, `NaN` for empty cells
+    pub cells: Vec<f64>,
+    pub bins: usize,
+    /// range of the finite cell values
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Heatmap {
+    /// Value of the cell that contains `p`.
+    pub fn value_at(&self, p: PlotPoint) -> Option<f64> {
+        let size = self.density.size();
+        let x = ((p.x - self.density.min.x) / size[0] as f64 * self.bins as f64).floor();
+        let y = ((p.y - self.density.min.y) / size[1] as f64 * self.bins as f64).floor();
+        let range = 0.0..self.bins as f64;
+        if !range.contains(&x) || !range.contains(&y) {
+            return None;
+        }
+        let v = self.cells[y as usize * self.bins + x as usize];
+        v.is_finite().then_some(v)
+    }
+}
+
+/// Bins the points into a `bins` x `bins` grid over their bounds. Each cell holds the mean of the
+/// values at the indices of its points, or the number of points if there are no values. Counts
+/// are colored logarithmically, means linearly.
+pub fn heatmap(points: &[PlotPoint], values: Option<&[f64]>, bins: usize) -> Heatmap {
+    let finite = || points.iter().filter(|p| p.x.is_finite() && p.y.is_finite());
+    let mut min = PlotPoint::new(f64::INFINITY, f64::INFINITY);
+    let mut max = PlotPoint::new(f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for p in finite() {
+        (min.x, min.y) = (min.x.min(p.x), min.y.min(p.y));
+        (max.x, max.y) = (max.x.max(p.x), max.y.max(p.y));
+    }
+    if finite().next().is_none() {
+        (min, max) = (PlotPoint::new(0.0, 0.0), PlotPoint::new(1.0, 1.0));
+    }
+    if max.x <= min.x {
+        (min.x, max.x) = (min.x - 0.5, max.x + 0.5);
+    }
+    if max.y <= min.y {
+        (min.y, max.y) = (min.y - 0.5, max.y + 0.5);
+    }
+
+    let mut counts = vec![0u32; bins * bins];
+    let mut sums = vec![0.0; bins * bins];
+    let (width, height) = (max.x - min.x, max.y - min.y);
+    // the points on the upper bounds belong to the last cells
+    let last = (bins - 1) as f64;
+    for (i, p) in points.iter().enumerate() {
+        let x = ((p.x - min.x) / width * bins as f64).floor().min(last);
+        let y = ((p.y - min.y) / height * bins as f64).floor().min(last);
+        if !(0.0..=last).contains(&x) || !(0.0..=last).contains(&y) {
+            continue;
+        }
+        let cell = y as usize * bins + x as usize;
+        match values.map(|v| v.get(i).copied().unwrap_or(f64::NAN)) {
+            Some(v) if !v.is_finite() => continue,
+            Some(v) => sums[cell] += v,
+            None => (),
+        }
+        counts[cell] += 1;
+    }
+
+    let cells = (counts.iter().zip(sums.iter()))
+        .map(|(&c, &s)| match (c, values) {
+            (0, _) => f64::NAN,
+            (c, Some(_)) => s / c as f64,
+            (c, None) => c as f64,
+        })
+        .collect::<Vec<_>>();
+    let finite_cells = || cells.iter().copied().filter(|v| v.is_finite());
+    let low = finite_cells().fold(f64::INFINITY, f64::min);
+    let high = finite_cells().fold(f64::NEG_INFINITY, f64::max);
+
+    let color = |v: f64| match values {
+        Some(_) if high > low => colormap(((v - low) / (high - low)) as f32),
+        Some(_) => colormap(0.5),
+        None => colormap(((v + 1.0).ln() / (high + 1.0).ln()) as f32),
+    };
+    // image rows start at the top
+    let pixels = (0..bins)
+        .rev()
+        .flat_map(|row| cells[row * bins..(row + 1) * bins].iter())
+        .map(|&v| match v.is_finite() {
+            true => color(v),
+            false => Color32::TRANSPARENT,
+        })
+        .collect();
+
+    Heatmap {
+        density: Density {
+            image: ColorImage {
+                size: [bins, bins],
+                pixels,
+            },
+            min,
+            max,
+        },
+        cells,
+        bins,
+        low,
+        high,
+    }
+}
+
+/// Maps `t` in `0.0..=1.0` onto the colormap.
+pub fn colormap(t: f32) -> Color32 {
+    let pos = t.clamp(0.0, 1.0) * (COLORMAP.len() - 1) as f32;
+    let i = (pos as usize).min(COLORMAP.len() - 2);
+    COLORMAP[i].lerp_to_gamma(COLORMAP[i + 1], pos - i as f32)
+}
@@ -0,0 +1,544 @@
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use anyhow::Context as _;
+use egui::{Align2, Color32, ComboBox, DragValue, FontId, Pos2, Rect, Ui, Vec2};
+use egui_plot::PlotPoint;
+
+use crate::util::{self, format_time};
+
+const MARGIN_LEFT: f32 = 80.0;
+const MARGIN_RIGHT: f32 = 40.0;
+const MARGIN_TOP: f32 = 40.0;
+const MARGIN_BOTTOM: f32 = 50.0;
+const TITLE_SIZE: f32 = 18.0;
+const LABEL_SIZE: f32 = 13.0;
+const TICK_LENGTH: f32 = 5.0;
+const NUM_TICKS: f64 = 8.0;
+const LINE_WIDTH: f32 = 1.5;
+const LEGEND_PADDING: f32 = 8.0;
+const LEGEND_SWATCH_WIDTH: f32 = 20.0;
+
+const BACKGROUND: Color32 = Color32::WHITE;
+const FOREGROUND: Color32 = Color32::from_gray(0x20);
+const GRID: Color32 = Color32::from_gray(0xe0);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImageFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+impl ImageFormat {
+    pub const ALL: [Self; 2] = [Self::Png, Self::Svg];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::Svg => "SVG",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Svg => "svg",
+        }
+    }
+}
+
+/// State of the image export dialog.
+pub struct ImageExport {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for ImageExport {
+    fn default() -> Self {
+        Self {
+            format: ImageFormat::Png,
+            width: 1600,
+            height: 900,
+        }
+    }
+}
+
+/// A line plot independent of the ui, that can be rendered to an image.
+pub struct Figure {
+    pub title: String,
+    /// Whether the x-values are times in seconds.
+    pub x_time: bool,
+    pub x_range: (f64, f64),
+    pub y_range: (f64, f64),
+    pub series: Vec<Series>,
+}
+
+pub struct Series {
+    pub name: String,
+    pub color: Color32,
+    /// `None` for the default line width
+    pub width: Option<f32>,
+    pub points: Vec<PlotPoint>,
+}
+
+/// Shows the export settings, returns `Some(true)` if the image should be exported and
+/// `Some(false)` if it was cancelled.
+pub fn image_export_dialog(ui: &mut Ui, export: &mut ImageExport) -> Option<bool> {
+    ui.horizontal(|ui| {
+        ComboBox::from_label("format")
+            .selected_text(export.format.name())
+            .show_ui(ui, |ui| {
+                for f in ImageFormat::ALL {
+                    ui.selectable_value(&mut export.format, f, f.name());
+                }
+            });
+    });
+    ui.horizontal(|ui| {
+        ui.add(
+            DragValue::new(&mut export.width)
+                .range(100..=10_000)
+                .suffix(" px"),
+        );
+        ui.label("x");
+        ui.add(
+            DragValue::new(&mut export.height)
+                .range(100..=10_000)
+                .suffix(" px"),
+        );
+    });
+
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        if ui.button("Export").clicked() {
+            return Some(true);
+        }
+        if ui.button("Cancel").clicked() {
+            return Some(false);
+        }
+        None
+    })
+    .inner
+}
+
+pub fn export(
+    ctx: &egui::Context,
+    path: &Path,
+    figure: &Figure,
+    export: &ImageExport,
+) -> anyhow::Result<()> {
+    let size = Vec2::new(export.width as f32, export.height as f32);
+    match export.format {
+        ImageFormat::Svg => {
+            let mut canvas = SvgCanvas::new(size);
+            draw(&mut canvas, figure, size);
+            let svg = canvas.finish();
+            std::fs::write(path, svg).with_context(|| format!("writing {}", path.display()))?;
+        }
+        ImageFormat::Png => {
+            let mut canvas = RasterCanvas::new(ctx, export.width as usize, export.height as usize);
+            draw(&mut canvas, figure, size);
+            let file = File::create(path).with_context(|| format!("writing {}", path.display()))?;
+            canvas.write_png(BufWriter::new(file))?;
+        }
+    }
+    Ok(())
+}
+
+trait Canvas {
+    fn set_clip(&mut self, clip: Option<Rect>);
+    fn fill_rect(&mut self, rect: Rect, color: Color32);
+    fn polyline(&mut self, points: &[Pos2], color: Color32, width: f32);
+    fn text(&mut self, pos: Pos2, anchor: Align2, text: &str, size: f32, color: Color32);
+}
+
+fn draw(canvas: &mut impl Canvas, figure: &Figure, size: Vec2) {
+    let area = Rect::from_min_max(
+        Pos2::new(MARGIN_LEFT, MARGIN_TOP),
+        Pos2::new(size.x - MARGIN_RIGHT, size.y - MARGIN_BOTTOM),
+    );
+    let (x_min, x_max) = figure.x_range;
+    let (y_min, y_max) = figure.y_range;
+    let to_screen = |p: PlotPoint| {
+        let x = area.left() + ((p.x - x_min) / (x_max - x_min)) as f32 * area.width();
+        let y = area.bottom() - ((p.y - y_min) / (y_max - y_min)) as f32 * area.height();
+        Pos2::new(x, y)
+    };
+
+    canvas.fill_rect(Rect::from_min_size(Pos2::ZERO, size), BACKGROUND);
+
+    let title_pos = Pos2::new(area.center().x, MARGIN_TOP / 2.0);
+    canvas.text(
+        title_pos,
+        Align2::CENTER_CENTER,
+        &figure.title,
+        TITLE_SIZE,
+        FOREGROUND,
+    );
+
+    // grid and ticks
+    let x_step = util::nice_step((x_max - x_min) / NUM_TICKS);
+    for x in ticks(x_min, x_max, x_step) {
+        let px = to_screen(PlotPoint::new(x, y_min)).x;
+        canvas.polyline(
+            &[Pos2::new(px, area.top()), Pos2::new(px, area.bottom())],
+            GRID,
+            1.0,
+        );
+        let bottom = Pos2::new(px, area.bottom());
+        canvas.polyline(
+            &[bottom, bottom + Vec2::new(0.0, TICK_LENGTH)],
+            FOREGROUND,
+            1.0,
+        );
+        let label = match figure.x_time {
+            true => format_time(x),
+            false => format_tick(x, x_step),
+        };
+        let pos = bottom + Vec2::new(0.0, TICK_LENGTH + 2.0);
+        canvas.text(pos, Align2::CENTER_TOP, &label, LABEL_SIZE, FOREGROUND);
+    }
+    let y_step = util::nice_step((y_max - y_min) / NUM_TICKS);
+    for y in ticks(y_min, y_max, y_step) {
+        let py = to_screen(PlotPoint::new(x_min, y)).y;
+        canvas.polyline(
+            &[Pos2::new(area.left(), py), Pos2::new(area.right(), py)],
+            GRID,
+            1.0,
+        );
+        let left = Pos2::new(area.left(), py);
+        canvas.polyline(&[left, left - Vec2::new(TICK_LENGTH, 0.0)], FOREGROUND, 1.0);
+        let pos = left - Vec2::new(TICK_LENGTH + 2.0, 0.0);
+        let label = format_tick(y, y_step);
+        canvas.text(pos, Align2::RIGHT_CENTER, &label, LABEL_SIZE, FOREGROUND);
+    }
+
+    let x_label = if figure.x_time { "time" } else { "x" };
+    let pos = Pos2::new(area.center().x, size.y - 4.0);
+    canvas.text(pos, Align2::CENTER_BOTTOM, x_label, LABEL_SIZE, FOREGROUND);
+
+    canvas.set_clip(Some(area));
+    for s in figure.series.iter() {
+        let points = s.points.iter().map(|p| to_screen(*p)).collect::<Vec<_>>();
+        for part in points.split(|p| !p.x.is_finite() || !p.y.is_finite()) {
+            canvas.polyline(part, s.color, s.width.unwrap_or(LINE_WIDTH));
+        }
+    }
+    canvas.set_clip(None);
+
+    let frame = [
+        area.left_top(),
+        area.right_top(),
+        area.right_bottom(),
+        area.left_bottom(),
+        area.left_top(),
+    ];
+    canvas.polyline(&frame, FOREGROUND, 1.0);
+
+    // legend
+    let line_height = LABEL_SIZE * 1.4;
+    let legend_width = (figure.series.iter())
+        .map(|s| s.name.chars().count() as f32 * LABEL_SIZE * 0.6)
+        .fold(0.0, f32::max)
+        + LEGEND_SWATCH_WIDTH
+        + 3.0 * LEGEND_PADDING;
+    let legend_height = figure.series.len() as f32 * line_height + 2.0 * LEGEND_PADDING;
+    if !figure.series.is_empty() {
+        let min = area.right_top() + Vec2::new(-legend_width - LEGEND_PADDING, LEGEND_PADDING);
+        let legend = Rect::from_min_size(min, Vec2::new(legend_width, legend_height));
+        canvas.fill_rect(legend, Color32::from_white_alpha(0xe0));
+        for (i, s) in figure.series.iter().enumerate() {
+            let y = legend.top() + LEGEND_PADDING + (i as f32 + 0.5) * line_height;
+            let x = legend.left() + LEGEND_PADDING;
+            let swatch = [Pos2::new(x, y), Pos2::new(x + LEGEND_SWATCH_WIDTH, y)];
+            canvas.polyline(&swatch, s.color, 2.0 * LINE_WIDTH);
+            let pos = Pos2::new(x + LEGEND_SWATCH_WIDTH + LEGEND_PADDING, y);
+            canvas.text(pos, Align2::LEFT_CENTER, &s.name, LABEL_SIZE, FOREGROUND);
+        }
+    }
+}
+
+fn ticks(min: f64, max: f64, step: f64) -> impl Iterator<Item = f64> {
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(move |i| i as f64 * step)
+}
+
+fn format_tick(value: f64, step: f64) -> String {
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    format!("{value:.decimals$}")
+}
+
+struct SvgCanvas {
+    svg: String,
+    clip_id: usize,
+    clipped: bool,
+}
+
+impl SvgCanvas {
+    fn new(size: Vec2) -> Self {
+        let mut svg = String::new();
+        _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = size.x,
+            h = size.y,
+        );
+        Self {
+            svg,
+            clip_id: 0,
+            clipped: false,
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.set_clip(None);
+        self.svg.push_str("</svg>\n");
+        self.svg
+    }
+}
+
+impl Canvas for SvgCanvas {
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        if self.clipped {
+            self.svg.push_str("</g>\n");
+            self.clipped = false;
+        }
+        if let Some(r) = clip {
+            self.clip_id += 1;
+            _ = writeln!(
+                self.svg,
+                r#"<clipPath id="clip{}"><rect x="{}" y="{}" width="{}" height="{}"/></clipPath>"#,
+                self.clip_id,
+                r.left(),
+                r.top(),
+                r.width(),
+                r.height(),
+            );
+            _ = writeln!(self.svg, r#"<g clip-path="url(#clip{})">"#, self.clip_id);
+            self.clipped = true;
+        }
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color32) {
+        _ = writeln!(
+            self.svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+            rect.left(),
+            rect.top(),
+            rect.width(),
+            rect.height(),
+            svg_paint("fill", color),
+        );
+    }
+
+    fn polyline(&mut self, points: &[Pos2], color: Color32, width: f32) {
+        if points.len() < 2 {
+            return;
+        }
+        self.svg.push_str(r#"<polyline fill="none" points=""#);
+        for p in points.iter() {
+            _ = write!(self.svg, "{:.2},{:.2} ", p.x, p.y);
+        }
+        _ = writeln!(
+            self.svg,
+            r#"" stroke-width="{width}" stroke-linejoin="round" {}/>"#,
+            svg_paint("stroke", color),
+        );
+    }
+
+    fn text(&mut self, pos: Pos2, anchor: Align2, text: &str, size: f32, color: Color32) {
+        let text_anchor = match anchor.x() {
+            egui::Align::Min => "start",
+            egui::Align::Center => "middle",
+            egui::Align::Max => "end",
+        };
+        let baseline = match anchor.y() {
+            egui::Align::Min => "hanging",
+            egui::Align::Center => "middle",
+            egui::Align::Max => "text-after-edge",
+        };
+        _ = writeln!(
+            self.svg,
+            r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{size}" text-anchor="{text_anchor}" dominant-baseline="{baseline}" {}>{}</text>"#,
+            pos.x,
+            pos.y,
+            svg_paint("fill", color),
+            escape_xml(text),
+        );
+    }
+}
+
+fn svg_paint(attr: &str, color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let opacity = a as f32 / 255.0;
+    format!(r##"{attr}="#{r:02x}{g:02x}{b:02x}" {attr}-opacity="{opacity}""##)
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Software rasterizer with anti-aliased lines. Text is laid out by egui and the glyphs are
+/// copied from the font atlas.
+struct RasterCanvas<'a> {
+    ctx: &'a egui::Context,
+    width: usize,
+    height: usize,
+    /// unmultiplied sRGB colors in `0.0..=1.0`
+    pixels: Vec<[f32; 3]>,
+    clip: Rect,
+}
+
+impl<'a> RasterCanvas<'a> {
+    fn new(ctx: &'a egui::Context, width: usize, height: usize) -> Self {
+        Self {
+            ctx,
+            width,
+            height,
+            pixels: vec![[1.0; 3]; width * height],
+            clip: Self::full_rect(width, height),
+        }
+    }
+
+    fn full_rect(width: usize, height: usize) -> Rect {
+        Rect::from_min_size(Pos2::ZERO, Vec2::new(width as f32, height as f32))
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: Color32, coverage: f32) {
+        let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+        if x >= self.width || y >= self.height || !self.clip.contains(p) {
+            return;
+        }
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+        let alpha = coverage.clamp(0.0, 1.0) * a as f32 / 255.0;
+        let pixel = &mut self.pixels[y * self.width + x];
+        for (c, v) in pixel.iter_mut().zip([r, g, b]) {
+            *c += (v as f32 / 255.0 - *c) * alpha;
+        }
+    }
+
+    /// Pixel range covered by `min..max`, clamped to the image.
+    fn pixel_range(
+        &self,
+        min: Pos2,
+        max: Pos2,
+    ) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let x0 = min.x.floor().max(0.0) as usize;
+        let y0 = min.y.floor().max(0.0) as usize;
+        let x1 = (max.x.ceil().max(0.0) as usize).min(self.width);
+        let y1 = (max.y.ceil().max(0.0) as usize).min(self.height);
+        (x0..x1, y0..y1)
+    }
+
+    fn segment(&mut self, a: Pos2, b: Pos2, color: Color32, width: f32) {
+        let half = 0.5 * width;
+        let pad = Vec2::splat(half + 1.0);
+        let (xs, ys) = self.pixel_range(a.min(b) - pad, a.max(b) + pad);
+        let ab = b - a;
+        let len_sq = ab.length_sq();
+        for y in ys {
+            for x in xs.clone() {
+                let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let t = match len_sq > 0.0 {
+                    true => ((p - a).dot(ab) / len_sq).clamp(0.0, 1.0),
+                    false => 0.0,
+                };
+                let dist = (p - (a + t * ab)).length();
+                let coverage = half + 0.5 - dist;
+                if coverage > 0.0 {
+                    self.blend(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    fn write_png(&self, writer: impl std::io::Write) -> anyhow::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        let data = (self.pixels.iter())
+            .flat_map(|p| p.map(|c| (c * 255.0).round() as u8))
+            .collect::<Vec<_>>();
+        writer.write_image_data(&data)?;
+        Ok(())
+    }
+}
+
+impl Canvas for RasterCanvas<'_> {
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        self.clip = clip.unwrap_or(Self::full_rect(self.width, self.height));
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color32) {
+        let (xs, ys) = self.pixel_range(rect.min, rect.max);
+        for y in ys {
+            for x in xs.clone() {
+                self.blend(x, y, color, 1.0);
+            }
+        }
+    }
+
+    fn polyline(&mut self, points: &[Pos2], color: Color32, width: f32) {
+        for w in points.windows(2) {
+            self.segment(w[0], w[1], color, width);
+        }
+    }
+
+    fn text(&mut self, pos: Pos2, anchor: Align2, text: &str, size: f32, color: Color32) {
+        // lay out with the pixel size of the image, so glyphs are copied 1:1
+        let ppp = self.ctx.pixels_per_point();
+        let font = FontId::proportional(size / ppp);
+        let galley = (self.ctx).fonts(|f| f.layout_no_wrap(text.to_string(), font, color));
+        let atlas = self.ctx.fonts(|f| f.image());
+
+        let rect = anchor.anchor_size(pos, galley.size() * ppp);
+        for row in galley.rows.iter() {
+            let mesh = &row.visuals.mesh;
+            for quad in mesh.indices.chunks_exact(6) {
+                let vertices = quad.iter().map(|&i| mesh.vertices[i as usize]);
+                let (mut min, mut max) = (Pos2::new(f32::MAX, f32::MAX), Pos2::ZERO);
+                let (mut uv_min, mut uv_max) = (Pos2::new(f32::MAX, f32::MAX), Pos2::ZERO);
+                let mut quad_color = color;
+                for v in vertices {
+                    min = min.min(v.pos);
+                    max = max.max(v.pos);
+                    uv_min = uv_min.min(v.uv);
+                    uv_max = uv_max.max(v.uv);
+                    quad_color = v.color;
+                }
+
+                let min = rect.min + min.to_vec2() * ppp;
+                let max = rect.min + max.to_vec2() * ppp;
+                let (xs, ys) = self.pixel_range(min, max);
+                for y in ys {
+                    for x in xs.clone() {
+                        let tx = (x as f32 + 0.5 - min.x) / (max.x - min.x);
+                        let ty = (y as f32 + 0.5 - min.y) / (max.y - min.y);
+                        let u = uv_min.x + tx * (uv_max.x - uv_min.x);
+                        let v = uv_min.y + ty * (uv_max.y - uv_min.y);
+                        let u = (u as usize).min(atlas.size[0] - 1);
+                        let v = (v as usize).min(atlas.size[1] - 1);
+                        let coverage = atlas.pixels[v * atlas.size[0] + u];
+                        self.blend(x, y, quad_color, coverage);
+                    }
+                }
+            }
+        }
+    }
+}
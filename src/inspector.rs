@@ -0,0 +1,169 @@
+use std::fmt::Write as _;
+use std::io;
+use std::path::PathBuf;
+
+use egui::{CollapsingHeader, Color32, DragValue, Grid, RichText, ScrollArea, TextStyle, Ui};
+
+use crate::data::{self, Endian, RawField, RawFile};
+
+const MAX_SAMPLES: usize = 200;
+const MAX_HEADER_BYTES: usize = 16;
+
+pub struct Inspector {
+    pub file: PathBuf,
+    pub raw: RawFile,
+    pub start: usize,
+    pub count: usize,
+}
+
+impl Inspector {
+    pub fn open(file: PathBuf) -> io::Result<Self> {
+        let bytes = std::fs::read(&file)?;
+        Ok(Self {
+            file,
+            raw: data::read_raw(bytes),
+            start: 0,
+            count: 10,
+        })
+    }
+}
+
+pub fn inspector_dialog(ui: &mut Ui, inspector: &mut Inspector) {
+    let raw = &inspector.raw;
+
+    ui.label(RichText::new(inspector.file.display().to_string()).strong());
+    ui.label(format!("{} bytes, {}", raw.bytes.len(), raw.endian));
+    if let Some(e) = &raw.error {
+        ui.label(RichText::new(e.to_string()).color(Color32::RED));
+    }
+
+    ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            CollapsingHeader::new(RichText::new("Header").text_style(TextStyle::Heading))
+                .default_open(true)
+                .show(ui, |ui| {
+                    header_grid(ui, &inspector.raw);
+                });
+
+            CollapsingHeader::new(RichText::new("Samples").text_style(TextStyle::Heading))
+                .default_open(true)
+                .show(ui, |ui| {
+                    samples(ui, inspector);
+                });
+        });
+}
+
+fn header_grid(ui: &mut Ui, raw: &RawFile) {
+    Grid::new("header_fields")
+        .striped(true)
+        .num_columns(4)
+        .show(ui, |ui| {
+            ui.strong("Offset");
+            ui.strong("Bytes");
+            ui.strong("Field");
+            ui.strong("Value");
+            ui.end_row();
+
+            for f in raw.header.iter() {
+                let bytes = raw.bytes.get(f.offset..).unwrap_or_default();
+                let mut hex = hex_string(&bytes[..f.len.min(MAX_HEADER_BYTES).min(bytes.len())]);
+                if f.len > MAX_HEADER_BYTES {
+                    hex.push_str(" …");
+                }
+
+                ui.monospace(format!("{:#06x}", f.offset));
+                ui.monospace(hex);
+                ui.label(&f.name);
+                ui.monospace(f.format_value(bytes, raw.endian));
+                ui.end_row();
+            }
+        });
+}
+
+fn samples(ui: &mut Ui, inspector: &mut Inspector) {
+    let raw = &inspector.raw;
+    let num_samples = raw.num_samples();
+
+    ui.label(format!(
+        "{num_samples} samples of {} bytes starting at {:#x}",
+        raw.sample_size, raw.data_offset
+    ));
+    if raw.trailing_bytes() != 0 {
+        let text = format!("{} trailing bytes", raw.trailing_bytes());
+        ui.label(RichText::new(text).color(Color32::YELLOW));
+    }
+    if num_samples == 0 {
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Start");
+        ui.add(DragValue::new(&mut inspector.start).range(0..=num_samples - 1));
+        ui.label("Count");
+        ui.add(DragValue::new(&mut inspector.count).range(1..=MAX_SAMPLES));
+    });
+
+    let end = (inspector.start + inspector.count).min(num_samples);
+    for i in inspector.start..end {
+        let offset = raw.sample_offset(i);
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 4.0;
+            ui.monospace(format!("{offset:#010x}"));
+            sample_fields(ui, &raw.sample_fields, raw.endian, raw.sample(i), offset);
+        });
+    }
+}
+
+/// Draws the hex bytes of a sample, alternating the background color of adjacent fields. Bools
+/// packed into the same byte are shown as one field.
+fn sample_fields(
+    ui: &mut Ui,
+    fields: &[RawField],
+    endian: Endian,
+    bytes: &[u8],
+    base_offset: usize,
+) {
+    let colors = if ui.visuals().dark_mode {
+        [Color32::from_gray(0x30), Color32::from_gray(0x48)]
+    } else {
+        [Color32::from_gray(0xe0), Color32::from_gray(0xc8)]
+    };
+
+    let mut i = 0;
+    let mut group_idx = 0;
+    while i < fields.len() {
+        let offset = fields[i].offset;
+        let len = fields[i].len;
+        let group_end = fields[i..]
+            .iter()
+            .position(|f| f.offset != offset)
+            .map_or(fields.len(), |p| i + p);
+
+        let mut hover = String::new();
+        for f in fields[i..group_end].iter() {
+            let value = f.format_value(&bytes[f.offset..], endian);
+            let _ = writeln!(hover, "{} @ {:#x}: {value}", f.name, base_offset + f.offset);
+        }
+
+        let hex = hex_string(&bytes[offset..offset + len]);
+        let text = RichText::new(hex)
+            .monospace()
+            .background_color(colors[group_idx % 2]);
+        ui.label(text).on_hover_text(hover.trim_end());
+
+        i = group_end;
+        group_idx += 1;
+    }
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(3 * bytes.len());
+    for (i, b) in bytes.iter().enumerate() {
+        if i != 0 {
+            hex.push(' ');
+        }
+        let _ = write!(hex, "{b:02x}");
+    }
+    hex
+}
@@ -0,0 +1,102 @@
+use egui::{Button, ComboBox, TextEdit, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::data::{DataEntry, EntryKind};
+
+/// How a channel is evaluated between two of its samples, e.g. at the samples of another stream.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// the last sample, for state-like channels
+    Hold,
+    Nearest,
+}
+
+impl Interpolation {
+    pub const ALL: [Self; 3] = [Self::Linear, Self::Hold, Self::Nearest];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Linear => "Linear",
+            Self::Hold => "Hold last",
+            Self::Nearest => "Nearest",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ChannelInterpolation {
+    pub name: String,
+    pub mode: Interpolation,
+}
+
+/// The configured interpolation of the entry. Enum channels hold their state by default, since
+/// values between two codes are meaningless.
+pub fn of_entry(table: &[ChannelInterpolation], entry: &DataEntry) -> Interpolation {
+    match table.iter().find(|c| c.name == entry.name) {
+        Some(c) => c.mode,
+        None if matches!(entry.kind, EntryKind::Enum(_)) => Interpolation::Hold,
+        None => Interpolation::Linear,
+    }
+}
+
+/// Returns true if the table changed, so the plots have to be evaluated again.
+pub fn channel_interpolation_sidebar(
+    ui: &mut Ui,
+    table: &mut Vec<ChannelInterpolation>,
+    new: &mut ChannelInterpolation,
+) -> bool {
+    let mut changed = false;
+    let mut i = 0;
+    while i < table.len() {
+        let c = &mut table[i];
+        let (edited, removed) = ui
+            .horizontal(|ui| {
+                let name = ui.add(TextEdit::singleline(&mut c.name).desired_width(150.0));
+                let mode = mode_combo_box(ui, ("interpolation", i), &mut c.mode);
+                (name.lost_focus() || mode, ui.button("🗙").clicked())
+            })
+            .inner;
+        changed |= edited || removed;
+        if removed {
+            table.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut new.name)
+                .hint_text("channel")
+                .desired_width(150.0),
+        );
+        mode_combo_box(ui, "new_interpolation", &mut new.mode);
+
+        let valid = !new.name.trim().is_empty();
+        if ui.add_enabled(valid, Button::new("Add")).clicked() {
+            let mut c = std::mem::take(new);
+            c.name = c.name.trim().to_string();
+            table.push(c);
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+/// Returns true if the mode was changed.
+fn mode_combo_box(ui: &mut Ui, id: impl std::hash::Hash, mode: &mut Interpolation) -> bool {
+    let mut changed = false;
+    ComboBox::from_id_source(id)
+        .selected_text(mode.name())
+        .show_ui(ui, |ui| {
+            for m in Interpolation::ALL {
+                changed |= ui.selectable_value(mode, m, m.name()).changed();
+            }
+        });
+    changed
+}
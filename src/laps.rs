@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use crate::data::{EntryKind, LogStream};
+use crate::track::Track;
+
+/// distance in meters to the gate, at which the car is considered to pass it
+const GATE_RADIUS: f64 = 15.0;
+/// parts of the names of boolean channels that are used as beacon automatically
+const BEACON_NAMES: [&str; 3] = ["beacon", "lap_trigger", "lap_marker"];
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum LapTrigger {
+    None,
+    /// the beacon channel found by [`find_beacon`], if any
+    #[default]
+    Auto,
+    /// rising edges of a channel
+    Beacon(String),
+    /// closest approaches to a position on the track map
+    GpsGate {
+        x: f64,
+        y: f64,
+    },
+    /// times in seconds
+    Markers(Vec<f64>),
+}
+
+impl LapTrigger {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Auto => "Automatic",
+            Self::Beacon(_) => "Beacon",
+            Self::GpsGate { .. } => "GPS gate",
+            Self::Markers(_) => "Markers",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LapOverlay {
+    #[default]
+    Off,
+    LapTime,
+    LapDistance,
+}
+
+impl LapOverlay {
+    pub const ALL: [Self; 3] = [Self::Off, Self::LapTime, Self::LapDistance];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "No overlay",
+            Self::LapTime => "Lap time",
+            Self::LapDistance => "Lap distance",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Lap {
+    pub number: usize,
+    /// time in seconds
+    pub start: f64,
+    /// time in seconds
+    pub end: f64,
+    pub selected: bool,
+}
+
+impl Lap {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Splits the log into laps between consecutive trigger times. The parts before the first and
+/// after the last trigger aren't complete laps and are left out.
+pub fn detect(streams: &[LogStream], track: Option<&Track>, trigger: &LapTrigger) -> Vec<Lap> {
+    let mut times = match trigger {
+        LapTrigger::None => Vec::new(),
+        LapTrigger::Auto => find_beacon(streams).map_or(Vec::new(), |c| beacon_times(streams, c)),
+        LapTrigger::Beacon(channel) => beacon_times(streams, channel),
+        LapTrigger::GpsGate { x, y } => track.map_or(Vec::new(), |t| gate_times(t, *x, *y)),
+        LapTrigger::Markers(times) => times.clone(),
+    };
+    times.sort_by(f64::total_cmp);
+
+    (times.windows(2).enumerate())
+        .map(|(i, w)| Lap {
+            number: i + 1,
+            start: w[0],
+            end: w[1],
+            selected: true,
+        })
+        .collect()
+}
+
+/// The first boolean channel that is named like a lap timing beacon.
+pub fn find_beacon(streams: &[LogStream]) -> Option<&str> {
+    (streams.iter().flat_map(|s| s.entries.iter()))
+        .filter(|e| matches!(e.kind, EntryKind::Bool(_)))
+        .find(|e| {
+            let name = e.name.to_lowercase();
+            BEACON_NAMES.iter().any(|b| name.contains(b))
+        })
+        .map(|e| e.name.as_str())
+}
+
+fn beacon_times(streams: &[LogStream], channel: &str) -> Vec<f64> {
+    let mut times = Vec::new();
+    for s in streams.iter() {
+        let Some(e) = s.entries.iter().find(|e| e.name == channel) else {
+            continue;
+        };
+        let mut prev = false;
+        for (i, &t) in s.time.iter().enumerate() {
+            let active = e.get_f64(i) != 0.0;
+            if active && !prev {
+                times.push(t as f64 / 1000.0);
+            }
+            prev = active;
+        }
+    }
+    times
+}
+
+fn gate_times(track: &Track, x: f64, y: f64) -> Vec<f64> {
+    let mut times = Vec::new();
+    // closest approach while inside the gate radius
+    let mut closest: Option<(f64, f64)> = None;
+    for (&t, p) in track.time.iter().zip(track.points.iter()) {
+        let dist = (p.x - x).hypot(p.y - y);
+        if dist <= GATE_RADIUS {
+            match closest {
+                Some((d, _)) if d <= dist => (),
+                _ => closest = Some((dist, t)),
+            }
+        } else if let Some((_, t)) = closest.take() {
+            times.push(t);
+        }
+    }
+    if let Some((_, t)) = closest {
+        times.push(t);
+    }
+    times
+}
@@ -0,0 +1,82 @@
+use egui::{Button, RichText, TextEdit, TextStyle, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::eval;
+use crate::plot::ERROR_RED;
+
+/// A named expression from the library, that can be used like a channel in all plot
+/// expressions, e.g. `total_power = P_fl + P_fr + P_rl + P_rr`. Expressions can use the ones
+/// defined before them.
+#[derive(Clone, Debug, Default, PartialEq, Hash, Serialize, Deserialize)]
+pub struct UserExpr {
+    pub name: String,
+    pub expr: String,
+}
+
+/// Returns true if the library was changed and the plots have to be evaluated again.
+pub fn library_sidebar(ui: &mut Ui, library: &mut Vec<UserExpr>, new: &mut UserExpr) -> bool {
+    let mut changed = false;
+
+    let mut i = 0;
+    while i < library.len() {
+        let u = &mut library[i];
+        let removed = ui
+            .horizontal(|ui| {
+                let name = TextEdit::singleline(&mut u.name)
+                    .font(TextStyle::Monospace)
+                    .desired_width(100.0);
+                changed |= ui.add(name).changed();
+                ui.monospace("=");
+                let expr = TextEdit::multiline(&mut u.expr)
+                    .font(TextStyle::Monospace)
+                    .desired_rows(1)
+                    .desired_width(ui.available_width() - 2.0 * ui.spacing().interact_size.x);
+                changed |= ui.add(expr).changed();
+                ui.button("🗙").clicked()
+            })
+            .inner;
+        if !is_valid_name(&u.name) {
+            ui.label(RichText::new("Invalid name").color(ERROR_RED));
+        }
+        if removed {
+            library.remove(i);
+            changed = true;
+        } else {
+            i += 1;
+        }
+    }
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut new.name)
+                .font(TextStyle::Monospace)
+                .hint_text("name")
+                .desired_width(100.0),
+        );
+        ui.monospace("=");
+        ui.add(
+            TextEdit::multiline(&mut new.expr)
+                .font(TextStyle::Monospace)
+                .hint_text("expression")
+                .desired_rows(1)
+                .desired_width(ui.available_width() - 2.0 * ui.spacing().interact_size.x),
+        );
+
+        let valid = is_valid_name(new.name.trim()) && !new.expr.trim().is_empty();
+        if ui.add_enabled(valid, Button::new("Add")).clicked() {
+            let mut u = std::mem::take(new);
+            u.name = u.name.trim().to_string();
+            library.push(u);
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(eval::is_ident_char)
+}
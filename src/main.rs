@@ -3,16 +3,68 @@ use app::PlotApp;
 
 use eframe::NativeOptions;
 
+mod alarms;
+mod aliases;
+mod annotations;
 mod app;
+mod audio;
+mod collab;
+mod color_by;
+mod compare;
+mod config_diff;
+mod curves;
+mod damper;
 mod data;
+mod energy;
 mod eval;
+mod export;
+mod faults;
+mod fft;
+mod find;
 mod fs;
+mod heatmap;
+mod image_export;
+mod inspector;
+mod interpolation;
+mod laps;
+mod library;
+mod markers;
+mod measure;
+mod parquet;
+mod pedal;
 mod plot;
+mod power;
+mod project;
+mod recovery;
+mod reference;
+mod regression;
+mod sanity_rules;
+mod scoreboard;
+mod sectors;
+mod series_cache;
+mod session;
+mod side_series;
+mod spill;
+mod theme;
+mod toasts;
+mod track;
+mod units;
 mod util;
+mod watch;
 
 const APP_NAME: &str = "s3plot";
 
 fn main() {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).is_some_and(|a| a == "regress") {
+        std::process::exit(regression::cli(&args[2..]));
+    }
+    if args.get(1).is_some_and(|a| a == "reference") {
+        std::process::exit(reference::cli(&args[2..]));
+    }
+
+    recovery::install_panic_hook();
+
     let options = NativeOptions {
         follow_system_theme: true,
         ..Default::default()
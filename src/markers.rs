@@ -0,0 +1,166 @@
+use egui::{Button, Color32, RichText, TextEdit, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::plot::ERROR_RED;
+use crate::util::{format_time, parse_time};
+
+pub const SYNC_MARKER_COLOR: Color32 = Color32::from_rgb(0xc0, 0x60, 0xff);
+pub const EVENT_MARKER_COLOR: Color32 = Color32::from_rgb(0xff, 0xa0, 0x40);
+
+/// A named vertical line at an exact time, shown on the time plots of all tabs. Used to line up
+/// the session with logs of external systems.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncMarker {
+    pub name: String,
+    /// time in seconds
+    pub time: f64,
+}
+
+/// Something that happened during the session, e.g. a cone hit or a driver change. Placed by
+/// double-clicking a time plot and shown on the time plots of all tabs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EventMarker {
+    pub name: String,
+    /// time in seconds
+    pub time: f64,
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl EventMarker {
+    pub fn new(time: f64, events: &[EventMarker]) -> Self {
+        Self {
+            name: format!("Event {}", events.len() + 1),
+            time,
+            notes: String::new(),
+        }
+    }
+}
+
+/// Inputs of a marker that is about to be added.
+#[derive(Clone, Debug, Default)]
+pub struct NewMarker {
+    pub name: String,
+    pub time: String,
+}
+
+pub fn markers_sidebar(
+    ui: &mut Ui,
+    markers: &mut Vec<SyncMarker>,
+    new: &mut NewMarker,
+    cursor: Option<f64>,
+) {
+    let mut i = 0;
+    while i < markers.len() {
+        let m = &mut markers[i];
+        let removed = ui
+            .horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut m.name).desired_width(100.0));
+                ui.monospace(format_time(m.time));
+                ui.button("🗙").clicked()
+            })
+            .inner;
+        if removed {
+            markers.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    ui.separator();
+
+    let time = parse_time(&new.time);
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut new.name)
+                .hint_text("name")
+                .desired_width(100.0),
+        );
+        ui.add(
+            TextEdit::singleline(&mut new.time)
+                .hint_text("mm:ss.ss or seconds")
+                .desired_width(100.0),
+        )
+        .on_hover_text("Type or paste a timestamp");
+
+        if ui.add_enabled(time.is_some(), Button::new("Add")).clicked() {
+            if let Some(time) = time {
+                let name = std::mem::take(&mut new.name);
+                markers.push(SyncMarker { name, time });
+                new.time.clear();
+            }
+        }
+    });
+    if time.is_none() && !new.time.trim().is_empty() {
+        ui.label(RichText::new("Invalid timestamp").color(ERROR_RED));
+    }
+
+    let resp = ui
+        .add_enabled(cursor.is_some(), Button::new("Use cursor"))
+        .on_hover_text("Place the cursor by clicking on a time plot or the track map");
+    if let (true, Some(t)) = (resp.clicked(), cursor) {
+        new.time = t.to_string();
+    }
+}
+
+/// Returns the time of the clicked event, to move the cursor to it.
+/// Returns the time of the bookmark that was clicked.
+pub fn bookmarks_sidebar(ui: &mut Ui, bookmarks: &mut [Option<f64>]) -> Option<f64> {
+    if bookmarks.iter().all(Option::is_none) {
+        ui.weak("Press Ctrl+1..9 to bookmark the cursor, 1..9 to jump to a bookmark");
+        return None;
+    }
+
+    let mut jump_to = None;
+    for (i, bookmark) in bookmarks.iter_mut().enumerate() {
+        let Some(time) = *bookmark else {
+            continue;
+        };
+        ui.horizontal(|ui| {
+            ui.label(format!("{}", i + 1));
+            let link = ui.link(RichText::new(format_time(time)).monospace());
+            if link.on_hover_text("Jump to the bookmark").clicked() {
+                jump_to = Some(time);
+            }
+            if ui.button("🗙").clicked() {
+                *bookmark = None;
+            }
+        });
+    }
+    jump_to
+}
+
+pub fn events_sidebar(ui: &mut Ui, events: &mut Vec<EventMarker>) -> Option<f64> {
+    if events.is_empty() {
+        ui.weak("Double-click a time plot to add an event");
+        return None;
+    }
+
+    let mut jump_to = None;
+    let mut i = 0;
+    while i < events.len() {
+        let e = &mut events[i];
+        let removed = ui
+            .horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut e.name).desired_width(100.0));
+                let time = ui.link(RichText::new(format_time(e.time)).monospace());
+                if time.on_hover_text("Move the cursor to the event").clicked() {
+                    jump_to = Some(e.time);
+                }
+                ui.button("🗙").clicked()
+            })
+            .inner;
+        ui.add(
+            TextEdit::multiline(&mut e.notes)
+                .hint_text("notes")
+                .desired_rows(1)
+                .desired_width(f32::INFINITY),
+        );
+        if removed {
+            events.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    jump_to
+}
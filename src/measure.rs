@@ -0,0 +1,128 @@
+use std::fmt::Write;
+
+use egui::{Grid, Ui};
+use egui_plot::PlotPoint;
+use serde::{Deserialize, Serialize};
+
+use crate::util::format_time;
+
+/// Two vertical cursors placed by clicking on a time plot, the third click starts over.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Measurement {
+    pub cursors: [Option<f64>; 2],
+}
+
+impl Measurement {
+    pub fn click(&mut self, x: f64) {
+        self.cursors = match self.cursors {
+            [Some(a), None] => [Some(a), Some(x)],
+            _ => [Some(x), None],
+        };
+    }
+
+    /// Ordered range between both cursors.
+    pub fn range(&self) -> Option<(f64, f64)> {
+        match self.cursors {
+            [Some(a), Some(b)] => Some((a.min(b), a.max(b))),
+            _ => None,
+        }
+    }
+}
+
+pub struct SeriesStats {
+    pub delta_y: f64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+impl SeriesStats {
+    /// Statistics of the finite values between `start` and `end`. The values have to be sorted
+    /// by x.
+    pub fn new(values: &[PlotPoint], start: f64, end: f64) -> Option<Self> {
+        let first = values.partition_point(|p| p.x < start);
+        let last = values.partition_point(|p| p.x <= end);
+        let range = values.get(first..last)?;
+        let finite = || range.iter().map(|p| p.y).filter(|y| y.is_finite());
+
+        let count = finite().count();
+        if count == 0 {
+            return None;
+        }
+
+        Some(Self {
+            delta_y: range.last()?.y - range.first()?.y,
+            min: finite().fold(f64::INFINITY, f64::min),
+            max: finite().fold(f64::NEG_INFINITY, f64::max),
+            mean: finite().sum::<f64>() / count as f64,
+        })
+    }
+}
+
+pub fn measurement_table(
+    ui: &mut Ui,
+    start: f64,
+    end: f64,
+    series: &[(&str, Option<SeriesStats>)],
+) {
+    ui.horizontal(|ui| {
+        ui.label(format!("Δt = {}", format_time(end - start)));
+        let copy = ui.small_button("Copy statistics");
+        if copy.on_hover_text("Copy the table as markdown").clicked() {
+            ui.output_mut(|o| o.copied_text = markdown_table(start, end, series));
+        }
+    });
+    Grid::new("measurement")
+        .striped(true)
+        .num_columns(5)
+        .show(ui, |ui| {
+            ui.strong("");
+            ui.strong("Δy");
+            ui.strong("min");
+            ui.strong("max");
+            ui.strong("mean");
+            ui.end_row();
+
+            for (name, stats) in series.iter() {
+                ui.label(*name);
+                match stats {
+                    Some(s) => {
+                        for v in [s.delta_y, s.min, s.max, s.mean] {
+                            ui.monospace(format!("{v:.3}"));
+                        }
+                    }
+                    None => {
+                        for _ in 0..4 {
+                            ui.label("-");
+                        }
+                    }
+                }
+                ui.end_row();
+            }
+        });
+}
+
+/// The statistics as a markdown table, for pasting into test reports.
+fn markdown_table(start: f64, end: f64, series: &[(&str, Option<SeriesStats>)]) -> String {
+    let mut table = format!(
+        "{} – {} (Δt = {})\n\n",
+        format_time(start),
+        format_time(end),
+        format_time(end - start)
+    );
+    table += "| series | Δy | min | max | mean |\n";
+    table += "|---|---:|---:|---:|---:|\n";
+    for (name, stats) in series.iter() {
+        let _ = write!(table, "| {} |", name.replace('|', "\\|"));
+        match stats {
+            Some(s) => {
+                for v in [s.delta_y, s.min, s.max, s.mean] {
+                    let _ = write!(table, " {v:.3} |");
+                }
+            }
+            None => table += " - | - | - | - |",
+        }
+        table.push('\n');
+    }
+    table
+}
@@ -0,0 +1,381 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use crate::data::LogStream;
+use crate::export;
+
+const MAGIC: &[u8] = b"PAR1";
+const CREATED_BY: &str = concat!("s3plot ", env!("CARGO_PKG_VERSION"));
+
+// parquet enums
+const TYPE_DOUBLE: i32 = 5;
+const REPETITION_REQUIRED: i32 = 0;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const CODEC_UNCOMPRESSED: i32 = 0;
+const PAGE_DATA: i32 = 0;
+
+// thrift compact protocol types
+const THRIFT_I32: u8 = 5;
+const THRIFT_I64: u8 = 6;
+const THRIFT_BINARY: u8 = 8;
+const THRIFT_LIST: u8 = 9;
+const THRIFT_STRUCT: u8 = 12;
+
+pub fn export_parquet(
+    path: &Path,
+    streams: &[LogStream],
+    offsets: &[i64],
+    selected: &[Vec<bool>],
+    range: Option<RangeInclusive<f64>>,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_parquet(&mut writer, streams, offsets, selected, range)?;
+    writer.flush()
+}
+
+/// Writes the selected entries as an uncompressed parquet file with a single row group, the rows
+/// are the same as the ones of [`crate::export::write_csv`]. All columns are required doubles,
+/// samples missing in other streams are written as `NaN`, which pandas reads as missing values.
+/// Column names have to be unique, so repeated entry names get a number, e.g. `rpm_2`.
+pub fn write_parquet(
+    writer: &mut impl Write,
+    streams: &[LogStream],
+    offsets: &[i64],
+    selected: &[Vec<bool>],
+    range: Option<RangeInclusive<f64>>,
+) -> io::Result<()> {
+    let rows = export::row_indices(streams, offsets, range);
+    let mut writer = CountingWriter {
+        inner: writer,
+        written: 0,
+    };
+    writer.write_all(MAGIC)?;
+
+    let mut columns = Vec::new();
+    let times = rows.iter().map(|(t, _)| *t as f64 / 1000.0);
+    columns.push(("time".to_string(), write_column(&mut writer, times)?));
+    for (j, (s, sel)) in streams.iter().zip(selected).enumerate() {
+        for (e, _) in s.entries.iter().zip(sel).filter(|(_, s)| **s) {
+            let values =
+                (rows.iter()).map(|(_, indices)| indices[j].map_or(f64::NAN, |i| e.get_f64(i)));
+            let name = unique_name(&columns, &e.name);
+            columns.push((name, write_column(&mut writer, values)?));
+        }
+    }
+
+    let metadata = file_metadata(&columns, rows.len());
+    writer.write_all(&metadata)?;
+    writer.write_all(&(metadata.len() as u32).to_le_bytes())?;
+    writer.write_all(MAGIC)?;
+    Ok(())
+}
+
+/// Keeps track of the offsets of the column chunks.
+struct CountingWriter<W> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The name, or the name with the first number starting at 2 that isn't used by a column yet.
+fn unique_name(columns: &[(String, ColumnChunk)], name: &str) -> String {
+    let used = |n: &str| columns.iter().any(|(c, _)| c == n);
+    if !used(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|i| format!("{name}_{i}"))
+        .find(|n| !used(n))
+        .unwrap()
+}
+
+struct ColumnChunk {
+    num_values: usize,
+    /// offset of the page header
+    offset: u64,
+    /// including the page header
+    size: usize,
+}
+
+/// Writes the values as a single plain encoded data page.
+fn write_column<W: Write>(
+    writer: &mut CountingWriter<W>,
+    values: impl Iterator<Item = f64>,
+) -> io::Result<ColumnChunk> {
+    let data = values.flat_map(f64::to_le_bytes).collect::<Vec<_>>();
+    let num_values = data.len() / 8;
+
+    let mut header = CompactWriter::default();
+    header.i32(1, PAGE_DATA);
+    header.i32(2, data.len() as i32);
+    header.i32(3, data.len() as i32);
+    header.begin_struct(5);
+    header.i32(1, num_values as i32);
+    header.i32(2, ENCODING_PLAIN);
+    header.i32(3, ENCODING_RLE);
+    header.i32(4, ENCODING_RLE);
+    header.end_struct();
+    header.stop();
+
+    let offset = writer.written;
+    writer.write_all(&header.buf)?;
+    writer.write_all(&data)?;
+    Ok(ColumnChunk {
+        num_values,
+        offset,
+        size: header.buf.len() + data.len(),
+    })
+}
+
+fn file_metadata(columns: &[(String, ColumnChunk)], num_rows: usize) -> Vec<u8> {
+    let mut w = CompactWriter::default();
+    w.i32(1, 1);
+
+    w.begin_list(2, THRIFT_STRUCT, columns.len() + 1);
+    w.begin_list_struct();
+    w.binary(4, b"schema");
+    w.i32(5, columns.len() as i32);
+    w.end_struct();
+    for (name, _) in columns.iter() {
+        w.begin_list_struct();
+        w.i32(1, TYPE_DOUBLE);
+        w.i32(3, REPETITION_REQUIRED);
+        w.binary(4, name.as_bytes());
+        w.end_struct();
+    }
+
+    w.i64(3, num_rows as i64);
+
+    let total_size = columns.iter().map(|(_, c)| c.size as i64).sum::<i64>();
+    w.begin_list(4, THRIFT_STRUCT, 1);
+    w.begin_list_struct();
+    w.begin_list(1, THRIFT_STRUCT, columns.len());
+    for (name, chunk) in columns.iter() {
+        w.begin_list_struct();
+        w.i64(2, chunk.offset as i64);
+        w.begin_struct(3);
+        w.i32(1, TYPE_DOUBLE);
+        w.begin_list(2, THRIFT_I32, 1);
+        w.list_i32(ENCODING_PLAIN);
+        w.begin_list(3, THRIFT_BINARY, 1);
+        w.list_binary(name.as_bytes());
+        w.i32(4, CODEC_UNCOMPRESSED);
+        w.i64(5, chunk.num_values as i64);
+        w.i64(6, chunk.size as i64);
+        w.i64(7, chunk.size as i64);
+        w.i64(9, chunk.offset as i64);
+        w.end_struct();
+        w.end_struct();
+    }
+    w.i64(2, total_size);
+    w.i64(3, num_rows as i64);
+    w.end_struct();
+
+    w.binary(6, CREATED_BY.as_bytes());
+    w.stop();
+    w.buf
+}
+
+/// Encodes the parquet metadata structs in the thrift compact protocol.
+#[derive(Default)]
+struct CompactWriter {
+    buf: Vec<u8>,
+    /// id of the last field of the current struct and the enclosing ones
+    last_field: Vec<i16>,
+    current: i16,
+}
+
+impl CompactWriter {
+    fn field_header(&mut self, id: i16, kind: u8) {
+        let delta = id - self.current;
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | kind);
+        } else {
+            self.buf.push(kind);
+            self.varint(zigzag(id as i64));
+        }
+        self.current = id;
+    }
+
+    fn varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.buf.push(v as u8 | 0x80);
+            v >>= 7;
+        }
+        self.buf.push(v as u8);
+    }
+
+    fn i32(&mut self, id: i16, v: i32) {
+        self.field_header(id, THRIFT_I32);
+        self.varint(zigzag(v as i64));
+    }
+
+    fn i64(&mut self, id: i16, v: i64) {
+        self.field_header(id, THRIFT_I64);
+        self.varint(zigzag(v));
+    }
+
+    fn binary(&mut self, id: i16, v: &[u8]) {
+        self.field_header(id, THRIFT_BINARY);
+        self.list_binary(v);
+    }
+
+    fn begin_struct(&mut self, id: i16) {
+        self.field_header(id, THRIFT_STRUCT);
+        self.begin_list_struct();
+    }
+
+    /// Begins a struct that is an element of a list.
+    fn begin_list_struct(&mut self) {
+        self.last_field.push(self.current);
+        self.current = 0;
+    }
+
+    fn end_struct(&mut self) {
+        self.stop();
+        self.current = self.last_field.pop().unwrap_or(0);
+    }
+
+    fn stop(&mut self) {
+        self.buf.push(0);
+    }
+
+    fn begin_list(&mut self, id: i16, elem_kind: u8, len: usize) {
+        self.field_header(id, THRIFT_LIST);
+        if len < 15 {
+            self.buf.push(((len as u8) << 4) | elem_kind);
+        } else {
+            self.buf.push(0xf0 | elem_kind);
+            self.varint(len as u64);
+        }
+    }
+
+    fn list_i32(&mut self, v: i32) {
+        self.varint(zigzag(v as i64));
+    }
+
+    fn list_binary(&mut self, v: &[u8]) {
+        self.varint(v.len() as u64);
+        self.buf.extend_from_slice(v);
+    }
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{DataEntry, EntryKind, LogFormat};
+
+    fn stream(time: &[u32], entries: &[(&str, &[f64])]) -> LogStream {
+        LogStream {
+            format: LogFormat::Motec,
+            start: None,
+            time: time.to_vec(),
+            entries: (entries.iter())
+                .map(|(name, values)| DataEntry {
+                    name: name.to_string(),
+                    unit: String::new(),
+                    scale: 1.0,
+                    offset: 0.0,
+                    kind: EntryKind::F64(values.to_vec()),
+                    labels: Vec::new(),
+                })
+                .collect(),
+            sources: Vec::new(),
+        }
+    }
+
+    /// Reads the values of the plain encoded pages, which all have the same number of rows.
+    fn read_columns(bytes: &[u8], num_columns: usize, num_rows: usize) -> Vec<Vec<f64>> {
+        let mut sink = CountingWriter {
+            inner: io::sink(),
+            written: 0,
+        };
+        let chunk = write_column(&mut sink, std::iter::repeat_n(0.0, num_rows)).unwrap();
+        let header_len = chunk.size - 8 * num_rows;
+
+        (0..num_columns)
+            .map(|c| {
+                let start = MAGIC.len() + c * chunk.size + header_len;
+                (bytes[start..start + 8 * num_rows].chunks_exact(8))
+                    .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn contains(bytes: &[u8], name: &[u8]) -> bool {
+        bytes.windows(name.len()).any(|w| w == name)
+    }
+
+    #[test]
+    fn page_header() {
+        let mut writer = CountingWriter {
+            inner: Vec::new(),
+            written: 0,
+        };
+        write_column(&mut writer, [1.0, 2.0].into_iter()).unwrap();
+
+        #[rustfmt::skip]
+        let header = [
+            0x15, 0x00, // type: DATA_PAGE
+            0x15, 0x20, // uncompressed size: 16
+            0x15, 0x20, // compressed size: 16
+            0x2c, // data page header
+            0x15, 0x04, // num values: 2
+            0x15, 0x00, // encoding: PLAIN
+            0x15, 0x06, // definition level encoding: RLE
+            0x15, 0x06, // repetition level encoding: RLE
+            0x00, 0x00,
+        ];
+        let (page_header, data) = writer.inner.split_at(header.len());
+        assert_eq!(page_header, header);
+        assert_eq!(data, [1.0f64.to_le_bytes(), 2.0f64.to_le_bytes()].concat());
+    }
+
+    #[test]
+    fn round_trip() {
+        let streams = [
+            stream(
+                &[0, 10, 20],
+                &[("time", &[1.0, 2.0, 3.0]), ("rpm", &[4.0, 5.0, 6.0])],
+            ),
+            stream(&[0, 10, 20], &[("rpm", &[7.0, 8.0, 9.0])]),
+        ];
+        let selected = [vec![true, true], vec![true]];
+        let mut bytes = Vec::new();
+        write_parquet(&mut bytes, &streams, &[0, 5], &selected, None).unwrap();
+
+        assert!(bytes.starts_with(MAGIC) && bytes.ends_with(MAGIC));
+        let footer_len = u32::from_le_bytes(bytes[bytes.len() - 8..][..4].try_into().unwrap());
+        let footer = &bytes[bytes.len() - 8 - footer_len as usize..bytes.len() - 8];
+        for name in ["time_2", "rpm_2"] {
+            assert!(contains(footer, name.as_bytes()), "missing column `{name}`");
+        }
+
+        let columns = read_columns(&bytes, 4, 3);
+        assert_eq!(columns[0], [0.0, 0.01, 0.02]);
+        assert_eq!(columns[1], [1.0, 2.0, 3.0]);
+        assert_eq!(columns[2], [4.0, 5.0, 6.0]);
+        // the second stream is shifted by 5 ms, so it has no sample at the first row
+        assert!(columns[3][0].is_nan());
+        assert_eq!(columns[3][1..], [7.0, 8.0]);
+    }
+}
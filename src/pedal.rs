@@ -0,0 +1,121 @@
+use std::hash::{Hash, Hasher};
+
+use egui_plot::PlotPoint;
+use serde::{Deserialize, Serialize};
+
+use crate::data::LogStream;
+
+const PEDAL_BINS: usize = 20;
+/// bins with fewer samples are left out, since their mean is mostly noise
+const MIN_BIN_SAMPLES: usize = 5;
+
+/// Channels used to compute the effective pedal map of a tab.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PedalMapConfig {
+    pub pedal: String,
+    pub torque: String,
+    pub speed: String,
+    /// width of the speed bins
+    pub speed_step: f64,
+}
+
+impl Default for PedalMapConfig {
+    fn default() -> Self {
+        Self {
+            pedal: "gas".into(),
+            torque: "torque_cmd".into(),
+            speed: "speed".into(),
+            speed_step: 20.0,
+        }
+    }
+}
+
+impl Hash for PedalMapConfig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pedal.hash(state);
+        self.torque.hash(state);
+        self.speed.hash(state);
+        self.speed_step.to_bits().hash(state);
+    }
+}
+
+/// Mean torque over pedal position for one speed bin.
+pub struct PedalCurve {
+    pub speed_min: f64,
+    pub speed_max: f64,
+    pub points: Vec<PlotPoint>,
+}
+
+/// The pedal map actually experienced on track: the commanded torque averaged in a grid of pedal
+/// position and speed bins. The torque and speed are sampled at the times of the pedal channel.
+pub fn pedal_map(streams: &[LogStream], cfg: &PedalMapConfig) -> Result<Vec<PedalCurve>, String> {
+    let (ps, pe) = find_channel(streams, &cfg.pedal)?;
+    let (ts, te) = find_channel(streams, &cfg.torque)?;
+    let (ss, se) = find_channel(streams, &cfg.speed)?;
+    if cfg.speed_step <= 0.0 {
+        return Err("speed step has to be positive".into());
+    }
+
+    let stream = &streams[ps];
+    let pedal = |i| stream.entries[pe].get_f64(i);
+    let finite_pedal = || (0..stream.len()).map(pedal).filter(|v| v.is_finite());
+    let pedal_min = finite_pedal().fold(f64::INFINITY, f64::min);
+    let pedal_max = finite_pedal().fold(f64::NEG_INFINITY, f64::max);
+    let pedal_range = pedal_max - pedal_min;
+    if pedal_range <= 0.0 || !pedal_range.is_finite() {
+        return Ok(Vec::new());
+    }
+
+    // (speed bin, pedal bin) -> (sum, count)
+    let mut bins: Vec<(i64, Vec<(f64, usize)>)> = Vec::new();
+    for (i, &time) in stream.time.iter().enumerate() {
+        let p = pedal(i);
+        let torque = streams[ts].value_at(te, time);
+        let speed = streams[ss].value_at(se, time);
+        if !(p.is_finite() && torque.is_finite() && speed.is_finite()) {
+            continue;
+        }
+
+        let speed_bin = (speed / cfg.speed_step).floor() as i64;
+        let pedal_bin = ((p - pedal_min) / pedal_range * PEDAL_BINS as f64) as usize;
+        let pedal_bin = pedal_bin.min(PEDAL_BINS - 1);
+
+        let idx = match bins.binary_search_by_key(&speed_bin, |(b, _)| *b) {
+            Ok(idx) => idx,
+            Err(idx) => {
+                bins.insert(idx, (speed_bin, vec![(0.0, 0); PEDAL_BINS]));
+                idx
+            }
+        };
+        let (sum, count) = &mut bins[idx].1[pedal_bin];
+        *sum += torque;
+        *count += 1;
+    }
+
+    let bin_width = pedal_range / PEDAL_BINS as f64;
+    let curves = (bins.into_iter())
+        .map(|(speed_bin, pedal_bins)| {
+            let points = (pedal_bins.iter().enumerate())
+                .filter(|(_, (_, count))| *count >= MIN_BIN_SAMPLES)
+                .map(|(i, (sum, count))| {
+                    let x = pedal_min + (i as f64 + 0.5) * bin_width;
+                    PlotPoint::new(x, sum / *count as f64)
+                })
+                .collect();
+            PedalCurve {
+                speed_min: speed_bin as f64 * cfg.speed_step,
+                speed_max: (speed_bin + 1) as f64 * cfg.speed_step,
+                points,
+            }
+        })
+        .filter(|c: &PedalCurve| !c.points.is_empty())
+        .collect();
+
+    Ok(curves)
+}
+
+fn find_channel(streams: &[LogStream], name: &str) -> Result<(usize, usize), String> {
+    (streams.iter().enumerate())
+        .find_map(|(i, s)| Some((i, s.entries.iter().position(|e| e.name == name)?)))
+        .ok_or_else(|| format!("channel `{name}` not found"))
+}
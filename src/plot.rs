@@ -1,20 +1,61 @@
 use std::fmt::Write;
-use std::ops::Range;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::{Range, RangeInclusive};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use cods::{BuiltinConst, BuiltinFun, DataType, Pos, SignatureKind, UserFacing};
 use egui::emath::TSTransform;
 use egui::text::{LayoutJob, LayoutSection};
 use egui::{
-    Align, Button, CentralPanel, CollapsingHeader, Color32, CursorIcon, Frame, Id, Key, Label,
-    LayerId, Layout, Margin, Modifiers, Order, Pos2, RichText, Rounding, ScrollArea, Sense,
-    SidePanel, TextEdit, TextFormat, TextStyle, Ui, Vec2, WidgetText,
+    Align, Align2, Area, Button, CentralPanel, CollapsingHeader, Color32, ComboBox, Context,
+    CursorIcon, DragValue, Frame, Grid, Id, Key, Label, LayerId, Layout, Margin, Modifiers, Order,
+    PointerButton, Pos2, Rect, RichText, Rounding, ScrollArea, Sense, Shape, SidePanel, Stroke,
+    TextEdit, TextFormat, TextStyle, TextureHandle, TextureOptions, Ui, Vec2, WidgetText, Window,
+};
+use egui_extras::{Column, TableBuilder};
+use egui_plot::{
+    AxisHints, Bar, BarChart, HPlacement, Legend, Line, LineStyle, Plot, PlotBounds, PlotImage,
+    PlotPoint, PlotPoints, PlotUi, Points, Polygon, Text, VLine,
 };
-use egui_plot::{Legend, Line, Plot, PlotPoint, PlotPoints};
 use serde::{Deserialize, Serialize};
 
-use crate::app::{Job, PlotData, PlotValues};
-use crate::eval::Expr;
+use crate::alarms::AlarmRule;
+use crate::aliases::{self, ChannelAlias};
+use crate::app::{self, PlotData, PlotValues};
+use crate::color_by;
+use crate::compare::RunB;
+use crate::curves::{self, NewCurve, ReferenceCurve};
+use crate::damper::{self, DamperConfig, DamperHistogram, CORNERS};
+use crate::data::{self, LogStream, RateSegment};
+use crate::energy;
+use crate::eval::{self, Expr, Timing};
+use crate::faults::{FaultDefinition, FaultOccurrence};
+use crate::fft::{self, FftWindow};
+use crate::find;
+use crate::fs;
+use crate::heatmap::{self, CellValue, Density, Heatmap, HeatmapConfig};
+use crate::image_export::{Figure, Series};
+use crate::interpolation::{self, ChannelInterpolation};
+use crate::laps::{self, Lap, LapOverlay, LapTrigger};
+use crate::library::{self, UserExpr};
+use crate::markers::{
+    self, EventMarker, NewMarker, SyncMarker, EVENT_MARKER_COLOR, SYNC_MARKER_COLOR,
+};
+use crate::measure::{self, Measurement, SeriesStats};
+use crate::pedal::{self, PedalCurve, PedalMapConfig};
+use crate::power::PowerLimit;
+use crate::regression::ValidationCheck;
+use crate::sanity_rules::SanityRule;
+use crate::scoreboard::ScoreMetric;
+use crate::sectors::Sectors;
+use crate::session::SessionMeta;
+use crate::side_series::{self, SideSeries};
+use crate::spill;
+use crate::theme::Theme;
+use crate::track::Track;
+use crate::units::{self, ChannelUnit};
 use crate::util::{self, format_time};
 
 const TAB_CROSS_WIDTH: f32 = 20.0;
@@ -25,16 +66,200 @@ const PLOT_FRAME_PADDING: f32 = 2.0;
 
 const TEXT_EDIT_MARGIN_X: f32 = 4.0;
 const TEXT_EDIT_MARGIN_Y: f32 = 2.0;
+const EXPAND_BUTTON_WIDTH: f32 = 24.0;
+
+const SPARKLINE_SIZE: Vec2 = Vec2::new(60.0, 16.0);
+
+const MAX_XY_POINTS: usize = 100_000;
+/// group of the time plots that share their x-range when the axes are linked
+const LINKED_X_GROUP: &str = "linked_x";
+const CHANNEL_BROWSER_WIDTH: f32 = 300.0;
+const CHANNEL_BROWSER_HEIGHT: f32 = 500.0;
+/// inputs of a plot in the sidebar, see [`plot_field_id`]
+const PLOT_FIELDS: [&str; 3] = ["name", "x", "y"];
+const DEFAULT_GRID_COLUMNS: usize = 2;
+const MAX_GRID_COLUMNS: usize = 8;
+const SPLIT_LINKED_X_GROUP: &str = "split_linked_x";
+/// between the panes of the split view
+const SPLIT_SPACING: f32 = 8.0;
+
+pub const JOB_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// time without changes after which an edited expression is evaluated
+const EDIT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// time in seconds shown before and after a fault
+const FAULT_WINDOW_PADDING: f64 = 2.0;
+/// time in seconds shown before and after an alarm violation
+const ALARM_WINDOW_PADDING: f64 = 2.0;
+/// time in seconds shown around a bookmark if the time plot has no range yet
+const BOOKMARK_WINDOW: f64 = 10.0;
+const BOOKMARK_KEYS: [Key; 9] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
+
+const GG_BINS: usize = 128;
+const GG_CIRCLE_SEGMENTS: usize = 128;
+const XY_POINT_RADIUS: f32 = 1.5;
+const DEFAULT_LINE_WIDTH: f32 = 1.5;
+
+const MAX_TRACK_POINTS: usize = 20_000;
+const TRACK_COLOR_BUCKETS: usize = 32;
+const TRACK_POINT_RADIUS: f32 = 2.0;
+const CURSOR_POINT_RADIUS: f32 = 5.0;
+const CURSOR_COLOR: Color32 = Color32::from_rgb(0xff, 0x80, 0x20);
+const MEASUREMENT_COLOR: Color32 = Color32::from_rgb(0x40, 0xa0, 0xff);
+const MEASUREMENT_TABLE_OFFSET: f32 = 8.0;
+const COLOR_LEGEND_OFFSET: f32 = 8.0;
+const OVERVIEW_HEIGHT: f32 = 60.0;
+const OVERVIEW_OPACITY: f32 = 0.5;
+const OVERVIEW_WINDOW_OPACITY: f32 = 0.15;
+const DIGITAL_LANE_HEIGHT: f32 = 24.0;
+/// levels of a digital trace inside of its lane, which is 1.0 high
+const DIGITAL_LOW: f64 = 0.15;
+const DIGITAL_HIGH: f64 = 0.85;
+const VALUE_TABLE_ROW_HEIGHT: f32 = 18.0;
+const HOVER_READOUT_OFFSET: f32 = 16.0;
+const BAND_OPACITY: f32 = 0.3;
+const SOURCE_BOUNDARY_OPACITY: f32 = 0.5;
+/// in points
+const SOURCE_BOUNDARY_HOVER_DISTANCE: f32 = 4.0;
+const DEFAULT_GAP_PERIODS: u32 = 5;
+const GAP_SHADE_COLOR: Color32 = Color32::from_rgba_premultiplied(0x40, 0x40, 0x40, 0x40);
+const VIOLATION_SHADE_COLOR: Color32 = Color32::from_rgba_premultiplied(0x50, 0x10, 0x10, 0x50);
+const ZOOM_SELECTION_COLOR: Color32 = Color32::from_rgba_premultiplied(0x20, 0x40, 0x60, 0x40);
+const WHEEL_CORNERS: [&str; 4] = ["fl", "fr", "rl", "rr"];
+const DAMPER_BUMP_COLOR: Color32 = Color32::from_rgb(0x40, 0xa0, 0xff);
+const DAMPER_REBOUND_COLOR: Color32 = Color32::from_rgb(0xff, 0x80, 0x20);
 
 const DEFAULT_ASPECT_RATIO: f32 = 0.1;
-const ERROR_RED: Color32 = Color32::from_rgb(0xf0, 0x56, 0x56);
+pub const ERROR_RED: Color32 = Color32::from_rgb(0xf0, 0x56, 0x56);
+const WARNING_YELLOW: Color32 = Color32::from_rgb(0xf0, 0xc0, 0x40);
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     pub show_help: bool,
+    #[serde(default)]
+    pub hide_constant: bool,
+    #[serde(default)]
+    pub only_used_channels: bool,
+    #[serde(default)]
+    pub fault_dictionary: Vec<FaultDefinition>,
+    #[serde(default)]
+    pub lap_trigger: LapTrigger,
+    #[serde(default)]
+    pub sectors: Sectors,
+    #[serde(default)]
+    pub validation_checks: Vec<ValidationCheck>,
+    #[serde(default)]
+    pub score_metrics: Vec<ScoreMetric>,
+    #[serde(default)]
+    pub alarm_rules: Vec<AlarmRule>,
+    #[serde(default)]
+    pub power_limit: PowerLimit,
+    /// in km, the energy summary projects the consumption onto it
+    #[serde(default = "default_endurance_distance")]
+    pub endurance_distance: f64,
+    #[serde(default)]
+    pub sanity_rules: Vec<SanityRule>,
+    /// store evaluated series on disk
+    #[serde(default)]
+    pub cache_series: bool,
+    /// in MiB, evaluated series are moved to disk when they exceed it, `0` for no limit
+    #[serde(default)]
+    pub memory_budget: u32,
+    /// warn about expressions that add or compare incompatible units
+    #[serde(default)]
+    pub unit_warnings: bool,
+    /// show the same time window in all time plots
+    #[serde(default)]
+    pub link_x_axes: bool,
+    #[serde(default)]
+    pub hover_readout: HoverReadout,
+    #[serde(default)]
+    pub theme: Theme,
+    /// in ms by stream index, added to the time of each stream during evaluation
+    #[serde(default)]
+    pub stream_offsets: Vec<i64>,
+    /// in Hz, all streams are resampled onto a fixed time grid, `0` to evaluate at the samples
+    /// of the first stream
+    #[serde(default)]
+    pub resample_rate: u32,
+    /// line plots are split where the sample interval is longer than this many sample periods,
+    /// `0` to always connect the samples
+    #[serde(default = "default_gap_periods")]
+    pub gap_periods: u32,
+    #[serde(default)]
+    pub shade_gaps: bool,
+    /// offer to append log files that are written to the opened directory
+    #[serde(default)]
+    pub watch_dir: bool,
+    #[serde(default)]
+    pub expr_library: Vec<UserExpr>,
+    #[serde(skip)]
+    pub new_user_expr: UserExpr,
+    /// evaluated once after loading and added to the first stream
+    #[serde(default)]
+    pub derived_channels: Vec<UserExpr>,
+    #[serde(skip)]
+    pub new_derived_channel: UserExpr,
+    #[serde(default)]
+    pub channel_units: Vec<ChannelUnit>,
+    #[serde(skip)]
+    pub new_channel_unit: ChannelUnit,
+    #[serde(default)]
+    pub channel_interpolation: Vec<ChannelInterpolation>,
+    #[serde(skip)]
+    pub new_channel_interpolation: ChannelInterpolation,
+    /// readable names shown instead of the channel names
+    #[serde(default)]
+    pub channel_aliases: Vec<ChannelAlias>,
+    #[serde(skip)]
+    pub new_channel_alias: ChannelAlias,
+    #[serde(skip)]
+    pub new_reference_curve: NewCurve,
+    /// allow the aliases as identifiers in expressions
+    #[serde(default)]
+    pub alias_identifiers: bool,
+    #[serde(default)]
+    pub tab_templates: Vec<TabTemplate>,
+    #[serde(skip)]
+    pub new_template_name: String,
+    #[serde(default)]
+    pub sync_markers: Vec<SyncMarker>,
+    #[serde(skip)]
+    pub new_marker: NewMarker,
+    #[serde(default)]
+    pub event_markers: Vec<EventMarker>,
+    /// times in seconds, set with Ctrl+1..9 and jumped to with 1..9
+    #[serde(default)]
+    pub bookmarks: [Option<f64>; 9],
     #[serde(skip)]
     pub search_help: String,
+    #[serde(skip)]
+    pub search_channels: String,
+    /// channels selected in the channel browser, to be added as plots at once
+    #[serde(skip)]
+    pub selected_channels: Vec<String>,
+    /// time in seconds, shown as a vertical line on time plots
+    #[serde(skip)]
+    pub cursor: Option<f64>,
     pub selected_tab: usize,
+    #[serde(default)]
+    pub split_view: SplitView,
+    /// shown in the second pane of the split view
+    #[serde(default)]
+    pub split_tab: usize,
+    /// link the x-axes of the two panes, even if [`Config::link_x_axes`] is off
+    #[serde(default)]
+    pub link_split_x_axes: bool,
     pub tabs: Vec<TabConfig>,
     #[serde(skip)]
     pub dragged_tab: Option<(usize, Pos2)>,
@@ -46,26 +271,60 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             show_help: true,
+            hide_constant: false,
+            only_used_channels: false,
+            fault_dictionary: Vec::new(),
+            lap_trigger: LapTrigger::Auto,
+            sectors: Sectors::default(),
+            validation_checks: Vec::new(),
+            score_metrics: Vec::new(),
+            alarm_rules: Vec::new(),
+            power_limit: PowerLimit::default(),
+            endurance_distance: energy::DEFAULT_ENDURANCE_DISTANCE,
+            sanity_rules: Vec::new(),
+            cache_series: false,
+            memory_budget: 0,
+            unit_warnings: false,
+            link_x_axes: false,
+            hover_readout: HoverReadout::Off,
+            theme: Theme::System,
+            stream_offsets: Vec::new(),
+            resample_rate: 0,
+            gap_periods: default_gap_periods(),
+            shade_gaps: false,
+            watch_dir: false,
+            expr_library: Vec::new(),
+            new_user_expr: UserExpr::default(),
+            derived_channels: Vec::new(),
+            new_derived_channel: UserExpr::default(),
+            channel_units: Vec::new(),
+            new_channel_unit: ChannelUnit::default(),
+            channel_interpolation: Vec::new(),
+            new_channel_interpolation: ChannelInterpolation::default(),
+            channel_aliases: Vec::new(),
+            new_channel_alias: ChannelAlias::default(),
+            new_reference_curve: NewCurve::default(),
+            alias_identifiers: false,
+            tab_templates: Vec::new(),
+            new_template_name: String::new(),
+            sync_markers: Vec::new(),
+            new_marker: NewMarker::default(),
+            event_markers: Vec::new(),
+            bookmarks: [None; 9],
             search_help: "".into(),
+            search_channels: String::new(),
+            selected_channels: Vec::new(),
+            cursor: None,
             selected_tab: 0,
+            split_view: SplitView::Off,
+            split_tab: 0,
+            link_split_x_axes: false,
             tabs: vec![TabConfig::new(
                 "Tab 1".into(),
                 DEFAULT_ASPECT_RATIO,
                 vec![
-                    NamedPlot {
-                        name: "1.".into(),
-                        expr: Expr {
-                            x: "time".into(),
-                            y: "sin(time / PI) * 10.0".into(),
-                        },
-                    },
-                    NamedPlot {
-                        name: "2.".into(),
-                        expr: Expr {
-                            x: "time".into(),
-                            y: "cos(time / PI - PI) * 10.0".into(),
-                        },
-                    },
+                    NamedPlot::new("1.".into(), Expr::new("time", "sin(time / PI) * 10.0")),
+                    NamedPlot::new("2.".into(), Expr::new("time", "cos(time / PI - PI) * 10.0")),
                 ],
             )],
             dragged_tab: None,
@@ -74,6 +333,28 @@ impl Default for Config {
     }
 }
 
+impl Config {
+    /// Aliases that are replaced with the channel names before evaluating expressions.
+    pub fn expr_aliases(&self) -> &[ChannelAlias] {
+        match self.alias_identifiers {
+            true => &self.channel_aliases,
+            false => &[],
+        }
+    }
+
+    pub fn timing(&self) -> Timing {
+        let resample_interval = match self.resample_rate {
+            0 => 0,
+            rate => (1000 / rate).max(1),
+        };
+        Timing {
+            offsets: self.stream_offsets.clone(),
+            resample_interval,
+            interpolation: self.channel_interpolation.clone(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct TabConfig {
     pub name: String,
@@ -83,6 +364,190 @@ pub struct TabConfig {
     #[serde(skip)]
     #[serde(default)]
     pub editing: bool,
+    #[serde(default)]
+    pub mode: PlotMode,
+    #[serde(default)]
+    pub fft_window: FftWindow,
+    #[serde(default)]
+    pub lap_overlay: LapOverlay,
+    #[serde(default)]
+    pub pedal_map: PedalMapConfig,
+    #[serde(default)]
+    pub damper: DamperConfig,
+    #[serde(default)]
+    pub heatmap: HeatmapConfig,
+    /// x expression that replaces the one of all line plots while `use_alt_x` is set
+    #[serde(default)]
+    pub alt_x: String,
+    #[serde(default)]
+    pub use_alt_x: bool,
+    #[serde(default)]
+    pub x_axis: XAxis,
+    /// static curves drawn on top of the time plot
+    #[serde(default)]
+    pub reference_curves: Vec<ReferenceCurve>,
+    #[serde(default)]
+    pub subsampling: Subsampling,
+    /// show every series of the time plot in its own plot
+    #[serde(default)]
+    pub grid: bool,
+    #[serde(default = "default_grid_columns")]
+    pub grid_columns: usize,
+    /// x- and y-range of the time plot, `None` while it uses auto-bounds. Restored when the time
+    /// plot is first shown.
+    #[serde(default)]
+    pub bounds: Option<([f64; 2], [f64; 2])>,
+    /// the saved bounds were applied to the time plot
+    #[serde(skip)]
+    #[serde(default)]
+    pub bounds_restored: bool,
+    /// x-range of the time plot during the last frame
+    #[serde(skip)]
+    #[serde(default)]
+    pub x_bounds: Option<RangeInclusive<f64>>,
+    /// x-range the time plot should be zoomed to in the next frame
+    #[serde(skip)]
+    #[serde(default)]
+    pub zoom_to: Option<RangeInclusive<f64>>,
+    /// measurement cursors of the time plot, `None` if not in measurement mode
+    #[serde(skip)]
+    #[serde(default)]
+    pub measurement: Option<Measurement>,
+    /// y expression of a plot that is being edited in the larger editor window
+    #[serde(skip)]
+    #[serde(default)]
+    pub expr_editor: Option<ExprEditor>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PlotMode {
+    #[default]
+    Time,
+    Spectrum,
+    GgDiagram,
+    TrackMap,
+    PedalMap,
+    ValueTable,
+    DamperHistogram,
+    Heatmap,
+}
+
+impl PlotMode {
+    pub const ALL: [Self; 8] = [
+        Self::Time,
+        Self::Spectrum,
+        Self::GgDiagram,
+        Self::TrackMap,
+        Self::PedalMap,
+        Self::ValueTable,
+        Self::DamperHistogram,
+        Self::Heatmap,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Time => "Time",
+            Self::Spectrum => "Spectrum",
+            Self::GgDiagram => "G-G diagram",
+            Self::TrackMap => "Track map",
+            Self::PedalMap => "Pedal map",
+            Self::ValueTable => "Value table",
+            Self::DamperHistogram => "Damper histogram",
+            Self::Heatmap => "Heatmap",
+        }
+    }
+}
+
+/// What line plots of a time plot are drawn against.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum XAxis {
+    #[default]
+    Time,
+    /// driven distance, to compare laps at the same point of the track
+    Distance,
+}
+
+impl XAxis {
+    pub const ALL: [Self; 2] = [Self::Time, Self::Distance];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Time => "Time",
+            Self::Distance => "Distance",
+        }
+    }
+}
+
+/// How line plots are reduced to about one point per pixel column when zoomed out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Subsampling {
+    /// mean of each chunk
+    #[default]
+    Mean,
+    /// Largest-Triangle-Three-Buckets, keeps the points that shape the line, e.g. peaks
+    Lttb,
+}
+
+impl Subsampling {
+    pub const ALL: [Self; 2] = [Self::Mean, Self::Lttb];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mean => "Mean",
+            Self::Lttb => "LTTB",
+        }
+    }
+}
+
+/// Which line plots are listed with their value at the time hovered in a time plot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HoverReadout {
+    #[default]
+    Off,
+    SelectedTab,
+    AllTabs,
+}
+
+impl HoverReadout {
+    pub const ALL: [Self; 3] = [Self::Off, Self::SelectedTab, Self::AllTabs];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::SelectedTab => "Selected tab",
+            Self::AllTabs => "All tabs",
+        }
+    }
+}
+
+/// Splits the central panel to show a second tab next to the selected one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitView {
+    #[default]
+    Off,
+    SideBySide,
+    Stacked,
+}
+
+impl SplitView {
+    pub const ALL: [Self; 3] = [Self::Off, Self::SideBySide, Self::Stacked];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::SideBySide => "Side by side",
+            Self::Stacked => "Stacked",
+        }
+    }
+}
+
+/// A saved set of plots, that new tabs can be created from in one click.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TabTemplate {
+    pub name: String,
+    pub mode: PlotMode,
+    pub aspect_ratio: f32,
+    pub plots: Vec<NamedPlot>,
 }
 
 impl TabConfig {
@@ -93,23 +558,209 @@ impl TabConfig {
             aspect_ratio,
             plots,
             editing: false,
+            mode: PlotMode::Time,
+            fft_window: FftWindow::default(),
+            lap_overlay: LapOverlay::Off,
+            pedal_map: PedalMapConfig::default(),
+            damper: DamperConfig::default(),
+            heatmap: HeatmapConfig::default(),
+            alt_x: String::new(),
+            use_alt_x: false,
+            x_axis: XAxis::Time,
+            reference_curves: Vec::new(),
+            subsampling: Subsampling::Mean,
+            grid: false,
+            grid_columns: DEFAULT_GRID_COLUMNS,
+            bounds: None,
+            bounds_restored: false,
+            x_bounds: None,
+            zoom_to: None,
+            measurement: None,
+            expr_editor: None,
         }
     }
 
     pub fn named(name: String) -> Self {
         Self::new(name, DEFAULT_ASPECT_RATIO, Vec::new())
     }
+
+    /// Expression the plot is evaluated with. The alternate x expression and the distance x-axis
+    /// only apply to line plots, since the x expression of XY plots is part of what they show.
+    /// The alternate x expression takes precedence over the x-axis.
+    pub fn plot_expr(&self, plot: &NamedPlot) -> Expr {
+        let use_alt_x = self.use_alt_x && !self.alt_x.trim().is_empty();
+        match (plot.xy_mode, self.x_axis) {
+            (XyMode::Off, _) if use_alt_x => Expr::new(&self.alt_x, &plot.expr.y),
+            (XyMode::Off, XAxis::Distance) => Expr::new(eval::DISTANCE, &plot.expr.y),
+            _ => plot.expr.clone(),
+        }
+    }
+
+    /// Whether line plots are drawn against time, so time based overlays like source boundaries
+    /// and hover readouts apply.
+    pub fn x_is_time(&self) -> bool {
+        !self.use_alt_x && self.x_axis == XAxis::Time
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct NamedPlot {
     pub name: String,
     pub expr: Expr,
+    #[serde(default)]
+    pub xy_mode: XyMode,
+    #[serde(default)]
+    pub y_axis: YAxis,
+    /// `None` to use the color automatically assigned by the plot
+    #[serde(default)]
+    pub color: Option<Color32>,
+    #[serde(default)]
+    pub width: Option<f32>,
+    #[serde(default)]
+    pub dash: DashStyle,
+    /// show the whole session in the overview below the time plot
+    #[serde(default)]
+    pub overview: bool,
+    /// line plots of the same group are drawn as a min/max band with a mean line, empty for none
+    #[serde(default)]
+    pub band_group: String,
+    /// line plots of the same group are drawn as stacked areas, in the order of the plots, empty
+    /// for none
+    #[serde(default)]
+    pub stack_group: String,
+    /// the expression is kept and evaluated, but the series isn't drawn
+    #[serde(default)]
+    pub hidden: bool,
+    /// draw line plots as a high/low trace in a lane below the time plot
+    #[serde(default)]
+    pub digital: bool,
+    /// expression the segments of line plots are colored by with a colormap, empty for none
+    #[serde(default)]
+    pub color_by: String,
+    /// expression the area between line plots and its values is filled, e.g. the minimum of an
+    /// envelope, empty for none
+    #[serde(default)]
+    pub fill_to: String,
 }
 
 impl NamedPlot {
     fn new(name: String, expr: Expr) -> Self {
-        Self { name, expr }
+        Self {
+            name,
+            expr,
+            xy_mode: XyMode::Off,
+            y_axis: YAxis::Left,
+            color: None,
+            width: None,
+            dash: DashStyle::Solid,
+            overview: false,
+            band_group: String::new(),
+            stack_group: String::new(),
+            hidden: false,
+            digital: false,
+            color_by: String::new(),
+            fill_to: String::new(),
+        }
+    }
+
+    fn in_band(&self) -> bool {
+        self.xy_mode == XyMode::Off && !self.band_group.is_empty() && !self.digital
+    }
+
+    fn in_lane(&self) -> bool {
+        self.xy_mode == XyMode::Off && self.digital
+    }
+
+    fn in_stack(&self) -> bool {
+        self.xy_mode == XyMode::Off
+            && !self.stack_group.is_empty()
+            && self.band_group.is_empty()
+            && !self.digital
+    }
+
+    /// Applies the configured color, width and dash style. Without a configured color, the
+    /// `auto` color of the theme palette is used.
+    fn style_line(&self, line: Line, auto: Color32) -> Line {
+        let mut line = (line.style(self.dash.line_style())).color(self.color.unwrap_or(auto));
+        if let Some(width) = self.width {
+            line = line.width(width);
+        }
+        line
+    }
+}
+
+/// Default color of the series at `index` in the current theme.
+fn auto_color(ctx: &Context, index: usize) -> Color32 {
+    Theme::current(ctx).line_color(index)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DashStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl DashStyle {
+    pub const ALL: [Self; 3] = [Self::Solid, Self::Dashed, Self::Dotted];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Solid => "Solid",
+            Self::Dashed => "Dashed",
+            Self::Dotted => "Dotted",
+        }
+    }
+
+    fn line_style(&self) -> LineStyle {
+        match self {
+            Self::Solid => LineStyle::Solid,
+            Self::Dashed => LineStyle::dashed_loose(),
+            Self::Dotted => LineStyle::dotted_loose(),
+        }
+    }
+}
+
+/// Rendering of plots with a non-monotonic x expression. Range based slicing and subsampling
+/// rely on sorted x-values, so they are disabled in xy mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum XyMode {
+    #[default]
+    Off,
+    Lines,
+    Points,
+}
+
+impl XyMode {
+    pub const ALL: [Self; 3] = [Self::Off, Self::Lines, Self::Points];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Lines => "Lines",
+            Self::Points => "Points",
+        }
+    }
+}
+
+/// The y-axis of a time plot a series is scaled to. The right axis is scaled independently and
+/// mapped onto the left one, see [`AxisMapping`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum YAxis {
+    #[default]
+    Left,
+    Right,
+}
+
+impl YAxis {
+    pub const ALL: [Self; 2] = [Self::Left, Self::Right];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Left => "Left",
+            Self::Right => "Right",
+        }
     }
 }
 
@@ -120,6 +771,201 @@ pub fn add_tab(data: &mut PlotData, cfg: &mut Config) {
     cfg.selected_tab = cfg.tabs.len() - 1;
 }
 
+/// Switches the tab between the x expressions of its plots and the alternate x expression.
+fn toggle_alt_x(data: &mut PlotData, cfg: &mut Config, tab: usize) {
+    change_x(data, cfg, tab, |t| t.use_alt_x = !t.use_alt_x);
+}
+
+/// Changes what the line plots of the tab are drawn against. The values of the previous x
+/// expression are kept, so switching back doesn't evaluate them again.
+fn change_x(
+    data: &mut PlotData,
+    cfg: &mut Config,
+    tab: usize,
+    change: impl FnOnce(&mut TabConfig),
+) {
+    let old_exprs = (cfg.tabs[tab].plots.iter())
+        .map(|p| cfg.tabs[tab].plot_expr(p))
+        .collect::<Vec<_>>();
+    change(&mut cfg.tabs[tab]);
+
+    let tab_cfg = &cfg.tabs[tab];
+    let mut previous = data.x_stash.remove(&tab_cfg.id).unwrap_or_default();
+    let mut stash = Vec::with_capacity(old_exprs.len());
+    for (i, old_expr) in old_exprs.into_iter().enumerate() {
+        let expr = tab_cfg.plot_expr(&tab_cfg.plots[i]);
+        if expr == old_expr {
+            continue;
+        }
+        let values = match previous.iter().position(|(e, _)| *e == expr) {
+            Some(j) => previous.swap_remove(j).1,
+            None => data.start_job(&expr, cfg),
+        };
+        let old_values = std::mem::replace(&mut data.plots[tab][i], values);
+        stash.push((old_expr, old_values));
+    }
+    data.x_stash.insert(tab_cfg.id, stash);
+}
+
+fn restart_tab_jobs(data: &mut PlotData, cfg: &Config, tab: usize) {
+    let tab_cfg = &cfg.tabs[tab];
+    data.plots[tab] = (tab_cfg.plots.iter())
+        .map(|p| data.start_job(&tab_cfg.plot_expr(p), cfg))
+        .collect();
+}
+
+/// Adds a tab with the plots of the template.
+fn add_template_tab(data: &mut PlotData, cfg: &mut Config, template: usize) {
+    let template = &cfg.tab_templates[template];
+    let values = (template.plots.iter())
+        .map(|p| data.start_job(&p.expr, cfg))
+        .collect();
+
+    let plots = template.plots.clone();
+    let mut tab = TabConfig::new(template.name.clone(), template.aspect_ratio, plots);
+    tab.mode = template.mode;
+
+    cfg.tabs.push(tab);
+    data.plots.push(values);
+    cfg.selected_tab = cfg.tabs.len() - 1;
+}
+
+/// Saves the current tab as a template, replacing one with the same name.
+fn save_template(cfg: &mut Config, name: String) {
+    let tab_cfg = &cfg.tabs[cfg.selected_tab];
+    let template = TabTemplate {
+        name,
+        mode: tab_cfg.mode,
+        aspect_ratio: tab_cfg.aspect_ratio,
+        plots: tab_cfg.plots.clone(),
+    };
+    match cfg
+        .tab_templates
+        .iter_mut()
+        .find(|t| t.name == template.name)
+    {
+        Some(t) => *t = template,
+        None => cfg.tab_templates.push(template),
+    }
+}
+
+fn templates_menu(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
+    if cfg.tab_templates.is_empty() {
+        ui.label(RichText::new("No templates").weak());
+    }
+
+    let mut i = 0;
+    while i < cfg.tab_templates.len() {
+        let (add, remove) = ui
+            .horizontal(|ui| {
+                let add = ui.button(&cfg.tab_templates[i].name).clicked();
+                let remove = ui.small_button("🗙").clicked();
+                (add, remove)
+            })
+            .inner;
+        if add {
+            add_template_tab(data, cfg, i);
+            ui.close_menu();
+        }
+        if remove {
+            cfg.tab_templates.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut cfg.new_template_name)
+                .hint_text("template name")
+                .desired_width(150.0),
+        );
+        let valid = !cfg.new_template_name.trim().is_empty();
+        let resp = ui
+            .add_enabled(valid, Button::new("Save tab"))
+            .on_hover_text("Save the plots of the current tab as a template");
+        if resp.clicked() {
+            let name = std::mem::take(&mut cfg.new_template_name);
+            save_template(cfg, name.trim().to_string());
+        }
+    });
+}
+
+/// Evaluates all plots of all tabs.
+pub fn start_jobs(cfg: &Config, data: &PlotData) -> Vec<Vec<PlotValues>> {
+    (cfg.tabs.iter())
+        .map(|t| {
+            (t.plots.iter())
+                .map(|p| data.start_job(&t.plot_expr(p), cfg))
+                .collect()
+        })
+        .collect()
+}
+
+/// Adds a tab with the channels relevant to the fault, zoomed to the fault occurrence.
+pub fn add_fault_tab(data: &mut PlotData, cfg: &mut Config, fault: &FaultOccurrence) {
+    let name = format!("{} @ {}", fault.name, format_time(fault.start));
+    let plots = (Some(&fault.channel).into_iter())
+        .chain(fault.related.iter())
+        .map(|c| NamedPlot::new(c.clone(), Expr::new("time", c)))
+        .collect::<Vec<_>>();
+
+    let values = (plots.iter())
+        .map(|p| data.start_job(&p.expr, cfg))
+        .collect();
+
+    let mut tab = TabConfig::new(name, DEFAULT_ASPECT_RATIO, plots);
+    tab.zoom_to = Some(fault.start - FAULT_WINDOW_PADDING..=fault.end + FAULT_WINDOW_PADDING);
+
+    cfg.tabs.push(tab);
+    data.plots.push(values);
+    cfg.selected_tab = cfg.tabs.len() - 1;
+}
+
+/// Zooms the selected tab, or the first time plot if the selected one isn't, to the interval and
+/// moves the cursor to its start.
+pub fn jump_to(cfg: &mut Config, start: f64, end: f64) {
+    let range = start - ALARM_WINDOW_PADDING..=end + ALARM_WINDOW_PADDING;
+    show_range(cfg, range, start);
+}
+
+/// Zooms the selected tab, or the first time plot if the selected one isn't, to the range and
+/// moves the cursor.
+fn show_range(cfg: &mut Config, range: RangeInclusive<f64>, cursor: f64) {
+    let selected = (cfg.tabs.get(cfg.selected_tab)).is_some_and(|t| t.mode == PlotMode::Time);
+    if !selected {
+        let Some(tab) = cfg.tabs.iter().position(|t| t.mode == PlotMode::Time) else {
+            return;
+        };
+        cfg.selected_tab = tab;
+    }
+    let tab = &mut cfg.tabs[cfg.selected_tab];
+    tab.zoom_to = Some(range);
+    cfg.cursor = Some(cursor);
+}
+
+/// The cursor, or the center of the selected time plot if there is no cursor.
+fn bookmark_time(cfg: &Config) -> Option<f64> {
+    let visible = cfg
+        .tabs
+        .get(cfg.selected_tab)
+        .and_then(|t| t.x_bounds.as_ref());
+    cfg.cursor
+        .or_else(|| visible.map(|r| 0.5 * (r.start() + r.end())))
+}
+
+/// Centers the selected time plot on the time, keeping its zoom, and moves the cursor there.
+fn center_on(cfg: &mut Config, time: f64) {
+    let visible = cfg
+        .tabs
+        .get(cfg.selected_tab)
+        .and_then(|t| t.x_bounds.as_ref());
+    let width = visible.map_or(BOOKMARK_WINDOW, |r| r.end() - r.start());
+    show_range(cfg, time - 0.5 * width..=time + 0.5 * width, time);
+}
+
 pub fn remove_tab(data: &mut PlotData, cfg: &mut Config, tab: usize) -> bool {
     if cfg.tabs.len() == 1 {
         return false;
@@ -127,33 +973,31 @@ pub fn remove_tab(data: &mut PlotData, cfg: &mut Config, tab: usize) -> bool {
     cfg.tabs.remove(tab);
     data.plots.remove(tab);
 
-    if cfg.selected_tab > tab || cfg.selected_tab == cfg.tabs.len() {
-        cfg.selected_tab -= 1;
+    for t in [&mut cfg.selected_tab, &mut cfg.split_tab] {
+        if *t > tab || *t == cfg.tabs.len() {
+            *t -= 1;
+        }
     }
 
     true
 }
 
 pub fn move_tab(data: &mut PlotData, cfg: &mut Config, from: usize, to: usize) {
-    let selected_tab = cfg.selected_tab;
     if from < to {
         cfg.tabs[from..=to].rotate_left(1);
         data.plots[from..=to].rotate_left(1);
-
-        if selected_tab > from && selected_tab <= to {
-            cfg.selected_tab -= 1;
-        }
     } else {
         cfg.tabs[to..=from].rotate_right(1);
         data.plots[to..=from].rotate_right(1);
-
-        if selected_tab < from && selected_tab >= to {
-            cfg.selected_tab += 1;
-        }
     }
 
-    if selected_tab == from {
-        cfg.selected_tab = to;
+    for t in [&mut cfg.selected_tab, &mut cfg.split_tab] {
+        *t = match *t {
+            t if t == from => to,
+            t if from < to && t > from && t <= to => t - 1,
+            t if from > to && t < from && t >= to => t + 1,
+            t => t,
+        };
     }
 }
 
@@ -167,33 +1011,92 @@ pub fn select_prev_tab(cfg: &mut Config) {
 
 pub fn add_plot(data: &mut PlotData, cfg: &mut Config, plot: NamedPlot, eval: bool) {
     let tab = cfg.selected_tab;
-    let plots = &mut cfg.tabs[tab].plots;
 
     if eval {
-        let job = Job::start(plot.expr.clone(), Arc::clone(&data.streams));
-        data.plots[tab].push(PlotValues::Job(job));
+        let expr = cfg.tabs[tab].plot_expr(&plot);
+        let job = data.start_job(&expr, cfg);
+        data.plots[tab].push(job);
     } else {
         data.plots[tab].push(PlotValues::Result(Ok(Vec::new())));
     }
-    plots.push(plot);
+    cfg.tabs[tab].plots.push(plot);
 }
 
-pub fn move_plot(data: &mut PlotData, cfg: &mut Config, from: usize, to: usize) {
-    let tab = cfg.selected_tab;
-    if from < to {
-        for i in from..to {
-            cfg.tabs[tab].plots.swap(i, i + 1);
-            data.plots[tab].swap(i, i + 1);
-        }
-    } else {
-        for i in (to..from).rev() {
-            cfg.tabs[tab].plots.swap(i + 1, i);
-            data.plots[tab].swap(i + 1, i);
-        }
+/// Splits a channel name like `tire_temp_fl` into the prefix `tire_temp_` and the corner suffix.
+fn wheel_channel(y: &str) -> Option<(&str, &str)> {
+    let y = y.trim();
+    if !y.chars().all(eval::is_ident_char) {
+        return None;
+    }
+    let split = y.len().checked_sub(2)?;
+    let (prefix, corner) = (y.get(..split)?, y.get(split..)?);
+    let is_corner = WHEEL_CORNERS.iter().any(|c| c.eq_ignore_ascii_case(corner));
+    (prefix.ends_with('_') && is_corner).then_some((prefix, corner))
+}
+
+/// Puts the plot into a band group together with the channels of the other three corners, which
+/// are added if they aren't plotted yet.
+fn add_wheel_group(data: &mut PlotData, cfg: &mut Config, plot: usize) {
+    let tab_cfg = &mut cfg.tabs[cfg.selected_tab];
+    let lead = &mut tab_cfg.plots[plot];
+    let Some((prefix, corner)) = wheel_channel(&lead.expr.y) else {
+        return;
+    };
+    let (prefix, uppercase) = (prefix.to_string(), corner.chars().all(|c| c.is_uppercase()));
+    let group = prefix.trim_end_matches('_').to_string();
+    lead.band_group = group.clone();
+    let x = lead.expr.x.clone();
+
+    for c in WHEEL_CORNERS {
+        let c = if uppercase {
+            c.to_uppercase()
+        } else {
+            c.to_string()
+        };
+        let y = format!("{prefix}{c}");
+        let tab_cfg = &mut cfg.tabs[cfg.selected_tab];
+        match tab_cfg.plots.iter_mut().find(|p| p.expr.y.trim() == y) {
+            Some(p) => p.band_group = group.clone(),
+            None => {
+                let mut p = NamedPlot::new(y.clone(), Expr::new(x.clone(), y));
+                p.band_group = group.clone();
+                add_plot(data, cfg, p, true);
+            }
+        }
+    }
+}
+
+pub fn move_plot(data: &mut PlotData, cfg: &mut Config, from: usize, to: usize) {
+    let tab = cfg.selected_tab;
+    let id = cfg.tabs[tab].id;
+    let moved = |i: usize| match i {
+        i if i == from => to,
+        i if from < i && i <= to => i - 1,
+        i if to <= i && i < from => i + 1,
+        i => i,
+    };
+    for (_, i, _) in data.edited.iter_mut().filter(|(t, ..)| *t == id) {
+        *i = moved(*i);
+    }
+    if let Some(editor) = &mut cfg.tabs[tab].expr_editor {
+        editor.plot = moved(editor.plot);
+    }
+    if from < to {
+        for i in from..to {
+            cfg.tabs[tab].plots.swap(i, i + 1);
+            data.plots[tab].swap(i, i + 1);
+        }
+    } else {
+        for i in (to..from).rev() {
+            cfg.tabs[tab].plots.swap(i + 1, i);
+            data.plots[tab].swap(i + 1, i);
+        }
     }
 }
 
 pub fn keybindings(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
+    // digits are typed into text fields
+    let typing = ui.memory(|m| m.focused().is_some());
     ui.input_mut(|input| {
         if input.consume_key(Modifiers::CTRL, Key::T) {
             add_tab(data, cfg);
@@ -214,6 +1117,37 @@ pub fn keybindings(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
             select_next_tab(cfg);
         }
 
+        let tab = cfg.selected_tab;
+        if input.consume_key(Modifiers::CTRL | Modifiers::SHIFT, Key::PageUp) && tab > 0 {
+            move_tab(data, cfg, tab, tab - 1);
+        }
+        if input.consume_key(Modifiers::CTRL | Modifiers::SHIFT, Key::PageDown)
+            && tab + 1 < cfg.tabs.len()
+        {
+            move_tab(data, cfg, tab, tab + 1);
+        }
+
+        if input.consume_key(Modifiers::CTRL | Modifiers::SHIFT, Key::X) {
+            let tab = cfg.selected_tab;
+            toggle_alt_x(data, cfg, tab);
+        }
+
+        for (i, key) in BOOKMARK_KEYS.into_iter().enumerate() {
+            if input.consume_key(Modifiers::CTRL, key) {
+                cfg.bookmarks[i] = bookmark_time(cfg);
+            } else if !typing && input.consume_key(Modifiers::NONE, key) {
+                if let Some(time) = cfg.bookmarks[i] {
+                    center_on(cfg, time);
+                }
+            }
+        }
+
+        if input.consume_key(Modifiers::CTRL, Key::Num0) {
+            let tab = cfg.selected_tab;
+            let tab_cfg = &mut cfg.tabs[tab];
+            tab_cfg.zoom_to = full_x_range(&data.plots[tab], &tab_cfg.plots);
+        }
+
         if input.consume_key(Modifiers::CTRL, Key::H) {
             cfg.show_help = !cfg.show_help;
         }
@@ -289,7 +1223,7 @@ pub fn tab_bar(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
                     let id = Id::new("tab").with(i);
                     let layer_id = LayerId::new(Order::Tooltip, id);
                     ui.with_layer_id(layer_id, |ui| {
-                        draw_tab(ui, &mut t.name, selected, t.editing)
+                        draw_tab(ui, &mut t.name, &data.session, selected, t.editing)
                     });
                     let transform = TSTransform::new(Vec2::new(dist, 0.0), 1.0);
                     ui.ctx().transform_layer_shapes(layer_id, transform);
@@ -299,14 +1233,14 @@ pub fn tab_bar(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
                     let id = Id::new("tab").with(i);
                     let layer_id = LayerId::new(Order::Foreground, id);
                     ui.with_layer_id(layer_id, |ui| {
-                        draw_tab(ui, &mut t.name, selected, t.editing)
+                        draw_tab(ui, &mut t.name, &data.session, selected, t.editing)
                     });
                     let offset = -dist.signum() * tab_distance;
                     let transform = TSTransform::new(Vec2::new(offset, 0.0), 1.0);
                     ui.ctx().transform_layer_shapes(layer_id, transform);
                 }
                 _ => {
-                    action = draw_tab(ui, &mut t.name, selected, t.editing);
+                    action = draw_tab(ui, &mut t.name, &data.session, selected, t.editing);
                 }
             };
 
@@ -335,13 +1269,189 @@ pub fn tab_bar(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
         if resp.clicked() {
             add_tab(data, cfg);
         }
+        ui.menu_button("▾", |ui| templates_menu(ui, data, cfg))
+            .response
+            .on_hover_text("Tab templates");
 
-        util::ratio_slider(
-            ui,
-            &mut cfg.tabs[cfg.selected_tab].aspect_ratio,
-            DEFAULT_ASPECT_RATIO,
-            1000.0,
-        );
+        let tab_cfg = &mut cfg.tabs[cfg.selected_tab];
+        ComboBox::from_id_source("plot_mode")
+            .selected_text(tab_cfg.mode.name())
+            .show_ui(ui, |ui| {
+                for m in PlotMode::ALL {
+                    ui.selectable_value(&mut tab_cfg.mode, m, m.name());
+                }
+            });
+
+        match tab_cfg.mode {
+            PlotMode::Time => {
+                util::ratio_slider(ui, &mut tab_cfg.aspect_ratio, DEFAULT_ASPECT_RATIO, 1000.0);
+                ComboBox::from_id_source("lap_overlay")
+                    .selected_text(tab_cfg.lap_overlay.name())
+                    .show_ui(ui, |ui| {
+                        for o in LapOverlay::ALL {
+                            ui.selectable_value(&mut tab_cfg.lap_overlay, o, o.name());
+                        }
+                    });
+                let mut x_axis = tab_cfg.x_axis;
+                ComboBox::from_id_source("x_axis")
+                    .selected_text(x_axis.name())
+                    .show_ui(ui, |ui| {
+                        for a in XAxis::ALL {
+                            ui.selectable_value(&mut x_axis, a, a.name());
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "X-axis of line plots, the distance is the `driven` channel or the \
+                        integrated mean of the wheel speeds",
+                    );
+                if x_axis != tab_cfg.x_axis {
+                    let tab = cfg.selected_tab;
+                    change_x(data, cfg, tab, |t| t.x_axis = x_axis);
+                }
+                let tab_cfg = &mut cfg.tabs[cfg.selected_tab];
+                ComboBox::from_id_source("subsampling")
+                    .selected_text(tab_cfg.subsampling.name())
+                    .show_ui(ui, |ui| {
+                        for s in Subsampling::ALL {
+                            ui.selectable_value(&mut tab_cfg.subsampling, s, s.name());
+                        }
+                    })
+                    .response
+                    .on_hover_text("Subsampling of zoomed out line plots");
+
+                ui.toggle_value(&mut tab_cfg.grid, "Grid")
+                    .on_hover_text("Show every plot separately, with linked x-axes");
+                if tab_cfg.grid {
+                    let columns = DragValue::new(&mut tab_cfg.grid_columns)
+                        .range(1..=MAX_GRID_COLUMNS)
+                        .suffix(" columns");
+                    ui.add(columns);
+                }
+
+                if let Some(run_b) = &mut data.run_b {
+                    let dir = run_b.dir.file_name().unwrap_or(run_b.dir.as_os_str());
+                    ui.label(format!("B: {}", dir.to_string_lossy()));
+                    ui.add(DragValue::new(&mut run_b.offset).speed(0.01).suffix(" s"))
+                        .on_hover_text("Time offset of run B");
+                    if ui.button("🗙").on_hover_text("Close run B").clicked() {
+                        data.run_b = None;
+                    }
+                }
+
+                let alt_x = TextEdit::singleline(&mut tab_cfg.alt_x)
+                    .font(TextStyle::Monospace)
+                    .hint_text("alternate x")
+                    .desired_width(120.0);
+                let resp = ui.add(alt_x);
+                let alt_x_changed = resp.lost_focus() && tab_cfg.use_alt_x;
+                let mut use_alt_x = tab_cfg.use_alt_x;
+                let toggled = ui
+                    .toggle_value(&mut use_alt_x, "Alt X")
+                    .on_hover_text(
+                        "Use the alternate x expression for all line plots (Ctrl+Shift+X)",
+                    )
+                    .clicked();
+                if toggled {
+                    let tab = cfg.selected_tab;
+                    toggle_alt_x(data, cfg, tab);
+                } else if alt_x_changed {
+                    let tab = cfg.selected_tab;
+                    data.x_stash.remove(&cfg.tabs[tab].id);
+                    restart_tab_jobs(data, cfg, tab);
+                }
+                let tab_cfg = &mut cfg.tabs[cfg.selected_tab];
+
+                let mut measuring = tab_cfg.measurement.is_some();
+                let resp = ui
+                    .toggle_value(&mut measuring, "Measure")
+                    .on_hover_text("Click on the plot to place two cursors");
+                if resp.changed() {
+                    tab_cfg.measurement = measuring.then(Measurement::default);
+                }
+            }
+            PlotMode::Spectrum => {
+                ComboBox::from_id_source("fft_window")
+                    .selected_text(tab_cfg.fft_window.name())
+                    .show_ui(ui, |ui| {
+                        for w in FftWindow::ALL {
+                            ui.selectable_value(&mut tab_cfg.fft_window, w, w.name());
+                        }
+                    });
+            }
+            PlotMode::GgDiagram => {
+                let resp = ui
+                    .button("+ accel")
+                    .on_hover_text("Add a plot of accel_y (lateral) vs accel_x (longitudinal)");
+                if resp.clicked() {
+                    let plot = NamedPlot::new("G-G".into(), Expr::new("accel_y", "accel_x"));
+                    add_plot(data, cfg, plot, true);
+                }
+            }
+            PlotMode::TrackMap => {
+                let text = match &data.track {
+                    Some(t) => format!("from {}", t.source.name()),
+                    None => "no GPS or speed and yaw rate channels".into(),
+                };
+                ui.label(text)
+                    .on_hover_text("The track is colored by the first expression of the tab");
+            }
+            PlotMode::PedalMap => {
+                let pedal_map = &mut tab_cfg.pedal_map;
+                channel_combo(ui, "pedal", &mut pedal_map.pedal, &data.streams);
+                channel_combo(ui, "torque", &mut pedal_map.torque, &data.streams);
+                channel_combo(ui, "speed", &mut pedal_map.speed, &data.streams);
+                ui.add(
+                    DragValue::new(&mut pedal_map.speed_step)
+                        .range(1.0..=f64::MAX)
+                        .prefix("speed step: "),
+                )
+                .on_hover_text("The plots of the tab are drawn as reference maps");
+            }
+            PlotMode::DamperHistogram => {
+                let damper = &mut tab_cfg.damper;
+                for (corner, channel) in CORNERS.iter().zip(damper.channels.iter_mut()) {
+                    channel_combo(ui, corner, channel, &data.streams);
+                }
+                ui.add(
+                    DragValue::new(&mut damper.bucket)
+                        .range(1.0..=f64::MAX)
+                        .prefix("bucket: ")
+                        .suffix(" mm/s"),
+                );
+                ui.add(
+                    DragValue::new(&mut damper.max_speed)
+                        .range(damper.bucket..=f64::MAX)
+                        .prefix("max: ")
+                        .suffix(" mm/s"),
+                )
+                .on_hover_text("Faster samples are counted in the outermost buckets");
+            }
+            PlotMode::Heatmap => {
+                let heatmap = &mut tab_cfg.heatmap;
+                ui.add(
+                    DragValue::new(&mut heatmap.bins)
+                        .range(2..=heatmap::MAX_HEATMAP_BINS)
+                        .prefix("bins: "),
+                );
+                ComboBox::from_id_source("heatmap_value")
+                    .selected_text(heatmap.value.name())
+                    .show_ui(ui, |ui| {
+                        for v in CellValue::ALL {
+                            ui.selectable_value(&mut heatmap.value, v, v.name());
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "The first expression of the tab is binned, the cells are colored by the \
+                        number of samples or the mean of the second expression",
+                    );
+            }
+            PlotMode::ValueTable => {
+                ui.label("Click a row to move the cursor, right click to copy")
+                    .on_hover_text("The rows are the samples of the first expression of the tab");
+            }
+        }
 
         ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
             ui.toggle_value(&mut cfg.show_help, "?");
@@ -349,6 +1459,18 @@ pub fn tab_bar(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
     });
 }
 
+fn channel_combo(ui: &mut Ui, label: &str, channel: &mut String, streams: &[LogStream]) {
+    ComboBox::from_label(label)
+        .selected_text(channel.as_str())
+        .show_ui(ui, |ui| {
+            for e in streams.iter().flat_map(|s| s.entries.iter()) {
+                if ui.selectable_label(*channel == e.name, &e.name).clicked() {
+                    *channel = e.name.clone();
+                }
+            }
+        });
+}
+
 enum TabAction {
     DragStarted,
     Removed,
@@ -357,7 +1479,13 @@ enum TabAction {
     StopEdit,
 }
 
-fn draw_tab(ui: &mut Ui, name: &mut String, selected: bool, editing: bool) -> Option<TabAction> {
+fn draw_tab(
+    ui: &mut Ui,
+    name: &mut String,
+    session: &SessionMeta,
+    selected: bool,
+    editing: bool,
+) -> Option<TabAction> {
     let mut action = None;
 
     let tab_fill = if selected {
@@ -388,7 +1516,7 @@ fn draw_tab(ui: &mut Ui, name: &mut String, selected: bool, editing: bool) -> Op
                     resp.request_focus();
                 }
             } else {
-                let label = Label::new(name.clone())
+                let label = Label::new(session.resolve(name))
                     .selectable(false)
                     .sense(Sense::click_and_drag());
                 let resp = ui.add_sized(Vec2::new(TAB_BUTTON_WIDTH, TAB_BUTTON_HEIGHT), label);
@@ -435,6 +1563,7 @@ pub fn tab_plot(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
                     input_sidebar(ui, data, cfg);
                 });
         });
+    start_edited_jobs(ui, data, cfg);
 
     if cfg.show_help {
         SidePanel::right("help")
@@ -454,181 +1583,2868 @@ pub fn tab_plot(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
     CentralPanel::default()
         .frame(Frame::none())
         .show_inside(ui, |ui| {
-            let tab_cfg = &mut cfg.tabs[cfg.selected_tab];
-
-            let num_pixels = ui.ctx().pixels_per_point() * ui.available_width();
-            Plot::new(tab_cfg.id)
-                .data_aspect(tab_cfg.aspect_ratio)
-                .label_formatter(|_, v| {
-                    let x = format_time(v.x);
-                    let y = (v.y * 1000.0).round() / 1000.0;
-                    format!("t = {x}\ny = {y}")
-                })
-                .legend(Legend::default())
-                .show(ui, |ui| {
-                    let auto_bounds = ui.auto_bounds().any();
-                    let x_min = *ui.plot_bounds().range_x().start();
-                    let x_max = *ui.plot_bounds().range_x().end();
+            let split_tab = (cfg.split_view != SplitView::Off && cfg.tabs.len() > 1)
+                .then(|| cfg.split_tab.min(cfg.tabs.len() - 1));
+            let Some(split_tab) = split_tab else {
+                enforce_memory_budget(data, cfg, &[cfg.selected_tab]);
+                tab_view(ui, data, cfg, cfg.selected_tab, None);
+                return;
+            };
 
-                    // HACK: logs are in 50Hz (20ms steps), but that frequency could change at any
-                    // time, or even be dynamic
-                    let steps = 50.0 * (x_max - x_min);
-                    let chunk_size = ((steps / num_pixels as f64) as usize).max(1);
+            cfg.split_tab = split_tab;
+            enforce_memory_budget(data, cfg, &[cfg.selected_tab, split_tab]);
+            let link = cfg.link_split_x_axes.then_some(SPLIT_LINKED_X_GROUP);
 
-                    for (values, p) in data.plots[cfg.selected_tab]
-                        .iter_mut()
-                        .zip(tab_cfg.plots.iter())
-                    {
-                        if let PlotValues::Job(j) = values {
-                            if j.is_done() {
-                                let job = std::mem::replace(values, PlotValues::empty());
-                                *values = PlotValues::Result(job.into_job().unwrap().join());
-                            } else {
-                                ui.ctx().request_repaint();
+            let rect = ui.available_rect_before_wrap();
+            let spacing = SPLIT_SPACING / 2.0;
+            let (first, second) = match cfg.split_view {
+                SplitView::Stacked => {
+                    let y = rect.center().y;
+                    let first = Rect::from_min_max(rect.min, Pos2::new(rect.max.x, y - spacing));
+                    let second = Rect::from_min_max(Pos2::new(rect.min.x, y + spacing), rect.max);
+                    (first, second)
+                }
+                _ => {
+                    let x = rect.center().x;
+                    let first = Rect::from_min_max(rect.min, Pos2::new(x - spacing, rect.max.y));
+                    let second = Rect::from_min_max(Pos2::new(x + spacing, rect.min.y), rect.max);
+                    (first, second)
+                }
+            };
+
+            ui.allocate_ui_at_rect(first, |ui| {
+                tab_view(ui, data, cfg, cfg.selected_tab, link);
+            });
+            ui.allocate_ui_at_rect(second, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Split view");
+                    ComboBox::from_id_source("split_tab")
+                        .selected_text(&cfg.tabs[cfg.split_tab].name)
+                        .show_ui(ui, |ui| {
+                            for (i, t) in cfg.tabs.iter().enumerate() {
+                                ui.selectable_value(&mut cfg.split_tab, i, &t.name);
                             }
+                        });
+                });
+                if cfg.split_tab == cfg.selected_tab {
+                    ui.label("The tab is already shown, select another one");
+                } else {
+                    tab_view(ui, data, cfg, cfg.split_tab, link);
+                }
+            });
+        });
+}
+
+/// Shows the plot of a tab. `link_x` is the group to link the x-axis of time plots with, if the
+/// x-axes aren't already linked across all tabs.
+fn tab_view(
+    ui: &mut Ui,
+    data: &mut PlotData,
+    cfg: &mut Config,
+    tab: usize,
+    link_x: Option<&'static str>,
+) {
+    if cfg.tabs[tab].mode == PlotMode::Time && side_series::request(data, cfg, tab) {
+        ui.ctx().request_repaint_after(JOB_POLL_INTERVAL);
+    }
+
+    let tab_cfg = &mut cfg.tabs[tab];
+    let plots = &mut data.plots[tab];
+    let cursor = &mut cfg.cursor;
+
+    poll_jobs(ui, plots);
+
+    match tab_cfg.mode {
+        PlotMode::Time if tab_cfg.lap_overlay != LapOverlay::Off => {
+            lap_plot(ui, tab_cfg, plots, &data.laps, data.track.as_ref());
+        }
+        PlotMode::Time => {
+            // borrowed separately from the event markers
+            let expr_aliases = match cfg.alias_identifiers {
+                true => &cfg.channel_aliases[..],
+                false => &[],
+            };
+            let ctx = TimePlotContext {
+                streams: &data.streams,
+                stream_offsets: &cfg.stream_offsets,
+                channel_units: &cfg.channel_units,
+                aliases: &cfg.channel_aliases,
+                markers: &cfg.sync_markers,
+                events: &mut cfg.event_markers,
+                link_x: match cfg.link_x_axes {
+                    true => Some(LINKED_X_GROUP),
+                    false => link_x,
+                },
+                hover_line: cfg.hover_readout != HoverReadout::Off,
+                gap_periods: cfg.gap_periods,
+                shade_gaps: cfg.shade_gaps,
+                violations: &data.power_violations,
+                side_series: &data.side_series,
+                run_b: (data.run_b.as_mut()).map(|b| (b, &*cfg.expr_library, expr_aliases)),
+            };
+            let hovered = match tab_cfg.grid && tab_cfg.plots.len() > 1 {
+                true => grid_plot(ui, tab_cfg, plots, ctx, cursor),
+                false => time_plot(ui, tab_cfg, plots, ctx, cursor),
+            };
+            if let Some(time) = hovered.filter(|_| cfg.hover_readout != HoverReadout::Off) {
+                hover_readout(ui, data, cfg, time);
+            }
+        }
+        PlotMode::Spectrum => spectrum_plot(ui, tab_cfg, plots),
+        PlotMode::GgDiagram => gg_plot(ui, tab_cfg, plots),
+        PlotMode::TrackMap => track_plot(ui, tab_cfg, plots, data.track.as_ref(), cursor),
+        PlotMode::PedalMap => pedal_plot(ui, tab_cfg, plots, &data.streams),
+        PlotMode::ValueTable => value_table(ui, tab_cfg, plots, cursor),
+        PlotMode::DamperHistogram => damper_plot(ui, tab_cfg, &data.streams),
+        PlotMode::Heatmap => heatmap_plot(ui, tab_cfg, plots),
+    }
+}
+
+/// Shows every plot of the tab separately, arranged in a grid. The x-axes of the line plots are
+/// linked, their y-axes fit the series. Bands, run B, the overview and measurements are only
+/// shown in the single plot.
+fn grid_plot(
+    ui: &mut Ui,
+    tab_cfg: &mut TabConfig,
+    plots: &[PlotValues],
+    ctx: TimePlotContext,
+    cursor: &mut Option<f64>,
+) -> Option<f64> {
+    let TimePlotContext {
+        streams,
+        channel_units,
+        aliases,
+        markers,
+        events,
+        link_x,
+        hover_line,
+        gap_periods,
+        ..
+    } = ctx;
+
+    let indices = (0..tab_cfg.plots.len())
+        .filter(|&i| !tab_cfg.plots[i].hidden)
+        .collect::<Vec<_>>();
+    let columns = tab_cfg.grid_columns.clamp(1, indices.len().max(1));
+    let rows = indices.len().div_ceil(columns);
+    let spacing = ui.spacing().item_spacing;
+    let width = (ui.available_width() - spacing.x * (columns - 1) as f32) / columns as f32;
+    let height = (ui.available_height() - spacing.y * (rows - 1) as f32) / rows as f32;
+    let num_pixels = ui.ctx().pixels_per_point() * width;
+    let group = match link_x {
+        Some(group) => Id::new(group),
+        None => Id::new(tab_cfg.id).with("grid"),
+    };
+    let loading = plots.iter().any(|p| matches!(p, PlotValues::Job(_)));
+    let zoom_to = if loading {
+        None
+    } else {
+        tab_cfg.zoom_to.take()
+    };
+
+    let mut hovered = None;
+    let mut x_bounds = None;
+    for row in indices.chunks(columns) {
+        ui.horizontal(|ui| {
+            for &i in row {
+                let p = &tab_cfg.plots[i];
+                let unit = units::channel_unit(streams, channel_units, p.expr.y.trim());
+                let name = legend_name(aliases::display_name(aliases, &p.name), unit.as_deref());
+                let is_line = p.xy_mode == XyMode::Off;
+                let mut plot = Plot::new((tab_cfg.id, "grid", i))
+                    .width(width)
+                    .height(height)
+                    .label_formatter(move |_, v| {
+                        let y = (v.y * 1000.0).round() / 1000.0;
+                        match &unit {
+                            Some(unit) => format!("t = {}\ny = {y} {unit}", format_time(v.x)),
+                            None => format!("t = {}\ny = {y}", format_time(v.x)),
                         }
+                    })
+                    .legend(Legend::default());
+                if is_line {
+                    plot = (plot.link_axis(group, true, false)).link_cursor(group, true, false);
+                }
 
-                        match values {
-                            PlotValues::Result(Ok(d)) if !d.is_empty() => {
-                                // when auto bounds are set, use full range to avoid slowly zooming out
-                                let range = if auto_bounds {
-                                    0..d.len()
-                                } else {
-                                    find_plot_range(d, x_min, x_max)
-                                };
+                let resp = plot.show(ui, |ui| {
+                    if let Some(range) = zoom_to.as_ref().filter(|_| is_line) {
+                        zoom_x_range(ui, &plots[i..=i], &tab_cfg.plots[i..=i], None, range);
+                    }
+
+                    let auto_bounds = ui.auto_bounds().any();
+                    let x_min = *ui.plot_bounds().range_x().start();
+                    let x_max = *ui.plot_bounds().range_x().end();
+                    let color = p.color.unwrap_or_else(|| auto_color(ui.ctx(), i));
+                    match &plots[i] {
+                        PlotValues::Result(Ok(d)) if !d.is_empty() && !is_line => {
+                            let stride = d.len().div_ceil(MAX_XY_POINTS);
+                            let values = d.iter().step_by(stride).copied().collect::<Vec<_>>();
+                            let values = PlotPoints::Owned(values);
+                            match p.xy_mode {
+                                XyMode::Points => {
+                                    let radius = p.width.unwrap_or(XY_POINT_RADIUS);
+                                    let points = Points::new(values).radius(radius).name(&name);
+                                    ui.points(points.color(color))
+                                }
+                                _ => ui.line(p.style_line(Line::new(values).name(&name), color)),
+                            }
+                        }
+                        PlotValues::Result(Ok(d)) if !d.is_empty() => {
+                            let range = if auto_bounds {
+                                0..d.len()
+                            } else {
+                                find_plot_range(d, x_min, x_max)
+                            };
 
-                                let values = subsample_plot(&d[range], chunk_size);
-                                ui.line(Line::new(PlotPoints::Owned(values)).name(&p.name));
+                            let d = &d[range];
+                            let chunk_size = chunk_size(d, x_max - x_min, num_pixels);
+                            for part in split_at_gaps(d, gap_periods) {
+                                let values = subsample_plot(part, chunk_size, tab_cfg.subsampling);
+                                let line = Line::new(PlotPoints::Owned(values)).name(&name);
+                                ui.line(p.style_line(line, color));
                             }
-                            _ => ui.line(Line::new([0.0, f64::NAN]).name(&p.name)),
+                        }
+                        _ => {
+                            let line = Line::new([0.0, f64::NAN]).name(&name);
+                            ui.line(p.style_line(line, color))
+                        }
+                    }
+                    if !is_line {
+                        return;
+                    }
+
+                    for m in markers.iter() {
+                        ui.vline(VLine::new(m.time).color(SYNC_MARKER_COLOR));
+                    }
+                    for e in events.iter() {
+                        ui.vline(VLine::new(e.time).color(EVENT_MARKER_COLOR));
+                    }
+                    if let Some(t) = *cursor {
+                        ui.vline(VLine::new(t).color(CURSOR_COLOR));
+                    }
+                    let pointer = ui.pointer_coordinate().map(|p| p.x);
+                    if let Some(x) = pointer.filter(|_| hover_line && ui.response().hovered()) {
+                        let color = ui.ctx().style().visuals.weak_text_color();
+                        ui.vline(VLine::new(x).color(color).style(LineStyle::dashed_dense()));
+                    }
+                    if ui.response().clicked() {
+                        if let Some(p) = ui.pointer_coordinate() {
+                            *cursor = Some(p.x);
                         }
                     }
                 });
+
+                if is_line {
+                    x_bounds.get_or_insert(resp.transform.bounds().range_x());
+                    if let Some(pos) = resp.response.hover_pos() {
+                        hovered = Some(resp.transform.value_from_position(pos).x);
+                    }
+                }
+            }
         });
+    }
+
+    tab_cfg.x_bounds = x_bounds;
+    hovered
 }
 
-fn input_sidebar(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
-    // HACK: calculation barely works, because expr inputs can be multiline
-    let plot_height = 3.0 * ui.spacing().interact_size.y
-        + 2.0 * ui.spacing().item_spacing.y
-        + 6.0 * TEXT_EDIT_MARGIN_Y
-        + 2.0 * PLOT_FRAME_PADDING;
-    let plot_spacing = ui.spacing().item_spacing.y;
-    let plot_distance = plot_height + plot_spacing;
+/// Reloads the spilled series of the visible tabs. Then, while the series in memory exceed the
+/// memory budget, moves the values of the other x expressions and the series of the least
+/// recently shown tabs to disk.
+fn enforce_memory_budget(data: &mut PlotData, cfg: &Config, visible: &[usize]) {
+    for &tab in visible.iter() {
+        let tab_cfg = &cfg.tabs[tab];
+        data.spill.touch(tab_cfg.id);
+        for i in 0..data.plots[tab].len() {
+            if let PlotValues::Spilled(s) = &data.plots[tab][i] {
+                data.plots[tab][i] = match s.load() {
+                    Ok(values) => PlotValues::Result(Ok(values)),
+                    Err(_) => {
+                        let expr = tab_cfg.plot_expr(&tab_cfg.plots[i]);
+                        data.start_job(&expr, cfg)
+                    }
+                };
+            }
+        }
+    }
 
-    let pointer_pos = ui.ctx().pointer_interact_pos();
-    let drag = match (pointer_pos, cfg.dragged_plot) {
-        (Some(pointer_pos), Some((from, grab_pos))) => {
-            let distance = pointer_pos.y - grab_pos.y;
-            let moved = (distance / plot_distance).round() as isize;
-            let len = cfg.tabs[cfg.selected_tab].plots.len();
-            let to = (from as isize + moved).clamp(0, len as isize - 1) as usize;
+    if cfg.memory_budget == 0 {
+        return;
+    }
+    let budget = cfg.memory_budget as usize * 1024 * 1024;
+    let mut used = (data.plots.iter().flatten())
+        .chain(data.x_stash.values().flatten().map(|(_, v)| v))
+        .map(resident_size)
+        .sum::<usize>();
+    // the recent series only speed up reevaluation, so they get what is left of the budget
+    data.recent_series.shrink_to(budget.saturating_sub(used));
+    if used <= budget {
+        return;
+    }
 
-            // move the plot if it was dropped
-            if ui.input(|i| i.pointer.any_released()) {
-                move_plot(data, cfg, from, to);
-                cfg.dragged_plot = None;
-                None
+    let mut tabs = (0..cfg.tabs.len())
+        .filter(|t| !visible.contains(t))
+        .collect::<Vec<_>>();
+    tabs.sort_by_key(|&t| data.spill.last_used(cfg.tabs[t].id));
+
+    let spill = &mut data.spill;
+    let mut spill_values = |values: &mut PlotValues| {
+        let size = resident_size(values);
+        if let PlotValues::Result(Ok(d)) = values {
+            if size > 0 {
+                if let Ok(s) = spill.spill(d) {
+                    *values = PlotValues::Spilled(s);
+                    used -= size;
+                }
+            }
+        }
+        used <= budget
+    };
+
+    for (_, values) in data.x_stash.values_mut().flatten() {
+        if spill_values(values) {
+            return;
+        }
+    }
+
+    for t in tabs {
+        for values in data.plots[t].iter_mut() {
+            if spill_values(values) {
+                return;
+            }
+        }
+    }
+}
+
+fn resident_size(values: &PlotValues) -> usize {
+    match values {
+        PlotValues::Result(Ok(d)) => spill::series_size(d),
+        _ => 0,
+    }
+}
+
+/// Collects the results of all finished jobs at once. While jobs are running the ui is only
+/// repainted every [`JOB_POLL_INTERVAL`] instead of every frame.
+fn poll_jobs(ui: &mut Ui, plots: &mut [PlotValues]) {
+    let mut pending = false;
+    for values in plots.iter_mut() {
+        if let PlotValues::Job(j) = values {
+            if j.is_done() {
+                let job = std::mem::replace(values, PlotValues::empty());
+                let job = job.into_job().unwrap();
+                *values = if job.is_cancelled() {
+                    PlotValues::Cancelled
+                } else {
+                    PlotValues::Result(job.join())
+                };
             } else {
-                let moved_plots = from.min(to)..=from.max(to);
-                Some((from, moved_plots, distance))
+                pending = true;
             }
         }
+    }
+    if pending {
+        ui.ctx().request_repaint_after(JOB_POLL_INTERVAL);
+    }
+}
+
+/// Everything a time plot shows besides the plots of its tab.
+struct TimePlotContext<'a> {
+    streams: &'a [LogStream],
+    stream_offsets: &'a [i64],
+    channel_units: &'a [ChannelUnit],
+    /// display names of channel plots
+    aliases: &'a [ChannelAlias],
+    markers: &'a [SyncMarker],
+    events: &'a mut Vec<EventMarker>,
+    /// group to link the x-axis with
+    link_x: Option<&'static str>,
+    /// draw a line at the hovered time
+    hover_line: bool,
+    gap_periods: u32,
+    shade_gaps: bool,
+    /// time ranges in seconds that are shaded as violations
+    violations: &'a [(f64, f64)],
+    side_series: &'a SideSeries,
+    /// the compared run and the library and aliases to evaluate its plots with
+    run_b: Option<(&'a mut RunB, &'a [UserExpr], &'a [ChannelAlias])>,
+}
+
+fn time_plot(
+    ui: &mut Ui,
+    tab_cfg: &mut TabConfig,
+    plots: &[PlotValues],
+    ctx: TimePlotContext,
+    cursor: &mut Option<f64>,
+) -> Option<f64> {
+    let TimePlotContext {
+        streams,
+        stream_offsets,
+        channel_units,
+        aliases,
+        markers,
+        events,
+        link_x,
+        hover_line,
+        gap_periods,
+        shade_gaps,
+        violations,
+        side_series,
+        run_b,
+    } = ctx;
+    let run_b = run_b.map(|(run_b, library, expr_aliases)| {
+        let line_plots = tab_cfg.plots.iter().filter(|p| p.xy_mode == XyMode::Off);
+        for p in line_plots {
+            run_b.request(
+                &aliases::resolve_expr(expr_aliases, &tab_cfg.plot_expr(p)),
+                library,
+            );
+        }
+        if run_b.poll_jobs() {
+            ui.ctx().request_repaint_after(JOB_POLL_INTERVAL);
+        }
+        (&*run_b, expr_aliases)
+    });
+
+    let num_pixels = ui.ctx().pixels_per_point() * ui.available_width();
+    // wait for the values to fit the y-range, e.g. when a restored project is still loading
+    let loading = plots.iter().any(|p| matches!(p, PlotValues::Job(_)));
+    let zoom_to = if loading {
+        None
+    } else {
+        tab_cfg.zoom_to.take()
+    };
+    let mapping_id = Id::new(tab_cfg.id).with("y_axes");
+    let mapping = cached_axis_mapping(ui.ctx(), mapping_id, plots, &tab_cfg.plots);
+    let units = (tab_cfg.plots.iter())
+        .map(|p| units::channel_unit(streams, channel_units, p.expr.y.trim()))
+        .collect::<Vec<_>>();
+    let names = (tab_cfg.plots.iter().zip(units.iter()))
+        .map(|(p, u)| legend_name(aliases::display_name(aliases, &p.name), u.as_deref()))
+        .collect::<Vec<_>>();
+    let labels = (tab_cfg.plots.iter())
+        .map(|p| enum_labels(streams, p.expr.y.trim()))
+        .collect::<Vec<_>>();
+    let mut series = (tab_cfg.plots.iter().zip(names.iter()).zip(units))
+        .zip(labels.iter())
+        .map(|(((p, n), u), l)| {
+            let label = aliases::display_name(aliases, &p.name).to_string();
+            (n.clone(), label, p.y_axis, u, l.clone())
+        })
+        .collect::<Vec<_>>();
+    if run_b.is_some() {
+        let series_b = (series.iter())
+            .map(|(n, l, a, u, e)| (run_b_name(n), run_b_name(l), *a, u.clone(), e.clone()))
+            .collect::<Vec<_>>();
+        series.extend(series_b);
+    }
+    // label the ticks with the codes if the left axis only shows one kind of enum channel
+    let mut left_labels = (tab_cfg.plots.iter().zip(labels.iter()))
+        .filter(|(p, _)| p.y_axis == YAxis::Left && !p.hidden)
+        .filter(|(p, _)| !p.in_band() && !p.in_lane() && !p.in_stack())
+        .map(|(_, l)| l.as_ref());
+    let left_labels = match left_labels.next() {
+        Some(Some(first)) if left_labels.all(|l| l == Some(first)) => Some(first.clone()),
         _ => None,
     };
 
-    let mut i = 0;
-    while i < cfg.tabs[cfg.selected_tab].plots.len() {
-        let plot = &mut cfg.tabs[cfg.selected_tab].plots[i];
-        let values = &data.plots[cfg.selected_tab][i];
+    let boundaries = if tab_cfg.x_is_time() {
+        source_boundaries(streams, stream_offsets)
+    } else {
+        Vec::new()
+    };
 
-        let mut input = None;
-        match drag {
-            Some((dragged_idx, _, dist)) if dragged_idx == i => {
-                let id = Id::new("plot").with(i);
-                let layer_id = LayerId::new(Order::Tooltip, id);
-                ui.with_layer_id(layer_id, |ui| {
-                    expr_inputs(ui, plot, values, i, &mut cfg.dragged_plot);
-                });
-                let transform = TSTransform::new(Vec2::new(0.0, dist), 1.0);
-                ui.ctx().transform_layer_shapes(layer_id, transform);
-                // FIXME: only works sometimes
-                ui.output_mut(|o| o.cursor_icon = CursorIcon::Grabbing);
+    let show_overview = tab_cfg.plots.iter().any(|p| p.overview);
+    let x_axis = match tab_cfg.use_alt_x {
+        true => XAxis::Time,
+        false => tab_cfg.x_axis,
+    };
+    let mut plot = Plot::new(tab_cfg.id)
+        .data_aspect(tab_cfg.aspect_ratio)
+        // double-clicking adds an event instead
+        .allow_double_click_reset(false)
+        .label_formatter(move |name, v| {
+            let x = match x_axis {
+                XAxis::Time => format!("t = {}", format_time(v.x)),
+                XAxis::Distance => format!("d = {:.1} m", v.x),
+            };
+            let (label, axis, unit, labels) = match series.iter().find(|(n, ..)| n == name) {
+                Some((_, label, axis, unit, labels)) => {
+                    (label.as_str(), *axis, unit.as_deref(), labels.as_deref())
+                }
+                None => ("y", YAxis::Left, None, None),
+            };
+            let y = match mapping {
+                Some(m) if axis == YAxis::Right => m.to_right(v.y),
+                _ => v.y,
+            };
+            let y = (y * 1000.0).round() / 1000.0;
+            if let Some(code) = labels.and_then(|l| data::enum_label(l, y)) {
+                return format!("{x}\n{label} = {code} ({y})");
             }
-            Some((_, ref moved_plots, dist)) if moved_plots.contains(&i) => {
-                let id = Id::new("plot").with(i);
-                let layer_id = LayerId::new(Order::Foreground, id);
-                ui.with_layer_id(layer_id, |ui| {
-                    expr_inputs(ui, plot, values, i, &mut cfg.dragged_plot);
-                });
-                let offset = -dist.signum() * plot_distance;
-                let transform = TSTransform::new(Vec2::new(0.0, offset), 1.0);
-                ui.ctx().transform_layer_shapes(layer_id, transform);
+            match unit {
+                Some(unit) => format!("{x}\n{label} = {y} {unit}"),
+                None => format!("{x}\ny = {y}"),
+            }
+        })
+        .legend(Legend::default());
+    let left = left_labels.map(|labels| {
+        AxisHints::new_y().formatter(move |mark, _| {
+            data::enum_label(&labels, mark.value)
+                .unwrap_or_default()
+                .to_string()
+        })
+    });
+    match (left, mapping) {
+        (left, Some(m)) => {
+            let right = AxisHints::new_y()
+                .placement(HPlacement::Right)
+                .formatter(move |mark, _| m.format_tick(mark.value, mark.step_size));
+            plot = plot.custom_y_axes(vec![left.unwrap_or_else(AxisHints::new_y), right]);
+        }
+        (Some(left), None) => plot = plot.custom_y_axes(vec![left]),
+        (None, None) => (),
+    }
+    let num_lanes = (tab_cfg.plots.iter())
+        .filter(|p| p.in_lane() && !p.hidden)
+        .count();
+    // the lanes share the x-axis with the time plot
+    let x_group = match link_x {
+        Some(group) => Some(Id::new(group)),
+        None => (num_lanes > 0).then(|| Id::new(tab_cfg.id).with("digital_lanes")),
+    };
+    if let Some(group) = x_group {
+        plot = plot.link_axis(group, true, false);
+    }
+    let spacing = ui.spacing().item_spacing.y;
+    let mut below = 0.0;
+    if show_overview {
+        below += OVERVIEW_HEIGHT + spacing;
+    }
+    if num_lanes > 0 {
+        below += num_lanes as f32 * DIGITAL_LANE_HEIGHT + spacing;
+    }
+    if below > 0.0 {
+        plot = plot.height(ui.available_height() - below);
+    }
+    // shift+drag selects the x-range to zoom to instead of panning
+    let selecting = ui.input(|i| i.modifiers.shift_only());
+    plot = plot.allow_drag(!selecting);
+
+    let restore = match std::mem::replace(&mut tab_cfg.bounds_restored, true) {
+        false => tab_cfg.bounds.filter(|_| zoom_to.is_none()),
+        true => None,
+    };
+    let mut auto_bounds = false;
+    let mut selected = None;
+    let resp = plot.show(ui, |ui| {
+        if let Some(range) = &zoom_to {
+            zoom_x_range(ui, plots, &tab_cfg.plots, mapping, range);
+        }
+        if let Some((min, max)) = restore {
+            ui.set_plot_bounds(PlotBounds::from_min_max(min, max));
+        }
+
+        auto_bounds = ui.auto_bounds().any();
+        let x_min = *ui.plot_bounds().range_x().start();
+        let x_max = *ui.plot_bounds().range_x().end();
+
+        let mut gap_ranges = Vec::new();
+        let plot_iter = plots.iter().zip(tab_cfg.plots.iter()).zip(names.iter());
+        for (i, ((values, p), name)) in plot_iter.enumerate() {
+            let is_enum = labels[i].is_some();
+            if p.in_band() || p.in_lane() || p.in_stack() || p.hidden {
+                continue;
+            }
+            match values {
+                PlotValues::Result(Ok(d)) if !d.is_empty() && p.xy_mode != XyMode::Off => {
+                    let stride = d.len().div_ceil(MAX_XY_POINTS);
+                    let mut values = d.iter().step_by(stride).copied().collect::<Vec<_>>();
+                    map_to_left_axis(mapping, p.y_axis, &mut values);
+                    let values = PlotPoints::Owned(values);
+                    match p.xy_mode {
+                        XyMode::Points => {
+                            let radius = p.width.unwrap_or(XY_POINT_RADIUS);
+                            let color = p.color.unwrap_or_else(|| auto_color(ui.ctx(), i));
+                            let points = Points::new(values).radius(radius).name(name);
+                            ui.points(points.color(color))
+                        }
+                        _ => {
+                            let line = Line::new(values).name(name);
+                            ui.line(p.style_line(line, auto_color(ui.ctx(), i)))
+                        }
+                    }
+                }
+                PlotValues::Result(Ok(d)) if !d.is_empty() => {
+                    // when auto bounds are set, use full range to avoid slowly zooming out
+                    let range = if auto_bounds {
+                        0..d.len()
+                    } else {
+                        find_plot_range(d, x_min, x_max)
+                    };
+
+                    let d = &d[range];
+                    let chunk_size = chunk_size(d, x_max - x_min, num_pixels);
+                    let parts = split_at_gaps(d, gap_periods);
+                    let colors =
+                        color_by::color_expr(tab_cfg, i).and_then(|expr| side_series.get(&expr));
+                    let fill =
+                        side_series::fill_expr(tab_cfg, i).and_then(|expr| side_series.get(&expr));
+                    for part in parts.iter() {
+                        let mut values = subsample_plot(part, chunk_size, tab_cfg.subsampling);
+                        if is_enum {
+                            values = to_steps(values);
+                        }
+                        map_to_left_axis(mapping, p.y_axis, &mut values);
+                        if let Some((fill, _)) = fill {
+                            let color = p.color.unwrap_or_else(|| auto_color(ui.ctx(), i));
+                            let mut to = (values.iter())
+                                .map(|v| {
+                                    PlotPoint::new(v.x, interpolate(fill, v.x).unwrap_or(f64::NAN))
+                                })
+                                .collect::<Vec<_>>();
+                            map_to_left_axis(mapping, p.y_axis, &mut to);
+                            fill_between(
+                                ui,
+                                &values,
+                                &to,
+                                color.gamma_multiply(BAND_OPACITY),
+                                name,
+                            );
+                        }
+                        let Some((colors, range)) = colors else {
+                            let line = Line::new(PlotPoints::Owned(values)).name(name);
+                            ui.line(p.style_line(line, auto_color(ui.ctx(), i)));
+                            continue;
+                        };
+                        // values without a color value keep the color of the series
+                        for (run, bucket) in color_by::colored_runs(values, colors, range) {
+                            let line = Line::new(PlotPoints::Owned(run)).name(name);
+                            let line = p.style_line(line, auto_color(ui.ctx(), i));
+                            match bucket {
+                                Some(b) => ui.line(line.color(color_by::bucket_color(b))),
+                                None => ui.line(line),
+                            }
+                        }
+                    }
+                    let gaps = parts.windows(2).map(|w| {
+                        let start = w[0].last().map_or(f64::NAN, |v| v.x);
+                        let end = w[1].first().map_or(f64::NAN, |v| v.x);
+                        (start, end)
+                    });
+                    gap_ranges.extend(gaps);
+                }
+                _ => {
+                    let line = Line::new([0.0, f64::NAN]).name(name);
+                    ui.line(p.style_line(line, auto_color(ui.ctx(), i)))
+                }
+            }
+
+            let values_b = match run_b {
+                Some((b, expr_aliases)) if p.xy_mode == XyMode::Off => {
+                    b.get(&aliases::resolve_expr(expr_aliases, &tab_cfg.plot_expr(p)))
+                }
+                _ => None,
+            };
+            if let (Some(PlotValues::Result(Ok(d))), Some((b, _))) = (values_b, run_b) {
+                if !d.is_empty() {
+                    let range = if auto_bounds {
+                        0..d.len()
+                    } else {
+                        find_plot_range(d, x_min - b.offset, x_max - b.offset)
+                    };
+
+                    let d = &d[range];
+                    let chunk_size = chunk_size(d, x_max - x_min, num_pixels);
+                    for part in split_at_gaps(d, gap_periods) {
+                        let mut values = subsample_plot(part, chunk_size, tab_cfg.subsampling);
+                        for v in values.iter_mut() {
+                            v.x += b.offset;
+                        }
+                        if is_enum {
+                            values = to_steps(values);
+                        }
+                        map_to_left_axis(mapping, p.y_axis, &mut values);
+                        // same color as the series of the opened run, but always dashed
+                        let line = Line::new(PlotPoints::Owned(values)).name(run_b_name(name));
+                        let line = (p.style_line(line, auto_color(ui.ctx(), i)))
+                            .style(LineStyle::dashed_dense());
+                        ui.line(line);
+                    }
+                }
+            }
+        }
+
+        let mut groups: Vec<(&str, Vec<usize>)> = Vec::new();
+        for (i, p) in tab_cfg
+            .plots
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.in_band() && !p.hidden)
+        {
+            match groups.iter_mut().find(|(g, _)| *g == p.band_group) {
+                Some((_, members)) => members.push(i),
+                None => groups.push((&p.band_group, vec![i])),
+            }
+        }
+        for (group, members) in groups.iter() {
+            let series = (members.iter())
+                .filter_map(|&i| match &plots[i] {
+                    PlotValues::Result(Ok(d)) if !d.is_empty() => Some(d.as_slice()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let Some(first) = series.first() else {
+                continue;
+            };
+            let range = if auto_bounds {
+                0..first.len()
+            } else {
+                find_plot_range(first, x_min, x_max)
+            };
+            let chunk_size = chunk_size(&first[range.clone()], x_max - x_min, num_pixels);
+            let [mut min, mut mean, mut max] = band_values(&first[range], &series, chunk_size);
+            let lead = &tab_cfg.plots[members[0]];
+            for values in [&mut min, &mut mean, &mut max] {
+                map_to_left_axis(mapping, lead.y_axis, values);
+            }
+
+            let color = (lead.color).unwrap_or_else(|| auto_color(ui.ctx(), members[0]));
+            fill_between(ui, &min, &max, color.gamma_multiply(BAND_OPACITY), group);
+            let line = Line::new(PlotPoints::Owned(mean)).color(color).name(*group);
+            ui.line(line);
+        }
+
+        let mut stacks: Vec<(&str, Vec<usize>)> = Vec::new();
+        for (i, p) in (tab_cfg.plots.iter().enumerate()).filter(|(_, p)| p.in_stack() && !p.hidden)
+        {
+            match stacks.iter_mut().find(|(g, _)| *g == p.stack_group) {
+                Some((_, members)) => members.push(i),
+                None => stacks.push((&p.stack_group, vec![i])),
+            }
+        }
+        for (_, members) in stacks.iter() {
+            let series = (members.iter())
+                .filter_map(|&i| match &plots[i] {
+                    PlotValues::Result(Ok(d)) if !d.is_empty() => Some((i, d.as_slice())),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let Some((_, first)) = series.first() else {
+                continue;
+            };
+            let range = if auto_bounds {
+                0..first.len()
+            } else {
+                find_plot_range(first, x_min, x_max)
+            };
+            let chunk_size = chunk_size(&first[range.clone()], x_max - x_min, num_pixels);
+            let values = series.iter().map(|(_, d)| *d).collect::<Vec<_>>();
+            let mut tops = stack_values(&first[range], &values, chunk_size);
+            let lead = &tab_cfg.plots[members[0]];
+            for values in tops.iter_mut() {
+                map_to_left_axis(mapping, lead.y_axis, values);
+            }
+
+            let mut bottom = (tops[0].iter())
+                .map(|p| PlotPoint::new(p.x, 0.0))
+                .collect::<Vec<_>>();
+            map_to_left_axis(mapping, lead.y_axis, &mut bottom);
+            for ((i, _), top) in series.iter().zip(tops) {
+                let p = &tab_cfg.plots[*i];
+                let color = p.color.unwrap_or_else(|| auto_color(ui.ctx(), *i));
+                fill_between(
+                    ui,
+                    &bottom,
+                    &top,
+                    color.gamma_multiply(BAND_OPACITY),
+                    &names[*i],
+                );
+                let line = Line::new(PlotPoints::Owned(top.clone())).name(&names[*i]);
+                ui.line(p.style_line(line, auto_color(ui.ctx(), *i)));
+                bottom = top;
+            }
+        }
+
+        curves::draw_curves(ui, &tab_cfg.reference_curves);
+
+        if shade_gaps {
+            let [y_min, y_max] = [ui.plot_bounds().min()[1], ui.plot_bounds().max()[1]];
+            for (start, end) in merge_ranges(gap_ranges) {
+                let rect = vec![[start, y_min], [end, y_min], [end, y_max], [start, y_max]];
+                let polygon = Polygon::new(rect)
+                    .stroke(Stroke::NONE)
+                    .fill_color(GAP_SHADE_COLOR);
+                ui.polygon(polygon);
+            }
+        }
+
+        if tab_cfg.x_is_time() {
+            let [y_min, y_max] = [ui.plot_bounds().min()[1], ui.plot_bounds().max()[1]];
+            for (start, end) in violations.iter() {
+                let rect = vec![
+                    [*start, y_min],
+                    [*end, y_min],
+                    [*end, y_max],
+                    [*start, y_max],
+                ];
+                let polygon = Polygon::new(rect)
+                    .stroke(Stroke::NONE)
+                    .fill_color(VIOLATION_SHADE_COLOR);
+                ui.polygon(polygon);
+            }
+        }
+
+        let y_max = *ui.plot_bounds().range_y().end();
+        for m in markers.iter() {
+            ui.vline(VLine::new(m.time).color(SYNC_MARKER_COLOR));
+            let text = Text::new(PlotPoint::new(m.time, y_max), format!(" {}", m.name))
+                .color(SYNC_MARKER_COLOR)
+                .anchor(Align2::LEFT_TOP);
+            ui.text(text);
+        }
+        let y_min = *ui.plot_bounds().range_y().start();
+        for e in events.iter() {
+            ui.vline(VLine::new(e.time).color(EVENT_MARKER_COLOR));
+            let text = Text::new(PlotPoint::new(e.time, y_min), format!(" {}", e.name))
+                .color(EVENT_MARKER_COLOR)
+                .anchor(Align2::LEFT_BOTTOM);
+            ui.text(text);
+        }
+        if ui.response().double_clicked() {
+            if let Some(p) = ui.pointer_coordinate() {
+                events.push(EventMarker::new(p.x, events));
+            }
+        }
+
+        let boundary_color = ui.ctx().style().visuals.weak_text_color();
+        let boundary_color = boundary_color.gamma_multiply(SOURCE_BOUNDARY_OPACITY);
+        for (time, _) in boundaries.iter() {
+            let line = VLine::new(*time)
+                .color(boundary_color)
+                .style(LineStyle::dotted_loose());
+            ui.vline(line);
+        }
+
+        if let Some(t) = *cursor {
+            ui.vline(VLine::new(t).color(CURSOR_COLOR));
+        }
+        let hovered = ui.pointer_coordinate().map(|p| p.x);
+        if let Some(x) = hovered.filter(|_| hover_line && ui.response().hovered()) {
+            let color = ui.ctx().style().visuals.weak_text_color();
+            ui.vline(VLine::new(x).color(color).style(LineStyle::dashed_dense()));
+        }
+        if let Some(m) = &tab_cfg.measurement {
+            for x in m.cursors.iter().flatten() {
+                ui.vline(
+                    VLine::new(*x)
+                        .color(MEASUREMENT_COLOR)
+                        .style(LineStyle::dashed_loose()),
+                );
+            }
+        }
+
+        let selection_id = Id::new(tab_cfg.id).with("zoom_selection");
+        let response = ui.response().clone();
+        if selecting && response.drag_started_by(PointerButton::Primary) {
+            if let Some(p) = ui.pointer_coordinate() {
+                ui.ctx().data_mut(|d| d.insert_temp(selection_id, p.x));
+            }
+        }
+        let start = ui.ctx().data(|d| d.get_temp::<f64>(selection_id));
+        // releasing shift cancels the selection
+        if start.is_some() && (response.drag_stopped() || !selecting) {
+            ui.ctx().data_mut(|d| d.remove::<f64>(selection_id));
+        }
+        if let (Some(start), Some(p), true) = (start, ui.pointer_coordinate(), selecting) {
+            let (start, end) = (start.min(p.x), start.max(p.x));
+            if response.drag_stopped() {
+                selected = (start < end).then_some(start..=end);
+            } else {
+                let [y_min, y_max] = [ui.plot_bounds().min()[1], ui.plot_bounds().max()[1]];
+                let rect = vec![[start, y_min], [end, y_min], [end, y_max], [start, y_max]];
+                let polygon = Polygon::new(rect)
+                    .stroke(Stroke::new(1.0, MEASUREMENT_COLOR))
+                    .fill_color(ZOOM_SELECTION_COLOR);
+                ui.polygon(polygon);
+            }
+        }
+
+        if ui.response().clicked() {
+            if let Some(p) = ui.pointer_coordinate() {
+                match &mut tab_cfg.measurement {
+                    Some(m) => m.click(p.x),
+                    None => *cursor = Some(p.x),
+                }
+            }
+        }
+    });
+
+    tab_cfg.x_bounds = Some(resp.transform.bounds().range_x());
+    if selected.is_some() {
+        tab_cfg.zoom_to = selected;
+    }
+    let bounds = resp.transform.bounds();
+    tab_cfg.bounds = (!auto_bounds).then(|| (bounds.min(), bounds.max()));
+    let visible = resp.transform.bounds().range_x();
+    resp.response.context_menu(|ui| {
+        let plot_iter = plots.iter().zip(tab_cfg.plots.iter()).zip(names.iter());
+        for ((values, p), name) in plot_iter.filter(|((_, p), _)| !p.hidden) {
+            let PlotValues::Result(Ok(d)) = values else {
+                continue;
+            };
+            if ui
+                .button(format!("Copy {name}"))
+                .on_hover_text("Copy the visible points")
+                .clicked()
+            {
+                // the x values of xy plots aren't sorted
+                let points = match p.xy_mode {
+                    XyMode::Off => d[find_plot_range(d, *visible.start(), *visible.end())].to_vec(),
+                    _ => d
+                        .iter()
+                        .filter(|v| visible.contains(&v.x))
+                        .copied()
+                        .collect(),
+                };
+                let text = format_points(&p.expr.x, name, &points);
+                ui.output_mut(|o| o.copied_text = text);
+                ui.close_menu();
+            }
+        }
+    });
+    let hover_pos = resp.response.hovered().then(|| resp.response.hover_pos());
+    let hover_pos = hover_pos.flatten();
+    let hovered = hover_pos.map(|pos| resp.transform.value_from_position(pos).x);
+
+    let hovered_boundary = hover_pos.and_then(|pos| {
+        (boundaries.iter()).find(|(time, _)| {
+            let x = resp
+                .transform
+                .position_from_point(&PlotPoint::new(*time, 0.0))
+                .x;
+            (x - pos.x).abs() <= SOURCE_BOUNDARY_HOVER_DISTANCE
+        })
+    });
+    if let Some((_, text)) = hovered_boundary {
+        resp.response.clone().on_hover_text_at_pointer(text);
+    }
+
+    if let (Some(group), true) = (x_group, num_lanes > 0) {
+        let right_axis = mapping.is_some();
+        digital_lanes(ui, tab_cfg, plots, &names, group, right_axis, num_lanes);
+    }
+    if show_overview {
+        overview_plot(ui, tab_cfg, plots, num_pixels as usize);
+    }
+
+    let legends = (0..tab_cfg.plots.len())
+        .filter(|&i| {
+            let p = &tab_cfg.plots[i];
+            !p.hidden && !p.in_band() && !p.in_lane() && !p.in_stack()
+        })
+        .filter_map(|i| {
+            let (_, range) = side_series.get(&color_by::color_expr(tab_cfg, i)?)?;
+            Some((&names[i], &tab_cfg.plots[i].color_by, range))
+        })
+        .collect::<Vec<_>>();
+    if !legends.is_empty() {
+        let pos =
+            resp.response.rect.left_bottom() + Vec2::new(COLOR_LEGEND_OFFSET, -COLOR_LEGEND_OFFSET);
+        Area::new(Id::new(tab_cfg.id).with("color_legend"))
+            .fixed_pos(pos)
+            .pivot(Align2::LEFT_BOTTOM)
+            .order(Order::Foreground)
+            .show(ui.ctx(), |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    for (name, expr, range) in legends {
+                        color_by::color_legend(ui, name, expr, range);
+                    }
+                });
+            });
+    }
+
+    let range = tab_cfg.measurement.as_ref().and_then(Measurement::range);
+    if let Some((start, end)) = range {
+        let series = (plots.iter().zip(tab_cfg.plots.iter()))
+            .map(|(values, p)| {
+                let stats = match values {
+                    PlotValues::Result(Ok(d)) if p.xy_mode == XyMode::Off => {
+                        SeriesStats::new(d, start, end)
+                    }
+                    _ => None,
+                };
+                (p.name.as_str(), stats)
+            })
+            .collect::<Vec<_>>();
+
+        let pos = resp.response.rect.left_top() + Vec2::splat(MEASUREMENT_TABLE_OFFSET);
+        Area::new(Id::new(tab_cfg.id).with("measurement"))
+            .fixed_pos(pos)
+            .order(Order::Foreground)
+            .show(ui.ctx(), |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    measure::measurement_table(ui, start, end, &series);
+                });
+            });
+    }
+
+    hovered
+}
+
+/// Times in seconds at which one concatenated file ends and the next one begins, with a
+/// description of the boundary.
+fn source_boundaries(streams: &[LogStream], offsets: &[i64]) -> Vec<(f64, String)> {
+    let mut boundaries = Vec::new();
+    for (i, s) in streams.iter().enumerate() {
+        let offset = offsets.get(i).copied().unwrap_or(0);
+        for w in s.sources.windows(2) {
+            let Some(&time) = s.time.get(w[1].samples.start) else {
+                continue;
+            };
+            let time = (time as i64 + offset) as f64 / 1000.0;
+            let name = |p: &Path| {
+                p.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string()
+            };
+            let text = format!(
+                "Stream {}: {} → {}",
+                i + 1,
+                name(&w[0].path),
+                name(&w[1].path)
+            );
+            boundaries.push((time, text));
+        }
+    }
+    boundaries
+}
+
+/// Lists the interpolated values of the line plots at the hovered time next to the pointer.
+/// Plots of tabs using an alternate x expression are left out, since their x isn't the time.
+fn hover_readout(ui: &Ui, data: &PlotData, cfg: &Config, time: f64) {
+    let Some(pointer) = ui.ctx().pointer_hover_pos() else {
+        return;
+    };
+    let tabs = match cfg.hover_readout {
+        HoverReadout::AllTabs => (0..cfg.tabs.len()).collect::<Vec<_>>(),
+        _ => vec![cfg.selected_tab],
+    };
+
+    Area::new(Id::new("hover_readout"))
+        .fixed_pos(pointer + Vec2::splat(HOVER_READOUT_OFFSET))
+        .order(Order::Tooltip)
+        .interactable(false)
+        .show(ui.ctx(), |ui| {
+            Frame::popup(ui.style()).show(ui, |ui| {
+                ui.strong(format!("t = {}", format_time(time)));
+                Grid::new("hover_readout_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for t in tabs {
+                            let tab_cfg = &cfg.tabs[t];
+                            if !tab_cfg.x_is_time() {
+                                continue;
+                            }
+                            if cfg.hover_readout == HoverReadout::AllTabs {
+                                ui.label(RichText::new(&tab_cfg.name).weak());
+                                ui.end_row();
+                            }
+
+                            for (p, values) in tab_cfg.plots.iter().zip(data.plots[t].iter()) {
+                                let PlotValues::Result(Ok(d)) = values else {
+                                    continue;
+                                };
+                                let y = match p.xy_mode {
+                                    XyMode::Off => interpolate(d, time),
+                                    _ => None,
+                                };
+                                let Some(y) = y else {
+                                    continue;
+                                };
+                                let unit = units::channel_unit(
+                                    &data.streams,
+                                    &cfg.channel_units,
+                                    p.expr.y.trim(),
+                                );
+                                ui.label(&p.name);
+                                match unit {
+                                    Some(unit) => ui.monospace(format!("{y:.3} {unit}")),
+                                    None => ui.monospace(format!("{y:.3}")),
+                                };
+                                ui.end_row();
+                            }
+                        }
+                    });
+            });
+        });
+}
+
+/// Overlays the selected laps of every plot against lap time or lap distance.
+fn lap_plot(
+    ui: &mut Ui,
+    tab_cfg: &TabConfig,
+    plots: &[PlotValues],
+    laps: &[Lap],
+    track: Option<&Track>,
+) {
+    let overlay = tab_cfg.lap_overlay;
+    Plot::new((tab_cfg.id, overlay))
+        .x_axis_label(match overlay {
+            LapOverlay::LapDistance => "lap distance [m]",
+            _ => "lap time",
+        })
+        .label_formatter(move |_, v| {
+            let x = match overlay {
+                LapOverlay::LapDistance => format!("{} m", v.x.round()),
+                _ => format_time(v.x),
+            };
+            let y = (v.y * 1000.0).round() / 1000.0;
+            format!("x = {x}\ny = {y}")
+        })
+        .legend(Legend::default())
+        .show(ui, |ui| {
+            // every lap of every plot is a series of its own
+            let mut index = 0;
+            for (values, p) in plots.iter().zip(tab_cfg.plots.iter()) {
+                let PlotValues::Result(Ok(d)) = values else {
+                    continue;
+                };
+                if p.hidden {
+                    continue;
+                }
+                for lap in laps.iter().filter(|l| l.selected) {
+                    let range = find_plot_range(d, lap.start, lap.end);
+                    let values = match overlay {
+                        LapOverlay::LapDistance => {
+                            let Some(track) = track else {
+                                continue;
+                            };
+                            let start = track.distance_at(lap.start).unwrap_or(0.0);
+                            (d[range].iter())
+                                .filter_map(|v| {
+                                    let dist = track.distance_at(v.x)?;
+                                    Some(PlotPoint::new(dist - start, v.y))
+                                })
+                                .collect()
+                        }
+                        _ => (d[range].iter())
+                            .map(|v| PlotPoint::new(v.x - lap.start, v.y))
+                            .collect(),
+                    };
+                    let name = format!("{} lap {}", p.name, lap.number);
+                    let line = Line::new(PlotPoints::Owned(values)).name(name);
+                    ui.line(p.style_line(line, auto_color(ui.ctx(), index)));
+                    index += 1;
+                }
+            }
+        });
+}
+
+/// The visible part of the current tab's time plot as a [`Figure`], `None` if the tab isn't in
+/// time mode.
+pub fn tab_figure(data: &PlotData, cfg: &Config, max_points: usize) -> Option<Figure> {
+    let tab_cfg = &cfg.tabs[cfg.selected_tab];
+    if tab_cfg.mode != PlotMode::Time || tab_cfg.lap_overlay != LapOverlay::Off {
+        return None;
+    }
+    let plots = &data.plots[cfg.selected_tab];
+    let values = |i: usize| match &plots[i] {
+        PlotValues::Result(Ok(d)) if !d.is_empty() => Some(d.as_slice()),
+        _ => None,
+    };
+
+    // digital lanes aren't exported, bands and stacks are grouped like in the time plot
+    let mut lines = Vec::new();
+    let mut groups: Vec<(&str, bool, Vec<usize>)> = Vec::new();
+    for (i, p) in (tab_cfg.plots.iter().enumerate()).filter(|(_, p)| !p.hidden && !p.in_lane()) {
+        let group = match (p.in_band(), p.in_stack()) {
+            (true, _) => Some((p.band_group.as_str(), true)),
+            (_, true) => Some((p.stack_group.as_str(), false)),
+            _ => None,
+        };
+        match group {
+            Some((name, band)) => {
+                match groups.iter_mut().find(|(g, b, _)| (*g, *b) == (name, band)) {
+                    Some((_, _, members)) => members.push(i),
+                    None => groups.push((name, band, vec![i])),
+                }
+            }
+            None if values(i).is_some() => lines.push(i),
+            None => (),
+        }
+    }
+
+    let xs = || {
+        (lines
+            .iter()
+            .chain(groups.iter().flat_map(|(_, _, m)| m.iter())))
+        .filter_map(|&i| values(i))
+        .flat_map(|d| d.iter().map(|p| p.x))
+        .filter(|x| x.is_finite())
+    };
+    let x_range = match &tab_cfg.x_bounds {
+        Some(r) => (*r.start(), *r.end()),
+        None => (
+            xs().fold(f64::INFINITY, f64::min),
+            xs().fold(f64::NEG_INFINITY, f64::max),
+        ),
+    };
+    if x_range.0 >= x_range.1 {
+        return Some(Figure {
+            title: data.session.resolve(&tab_cfg.name),
+            x_time: tab_cfg.x_is_time(),
+            x_range: (0.0, 1.0),
+            y_range: (0.0, 1.0),
+            series: Vec::new(),
+        });
+    }
+
+    let chunk_size = |visible: &[PlotPoint]| (visible.len() / max_points.max(1)).max(1);
+    let mut series = Vec::with_capacity(lines.len() + groups.len());
+    for i in lines {
+        let (d, p) = (values(i).unwrap(), &tab_cfg.plots[i]);
+        let points = if p.xy_mode == XyMode::Off {
+            let visible = &d[find_plot_range(d, x_range.0, x_range.1)];
+            subsample_plot(visible, chunk_size(visible), tab_cfg.subsampling)
+        } else {
+            let stride = d.len().div_ceil(MAX_XY_POINTS);
+            d.iter().step_by(stride).copied().collect()
+        };
+        series.push(Series {
+            name: p.name.clone(),
+            color: p.color.unwrap_or_else(|| cfg.theme.line_color(i)),
+            width: p.width,
+            points,
+        });
+    }
+    for (group, band, members) in groups {
+        let members = (members.into_iter())
+            .filter_map(|i| Some((i, values(i)?)))
+            .collect::<Vec<_>>();
+        let Some(&(lead, first)) = members.first() else {
+            continue;
+        };
+        let visible = &first[find_plot_range(first, x_range.0, x_range.1)];
+        let values = members.iter().map(|(_, d)| *d).collect::<Vec<_>>();
+        if band {
+            // the mean line, the range around it is only filled in the time plot
+            let [_, mean, _] = band_values(visible, &values, chunk_size(visible));
+            let p = &tab_cfg.plots[lead];
+            series.push(Series {
+                name: group.to_string(),
+                color: p.color.unwrap_or_else(|| cfg.theme.line_color(lead)),
+                width: p.width,
+                points: mean,
+            });
+        } else {
+            let tops = stack_values(visible, &values, chunk_size(visible));
+            for ((i, _), top) in members.iter().zip(tops) {
+                let p = &tab_cfg.plots[*i];
+                series.push(Series {
+                    name: p.name.clone(),
+                    color: p.color.unwrap_or_else(|| cfg.theme.line_color(*i)),
+                    width: p.width,
+                    points: top,
+                });
+            }
+        }
+    }
+
+    let mut y_range = (f64::INFINITY, f64::NEG_INFINITY);
+    for v in (series.iter().flat_map(|s| s.points.iter()))
+        .filter(|v| x_range.0 <= v.x && v.x <= x_range.1 && v.y.is_finite())
+    {
+        y_range = (y_range.0.min(v.y), y_range.1.max(v.y));
+    }
+    if y_range.0 > y_range.1 {
+        y_range = (-1.0, 1.0);
+    } else {
+        let padding = ((y_range.1 - y_range.0) * 0.05).max(f64::EPSILON);
+        y_range = (y_range.0 - padding, y_range.1 + padding);
+    }
+
+    Some(Figure {
+        title: data.session.resolve(&tab_cfg.name),
+        x_time: tab_cfg.x_is_time(),
+        x_range,
+        y_range,
+        series,
+    })
+}
+
+/// The x-range of the values of all line plots.
+fn full_x_range(plots: &[PlotValues], named: &[NamedPlot]) -> Option<RangeInclusive<f64>> {
+    let mut x_min = f64::INFINITY;
+    let mut x_max = f64::NEG_INFINITY;
+    for (values, p) in plots.iter().zip(named.iter()) {
+        if let (PlotValues::Result(Ok(d)), XyMode::Off) = (values, p.xy_mode) {
+            if let (Some(first), Some(last)) = (d.first(), d.last()) {
+                x_min = x_min.min(first.x);
+                x_max = x_max.max(last.x);
+            }
+        }
+    }
+    (x_min < x_max).then_some(x_min..=x_max)
+}
+
+/// Sets the x-range and fits the y-range to the values inside of it.
+fn zoom_x_range(
+    ui: &mut PlotUi,
+    plots: &[PlotValues],
+    named: &[NamedPlot],
+    mapping: Option<AxisMapping>,
+    range: &RangeInclusive<f64>,
+) {
+    let mut y_min = f64::INFINITY;
+    let mut y_max = f64::NEG_INFINITY;
+    let visible = |p: &&NamedPlot| !p.hidden && !p.in_lane();
+    for (values, p) in plots.iter().zip(named.iter()).filter(|(_, p)| visible(p)) {
+        if let PlotValues::Result(Ok(d)) = values {
+            let r = find_plot_range(d, *range.start(), *range.end());
+            let mut visible = d[r].to_vec();
+            map_to_left_axis(mapping, p.y_axis, &mut visible);
+            for p in visible.iter().filter(|p| p.y.is_finite()) {
+                y_min = y_min.min(p.y);
+                y_max = y_max.max(p.y);
+            }
+        }
+    }
+    if y_min > y_max {
+        y_min = -1.0;
+        y_max = 1.0;
+    } else if y_min == y_max {
+        y_min -= 1.0;
+        y_max += 1.0;
+    }
+
+    let bounds = PlotBounds::from_min_max([*range.start(), y_min], [*range.end(), y_max]);
+    ui.set_plot_bounds(bounds);
+}
+
+/// Stacked lanes below the time plot, drawing the series with the digital option as high/low
+/// traces, e.g. of boolean channels. Non-zero values are high. The empty y-axes keep the x-axis
+/// aligned with the time plot.
+fn digital_lanes(
+    ui: &mut Ui,
+    tab_cfg: &TabConfig,
+    plots: &[PlotValues],
+    names: &[String],
+    x_group: Id,
+    right_axis: bool,
+    num_lanes: usize,
+) {
+    let num_pixels = ui.ctx().pixels_per_point() * ui.available_width();
+    let empty_axis = || AxisHints::new_y().formatter(|_, _| String::new());
+    let mut axes = vec![empty_axis()];
+    if right_axis {
+        axes.push(empty_axis().placement(HPlacement::Right));
+    }
+
+    Plot::new((tab_cfg.id, "digital_lanes"))
+        .height(num_lanes as f32 * DIGITAL_LANE_HEIGHT)
+        .custom_y_axes(axes)
+        .show_x(false)
+        .show_y(false)
+        .allow_zoom([true, false])
+        .allow_drag([true, false])
+        .allow_scroll([true, false])
+        .allow_double_click_reset(false)
+        .include_y(0.0)
+        .include_y(num_lanes as f64)
+        .link_axis(x_group, true, false)
+        .link_cursor(x_group, true, false)
+        .show(ui, |ui| {
+            let x_min = *ui.plot_bounds().range_x().start();
+            let x_max = *ui.plot_bounds().range_x().end();
+            let lanes = (plots.iter().zip(tab_cfg.plots.iter()).zip(names.iter()))
+                .enumerate()
+                .filter(|(_, ((_, p), _))| p.in_lane() && !p.hidden);
+            for (lane, (i, ((values, p), name))) in lanes.enumerate() {
+                let base = (num_lanes - 1 - lane) as f64;
+                let color = p.color.unwrap_or_else(|| auto_color(ui.ctx(), i));
+                let label = Text::new(PlotPoint::new(x_min, base + 0.5), name.as_str())
+                    .anchor(Align2::LEFT_CENTER)
+                    .color(color);
+                ui.text(label);
+
+                let PlotValues::Result(Ok(d)) = values else {
+                    continue;
+                };
+                let d = &d[find_plot_range(d, x_min, x_max)];
+                let chunk_size = chunk_size(d, x_max - x_min, num_pixels);
+                let mut values = subsample_plot(d, chunk_size, tab_cfg.subsampling);
+                for v in values.iter_mut().filter(|v| !v.y.is_nan()) {
+                    v.y = base
+                        + if v.y != 0.0 {
+                            DIGITAL_HIGH
+                        } else {
+                            DIGITAL_LOW
+                        };
+                }
+                let line = Line::new(PlotPoints::Owned(to_steps(values))).name(name);
+                ui.line(p.style_line(line, color));
+            }
+        });
+}
+
+/// A strip below the time plot showing the whole session of the series with the overview option
+/// enabled, each normalized to its own value range. The visible range of the time plot is
+/// highlighted, clicking or dragging moves it.
+fn overview_plot(ui: &mut Ui, tab_cfg: &mut TabConfig, plots: &[PlotValues], num_points: usize) {
+    let visible = tab_cfg.x_bounds.clone();
+    let mut move_to = None;
+    Plot::new((tab_cfg.id, "overview"))
+        .height(OVERVIEW_HEIGHT)
+        .show_axes([false, false])
+        .show_grid(false)
+        .allow_zoom(false)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .allow_boxed_zoom(false)
+        .allow_double_click_reset(false)
+        .show_x(false)
+        .show_y(false)
+        .include_y(0.0)
+        .include_y(1.0)
+        .show(ui, |ui| {
+            for (i, (values, p)) in plots.iter().zip(tab_cfg.plots.iter()).enumerate() {
+                let PlotValues::Result(Ok(d)) = values else {
+                    continue;
+                };
+                if !p.overview || p.hidden || p.xy_mode != XyMode::Off || d.is_empty() {
+                    continue;
+                }
+                let id = Id::new(tab_cfg.id).with(i).with("overview");
+                let points = cached_overview(ui.ctx(), id, d, num_points);
+                let color = p.color.unwrap_or_else(|| auto_color(ui.ctx(), i));
+                let line = Line::new(PlotPoints::Owned(points.to_vec()))
+                    .color(color.gamma_multiply(OVERVIEW_OPACITY));
+                ui.line(line);
+            }
+
+            if let Some(range) = &visible {
+                let rect = vec![
+                    [*range.start(), 0.0],
+                    [*range.end(), 0.0],
+                    [*range.end(), 1.0],
+                    [*range.start(), 1.0],
+                ];
+                let polygon = Polygon::new(rect)
+                    .stroke(Stroke::new(1.0, CURSOR_COLOR))
+                    .fill_color(CURSOR_COLOR.gamma_multiply(OVERVIEW_WINDOW_OPACITY));
+                ui.polygon(polygon);
+            }
+
+            let resp = ui.response();
+            if resp.clicked() || resp.dragged() {
+                move_to = ui.pointer_coordinate().map(|p| p.x);
+            }
+        });
+
+    if let (Some(x), Some(range)) = (move_to, visible) {
+        let half_width = 0.5 * (range.end() - range.start());
+        tab_cfg.zoom_to = Some(x - half_width..=x + half_width);
+    }
+}
+
+/// The whole series subsampled to about `num_points` points and normalized to `0..=1`, cached
+/// in the temporary egui memory until the values change.
+fn cached_overview(
+    ctx: &egui::Context,
+    id: Id,
+    values: &[PlotPoint],
+    num_points: usize,
+) -> Arc<[PlotPoint]> {
+    let mut hasher = DefaultHasher::new();
+    (values.as_ptr() as usize, values.len(), num_points).hash(&mut hasher);
+    let key = hasher.finish();
+
+    let cached = ctx.memory(|m| m.data.get_temp::<(u64, Arc<[PlotPoint]>)>(id));
+    match cached {
+        Some((k, overview)) if k == key => overview,
+        _ => {
+            let chunk_size = (values.len() / num_points.max(1)).max(1);
+            let mut points = subsample_plot(values, chunk_size, Subsampling::Mean);
+            let finite = || points.iter().map(|p| p.y).filter(|y| y.is_finite());
+            let min = finite().fold(f64::INFINITY, f64::min);
+            let max = finite().fold(f64::NEG_INFINITY, f64::max);
+            let range = if max > min { max - min } else { 1.0 };
+            for p in points.iter_mut() {
+                p.y = (p.y - min) / range;
+            }
+
+            let overview: Arc<[PlotPoint]> = points.into();
+            ctx.memory_mut(|m| m.data.insert_temp(id, (key, Arc::clone(&overview))));
+            overview
+        }
+    }
+}
+
+/// Linear mapping of values of the right y-axis onto the coordinates of the plot, which are the
+/// values of the left y-axis. The value ranges of both axes are mapped onto each other.
+#[derive(Clone, Copy)]
+struct AxisMapping {
+    scale: f64,
+    offset: f64,
+}
+
+impl AxisMapping {
+    fn new(left: (f64, f64), right: (f64, f64)) -> Self {
+        let scale = (left.1 - left.0) / (right.1 - right.0);
+        let offset = left.0 - right.0 * scale;
+        Self { scale, offset }
+    }
+
+    fn to_left(self, y: f64) -> f64 {
+        y * self.scale + self.offset
+    }
+
+    fn to_right(self, y: f64) -> f64 {
+        (y - self.offset) / self.scale
+    }
+
+    /// Formats a grid mark of the left axis as a value of the right axis, with enough decimals
+    /// to distinguish neighbouring marks.
+    fn format_tick(self, y: f64, step: f64) -> String {
+        let step = (step / self.scale).abs();
+        let decimals = (-step.log10().floor()).max(0.0) as usize;
+        format!("{:.*}", decimals, self.to_right(y))
+    }
+}
+
+fn map_to_left_axis(mapping: Option<AxisMapping>, axis: YAxis, values: &mut [PlotPoint]) {
+    if let (Some(m), YAxis::Right) = (mapping, axis) {
+        for p in values.iter_mut() {
+            p.y = m.to_left(p.y);
+        }
+    }
+}
+
+/// Computes the mapping of the right y-axis from the value ranges of the series on each axis,
+/// `None` if no series is on the right axis. The mapping is cached in the temporary egui memory
+/// until the values or axes change.
+fn cached_axis_mapping(
+    ctx: &egui::Context,
+    id: Id,
+    plots: &[PlotValues],
+    named: &[NamedPlot],
+) -> Option<AxisMapping> {
+    if named.iter().all(|p| p.y_axis == YAxis::Left) {
+        return None;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    for (values, p) in plots.iter().zip(named.iter()) {
+        if let PlotValues::Result(Ok(d)) = values {
+            (d.as_ptr() as usize, d.len()).hash(&mut hasher);
+        }
+        p.y_axis.hash(&mut hasher);
+    }
+    let key = hasher.finish();
+
+    type Cached = (u64, AxisMapping);
+    if let Some((k, mapping)) = ctx.memory(|m| m.data.get_temp::<Cached>(id)) {
+        if k == key {
+            return Some(mapping);
+        }
+    }
+
+    let mut ranges = [(f64::INFINITY, f64::NEG_INFINITY); 2];
+    for (values, p) in plots.iter().zip(named.iter()) {
+        if let PlotValues::Result(Ok(d)) = values {
+            let (min, max) = &mut ranges[p.y_axis as usize];
+            for y in d.iter().map(|p| p.y).filter(|y| y.is_finite()) {
+                *min = min.min(y);
+                *max = max.max(y);
+            }
+        }
+    }
+    let [left, right] = ranges.map(|(min, max)| {
+        if min > max {
+            None
+        } else if min == max {
+            Some((min - 1.0, max + 1.0))
+        } else {
+            Some((min, max))
+        }
+    });
+    let mapping = match (left, right) {
+        (Some(left), Some(right)) => AxisMapping::new(left, right),
+        _ => AxisMapping {
+            scale: 1.0,
+            offset: 0.0,
+        },
+    };
+
+    ctx.memory_mut(|m| m.data.insert_temp(id, (key, mapping)));
+    Some(mapping)
+}
+
+/// Plots the magnitude spectrum of the values inside the x-range, that was last visible in
+/// [`PlotMode::Time`].
+fn spectrum_plot(ui: &mut Ui, tab_cfg: &TabConfig, plots: &[PlotValues]) {
+    Plot::new((tab_cfg.id, PlotMode::Spectrum))
+        .x_axis_label("frequency [Hz]")
+        .label_formatter(|_, v| {
+            let f = (v.x * 1000.0).round() / 1000.0;
+            let y = (v.y * 1000.0).round() / 1000.0;
+            format!("f = {f} Hz\n|y| = {y}")
+        })
+        .legend(Legend::default())
+        .show(ui, |ui| {
+            let visible = plots.iter().zip(tab_cfg.plots.iter()).enumerate();
+            for (i, (values, p)) in visible.filter(|(_, (_, p))| !p.hidden) {
+                match values {
+                    PlotValues::Result(Ok(d)) if !d.is_empty() => {
+                        let range = match &tab_cfg.x_bounds {
+                            Some(r) => find_plot_range(d, *r.start(), *r.end()),
+                            None => 0..d.len(),
+                        };
+
+                        let id = Id::new(tab_cfg.id).with(i).with("spectrum");
+                        let spectrum = cached_spectrum(ui.ctx(), id, &d[range], tab_cfg.fft_window);
+                        let line = Line::new(PlotPoints::Owned(spectrum.to_vec())).name(&p.name);
+                        ui.line(p.style_line(line, auto_color(ui.ctx(), i)));
+                    }
+                    _ => {
+                        let line = Line::new([0.0, f64::NAN]).name(&p.name);
+                        ui.line(p.style_line(line, auto_color(ui.ctx(), i)))
+                    }
+                }
+            }
+        });
+}
+
+/// Plots the density of lateral (x) vs longitudinal (y) acceleration together with rings of the
+/// friction circle.
+fn gg_plot(ui: &mut Ui, tab_cfg: &TabConfig, plots: &[PlotValues]) {
+    Plot::new((tab_cfg.id, PlotMode::GgDiagram))
+        .data_aspect(1.0)
+        .x_axis_label("lateral")
+        .y_axis_label("longitudinal")
+        .label_formatter(|_, v| {
+            let x = (v.x * 1000.0).round() / 1000.0;
+            let y = (v.y * 1000.0).round() / 1000.0;
+            let r = (v.x.hypot(v.y) * 1000.0).round() / 1000.0;
+            format!("lat = {x}\nlon = {y}\n|a| = {r}")
+        })
+        .legend(Legend::default())
+        .show(ui, |ui| {
+            let mut max_radius: f64 = 0.0;
+            let visible = plots.iter().zip(tab_cfg.plots.iter()).enumerate();
+            for (i, (values, p)) in visible.filter(|(_, (_, p))| !p.hidden) {
+                match values {
+                    PlotValues::Result(Ok(d)) if !d.is_empty() => {
+                        let id = Id::new(tab_cfg.id).with(i).with("gg");
+                        let (texture, density) = cached_gg_density(ui.ctx(), id, d);
+                        let image = PlotImage::new(&texture, density.center(), density.size());
+                        ui.image(image.name(&p.name));
+                        max_radius = max_radius.max(density.max.x);
+                    }
+                    _ => ui.line(Line::new([0.0, f64::NAN]).name(&p.name)),
+                }
+            }
+
+            if max_radius > 0.0 {
+                let step = util::nice_step(max_radius / 4.0);
+                let mut r = step;
+                while r <= max_radius + step {
+                    let circle = (0..=GG_CIRCLE_SEGMENTS)
+                        .map(|i| {
+                            let a = std::f64::consts::TAU * i as f64 / GG_CIRCLE_SEGMENTS as f64;
+                            [r * a.cos(), r * a.sin()]
+                        })
+                        .collect::<PlotPoints>();
+                    ui.line(
+                        Line::new(circle)
+                            .color(Color32::from_gray(0x80))
+                            .style(LineStyle::dashed_dense()),
+                    );
+                    r += step;
+                }
+            }
+        });
+}
+
+/// Bins the x and y values of the first expression of the tab, colored by the number of samples
+/// or the mean of the y values of the second expression at the same samples.
+fn heatmap_plot(ui: &mut Ui, tab_cfg: &TabConfig, plots: &[PlotValues]) {
+    let values = |i: usize| match plots.get(i) {
+        Some(PlotValues::Result(Ok(d))) if !d.is_empty() => Some(d),
+        _ => None,
+    };
+    let Some(points) = values(0) else {
+        ui.label("Add a plot, its x and y expressions are binned");
+        return;
+    };
+    let cell_values = match tab_cfg.heatmap.value {
+        CellValue::Count => None,
+        CellValue::Mean => match values(1) {
+            Some(v) if v.len() == points.len() => Some(v),
+            Some(_) => {
+                ui.label("The second expression has a different number of samples");
+                return;
+            }
+            None => {
+                ui.label("Add a second plot, the cells are colored by the mean of its y values");
+                return;
+            }
+        },
+    };
+
+    let id = Id::new(tab_cfg.id).with("heatmap");
+    let (texture, heatmap) = cached_heatmap(ui.ctx(), id, points, cell_values, &tab_cfg.heatmap);
+    let value_name = match cell_values {
+        Some(_) => tab_cfg.plots[1].name.clone(),
+        None => "count".to_string(),
+    };
+    ui.label(format!(
+        "{value_name}: {} to {}",
+        (heatmap.low * 1000.0).round() / 1000.0,
+        (heatmap.high * 1000.0).round() / 1000.0
+    ));
+
+    let first = &tab_cfg.plots[0];
+    let hovered = Arc::clone(&heatmap);
+    Plot::new((tab_cfg.id, PlotMode::Heatmap))
+        .x_axis_label(first.expr.x.clone())
+        .y_axis_label(first.expr.y.clone())
+        .label_formatter(move |_, v| {
+            let x = (v.x * 1000.0).round() / 1000.0;
+            let y = (v.y * 1000.0).round() / 1000.0;
+            match hovered.value_at(*v) {
+                Some(c) => format!(
+                    "x = {x}\ny = {y}\n{value_name} = {}",
+                    (c * 1000.0).round() / 1000.0
+                ),
+                None => format!("x = {x}\ny = {y}"),
+            }
+        })
+        .show(ui, |ui| {
+            let density = &heatmap.density;
+            let image = PlotImage::new(&texture, density.center(), density.size());
+            ui.image(image.name(&first.name));
+        });
+}
+
+/// The heatmap texture is cached in the temporary egui memory until the values or the config
+/// change.
+fn cached_heatmap(
+    ctx: &egui::Context,
+    id: Id,
+    points: &[PlotPoint],
+    values: Option<&Vec<PlotPoint>>,
+    cfg: &HeatmapConfig,
+) -> (TextureHandle, Arc<Heatmap>) {
+    let mut hasher = DefaultHasher::new();
+    (points.as_ptr() as usize, points.len()).hash(&mut hasher);
+    values.map(|v| v.as_ptr() as usize).hash(&mut hasher);
+    (cfg.bins, cfg.value).hash(&mut hasher);
+    let key = hasher.finish();
+
+    type Cached = (u64, TextureHandle, Arc<Heatmap>);
+    let cached = ctx.memory(|m| m.data.get_temp::<Cached>(id));
+    match cached {
+        Some((k, texture, heatmap)) if k == key => (texture, heatmap),
+        _ => {
+            let values = values.map(|v| v.iter().map(|p| p.y).collect::<Vec<_>>());
+            let heatmap = heatmap::heatmap(points, values.as_deref(), cfg.bins);
+            let texture = ctx.load_texture(
+                format!("heatmap_{id:?}"),
+                heatmap.density.image.clone(),
+                TextureOptions::NEAREST,
+            );
+            let heatmap = Arc::new(heatmap);
+            let cached = (key, texture.clone(), Arc::clone(&heatmap));
+            ctx.memory_mut(|m| m.data.insert_temp(id, cached));
+            (texture, heatmap)
+        }
+    }
+}
+
+/// Draws the driven path colored by the first expression of the tab. Clicking on the map moves
+/// the cursor of the time plots to the nearest point of the path.
+fn track_plot(
+    ui: &mut Ui,
+    tab_cfg: &TabConfig,
+    plots: &[PlotValues],
+    track: Option<&Track>,
+    cursor: &mut Option<f64>,
+) {
+    let color_values = match (plots.first(), tab_cfg.plots.first()) {
+        (Some(PlotValues::Result(Ok(d))), Some(p)) if !d.is_empty() => Some((d, &p.name)),
+        _ => None,
+    };
+
+    Plot::new((tab_cfg.id, PlotMode::TrackMap))
+        .data_aspect(1.0)
+        .x_axis_label("x [m]")
+        .y_axis_label("y [m]")
+        .label_formatter(|_, v| {
+            let x = v.x.round();
+            let y = v.y.round();
+            format!("x = {x} m\ny = {y} m")
+        })
+        .legend(Legend::default())
+        .show(ui, |ui| {
+            let Some(track) = track else {
+                return;
+            };
+
+            let stride = track.points.len().div_ceil(MAX_TRACK_POINTS);
+            let path = track.points.iter().step_by(stride).copied().collect();
+            ui.line(Line::new(PlotPoints::Owned(path)).color(Color32::from_gray(0x80)));
+
+            if let Some((values, name)) = color_values {
+                let min = values.iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+                let max = values.iter().map(|p| p.y).fold(f64::NEG_INFINITY, f64::max);
+                let range = max - min;
+
+                let mut buckets = vec![Vec::new(); TRACK_COLOR_BUCKETS];
+                for (&t, &p) in track.time.iter().zip(track.points.iter()).step_by(stride) {
+                    let i = values.partition_point(|v| v.x <= t).saturating_sub(1);
+                    let v = values[i].y;
+                    if !v.is_finite() {
+                        continue;
+                    }
+                    let norm = if range > 0.0 { (v - min) / range } else { 0.5 };
+                    let b = (norm * (TRACK_COLOR_BUCKETS - 1) as f64).round() as usize;
+                    buckets[b.min(TRACK_COLOR_BUCKETS - 1)].push(p);
+                }
+
+                for (b, points) in buckets.into_iter().enumerate() {
+                    let t = b as f32 / (TRACK_COLOR_BUCKETS - 1) as f32;
+                    let points = Points::new(PlotPoints::Owned(points))
+                        .radius(TRACK_POINT_RADIUS)
+                        .color(heatmap::colormap(t));
+                    ui.points(if b == 0 { points.name(name) } else { points });
+                }
+            }
+
+            if let Some(pos) = cursor.and_then(|t| track.position_at(t)) {
+                ui.points(
+                    Points::new([pos.x, pos.y])
+                        .radius(CURSOR_POINT_RADIUS)
+                        .color(CURSOR_COLOR),
+                );
+            }
+
+            if ui.response().clicked() {
+                let nearest = ui.pointer_coordinate().and_then(|p| track.nearest(p));
+                if let Some(i) = nearest {
+                    *cursor = Some(track.time[i]);
+                }
+            }
+        });
+}
+
+/// Plots the effective pedal map with one curve per speed bin. The plots of the tab are drawn
+/// dashed, so the configured pedal map can be entered as an expression for comparison.
+fn pedal_plot(ui: &mut Ui, tab_cfg: &TabConfig, plots: &[PlotValues], streams: &Arc<[LogStream]>) {
+    let id = Id::new(tab_cfg.id).with("pedal_map");
+    let curves = cached_pedal_map(ui.ctx(), id, streams, &tab_cfg.pedal_map);
+    if let Err(e) = curves.as_ref() {
+        ui.label(RichText::new(e).color(ERROR_RED));
+    }
+
+    Plot::new((tab_cfg.id, PlotMode::PedalMap))
+        .x_axis_label(tab_cfg.pedal_map.pedal.as_str())
+        .y_axis_label(tab_cfg.pedal_map.torque.as_str())
+        .legend(Legend::default())
+        .show(ui, |ui| {
+            if let Ok(curves) = curves.as_ref() {
+                for (i, c) in curves.iter().enumerate() {
+                    let t = i as f32 / (curves.len().max(2) - 1) as f32;
+                    let name = format!("speed {} - {}", c.speed_min, c.speed_max);
+                    let line = Line::new(PlotPoints::Owned(c.points.clone()))
+                        .color(heatmap::colormap(t))
+                        .name(name);
+                    ui.line(line);
+                }
+            }
+
+            for (values, p) in plots.iter().zip(tab_cfg.plots.iter()) {
+                if p.hidden {
+                    continue;
+                }
+                if let PlotValues::Result(Ok(d)) = values {
+                    let line = Line::new(PlotPoints::Owned(d.clone()))
+                        .style(LineStyle::dashed_loose())
+                        .name(&p.name);
+                    ui.line(line);
+                }
+            }
+        });
+}
+
+/// Lists the samples of the tab's plots. The rows are the samples of the first plot, the other
+/// plots show their last sample at or before the time of the row.
+fn value_table(ui: &mut Ui, tab_cfg: &TabConfig, plots: &[PlotValues], cursor: &mut Option<f64>) {
+    let rows = match plots.first() {
+        Some(PlotValues::Result(Ok(rows))) => rows,
+        Some(PlotValues::Job(_)) => {
+            ui.spinner();
+            return;
+        }
+        _ => {
+            ui.label("The first plot has no values");
+            return;
+        }
+    };
+    let columns = (plots.iter())
+        .map(|v| match v {
+            PlotValues::Result(Ok(d)) => Some(d.as_slice()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let row_values = |x: f64| {
+        (columns.iter())
+            .map(|c| c.and_then(|c| sample_at(c, x)).map(|p| p.y))
+            .collect::<Vec<_>>()
+    };
+
+    // only scroll to the cursor if it was moved somewhere else, so the table can be scrolled
+    let id = Id::new(tab_cfg.id).with("value_table_cursor");
+    let last_cursor = ui
+        .ctx()
+        .memory(|m| m.data.get_temp::<Option<f64>>(id))
+        .flatten();
+    let cursor_row = cursor.map(|c| rows.partition_point(|p| p.x <= c).saturating_sub(1));
+
+    let mut table = TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .sense(Sense::click())
+        .column(Column::initial(100.0).at_least(60.0))
+        .columns(Column::initial(140.0).at_least(60.0), tab_cfg.plots.len());
+    if let Some(row) = cursor_row.filter(|_| *cursor != last_cursor) {
+        table = table.scroll_to_row(row, Some(Align::Center));
+    }
+
+    let mut clicked = None;
+    let mut copied = None;
+    table
+        .header(20.0, |mut header| {
+            header.col(|ui| {
+                ui.strong("time [s]");
+            });
+            for p in tab_cfg.plots.iter() {
+                header.col(|ui| {
+                    ui.strong(&p.name);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(VALUE_TABLE_ROW_HEIGHT, rows.len(), |mut row| {
+                let i = row.index();
+                let x = rows[i].x;
+                let values = row_values(x);
+                row.set_selected(cursor_row == Some(i));
+                row.col(|ui| {
+                    ui.monospace(format!("{x:.3}"));
+                });
+                for v in values.iter() {
+                    row.col(|ui| {
+                        match v {
+                            Some(v) => ui.monospace(v.to_string()),
+                            None => ui.label("-"),
+                        };
+                    });
+                }
+
+                let resp = row.response();
+                if resp.clicked() {
+                    clicked = Some(x);
+                }
+                resp.context_menu(|ui| {
+                    if ui.button("Copy row").clicked() {
+                        copied = Some(format_table_row(x, &values));
+                        ui.close_menu();
+                    }
+                    if ui.button("Copy all rows").clicked() {
+                        let mut text = String::from("time");
+                        for p in tab_cfg.plots.iter() {
+                            let _ = write!(text, "\t{}", p.name);
+                        }
+                        for p in rows.iter() {
+                            text.push('\n');
+                            text.push_str(&format_table_row(p.x, &row_values(p.x)));
+                        }
+                        copied = Some(text);
+                        ui.close_menu();
+                    }
+                });
+            });
+        });
+
+    if let Some(text) = copied {
+        ui.output_mut(|o| o.copied_text = text);
+    }
+    if clicked.is_some() {
+        *cursor = clicked;
+    }
+    ui.ctx().memory_mut(|m| m.data.insert_temp(id, *cursor));
+}
+
+/// Tab separated with a header, so it can be pasted into a spreadsheet.
+fn format_points(x_name: &str, y_name: &str, points: &[PlotPoint]) -> String {
+    let mut text = format!("{x_name}\t{y_name}");
+    for p in points.iter() {
+        let _ = write!(text, "\n{}\t{}", p.x, p.y);
+    }
+    text
+}
+
+/// Tab separated, so it can be pasted into a spreadsheet.
+fn format_table_row(x: f64, values: &[Option<f64>]) -> String {
+    let mut text = format!("{x:.3}");
+    for v in values.iter() {
+        text.push('\t');
+        if let Some(v) = v {
+            let _ = write!(text, "{v}");
+        }
+    }
+    text
+}
+
+/// Linear interpolation between the points around `x`, `None` outside of the values.
+fn interpolate(values: &[PlotPoint], x: f64) -> Option<f64> {
+    let i = values.partition_point(|p| p.x <= x);
+    let a = values.get(i.checked_sub(1)?)?;
+    let Some(b) = values.get(i) else {
+        return (a.x == x).then_some(a.y);
+    };
+    let t = (x - a.x) / (b.x - a.x);
+    Some(a.y + t * (b.y - a.y))
+}
+
+/// The last point at or before `x`.
+fn sample_at(values: &[PlotPoint], x: f64) -> Option<&PlotPoint> {
+    let i = values.partition_point(|p| p.x <= x);
+    values.get(i.checked_sub(1)?)
+}
+
+/// Plots the damper velocity histograms of the four corners in a grid, computed from the spring
+/// displacement channels.
+fn damper_plot(ui: &mut Ui, tab_cfg: &TabConfig, streams: &Arc<[LogStream]>) {
+    let id = Id::new(tab_cfg.id).with("damper");
+    let histograms = cached_damper_histograms(ui.ctx(), id, streams, &tab_cfg.damper);
+
+    let height = (ui.available_height() / 2.0 - ui.spacing().item_spacing.y).max(0.0);
+    for (r, histograms) in histograms.chunks(2).enumerate() {
+        ui.columns(2, |columns| {
+            for (c, (ui, histogram)) in columns.iter_mut().zip(histograms).enumerate() {
+                let corner = 2 * r + c;
+                let title = match histogram {
+                    Ok(h) => format!(
+                        "{}   bump {:.1} %   rebound {:.1} %",
+                        CORNERS[corner], h.bump, h.rebound
+                    ),
+                    Err(e) => format!("{}   {e}", CORNERS[corner]),
+                };
+                ui.strong(title);
+
+                Plot::new((tab_cfg.id, PlotMode::DamperHistogram, corner))
+                    .height(height - ui.spacing().interact_size.y)
+                    .x_axis_label("damper velocity [mm/s]")
+                    .y_axis_label("samples [%]")
+                    .show(ui, |ui| {
+                        let Ok(h) = histogram else {
+                            return;
+                        };
+                        let bars = (h.buckets.iter())
+                            .map(|&(x, y)| {
+                                let color = if x > 0.0 {
+                                    DAMPER_BUMP_COLOR
+                                } else {
+                                    DAMPER_REBOUND_COLOR
+                                };
+                                Bar::new(x, y).width(tab_cfg.damper.bucket).fill(color)
+                            })
+                            .collect();
+                        ui.bar_chart(BarChart::new(bars));
+                    });
+            }
+        });
+    }
+}
+
+/// Differentiating every sample is expensive, so the histograms are cached in the temporary egui
+/// memory until the data or the config change.
+fn cached_damper_histograms(
+    ctx: &egui::Context,
+    id: Id,
+    streams: &Arc<[LogStream]>,
+    cfg: &DamperConfig,
+) -> Arc<[Result<DamperHistogram, String>]> {
+    let mut hasher = DefaultHasher::new();
+    (streams.as_ptr() as usize, cfg).hash(&mut hasher);
+    let key = hasher.finish();
+
+    type Cached = (u64, Arc<[Result<DamperHistogram, String>]>);
+    let cached = ctx.memory(|m| m.data.get_temp::<Cached>(id));
+    match cached {
+        Some((k, histograms)) if k == key => histograms,
+        _ => {
+            let histograms: Arc<[_]> = damper::histograms(streams, cfg).into();
+            ctx.memory_mut(|m| m.data.insert_temp(id, (key, Arc::clone(&histograms))));
+            histograms
+        }
+    }
+}
+
+/// Binning every sample is expensive, so the pedal map is cached in the temporary egui memory
+/// until the data or the config change.
+fn cached_pedal_map(
+    ctx: &egui::Context,
+    id: Id,
+    streams: &Arc<[LogStream]>,
+    cfg: &PedalMapConfig,
+) -> Arc<Result<Vec<PedalCurve>, String>> {
+    let mut hasher = DefaultHasher::new();
+    (streams.as_ptr() as usize, cfg).hash(&mut hasher);
+    let key = hasher.finish();
+
+    type Cached = (u64, Arc<Result<Vec<PedalCurve>, String>>);
+    let cached = ctx.memory(|m| m.data.get_temp::<Cached>(id));
+    match cached {
+        Some((k, curves)) if k == key => curves,
+        _ => {
+            let curves = Arc::new(pedal::pedal_map(streams, cfg));
+            ctx.memory_mut(|m| m.data.insert_temp(id, (key, Arc::clone(&curves))));
+            curves
+        }
+    }
+}
+
+/// The density texture is cached in the temporary egui memory until the values change. The
+/// bounds are symmetric around zero, so the friction circle is centered.
+fn cached_gg_density(
+    ctx: &egui::Context,
+    id: Id,
+    values: &[PlotPoint],
+) -> (TextureHandle, Arc<Density>) {
+    let mut hasher = DefaultHasher::new();
+    (values.as_ptr() as usize, values.len()).hash(&mut hasher);
+    let key = hasher.finish();
+
+    type Cached = (u64, TextureHandle, Arc<Density>);
+    let cached = ctx.memory(|m| m.data.get_temp::<Cached>(id));
+    match cached {
+        Some((k, texture, density)) if k == key => (texture, density),
+        _ => {
+            let radius = (values.iter())
+                .flat_map(|p| [p.x.abs(), p.y.abs()])
+                .filter(|v| v.is_finite())
+                .fold(0.0, f64::max);
+            let min = PlotPoint::new(-radius, -radius);
+            let max = PlotPoint::new(radius, radius);
+            let density = heatmap::density(values, GG_BINS, min, max);
+            let texture = ctx.load_texture(
+                format!("gg_{id:?}"),
+                density.image.clone(),
+                TextureOptions::NEAREST,
+            );
+            let density = Arc::new(density);
+            let cached = (key, texture.clone(), Arc::clone(&density));
+            ctx.memory_mut(|m| m.data.insert_temp(id, cached));
+            (texture, density)
+        }
+    }
+}
+
+/// Computing the spectrum every frame is expensive, so it is cached in the temporary egui memory
+/// until the values or the window change.
+fn cached_spectrum(
+    ctx: &egui::Context,
+    id: Id,
+    values: &[PlotPoint],
+    window: FftWindow,
+) -> Arc<[PlotPoint]> {
+    let mut hasher = DefaultHasher::new();
+    (values.as_ptr() as usize, values.len(), window).hash(&mut hasher);
+    let key = hasher.finish();
+
+    let cached = ctx.memory(|m| m.data.get_temp::<(u64, Arc<[PlotPoint]>)>(id));
+    match cached {
+        Some((k, spectrum)) if k == key => spectrum,
+        _ => {
+            let spectrum: Arc<[PlotPoint]> = fft::spectrum(values, window).into();
+            ctx.memory_mut(|m| m.data.insert_temp(id, (key, Arc::clone(&spectrum))));
+            spectrum
+        }
+    }
+}
+
+fn input_sidebar(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
+    // HACK: calculation barely works, because expr inputs can be multiline
+    let plot_height = 3.0 * ui.spacing().interact_size.y
+        + 2.0 * ui.spacing().item_spacing.y
+        + 6.0 * TEXT_EDIT_MARGIN_Y
+        + 2.0 * PLOT_FRAME_PADDING;
+    let plot_spacing = ui.spacing().item_spacing.y;
+    let plot_distance = plot_height + plot_spacing;
+
+    move_focused_plot(ui, data, cfg);
+
+    let pointer_pos = ui.ctx().pointer_interact_pos();
+    let drag = match (pointer_pos, cfg.dragged_plot) {
+        (Some(pointer_pos), Some((from, grab_pos))) => {
+            let distance = pointer_pos.y - grab_pos.y;
+            let moved = (distance / plot_distance).round() as isize;
+            let len = cfg.tabs[cfg.selected_tab].plots.len();
+            let to = (from as isize + moved).clamp(0, len as isize - 1) as usize;
+
+            // move the plot if it was dropped
+            if ui.input(|i| i.pointer.any_released()) {
+                move_plot(data, cfg, from, to);
+                cfg.dragged_plot = None;
+                None
+            } else {
+                let moved_plots = from.min(to)..=from.max(to);
+                Some((from, moved_plots, distance))
+            }
+        }
+        _ => None,
+    };
+
+    let tab_cfg = &cfg.tabs[cfg.selected_tab];
+    if tab_cfg.use_alt_x && !tab_cfg.alt_x.trim().is_empty() {
+        let text = format!("X of line plots: {}", tab_cfg.alt_x);
+        ui.label(RichText::new(text).monospace().weak());
+    }
+
+    let mut i = 0;
+    while i < cfg.tabs[cfg.selected_tab].plots.len() {
+        let plot = &mut cfg.tabs[cfg.selected_tab].plots[i];
+        let values = &data.plots[cfg.selected_tab][i];
+        let unit_sources = (cfg.unit_warnings).then_some((&*data.streams, &*cfg.channel_units));
+
+        let mut input = None;
+        match drag {
+            Some((dragged_idx, _, dist)) if dragged_idx == i => {
+                let id = Id::new("plot").with(i);
+                let layer_id = LayerId::new(Order::Tooltip, id);
+                ui.with_layer_id(layer_id, |ui| {
+                    expr_inputs(ui, plot, values, unit_sources, i, &mut cfg.dragged_plot);
+                });
+                let transform = TSTransform::new(Vec2::new(0.0, dist), 1.0);
+                ui.ctx().transform_layer_shapes(layer_id, transform);
+                // FIXME: only works sometimes
+                ui.output_mut(|o| o.cursor_icon = CursorIcon::Grabbing);
+            }
+            Some((_, ref moved_plots, dist)) if moved_plots.contains(&i) => {
+                let id = Id::new("plot").with(i);
+                let layer_id = LayerId::new(Order::Foreground, id);
+                ui.with_layer_id(layer_id, |ui| {
+                    expr_inputs(ui, plot, values, unit_sources, i, &mut cfg.dragged_plot);
+                });
+                let offset = -dist.signum() * plot_distance;
+                let transform = TSTransform::new(Vec2::new(0.0, offset), 1.0);
+                ui.ctx().transform_layer_shapes(layer_id, transform);
+            }
+            _ => {
+                input = Some(expr_inputs(
+                    ui,
+                    plot,
+                    values,
+                    unit_sources,
+                    i,
+                    &mut cfg.dragged_plot,
+                ));
+            }
+        };
+
+        let tab_cfg = &mut cfg.tabs[cfg.selected_tab];
+        match input {
+            Some(input) if input.removed => {
+                tab_cfg.plots.remove(i);
+                let _ = data.plots[cfg.selected_tab].remove(i);
+                data.edited
+                    .retain(|(tab, j, _)| (*tab, *j) != (tab_cfg.id, i));
+                for (tab, j, _) in data.edited.iter_mut() {
+                    if *tab == tab_cfg.id && *j > i {
+                        *j -= 1;
+                    }
+                }
+                match &mut tab_cfg.expr_editor {
+                    Some(editor) if editor.plot == i => tab_cfg.expr_editor = None,
+                    Some(editor) if editor.plot > i => editor.plot -= 1,
+                    _ => (),
+                }
+            }
+            Some(input) => {
+                if input.open_editor {
+                    let text = tab_cfg.plots[i].expr.y.clone();
+                    tab_cfg.expr_editor = Some(ExprEditor { plot: i, text });
+                }
+                // evaluated once typing paused, instead of on every keystroke
+                if input.x_changed || input.y_changed {
+                    data.edited
+                        .retain(|(tab, j, _)| (*tab, *j) != (tab_cfg.id, i));
+                    data.edited.push((tab_cfg.id, i, Instant::now()));
+                }
+                if input.restarted {
+                    let expr = tab_cfg.plot_expr(&tab_cfg.plots[i]);
+                    data.plots[cfg.selected_tab][i] = data.start_job(&expr, cfg);
+                }
+                if input.add_wheel_group {
+                    add_wheel_group(data, cfg, i);
+                }
+                i += 1;
+            }
+            None => i += 1,
+        }
+    }
+
+    expr_editor_window(ui.ctx(), data, cfg);
+
+    ui.horizontal(|ui| {
+        if ui.button(" + ").clicked() {
+            let name = format!("{}.", cfg.tabs[cfg.selected_tab].plots.len() + 1);
+            add_plot(
+                data,
+                cfg,
+                NamedPlot::new(name, Expr::new("time", "")),
+                false,
+            );
+        }
+
+        ui.menu_button("...", |ui| channel_browser(ui, data, cfg));
+        ui.menu_button("A − B", |ui| difference_menu(ui, data, cfg))
+            .response
+            .on_hover_text("Add the difference of two plots");
+    });
+
+    ui.add_space(10.0);
+    CollapsingHeader::new("Laps").show(ui, |ui| {
+        lap_sidebar(ui, data, cfg);
+    });
+
+    CollapsingHeader::new("Reference curves").show(ui, |ui| {
+        let curves = &mut cfg.tabs[cfg.selected_tab].reference_curves;
+        curves::reference_curves_sidebar(ui, curves, &mut cfg.new_reference_curve);
+    });
+
+    CollapsingHeader::new("Sync markers").show(ui, |ui| {
+        let markers = &mut cfg.sync_markers;
+        markers::markers_sidebar(ui, markers, &mut cfg.new_marker, cfg.cursor);
+    });
+
+    CollapsingHeader::new("Events").show(ui, |ui| {
+        if let Some(time) = markers::events_sidebar(ui, &mut cfg.event_markers) {
+            cfg.cursor = Some(time);
+        }
+    });
+
+    CollapsingHeader::new("Find").show(ui, |ui| {
+        let from = bookmark_time(cfg).unwrap_or(f64::NEG_INFINITY);
+        if let Some(time) = find::find_sidebar(ui, data, cfg, from) {
+            center_on(cfg, time);
+        }
+    });
+
+    CollapsingHeader::new("Bookmarks").show(ui, |ui| {
+        if let Some(time) = markers::bookmarks_sidebar(ui, &mut cfg.bookmarks) {
+            center_on(cfg, time);
+        }
+    });
+
+    CollapsingHeader::new("Expression library").show(ui, |ui| {
+        let library = &mut cfg.expr_library;
+        if library::library_sidebar(ui, library, &mut cfg.new_user_expr) {
+            data.plots = start_jobs(cfg, data);
+            data.x_stash.clear();
+            if let Some(run_b) = &mut data.run_b {
+                run_b.clear();
+            }
+        }
+    });
+
+    CollapsingHeader::new("Derived channels").show(ui, |ui| {
+        let channels = &mut cfg.derived_channels;
+        library::library_sidebar(ui, channels, &mut cfg.new_derived_channel);
+        for e in data.derived_errors.iter() {
+            ui.colored_label(ERROR_RED, e);
+        }
+        let changed = cfg.derived_channels != data.derived;
+        let apply = ui
+            .add_enabled(changed, Button::new("Apply"))
+            .on_hover_text("Compute the derived channels and add them to the data");
+        if apply.clicked() {
+            fs::rebuild_streams(data, cfg);
+        }
+    });
+
+    CollapsingHeader::new("Channel units").show(ui, |ui| {
+        let table = &mut cfg.channel_units;
+        units::channel_units_sidebar(ui, table, &mut cfg.new_channel_unit);
+    });
+
+    CollapsingHeader::new("Interpolation").show(ui, |ui| {
+        ui.label("How channels are evaluated between their samples")
+            .on_hover_text("Applies to streams other than the first one and to resampling");
+        let table = &mut cfg.channel_interpolation;
+        let new = &mut cfg.new_channel_interpolation;
+        if interpolation::channel_interpolation_sidebar(ui, table, new) {
+            // derived channels are evaluated with the interpolation too
+            if cfg.derived_channels.is_empty() {
+                data.plots = start_jobs(cfg, data);
+                data.x_stash.clear();
+            } else {
+                fs::rebuild_streams(data, cfg);
+            }
+        }
+    });
+
+    CollapsingHeader::new("Channel aliases").show(ui, |ui| {
+        let table = &mut cfg.channel_aliases;
+        let new = &mut cfg.new_channel_alias;
+        if aliases::channel_aliases_sidebar(ui, table, new, &mut cfg.alias_identifiers) {
+            data.plots = start_jobs(cfg, data);
+            data.x_stash.clear();
+            if let Some(run_b) = &mut data.run_b {
+                run_b.clear();
+            }
+        }
+    });
+
+    if data.streams.len() > 1 {
+        CollapsingHeader::new("Stream time offsets").show(ui, |ui| {
+            if stream_offsets_sidebar(ui, &mut cfg.stream_offsets, &data.streams) {
+                data.plots = start_jobs(cfg, data);
+                data.x_stash.clear();
+            }
+        });
+    }
+}
+
+/// Returns true if an offset was changed and the plots have to be evaluated again.
+fn stream_offsets_sidebar(ui: &mut Ui, offsets: &mut Vec<i64>, streams: &[LogStream]) -> bool {
+    let mut changed = false;
+    for (i, s) in streams.iter().enumerate() {
+        let mut offset = offsets.get(i).copied().unwrap_or(0);
+        ui.horizontal(|ui| {
+            let channels = s.entries.iter().map(|e| e.name.as_str());
+            ui.label(format!("Stream {}", i + 1))
+                .on_hover_text(channels.collect::<Vec<_>>().join(", "));
+            let resp = ui.add(DragValue::new(&mut offset).speed(1).suffix(" ms"));
+            // only evaluate again once dragging stopped
+            if resp.drag_stopped() || resp.changed() && !resp.dragged() {
+                changed = true;
+            }
+        });
+        if offset != offsets.get(i).copied().unwrap_or(0) {
+            offsets.resize(offsets.len().max(i + 1), 0);
+            offsets[i] = offset;
+        }
+    }
+    changed
+}
+
+fn lap_sidebar(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
+    let mut changed = false;
+    let trigger = &mut cfg.lap_trigger;
+    let cursor_pos = (cfg.cursor.zip(data.track.as_ref())).and_then(|(t, tr)| tr.position_at(t));
+
+    ComboBox::from_label("trigger")
+        .selected_text(trigger.name())
+        .show_ui(ui, |ui| {
+            let gate = cursor_pos.or_else(|| data.track.as_ref()?.points.first().copied());
+            let beacon = laps::find_beacon(&data.streams).unwrap_or_default();
+            let mut options = vec![
+                LapTrigger::None,
+                LapTrigger::Auto,
+                LapTrigger::Beacon(beacon.to_string()),
+            ];
+            if let Some(p) = gate {
+                options.push(LapTrigger::GpsGate { x: p.x, y: p.y });
+            }
+            options.push(LapTrigger::Markers(Vec::new()));
+
+            for o in options {
+                let selected = std::mem::discriminant(trigger) == std::mem::discriminant(&o);
+                if ui.selectable_label(selected, o.name()).clicked() && !selected {
+                    *trigger = o;
+                    changed = true;
+                }
+            }
+        });
+
+    match trigger {
+        LapTrigger::None => (),
+        LapTrigger::Auto => {
+            let text = match laps::find_beacon(&data.streams) {
+                Some(channel) => format!("Beacon channel `{channel}`"),
+                None => "No beacon channel found".to_string(),
+            };
+            ui.weak(text)
+                .on_hover_text("Boolean channels named like a beacon or lap trigger are used");
+        }
+        LapTrigger::Beacon(channel) => {
+            ComboBox::from_label("channel")
+                .selected_text(channel.as_str())
+                .show_ui(ui, |ui| {
+                    for e in data.streams.iter().flat_map(|s| s.entries.iter()) {
+                        if ui.selectable_label(*channel == e.name, &e.name).clicked() {
+                            *channel = e.name.clone();
+                            changed = true;
+                        }
+                    }
+                });
+        }
+        LapTrigger::GpsGate { x, y } => {
+            ui.horizontal(|ui| {
+                ui.label(format!("x = {} m, y = {} m", x.round(), y.round()));
+                let resp = ui
+                    .add_enabled(cursor_pos.is_some(), Button::new("Set to cursor"))
+                    .on_hover_text("Place the cursor by clicking on the track map");
+                if let (true, Some(p)) = (resp.clicked(), cursor_pos) {
+                    (*x, *y) = (p.x, p.y);
+                    changed = true;
+                }
+            });
+        }
+        LapTrigger::Markers(times) => {
+            let mut i = 0;
+            while i < times.len() {
+                let removed = ui
+                    .horizontal(|ui| {
+                        ui.label(format_time(times[i]));
+                        ui.button("🗙").clicked()
+                    })
+                    .inner;
+                if removed {
+                    times.remove(i);
+                    changed = true;
+                } else {
+                    i += 1;
+                }
             }
-            _ => {
-                input = Some(expr_inputs(ui, plot, values, i, &mut cfg.dragged_plot));
+            let resp = ui
+                .add_enabled(cfg.cursor.is_some(), Button::new("Add marker at cursor"))
+                .on_hover_text("Place the cursor by clicking on a time plot or the track map");
+            if let (true, Some(t)) = (resp.clicked(), cfg.cursor) {
+                times.push(t);
+                changed = true;
             }
-        };
+        }
+    }
 
-        let tab_cfg = &mut cfg.tabs[cfg.selected_tab];
-        let plot = &mut tab_cfg.plots[i];
-        match input {
-            Some(input) if input.removed => {
-                tab_cfg.plots.remove(i);
-                let _ = data.plots[cfg.selected_tab].remove(i);
+    if changed {
+        data.laps = laps::detect(&data.streams, data.track.as_ref(), &cfg.lap_trigger);
+    }
+
+    let fastest = (data.laps.iter())
+        .min_by(|a, b| a.duration().total_cmp(&b.duration()))
+        .map(|l| l.number);
+    let fastest_time =
+        (fastest.and_then(|n| data.laps.iter().find(|l| l.number == n))).map(|l| l.duration());
+    let mut zoom_to = None;
+    Grid::new("laps")
+        .striped(true)
+        .num_columns(4)
+        .show(ui, |ui| {
+            for lap in data.laps.iter_mut() {
+                ui.checkbox(&mut lap.selected, "")
+                    .on_hover_text("Show the lap in lap overlays");
+                let number = ui.link(format!("Lap {}", lap.number));
+                if number.on_hover_text("Zoom the plots to the lap").clicked() {
+                    zoom_to = Some((lap.start, lap.end));
+                }
+                let mut time = RichText::new(format_time(lap.duration())).monospace();
+                if Some(lap.number) == fastest {
+                    time = time.strong();
+                }
+                ui.label(time);
+                match fastest_time {
+                    Some(t) if Some(lap.number) != fastest => {
+                        ui.monospace(format!("+{:.3}", lap.duration() - t))
+                    }
+                    _ => ui.label(""),
+                };
+                ui.end_row();
             }
-            Some(input) => {
-                if input.x_changed || input.y_changed {
-                    data.plots[cfg.selected_tab][i] =
-                        PlotValues::Job(Job::start(plot.expr.clone(), Arc::clone(&data.streams)));
+        });
+    if let Some((start, end)) = zoom_to {
+        show_range(cfg, start..=end, start);
+    }
+}
+
+/// Adds a plot of the difference of two line plots of the selected tab. Both y expressions are
+/// evaluated against the x expression of the first plot, so they are resampled onto common x
+/// values.
+fn difference_menu(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
+    let tab_cfg = &cfg.tabs[cfg.selected_tab];
+    let line_plots = (tab_cfg.plots.iter().enumerate())
+        .filter(|(_, p)| p.xy_mode == XyMode::Off)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if line_plots.len() < 2 {
+        ui.weak("Add two line plots first");
+        return;
+    }
+
+    let id = Id::new(tab_cfg.id).with("difference");
+    let selected = ui.data(|d| d.get_temp::<[usize; 2]>(id));
+    let mut selected = selected
+        .filter(|s| s.iter().all(|i| line_plots.contains(i)))
+        .unwrap_or([line_plots[0], line_plots[1]]);
+    for (label, s) in ["A", "B"].into_iter().zip(selected.iter_mut()) {
+        ComboBox::new(id.with(label), label)
+            .selected_text(&tab_cfg.plots[*s].name)
+            .show_ui(ui, |ui| {
+                for &i in line_plots.iter() {
+                    ui.selectable_value(s, i, &tab_cfg.plots[i].name);
                 }
-                i += 1;
+            });
+    }
+    ui.data_mut(|d| d.insert_temp(id, selected));
+
+    let [a, b] = selected.map(|i| &tab_cfg.plots[i]);
+    if a.expr.x.trim() != b.expr.x.trim() {
+        ui.weak(format!("B is evaluated against `{}`", a.expr.x.trim()));
+    }
+    if ui
+        .add_enabled(selected[0] != selected[1], Button::new("Add A − B"))
+        .clicked()
+    {
+        let name = format!("{} − {}", a.name, b.name);
+        let y = format!("({}) - ({})", a.expr.y.trim(), b.expr.y.trim());
+        let plot = NamedPlot::new(name, Expr::new(a.expr.x.clone(), y));
+        add_plot(data, cfg, plot, true);
+        ui.close_menu();
+    }
+}
+
+/// Searchable list of the channels, grouped by their prefix. Clicking a channel adds it as a plot,
+/// several channels can be selected to add them at once.
+fn channel_browser(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
+    ui.checkbox(&mut cfg.hide_constant, "Hide constant channels");
+    TextEdit::singleline(&mut cfg.search_channels)
+        .hint_text("Search channels")
+        .desired_width(CHANNEL_BROWSER_WIDTH)
+        .show(ui);
+
+    let search = cfg.search_channels.trim().to_lowercase();
+    let channels = (data.streams.iter().enumerate())
+        .flat_map(|(i, s)| (0..s.entries.len()).map(move |j| (i, j)))
+        .filter(|&(i, j)| !(data.channels[i][j].constant && cfg.hide_constant))
+        .filter(|&(i, j)| {
+            let name = &data.streams[i].entries[j].name;
+            let alias = aliases::display_name(&cfg.channel_aliases, name);
+            name.to_lowercase().contains(&search) || alias.to_lowercase().contains(&search)
+        })
+        .collect::<Vec<_>>();
+    let groups = channel_groups(channels.iter().map(|&(i, j)| {
+        let name = data.streams[i].entries[j].name.as_str();
+        ((i, j), name)
+    }));
+
+    let selected = &mut cfg.selected_channels;
+    let add_selected = ui
+        .horizontal(|ui| {
+            let add = Button::new(format!("Add {} selected", selected.len()));
+            let add = ui.add_enabled(!selected.is_empty(), add).clicked();
+            if ui.button("Clear").clicked() {
+                selected.clear();
             }
-            None => i += 1,
+            add
+        })
+        .inner;
+    ui.separator();
+
+    let mut clicked = Vec::new();
+    ScrollArea::vertical()
+        .max_height(CHANNEL_BROWSER_HEIGHT)
+        .show(ui, |ui| {
+            ui.set_width(CHANNEL_BROWSER_WIDTH);
+            for (group, members) in groups.iter() {
+                let title = match group.is_empty() {
+                    true => format!("Other ({})", members.len()),
+                    false => format!("{group}_* ({})", members.len()),
+                };
+                CollapsingHeader::new(title)
+                    .id_source(("channel_group", group))
+                    .open((!search.is_empty()).then_some(true))
+                    .show(ui, |ui| {
+                        for &(i, j) in members.iter() {
+                            let name = &data.streams[i].entries[j].name;
+                            let channel = &data.channels[i][j];
+                            ui.horizontal(|ui| {
+                                let mut checked = selected.contains(name);
+                                if ui.checkbox(&mut checked, "").changed() {
+                                    match checked {
+                                        true => selected.push(name.clone()),
+                                        false => selected.retain(|s| s != name),
+                                    }
+                                }
+                                sparkline(ui, &channel.sparkline);
+                                let label = aliases::display_name(&cfg.channel_aliases, name);
+                                let resp = if channel.constant {
+                                    let text = RichText::new(label).weak();
+                                    ui.button(text).on_hover_text("constant")
+                                } else {
+                                    ui.button(label)
+                                };
+                                if resp.clicked() {
+                                    clicked.push(name.clone());
+                                }
+                            });
+                        }
+                    });
+            }
+        });
+
+    if add_selected {
+        clicked = std::mem::take(&mut cfg.selected_channels);
+    }
+    if !clicked.is_empty() {
+        for name in clicked {
+            let plot = NamedPlot::new(name.clone(), Expr::new("time", &name));
+            add_plot(data, cfg, plot, true);
+        }
+        ui.close_menu();
+    }
+}
+
+/// Evaluates the edited plots whose expressions weren't changed for [`EDIT_DEBOUNCE`].
+fn start_edited_jobs(ui: &Ui, data: &mut PlotData, cfg: &Config) {
+    let (due, pending) = std::mem::take(&mut data.edited)
+        .into_iter()
+        .partition::<Vec<_>, _>(|(.., edited)| edited.elapsed() >= EDIT_DEBOUNCE);
+    if !pending.is_empty() {
+        ui.ctx().request_repaint_after(EDIT_DEBOUNCE);
+    }
+    data.edited = pending;
+    for (id, i, _) in due {
+        let Some(tab) = cfg.tabs.iter().position(|t| t.id == id) else {
+            continue;
+        };
+        let tab_cfg = &cfg.tabs[tab];
+        if let Some(plot) = tab_cfg.plots.get(i) {
+            data.plots[tab][i] = data.start_job(&tab_cfg.plot_expr(plot), cfg);
+        }
+    }
+}
+
+/// Groups the channels by the prefix before the first `_`, in the order the groups first appear.
+/// Channels without a prefix shared with another channel are put into the unnamed group at the
+/// end.
+fn channel_groups<'a, T>(channels: impl Iterator<Item = (T, &'a str)>) -> Vec<(String, Vec<T>)> {
+    let mut groups: Vec<(String, Vec<T>)> = Vec::new();
+    for (c, name) in channels {
+        let prefix = name.split_once('_').map_or("", |(p, _)| p);
+        match groups.iter_mut().find(|(g, _)| g == prefix) {
+            Some((_, members)) => members.push(c),
+            None => groups.push((prefix.to_string(), vec![c])),
+        }
+    }
+
+    let mut other = Vec::new();
+    groups.retain_mut(|(g, members)| {
+        let single = g.is_empty() || members.len() == 1;
+        if single {
+            other.append(members);
         }
+        !single
+    });
+    if !other.is_empty() {
+        groups.push((String::new(), other));
     }
+    groups
+}
+
+fn sparkline(ui: &mut Ui, values: &[f32]) {
+    let (rect, _) = ui.allocate_exact_size(SPARKLINE_SIZE, Sense::hover());
+    let stroke = Stroke::new(1.0, ui.visuals().weak_text_color());
+    let points = (values.iter().enumerate())
+        .map(|(i, v)| {
+            let x = rect.left() + rect.width() * i as f32 / (values.len() - 1).max(1) as f32;
+            let y = rect.bottom() - rect.height() * v;
+            Pos2::new(x, y)
+        })
+        .collect();
+    ui.painter().add(Shape::line(points, stroke));
+}
 
+fn line_style_input(ui: &mut Ui, plot: &mut NamedPlot, idx: usize) {
     ui.horizontal(|ui| {
-        if ui.button(" + ").clicked() {
-            let name = format!("{}.", cfg.tabs[cfg.selected_tab].plots.len() + 1);
-            add_plot(
-                data,
-                cfg,
-                NamedPlot::new(name, Expr::new("time", "")),
-                false,
-            );
+        let mut custom = plot.color.is_some();
+        if ui.checkbox(&mut custom, "Color").changed() {
+            plot.color = custom.then(|| auto_color(ui.ctx(), idx));
+        }
+        if let Some(color) = &mut plot.color {
+            ui.color_edit_button_srgba(color);
+        }
+    });
+    ui.horizontal(|ui| {
+        let mut custom = plot.width.is_some();
+        if ui.checkbox(&mut custom, "Width").changed() {
+            plot.width = custom.then_some(DEFAULT_LINE_WIDTH);
         }
+        if let Some(width) = &mut plot.width {
+            ui.add(DragValue::new(width).range(0.5..=10.0).speed(0.1));
+        }
+    });
+    ui.label("Dash style");
+    for d in DashStyle::ALL {
+        ui.radio_value(&mut plot.dash, d, d.name());
+    }
+}
 
-        ui.menu_button("...", |ui| {
-            ScrollArea::vertical().show(ui, |ui| {
-                ui.allocate_ui(Vec2::new(300.0, 500.0), |ui| {
-                    for i in 0..data.streams.len() {
-                        for j in 0..data.streams[i].entries.len() {
-                            let name = &data.streams[i].entries[j].name;
-                            if ui.button(name).clicked() {
-                                let plot = NamedPlot::new(name.into(), Expr::new("time", name));
-                                add_plot(data, cfg, plot, true);
+/// Moves the plot with a focused input up or down with Alt+Up/Down. The focus moves along with
+/// the plot, so it can be moved several times in a row.
+fn move_focused_plot(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
+    let Some(focused) = ui.memory(|m| m.focused()) else {
+        return;
+    };
+    let len = cfg.tabs[cfg.selected_tab].plots.len();
+    let field = (0..len)
+        .flat_map(|i| PLOT_FIELDS.map(|f| (i, f)))
+        .find(|(i, f)| plot_field_id(*i, f) == focused);
+    let Some((from, field)) = field else {
+        return;
+    };
 
-                                ui.close_menu();
-                            }
-                        }
-                    }
-                });
-            });
-        });
+    let to = ui.input_mut(|i| {
+        if i.consume_key(Modifiers::ALT, Key::ArrowUp) {
+            from.checked_sub(1)
+        } else if i.consume_key(Modifiers::ALT, Key::ArrowDown) {
+            Some(from + 1).filter(|&to| to < len)
+        } else {
+            None
+        }
     });
+    if let Some(to) = to {
+        move_plot(data, cfg, from, to);
+        ui.memory_mut(|m| m.request_focus(plot_field_id(to, field)));
+    }
+}
+
+/// Id of the name or expression input of the plot at `idx`, which only depends on the position,
+/// so the focus can be moved to the same input of another plot.
+fn plot_field_id(idx: usize, field: &str) -> Id {
+    Id::new("plot_field").with(idx).with(field)
 }
 
 struct ExprInput {
     removed: bool,
+    /// evaluate the cancelled expression again
+    restarted: bool,
     x_changed: bool,
     y_changed: bool,
+    open_editor: bool,
+    add_wheel_group: bool,
 }
 
 fn expr_inputs(
     ui: &mut Ui,
     plot: &mut NamedPlot,
     values: &PlotValues,
+    unit_sources: Option<(&[LogStream], &[ChannelUnit])>,
     idx: usize,
     dragged_plot: &mut Option<(usize, Pos2)>,
 ) -> ExprInput {
@@ -641,23 +4457,130 @@ fn expr_inputs(
         .fill(plot_fill)
         .inner_margin(PLOT_FRAME_PADDING)
         .show(ui, |ui| {
+            let mut add_wheel_group = false;
             let removed = ui.horizontal(|ui| {
                 let r = ui.add(Button::new(" − ").sense(Sense::click_and_drag()));
-                let width = ui.available_width() - ui.spacing().interact_size.x;
+                let width = ui.available_width() - 3.0 * ui.spacing().interact_size.x;
                 TextEdit::singleline(&mut plot.name)
+                    .id(plot_field_id(idx, "name"))
                     .desired_width(width)
                     .frame(false)
                     .show(ui);
 
-                if let PlotValues::Job(_) = values {
-                    ui.spinner();
+                let eye = RichText::new("👁");
+                let eye = if plot.hidden {
+                    eye.weak().strikethrough()
+                } else {
+                    eye
+                };
+                let resp = ui.add(Button::new(eye).frame(false));
+                if resp.on_hover_text("Show or hide the series").clicked() {
+                    plot.hidden = !plot.hidden;
                 }
 
+                ui.menu_button("⚙", |ui| {
+                    ui.label("XY mode");
+                    for m in XyMode::ALL {
+                        ui.radio_value(&mut plot.xy_mode, m, m.name());
+                    }
+                    ui.separator();
+                    ui.label("Y-axis");
+                    for a in YAxis::ALL {
+                        ui.radio_value(&mut plot.y_axis, a, a.name());
+                    }
+                    ui.separator();
+                    line_style_input(ui, plot, idx);
+                    ui.separator();
+                    ui.checkbox(&mut plot.overview, "Session overview")
+                        .on_hover_text("Show the whole session below the time plot");
+                    ui.checkbox(&mut plot.digital, "Digital lane")
+                        .on_hover_text(
+                            "Draw the series as a high/low trace in a lane below the time plot",
+                        );
+                    ui.horizontal(|ui| {
+                        ui.label("Fill to");
+                        let input = TextEdit::singleline(&mut plot.fill_to)
+                            .hint_text("e.g. cell_voltage_min")
+                            .desired_width(100.0);
+                        ui.add(input);
+                    })
+                    .response
+                    .on_hover_text(
+                        "Fill the area between the line and the values of an expression",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Color by");
+                        let input = TextEdit::singleline(&mut plot.color_by)
+                            .hint_text("e.g. throttle")
+                            .desired_width(100.0);
+                        ui.add(input);
+                    })
+                    .response
+                    .on_hover_text("Color the segments of the line by the values of an expression");
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Band group");
+                        ui.add(TextEdit::singleline(&mut plot.band_group).desired_width(100.0));
+                    })
+                    .response
+                    .on_hover_text(
+                        "Line plots of the same group are drawn as a min/max band with a mean line",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Stack group");
+                        ui.add(TextEdit::singleline(&mut plot.stack_group).desired_width(100.0));
+                    })
+                    .response
+                    .on_hover_text(
+                        "Line plots of the same group are drawn as areas stacked on each other",
+                    );
+                    if wheel_channel(&plot.expr.y).is_some()
+                        && ui.button("Add wheel group").clicked()
+                    {
+                        add_wheel_group = true;
+                        ui.close_menu();
+                    }
+                });
+
                 r.clicked()
             });
 
-            let x_action = expr_input(ui, " X ", &mut plot.expr.x, values.x_err());
-            let y_action = expr_input(ui, " Y ", &mut plot.expr.y, values.y_err());
+            let mut restarted = false;
+            match values {
+                PlotValues::Job(job) => app::job_progress(ui, job),
+                PlotValues::Cancelled => {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new("Evaluation cancelled").weak());
+                        restarted = ui.button("⟳").on_hover_text("Evaluate again").clicked();
+                    });
+                }
+                _ => (),
+            }
+
+            let unit_warnings = |input: &str| match unit_sources {
+                Some((s, t)) => units::check(input, |name| units::channel_unit(s, t, name)),
+                None => Vec::new(),
+            };
+            let x_warnings = unit_warnings(&plot.expr.x);
+            let y_warnings = unit_warnings(&plot.expr.y);
+            let x_action = expr_input(
+                ui,
+                plot_field_id(idx, "x"),
+                " X ",
+                &mut plot.expr.x,
+                values.x_err(),
+                &x_warnings,
+                false,
+            );
+            let y_action = expr_input(
+                ui,
+                plot_field_id(idx, "y"),
+                " Y ",
+                &mut plot.expr.y,
+                values.y_err(),
+                &y_warnings,
+                true,
+            );
 
             ui.add_space(10.0);
 
@@ -673,8 +4596,11 @@ fn expr_inputs(
 
             ExprInput {
                 removed: removed.inner,
+                restarted,
                 x_changed: x_action == Some(PlotAction::Changed),
                 y_changed: y_action == Some(PlotAction::Changed),
+                open_editor: y_action == Some(PlotAction::OpenEditor),
+                add_wheel_group,
             }
         });
 
@@ -685,13 +4611,17 @@ fn expr_inputs(
 enum PlotAction {
     DragStarted,
     Changed,
+    OpenEditor,
 }
 
 fn expr_input(
     ui: &mut Ui,
+    id: Id,
     label: &str,
     expr: &mut String,
     error: Option<&cods::Error>,
+    warnings: &[String],
+    expandable: bool,
 ) -> Option<PlotAction> {
     let mut action = None;
 
@@ -720,9 +4650,14 @@ fn expr_input(
             action = Some(PlotAction::DragStarted);
         }
 
+        let width = match expandable {
+            true => ui.available_width() - EXPAND_BUTTON_WIDTH,
+            false => ui.available_width(),
+        };
         let resp = ui.add(
             TextEdit::multiline(expr)
-                .desired_width(ui.available_width())
+                .id(id)
+                .desired_width(width)
                 .desired_rows(1)
                 .layouter(&mut layouter),
         );
@@ -730,11 +4665,140 @@ fn expr_input(
         if resp.changed() {
             action = Some(PlotAction::Changed);
         }
+
+        if expandable {
+            let resp = ui.small_button("⤢").on_hover_text("Open in the editor");
+            if resp.clicked() {
+                action = Some(PlotAction::OpenEditor);
+            }
+        }
     });
     if let Some(e) = error {
         ui.colored_label(ERROR_RED, e.to_string());
+    } else {
+        for w in warnings.iter() {
+            ui.colored_label(WARNING_YELLOW, w);
+        }
+    }
+
+    action
+}
+
+/// Larger editor for long y expressions, the text is only applied on request.
+pub struct ExprEditor {
+    plot: usize,
+    text: String,
+}
+
+enum EditorAction {
+    Apply,
+    ApplyAndClose,
+    Close,
+}
+
+fn expr_editor_window(ctx: &egui::Context, data: &mut PlotData, cfg: &mut Config) {
+    let tab = cfg.selected_tab;
+    let tab_cfg = &mut cfg.tabs[tab];
+    let Some(editor) = &mut tab_cfg.expr_editor else {
+        return;
+    };
+    let Some(plot) = tab_cfg.plots.get(editor.plot) else {
+        tab_cfg.expr_editor = None;
+        return;
+    };
+    // positions of the errors only match the applied expression
+    let error = (editor.text == plot.expr.y)
+        .then(|| data.plots[tab][editor.plot].y_err())
+        .flatten();
+
+    let mut open = true;
+    let r = Window::new(format!("Y expression of {}", plot.name))
+        .id(Id::new("expr_editor"))
+        .default_size(Vec2::new(700.0, 400.0))
+        .collapsible(false)
+        .open(&mut open)
+        .show(ctx, |ui| expr_editor(ui, editor, error));
+
+    let action = r.and_then(|r| r.inner.flatten());
+    if let Some(EditorAction::Apply | EditorAction::ApplyAndClose) = action {
+        let i = editor.plot;
+        tab_cfg.plots[i].expr.y = editor.text.clone();
+        let expr = tab_cfg.plot_expr(&tab_cfg.plots[i]);
+        data.plots[tab][i] = data.start_job(&expr, cfg);
+    }
+    if !open
+        || matches!(
+            action,
+            Some(EditorAction::ApplyAndClose | EditorAction::Close)
+        )
+    {
+        cfg.tabs[tab].expr_editor = None;
+    }
+}
+
+fn expr_editor(
+    ui: &mut Ui,
+    editor: &mut ExprEditor,
+    error: Option<&cods::Error>,
+) -> Option<EditorAction> {
+    let mut action = None;
+
+    let mut layouter = |ui: &egui::Ui, string: &str, _wrap_width: f32| {
+        let format = TextFormat {
+            font_id: TextStyle::Monospace.resolve(ui.style()),
+            ..Default::default()
+        };
+        let layout_job = match error {
+            Some(e) => mark_errors(string, e, format),
+            None => LayoutJob::single_section(string.to_string(), format),
+        };
+        // lines aren't wrapped, so they match the line numbers
+        ui.fonts(|f| f.layout_job(layout_job))
+    };
+    ScrollArea::both()
+        .max_height(ui.available_height() - 3.0 * ui.spacing().interact_size.y)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            ui.horizontal_top(|ui| {
+                let lines = editor.text.split('\n').count();
+                let numbers = (1..=lines)
+                    .map(|l| format!("{l:>3}"))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ui.vertical(|ui| {
+                    ui.add_space(TEXT_EDIT_MARGIN_Y);
+                    ui.add(Label::new(RichText::new(numbers).monospace().weak()).selectable(false));
+                });
+                ui.add(
+                    TextEdit::multiline(&mut editor.text)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(10)
+                        .layouter(&mut layouter),
+                );
+            });
+        });
+
+    if let Some(e) = error {
+        ui.colored_label(ERROR_RED, e.to_string());
+        for s in e.spans().iter() {
+            let pos = format!("at line {}, column {}", s.start.line + 1, s.start.col + 1);
+            ui.colored_label(ERROR_RED, pos);
+        }
     }
 
+    ui.horizontal(|ui| {
+        if ui.button("Ok").clicked() {
+            action = Some(EditorAction::ApplyAndClose);
+        }
+        if ui.button("Apply").clicked() {
+            action = Some(EditorAction::Apply);
+        }
+        if ui.button("Cancel").clicked() {
+            action = Some(EditorAction::Close);
+        }
+    });
+
     action
 }
 
@@ -833,17 +4897,86 @@ fn help_sidebar(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
             CollapsingHeader::new(RichText::new("Variables").text_style(TextStyle::Heading))
                 .default_open(true)
                 .show(ui, |ui| {
-                    for s in data.streams.iter() {
-                        let mut one_shown = false;
-                        for e in s.entries.iter() {
-                            one_shown |= highlight_matches(ui, &e.name, query);
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut cfg.hide_constant, "Hide constant channels");
+                        ui.toggle_value(&mut cfg.only_used_channels, "Used in tab")
+                            .on_hover_text("Only show channels used by the current tab");
+                    });
+
+                    let tab_cfg = &cfg.tabs[cfg.selected_tab];
+                    let used = |name: &str| {
+                        (tab_cfg.plots.iter()).any(|p| {
+                            eval::references(&p.expr.x, name) || eval::references(&p.expr.y, name)
+                        })
+                    };
+                    let weak_color = ui.visuals().weak_text_color();
+                    let streams = data.streams.iter().zip(data.channels.iter());
+                    for (i, (s, channels)) in streams.enumerate() {
+                        let shown = (s.entries.iter().zip(channels.iter()))
+                            .filter(|(_, c)| !(c.constant && cfg.hide_constant))
+                            .filter(|(e, _)| !cfg.only_used_channels || used(&e.name))
+                            .filter(|(e, _)| {
+                                let alias = aliases::alias_of(&cfg.channel_aliases, &e.name);
+                                e.name.to_lowercase().contains(query)
+                                    || alias.is_some_and(|a| a.to_lowercase().contains(query))
+                            })
+                            .collect::<Vec<_>>();
+                        if shown.is_empty() && (!query.is_empty() || cfg.only_used_channels) {
+                            continue;
                         }
-                        if one_shown {
-                            ui.add_space(10.0);
+
+                        let rates = &data.rates[i];
+                        let title = format!(
+                            "Stream {} ({}, {})  {}/{}",
+                            i + 1,
+                            s.format,
+                            format_rate(rates),
+                            shown.len(),
+                            s.entries.len()
+                        );
+                        let header = CollapsingHeader::new(title)
+                            .id_source(("help_stream", i))
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                for (e, c) in shown {
+                                    ui.horizontal(|ui| {
+                                        if c.constant {
+                                            highlight_matches_colored(
+                                                ui, &e.name, query, weak_color,
+                                            );
+                                        } else {
+                                            highlight_matches(ui, &e.name, query);
+                                        }
+                                        let table = &cfg.channel_aliases;
+                                        if let Some(alias) = aliases::alias_of(table, &e.name) {
+                                            ui.label(RichText::new(alias).italics());
+                                        }
+                                        let unit = units::entry_unit(e, &cfg.channel_units);
+                                        if let Some(unit) = unit {
+                                            ui.label(
+                                                RichText::new(format!("[{unit}]"))
+                                                    .monospace()
+                                                    .color(weak_color),
+                                            );
+                                        }
+                                    });
+                                }
+                            });
+                        if rates.len() > 1 {
+                            (header.header_response).on_hover_text(format_rate_changes(s, rates));
                         }
                     }
                 });
 
+            CollapsingHeader::new(RichText::new("Session").text_style(TextStyle::Heading))
+                .default_open(true)
+                .show(ui, |ui| {
+                    for (key, value) in data.session.values.iter() {
+                        let text = format!("${{{key}}} = {value}");
+                        highlight_matches(ui, &text, query);
+                    }
+                });
+
             CollapsingHeader::new(RichText::new("Constants").text_style(TextStyle::Heading))
                 .default_open(true)
                 .show(ui, |ui| {
@@ -861,6 +4994,14 @@ fn help_sidebar(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
                     }
                 });
 
+            CollapsingHeader::new(RichText::new("Filters").text_style(TextStyle::Heading))
+                .default_open(true)
+                .show(ui, |ui| {
+                    for s in eval::FILTER_SIGNATURES {
+                        highlight_matches(ui, s, query);
+                    }
+                });
+
             CollapsingHeader::new(RichText::new("Functions").text_style(TextStyle::Heading))
                 .default_open(true)
                 .show(ui, |ui| {
@@ -907,6 +5048,11 @@ fn help_sidebar(ui: &mut Ui, data: &mut PlotData, cfg: &mut Config) {
 }
 
 fn highlight_matches(ui: &mut Ui, text: &str, query: &str) -> bool {
+    let color = ui.visuals().text_color();
+    highlight_matches_colored(ui, text, query, color)
+}
+
+fn highlight_matches_colored(ui: &mut Ui, text: &str, query: &str, color: Color32) -> bool {
     if query.is_empty() {
         let label = Label::new(WidgetText::LayoutJob(LayoutJob {
             text: text.into(),
@@ -914,7 +5060,7 @@ fn highlight_matches(ui: &mut Ui, text: &str, query: &str) -> bool {
                 byte_range: 0..text.len(),
                 format: TextFormat {
                     font_id: TextStyle::Monospace.resolve(ui.style()),
-                    color: ui.visuals().text_color(),
+                    color,
                     ..Default::default()
                 },
                 leading_space: 0.0,
@@ -936,7 +5082,7 @@ fn highlight_matches(ui: &mut Ui, text: &str, query: &str) -> bool {
                     byte_range: 0..pos,
                     format: TextFormat {
                         font_id: TextStyle::Monospace.resolve(ui.style()),
-                        color: ui.visuals().text_color(),
+                        color,
                         ..Default::default()
                     },
                     leading_space: 0.0,
@@ -945,7 +5091,7 @@ fn highlight_matches(ui: &mut Ui, text: &str, query: &str) -> bool {
                     byte_range: pos..pos + query.len(),
                     format: TextFormat {
                         font_id: TextStyle::Monospace.resolve(ui.style()),
-                        color: ui.visuals().text_color(),
+                        color,
                         background: hl_color,
                         ..Default::default()
                     },
@@ -955,7 +5101,7 @@ fn highlight_matches(ui: &mut Ui, text: &str, query: &str) -> bool {
                     byte_range: pos + query.len()..text.len(),
                     format: TextFormat {
                         font_id: TextStyle::Monospace.resolve(ui.style()),
-                        color: ui.visuals().text_color(),
+                        color,
                         ..Default::default()
                     },
                     leading_space: 0.0,
@@ -972,6 +5118,36 @@ fn highlight_matches(ui: &mut Ui, text: &str, query: &str) -> bool {
     true
 }
 
+fn run_b_name(name: &str) -> String {
+    format!("{name} (B)")
+}
+
+/// Labels of the codes if the expression is just an enum channel.
+fn enum_labels(streams: &[LogStream], expr: &str) -> Option<Vec<String>> {
+    (streams.iter().flat_map(|s| s.entries.iter()))
+        .find(|e| e.name == expr && !e.labels.is_empty())
+        .map(|e| e.labels.clone())
+}
+
+/// Holds each value until the next sample, so states are drawn as steps.
+fn to_steps(values: Vec<PlotPoint>) -> Vec<PlotPoint> {
+    let mut steps = Vec::with_capacity(2 * values.len());
+    for (i, v) in values.iter().enumerate() {
+        if i > 0 {
+            steps.push(PlotPoint::new(v.x, values[i - 1].y));
+        }
+        steps.push(*v);
+    }
+    steps
+}
+
+fn legend_name(name: &str, unit: Option<&str>) -> String {
+    match unit {
+        Some(unit) => format!("{name} [{unit}]"),
+        None => name.to_string(),
+    }
+}
+
 fn find_plot_range(values: &[PlotPoint], x_min: f64, x_max: f64) -> std::ops::Range<usize> {
     let min = values.binary_search_by(|v| v.x.total_cmp(&x_min));
     let min = match min {
@@ -987,7 +5163,97 @@ fn find_plot_range(values: &[PlotPoint], x_min: f64, x_max: f64) -> std::ops::Ra
     min..max
 }
 
-fn subsample_plot(values: &[PlotPoint], chunk_size: usize) -> Vec<PlotPoint> {
+/// Sample rate of a stream, the range if it changes mid-file.
+fn format_rate(segments: &[RateSegment]) -> String {
+    let rates = segments.iter().filter_map(RateSegment::rate);
+    let min = rates.clone().min_by(f64::total_cmp);
+    let max = rates.max_by(f64::total_cmp);
+    match (min, max) {
+        (Some(min), Some(max)) if (max - min) >= 0.5 => format!("{min:.0}-{max:.0} Hz"),
+        (Some(rate), _) => format!("{rate:.0} Hz"),
+        _ => "? Hz".to_string(),
+    }
+}
+
+fn format_rate_changes(stream: &LogStream, segments: &[RateSegment]) -> String {
+    let mut text = "Sample rate changes:".to_string();
+    for seg in segments.iter() {
+        let time = stream.time[seg.start] as f64 / 1000.0;
+        let rate = seg.rate().map_or("?".to_string(), |r| format!("{r:.1}"));
+        let _ = write!(text, "\n{}  {rate} Hz", format_time(time));
+    }
+    text
+}
+
+fn default_gap_periods() -> u32 {
+    DEFAULT_GAP_PERIODS
+}
+
+fn default_endurance_distance() -> f64 {
+    energy::DEFAULT_ENDURANCE_DISTANCE
+}
+
+fn default_grid_columns() -> usize {
+    DEFAULT_GRID_COLUMNS
+}
+
+/// Splits the series where the logger stopped. A sample interval is a gap if it's longer than
+/// `periods` times the intervals before and after it, so a change of the sample rate isn't one.
+fn split_at_gaps(values: &[PlotPoint], periods: u32) -> Vec<&[PlotPoint]> {
+    if periods == 0 || values.len() < 3 {
+        return vec![values];
+    }
+
+    let dx = |i: usize| values[i].x - values[i - 1].x;
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in 1..values.len() {
+        let before = if i > 1 { dx(i - 1) } else { 0.0 };
+        let after = if i + 1 < values.len() { dx(i + 1) } else { 0.0 };
+        let period = before.max(after);
+        if period > 0.0 && dx(i) > period * periods as f64 {
+            parts.push(&values[start..i]);
+            start = i;
+        }
+    }
+    parts.push(&values[start..]);
+    parts
+}
+
+/// Sorts and merges overlapping ranges.
+fn merge_ranges(mut ranges: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    ranges.retain(|(start, end)| start < end);
+    ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut merged: Vec<(f64, f64)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Number of points reduced to one pixel column, based on the average sample interval of the
+/// visible part of the series, since derived series and streams can be sampled at different
+/// rates and the logger can change its rate mid-file.
+fn chunk_size(values: &[PlotPoint], x_range: f64, num_pixels: f32) -> usize {
+    let [first, .., last] = values else {
+        return 1;
+    };
+    let dt = (last.x - first.x) / (values.len() - 1) as f64;
+    if dt.is_nan() || dt <= 0.0 {
+        return 1;
+    }
+    let steps = x_range / dt;
+    ((steps / num_pixels as f64) as usize).max(1)
+}
+
+fn subsample_plot(
+    values: &[PlotPoint],
+    chunk_size: usize,
+    subsampling: Subsampling,
+) -> Vec<PlotPoint> {
     if chunk_size == 1 {
         return values.to_vec();
     }
@@ -996,11 +5262,10 @@ fn subsample_plot(values: &[PlotPoint], chunk_size: usize) -> Vec<PlotPoint> {
         return values.to_vec();
     };
 
-    let middle = middle.chunks(chunk_size).map(|c| {
-        let x = c.iter().map(|p| p.x).sum::<f64>() / c.len() as f64;
-        let y = c.iter().map(|p| p.y).sum::<f64>() / c.len() as f64;
-        PlotPoint { x, y }
-    });
+    let middle = match subsampling {
+        Subsampling::Mean => middle.chunks(chunk_size).map(chunk_mean).collect(),
+        Subsampling::Lttb => lttb(*first, middle, *last, chunk_size),
+    };
 
     Some(*first)
         .into_iter()
@@ -1008,3 +5273,124 @@ fn subsample_plot(values: &[PlotPoint], chunk_size: usize) -> Vec<PlotPoint> {
         .chain(Some(*last))
         .collect()
 }
+
+/// Fills the area between two lines with the same x values, leaving out segments where either
+/// isn't finite.
+fn fill_between(
+    ui: &mut PlotUi,
+    lower: &[PlotPoint],
+    upper: &[PlotPoint],
+    fill: Color32,
+    name: &str,
+) {
+    for i in 1..lower.len().min(upper.len()) {
+        let quad = vec![
+            [lower[i - 1].x, lower[i - 1].y],
+            [lower[i].x, lower[i].y],
+            [upper[i].x, upper[i].y],
+            [upper[i - 1].x, upper[i - 1].y],
+        ];
+        if quad.iter().flatten().any(|v| !v.is_finite()) {
+            continue;
+        }
+        let polygon = Polygon::new(quad)
+            .stroke(Stroke::NONE)
+            .fill_color(fill)
+            .name(name);
+        ui.polygon(polygon);
+    }
+}
+
+/// Min, mean and max of all series at the x values of the first one, reduced to the min, mean
+/// and max of each chunk. The other series are interpolated.
+fn band_values(
+    first: &[PlotPoint],
+    series: &[&[PlotPoint]],
+    chunk_size: usize,
+) -> [Vec<PlotPoint>; 3] {
+    let mut band = [Vec::new(), Vec::new(), Vec::new()];
+    for chunk in first.chunks(chunk_size) {
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        let mut sum = 0.0;
+        let mut count = 0;
+        for p in chunk.iter() {
+            let values = std::iter::once(Some(p.y))
+                .chain(series.iter().skip(1).map(|s| interpolate(s, p.x)))
+                .flatten()
+                .filter(|v| v.is_finite());
+            for v in values {
+                min = min.min(v);
+                max = max.max(v);
+                sum += v;
+                count += 1;
+            }
+        }
+        if count == 0 {
+            continue;
+        }
+
+        let x = chunk.iter().map(|p| p.x).sum::<f64>() / chunk.len() as f64;
+        band[0].push(PlotPoint::new(x, min));
+        band[1].push(PlotPoint::new(x, sum / count as f64));
+        band[2].push(PlotPoint::new(x, max));
+    }
+    band
+}
+
+/// Cumulative sums of all series at the x values of the first one, reduced to the mean of each
+/// chunk. The other series are interpolated, values that aren't finite count as zero so the
+/// stack stays closed.
+fn stack_values(
+    first: &[PlotPoint],
+    series: &[&[PlotPoint]],
+    chunk_size: usize,
+) -> Vec<Vec<PlotPoint>> {
+    let mut tops = vec![Vec::new(); series.len()];
+    for chunk in first.chunks(chunk_size) {
+        let x = chunk.iter().map(|p| p.x).sum::<f64>() / chunk.len() as f64;
+        let mut sum = 0.0;
+        for (k, s) in series.iter().enumerate() {
+            let values = (chunk.iter()).map(|p| match k {
+                0 => p.y,
+                _ => interpolate(s, p.x).unwrap_or(f64::NAN),
+            });
+            let mean = values.filter(|v| v.is_finite()).sum::<f64>() / chunk.len() as f64;
+            sum += mean;
+            tops[k].push(PlotPoint::new(x, sum));
+        }
+    }
+    tops
+}
+
+fn chunk_mean(chunk: &[PlotPoint]) -> PlotPoint {
+    let x = chunk.iter().map(|p| p.x).sum::<f64>() / chunk.len() as f64;
+    let y = chunk.iter().map(|p| p.y).sum::<f64>() / chunk.len() as f64;
+    PlotPoint { x, y }
+}
+
+/// Picks the point of each chunk that forms the largest triangle with the previously picked
+/// point and the mean of the next chunk.
+fn lttb(
+    first: PlotPoint,
+    middle: &[PlotPoint],
+    last: PlotPoint,
+    chunk_size: usize,
+) -> Vec<PlotPoint> {
+    let chunks = middle.chunks(chunk_size).collect::<Vec<_>>();
+    let mut points = Vec::with_capacity(chunks.len());
+    let mut prev = first;
+    for (i, chunk) in chunks.iter().enumerate() {
+        let next = chunks.get(i + 1).map_or(last, |c| chunk_mean(c));
+        let area = |p: &PlotPoint| {
+            ((prev.x - next.x) * (p.y - prev.y) - (prev.x - p.x) * (next.y - prev.y)).abs()
+        };
+        let picked = (chunk.iter())
+            .max_by(|a, b| area(a).total_cmp(&area(b)))
+            .copied()
+            .unwrap_or(prev);
+        points.push(picked);
+        prev = picked;
+    }
+    points
+}
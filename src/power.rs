@@ -0,0 +1,275 @@
+use std::sync::Arc;
+
+use egui::{Button, DragValue, Grid, ScrollArea, TextEdit, Ui};
+use egui_plot::PlotPoint;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{self, Job};
+use crate::data::LogStream;
+use crate::eval::{self, Expr, Progress, Timing};
+use crate::library::UserExpr;
+use crate::plot::{Config, ERROR_RED};
+use crate::util;
+
+/// The power drawn from the accumulator, which must stay below the limit. The defaults are the
+/// Formula Student rules: 80 kW, exceeded for at most 100 ms and a 500 ms moving average.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PowerLimit {
+    /// expression of the accumulator current in A
+    pub current: String,
+    /// expression of the accumulator voltage in V
+    pub voltage: String,
+    /// in kW
+    pub limit: f64,
+    /// in ms, longest time the power may exceed the limit
+    pub max_duration: u32,
+    /// in ms, window of the moving average that must stay below the limit
+    pub average_window: u32,
+}
+
+impl Default for PowerLimit {
+    fn default() -> Self {
+        Self {
+            current: String::new(),
+            voltage: String::new(),
+            limit: 80.0,
+            max_duration: 100,
+            average_window: 500,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// the power exceeded the limit for longer than the maximum duration
+    Power,
+    /// the moving average exceeded the limit
+    Average,
+}
+
+impl ViolationKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Power => "Power",
+            Self::Average => "Average",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PowerViolation {
+    pub kind: ViolationKind,
+    /// time in seconds
+    pub start: f64,
+    /// time in seconds
+    pub end: f64,
+    /// in kW, the highest power or average in the interval
+    pub peak: f64,
+}
+
+pub struct PowerReport {
+    /// in kW
+    pub max_power: f64,
+    /// in kW
+    pub max_average: f64,
+    /// sorted by their start
+    pub violations: Vec<PowerViolation>,
+}
+
+/// State of the power limit window.
+#[derive(Default)]
+pub struct PowerCheck {
+    pub report: Option<PowerReport>,
+    pub error: Option<String>,
+    pub job: Option<Job<Result<PowerReport, String>>>,
+}
+
+impl PowerCheck {
+    /// Checks the session against the power limit on a worker thread.
+    pub fn start(&mut self, cfg: &Config, streams: &Arc<[LogStream]>) {
+        let limit = cfg.power_limit.clone();
+        let library = cfg.expr_library.clone();
+        let timing = cfg.timing();
+        let streams = Arc::clone(streams);
+        self.job = Some(Job::spawn(move |progress| {
+            check(&limit, &library, &timing, &streams, progress)
+        }));
+    }
+}
+
+pub enum PowerAction {
+    Check,
+    /// jump to the violation at the index
+    JumpTo(usize),
+}
+
+/// Computes the power from the current and voltage and finds all intervals that violate the
+/// limit.
+pub fn check(
+    limit: &PowerLimit,
+    library: &[UserExpr],
+    timing: &Timing,
+    streams: &Arc<[LogStream]>,
+    progress: &Progress,
+) -> Result<PowerReport, String> {
+    let y = format!("({}) * ({}) / 1000", limit.current, limit.voltage);
+    let expr = Expr::new("time", y);
+    let power = eval::eval(&expr, Arc::clone(streams), library, timing, progress).map_err(|e| {
+        match e.y.or(e.x) {
+            Some(e) => e.to_string(),
+            None => "invalid expression".to_string(),
+        }
+    })?;
+    let power = (power.into_iter())
+        .filter(|p| p.y.is_finite())
+        .collect::<Vec<_>>();
+    let average = moving_average(&power, limit.average_window as f64 / 1000.0);
+
+    let max_duration = limit.max_duration as f64 / 1000.0;
+    let mut violations = exceeding(&power, limit.limit, ViolationKind::Power);
+    violations.retain(|v| v.end - v.start > max_duration);
+    violations.extend(exceeding(&average, limit.limit, ViolationKind::Average));
+    violations.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let max = |points: &[PlotPoint]| points.iter().map(|p| p.y).fold(f64::NAN, f64::max);
+    Ok(PowerReport {
+        max_power: max(&power),
+        max_average: max(&average),
+        violations,
+    })
+}
+
+/// Mean of the samples in the window ending at each sample.
+fn moving_average(points: &[PlotPoint], window: f64) -> Vec<PlotPoint> {
+    let mut start = 0;
+    let mut sum = 0.0;
+    let mut average = Vec::with_capacity(points.len());
+    for p in points.iter() {
+        sum += p.y;
+        while start < average.len() && points[start].x <= p.x - window {
+            sum -= points[start].y;
+            start += 1;
+        }
+        let len = average.len() + 1 - start;
+        average.push(PlotPoint::new(p.x, sum / len as f64));
+    }
+    average
+}
+
+/// Intervals in which the values are above the limit.
+fn exceeding(points: &[PlotPoint], limit: f64, kind: ViolationKind) -> Vec<PowerViolation> {
+    let mut violations = Vec::new();
+    let mut current: Option<PowerViolation> = None;
+    for p in points.iter() {
+        match (&mut current, p.y > limit) {
+            (None, true) => {
+                current = Some(PowerViolation {
+                    kind,
+                    start: p.x,
+                    end: p.x,
+                    peak: p.y,
+                });
+            }
+            (Some(v), true) => {
+                v.end = p.x;
+                v.peak = v.peak.max(p.y);
+            }
+            (Some(_), false) => violations.extend(current.take()),
+            (None, false) => (),
+        }
+    }
+    violations.extend(current);
+    violations
+}
+
+pub fn power_limit_dialog(
+    ui: &mut Ui,
+    check: &PowerCheck,
+    limit: &mut PowerLimit,
+) -> Option<PowerAction> {
+    let mut action = None;
+
+    Grid::new("power_limit").num_columns(2).show(ui, |ui| {
+        ui.label("Current");
+        let current = TextEdit::singleline(&mut limit.current)
+            .code_editor()
+            .hint_text("in A");
+        ui.add(current);
+        ui.end_row();
+
+        ui.label("Voltage");
+        let voltage = TextEdit::singleline(&mut limit.voltage)
+            .code_editor()
+            .hint_text("in V");
+        ui.add(voltage);
+        ui.end_row();
+
+        ui.label("Limit");
+        ui.add(DragValue::new(&mut limit.limit).speed(0.1).suffix(" kW"));
+        ui.end_row();
+
+        ui.label("Max duration")
+            .on_hover_text("Longest time the power may exceed the limit");
+        ui.add(DragValue::new(&mut limit.max_duration).suffix(" ms"));
+        ui.end_row();
+
+        ui.label("Average window")
+            .on_hover_text("The moving average must always stay below the limit");
+        ui.add(DragValue::new(&mut limit.average_window).suffix(" ms"));
+        ui.end_row();
+    });
+
+    ui.separator();
+
+    let valid = !limit.current.trim().is_empty() && !limit.voltage.trim().is_empty();
+    let can_check = valid && check.job.is_none();
+    if ui.add_enabled(can_check, Button::new("Check")).clicked() {
+        action = Some(PowerAction::Check);
+    }
+    if let Some(job) = &check.job {
+        app::job_progress(ui, job);
+    }
+    if let Some(e) = &check.error {
+        ui.colored_label(ERROR_RED, e);
+    }
+
+    let Some(report) = &check.report else {
+        return action;
+    };
+    ui.separator();
+    ui.label(format!("Peak power: {:.2} kW", report.max_power));
+    ui.label(format!("Peak average: {:.2} kW", report.max_average));
+    if report.violations.is_empty() {
+        ui.label("No violations found");
+        return action;
+    }
+
+    ScrollArea::vertical()
+        .max_height(400.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            Grid::new("power_violations")
+                .striped(true)
+                .num_columns(3)
+                .show(ui, |ui| {
+                    ui.strong("Check");
+                    ui.strong("Time");
+                    ui.strong("Peak");
+                    ui.end_row();
+
+                    for (i, v) in report.violations.iter().enumerate() {
+                        ui.label(v.kind.name());
+                        let start = util::format_time(v.start);
+                        let end = util::format_time(v.end);
+                        let time = ui.link(format!("{start} - {end}"));
+                        if time.on_hover_text("Jump to the violation").clicked() {
+                            action = Some(PowerAction::JumpTo(i));
+                        }
+                        ui.monospace(format!("{:.2} kW", v.peak));
+                        ui.end_row();
+                    }
+                });
+        });
+
+    action
+}
@@ -0,0 +1,105 @@
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::fs::Files;
+use crate::measure::Measurement;
+use crate::plot::Config;
+
+pub const PROJECT_EXTENSION: &str = "s3proj";
+
+/// A shareable analysis, bundling the config with the selected files in their order and the
+/// current view. File paths are stored relative to the project file when possible, so the project
+/// can be opened on other machines with the same directory layout.
+#[derive(Serialize, Deserialize)]
+pub struct Project {
+    pub config: Config,
+    pub files: Files,
+    #[serde(default)]
+    pub view: ViewState,
+}
+
+/// The runtime state of the plots, that isn't part of the config.
+#[derive(Default, Serialize, Deserialize)]
+pub struct ViewState {
+    pub cursor: Option<f64>,
+    pub tabs: Vec<TabView>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct TabView {
+    pub x_bounds: Option<RangeInclusive<f64>>,
+    pub measurement: Option<Measurement>,
+}
+
+impl ViewState {
+    pub fn from_config(config: &Config) -> Self {
+        let tabs = (config.tabs.iter())
+            .map(|t| TabView {
+                x_bounds: t.x_bounds.clone(),
+                measurement: t.measurement,
+            })
+            .collect();
+        Self {
+            cursor: config.cursor,
+            tabs,
+        }
+    }
+
+    /// Restores the view, the time plots are zoomed to the saved range in the next frame.
+    pub fn apply(self, config: &mut Config) {
+        config.cursor = self.cursor;
+        for (tab, view) in config.tabs.iter_mut().zip(self.tabs) {
+            tab.zoom_to = view.x_bounds;
+            tab.measurement = view.measurement;
+        }
+    }
+}
+
+pub fn save(path: &Path, config: &Config, files: &Files) -> anyhow::Result<()> {
+    let base = path.parent().unwrap_or("".as_ref());
+    let files = Files {
+        dir: relative_to(&files.dir, base),
+        items: files.items.iter().map(|f| relative_to(f, base)).collect(),
+    };
+
+    #[derive(Serialize)]
+    struct ProjectRef<'a> {
+        config: &'a Config,
+        files: &'a Files,
+        view: ViewState,
+    }
+    let project = ProjectRef {
+        config,
+        files: &files,
+        view: ViewState::from_config(config),
+    };
+
+    let string = ron::ser::to_string_pretty(&project, PrettyConfig::default())?;
+    std::fs::write(path, string).with_context(|| format!("writing {}", path.display()))?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> anyhow::Result<Project> {
+    let string =
+        std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let mut project: Project = ron::from_str(&string)?;
+
+    let base = path.parent().unwrap_or("".as_ref());
+    project.files.dir = base.join(&project.files.dir);
+    for f in project.files.items.iter_mut() {
+        *f = base.join(&*f);
+    }
+
+    Ok(project)
+}
+
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    match path.strip_prefix(base) {
+        Ok(p) => p.to_path_buf(),
+        Err(_) => path.to_path_buf(),
+    }
+}
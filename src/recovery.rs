@@ -0,0 +1,151 @@
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use chrono::Local;
+use egui::{Button, Color32, RichText, ScrollArea, TextEdit, Ui};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::fs::Files;
+use crate::plot::Config;
+use crate::APP_NAME;
+
+const RECOVERY_DIR: &str = "recovery";
+const STATE_FILE: &str = "state.ron";
+const LOG_FILE: &str = "crash.log";
+
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The last serialized [`RecoveryState`]. The panic hook has no access to the app, so the state
+/// is serialized periodically on the ui thread and only written to disk when a panic occurs.
+static SNAPSHOT: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Serialize, Deserialize)]
+pub struct RecoveryState {
+    pub config: Config,
+    pub files: Option<Files>,
+}
+
+/// The dump of a previous run that crashed.
+pub struct Recovery {
+    /// `None` if the crash happened before the first snapshot
+    pub state: Option<RecoveryState>,
+    pub log: String,
+}
+
+fn recovery_dir() -> Option<PathBuf> {
+    Some(eframe::storage_dir(APP_NAME)?.join(RECOVERY_DIR))
+}
+
+/// Dumps the last snapshot and a diagnostic log to the recovery directory on panic, before
+/// running the default hook.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Err(e) = write_dump(&info.to_string()) {
+            eprintln!("error writing crash dump: {e:#}");
+        }
+        default_hook(info);
+    }));
+}
+
+fn write_dump(message: &str) -> anyhow::Result<()> {
+    let dir = recovery_dir().context("no storage directory")?;
+    std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+
+    // the panic might have happened while the snapshot was locked
+    let snapshot = SNAPSHOT.try_lock().ok().and_then(|s| s.clone());
+    if let Some(snapshot) = snapshot {
+        std::fs::write(dir.join(STATE_FILE), snapshot)?;
+    }
+
+    let mut log = String::new();
+    _ = writeln!(log, "time: {}", Local::now().format("%Y-%m-%d %H:%M:%S"));
+    _ = writeln!(log, "version: {}", env!("CARGO_PKG_VERSION"));
+    let thread = std::thread::current();
+    _ = writeln!(log, "thread: {}", thread.name().unwrap_or("<unnamed>"));
+    _ = writeln!(log, "{message}");
+    _ = writeln!(log);
+    _ = writeln!(log, "{}", std::backtrace::Backtrace::force_capture());
+    std::fs::write(dir.join(LOG_FILE), log)?;
+
+    Ok(())
+}
+
+/// Serializes the state, that is dumped if a panic occurs.
+pub fn snapshot(config: &Config, files: Option<&Files>) {
+    #[derive(Serialize)]
+    struct RecoveryStateRef<'a> {
+        config: &'a Config,
+        files: Option<&'a Files>,
+    }
+    let state = RecoveryStateRef { config, files };
+
+    if let Ok(string) = ron::ser::to_string_pretty(&state, PrettyConfig::default()) {
+        if let Ok(mut snapshot) = SNAPSHOT.lock() {
+            *snapshot = Some(string);
+        }
+    }
+}
+
+/// Reads the dump of a previous run, `None` if it didn't crash.
+pub fn load() -> Option<Recovery> {
+    let dir = recovery_dir()?;
+    let log = std::fs::read_to_string(dir.join(LOG_FILE)).ok()?;
+    let state =
+        (std::fs::read_to_string(dir.join(STATE_FILE)).ok()).and_then(|s| ron::from_str(&s).ok());
+    Some(Recovery { state, log })
+}
+
+/// Removes the dump, so the recovery prompt isn't shown again.
+pub fn discard() -> std::io::Result<()> {
+    match recovery_dir() {
+        Some(dir) if dir.exists() => std::fs::remove_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+/// Returns `Some(true)` if the state should be restored and `Some(false)` if it should be
+/// discarded.
+pub fn recovery_dialog(ui: &mut Ui, recovery: &Recovery) -> Option<bool> {
+    ui.label("s3plot crashed during the last session.");
+    match &recovery.state {
+        Some(state) => {
+            let num_files = state.files.as_ref().map_or(0, |f| f.items.len());
+            ui.label(format!(
+                "The session with {} tabs and {num_files} files can be restored.",
+                state.config.tabs.len()
+            ));
+        }
+        None => {
+            ui.label(RichText::new("No session state was saved.").color(Color32::YELLOW));
+        }
+    }
+
+    ui.add_space(10.0);
+    ui.collapsing("Diagnostic log", |ui| {
+        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            let mut log = recovery.log.as_str();
+            ui.add(TextEdit::multiline(&mut log).code_editor());
+        });
+        if ui.button("Copy log").clicked() {
+            ui.output_mut(|o| o.copied_text = recovery.log.clone());
+        }
+    });
+    ui.add_space(10.0);
+
+    let mut action = None;
+    ui.horizontal(|ui| {
+        let restore = ui.add_enabled(recovery.state.is_some(), Button::new("Restore"));
+        if restore.clicked() {
+            action = Some(true);
+        }
+        if ui.button("Discard").clicked() {
+            action = Some(false);
+        }
+    });
+    action
+}
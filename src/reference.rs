@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use anyhow::Context as _;
+use cods::{BuiltinConst, BuiltinFun, DataType, SignatureKind, UserFacing};
+use serde::Serialize;
+
+use crate::data::LogStream;
+use crate::eval;
+use crate::fs;
+use crate::library::UserExpr;
+use crate::units::{self, ChannelUnit};
+
+pub const REFERENCE_EXTENSION: &str = "json";
+const CLI_USAGE: &str = "usage: s3plot reference [session_dir]";
+
+/// Everything the help sidebar shows, in a machine readable form.
+#[derive(Serialize)]
+pub struct Reference {
+    pub variables: Vec<Variable>,
+    pub library: Vec<UserExpr>,
+    pub constants: Vec<Constant>,
+    pub datatypes: Vec<String>,
+    pub filters: Vec<String>,
+    pub functions: Vec<Function>,
+}
+
+#[derive(Serialize)]
+pub struct Variable {
+    pub name: String,
+    pub stream: usize,
+    /// datatype in the log file, all variables are floats in expressions
+    pub kind: &'static str,
+    pub unit: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Constant {
+    pub name: String,
+    pub datatype: String,
+    pub value: String,
+}
+
+#[derive(Serialize)]
+pub struct Function {
+    pub name: String,
+    pub signatures: Vec<Signature>,
+}
+
+#[derive(Serialize)]
+pub struct Signature {
+    pub params: Vec<String>,
+    /// how often the last parameter may be repeated, `one`, `zero_or_more` or `one_or_more`
+    pub repetition: &'static str,
+    pub return_type: Option<String>,
+}
+
+impl Reference {
+    pub fn new(streams: &[LogStream], library: &[UserExpr], channel_units: &[ChannelUnit]) -> Self {
+        let variables = (streams.iter().enumerate())
+            .flat_map(|(i, s)| s.entries.iter().map(move |e| (i, e)))
+            .map(|(stream, e)| Variable {
+                name: e.name.clone(),
+                stream,
+                kind: e.kind.name(),
+                unit: units::entry_unit(e, channel_units).map(str::to_string),
+            })
+            .collect();
+
+        let constants = (BuiltinConst::members().iter())
+            .map(|c| Constant {
+                name: c.to_string(),
+                datatype: c.data_type().to_string(),
+                value: c.val().to_string(),
+            })
+            .collect();
+
+        let functions = (BuiltinFun::members().iter())
+            .filter_map(|f| {
+                let signatures: &[(_, _)] = match f.signatures() {
+                    SignatureKind::Normal(s) => s,
+                    SignatureKind::Spill(_) => return None,
+                };
+                let signatures = (signatures.iter())
+                    .map(|(_, s)| Signature {
+                        params: s.params.iter().map(|d| d.to_string()).collect(),
+                        repetition: match s.repetition {
+                            cods::Repetition::One => "one",
+                            cods::Repetition::ZeroOrMore => "zero_or_more",
+                            cods::Repetition::OneOrMore => "one_or_more",
+                        },
+                        return_type: (s.return_type != DataType::Unit)
+                            .then(|| s.return_type.to_string()),
+                    })
+                    .collect();
+                Some(Function {
+                    name: f.to_string(),
+                    signatures,
+                })
+            })
+            .collect();
+
+        Self {
+            variables,
+            library: library.to_vec(),
+            constants,
+            datatypes: DataType::members().iter().map(|d| d.to_string()).collect(),
+            filters: eval::FILTER_SIGNATURES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            functions,
+        }
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+pub fn save(path: &Path, reference: &Reference) -> anyhow::Result<()> {
+    let string = reference.to_json()?;
+    std::fs::write(path, string).with_context(|| format!("writing {}", path.display()))?;
+    Ok(())
+}
+
+/// Prints the reference as JSON, with the variables of the session if a directory is passed.
+/// Returns the exit code, `0` on success and `2` on usage or load errors.
+pub fn cli(args: &[String]) -> i32 {
+    let res = (|| -> anyhow::Result<String> {
+        let streams = match args {
+            [] => Vec::new().into(),
+            [dir] => fs::load_session(dir.into())?,
+            _ => anyhow::bail!("{CLI_USAGE}"),
+        };
+        Reference::new(&streams, &[], &[]).to_json()
+    })();
+
+    match res {
+        Ok(json) => {
+            println!("{json}");
+            0
+        }
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            2
+        }
+    }
+}
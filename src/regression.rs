@@ -0,0 +1,351 @@
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use egui::{Button, Color32, ComboBox, DragValue, Grid, RichText, ScrollArea, TextEdit, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{self, Job};
+use crate::data::LogStream;
+use crate::eval::{self, Expr, Progress, Timing};
+use crate::fs;
+use crate::library::UserExpr;
+use crate::plot::Config;
+
+const PASS_GREEN: Color32 = Color32::from_rgb(0x56, 0xc0, 0x56);
+const FAIL_RED: Color32 = Color32::from_rgb(0xf0, 0x56, 0x56);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Aggregate {
+    #[default]
+    Mean,
+    Min,
+    Max,
+    Last,
+}
+
+impl Aggregate {
+    pub const ALL: [Self; 4] = [Self::Mean, Self::Min, Self::Max, Self::Last];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mean => "mean",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Last => "last",
+        }
+    }
+
+    pub fn apply(&self, values: impl Iterator<Item = f64>) -> Option<f64> {
+        let mut values = values.filter(|v| v.is_finite()).peekable();
+        values.peek()?;
+        let value = match self {
+            Self::Mean => {
+                let (sum, count) = values.fold((0.0, 0), |(s, c), v| (s + v, c + 1));
+                sum / count as f64
+            }
+            Self::Min => values.fold(f64::INFINITY, f64::min),
+            Self::Max => values.fold(f64::NEG_INFINITY, f64::max),
+            Self::Last => values.last()?,
+        };
+        Some(value)
+    }
+}
+
+/// An expression evaluated over time, aggregated to a single value that may only differ by the
+/// tolerance between two sessions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ValidationCheck {
+    pub name: String,
+    pub expr: String,
+    pub aggregate: Aggregate,
+    /// maximum absolute difference
+    pub tolerance: f64,
+}
+
+impl ValidationCheck {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            expr: String::new(),
+            aggregate: Aggregate::Mean,
+            tolerance: 0.0,
+        }
+    }
+}
+
+pub struct CheckResult {
+    pub name: String,
+    pub before: Result<f64, String>,
+    pub after: Result<f64, String>,
+    pub tolerance: f64,
+}
+
+impl CheckResult {
+    pub fn delta(&self) -> Option<f64> {
+        match (&self.before, &self.after) {
+            (Ok(b), Ok(a)) => Some(a - b),
+            _ => None,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.delta().is_some_and(|d| d.abs() <= self.tolerance)
+    }
+}
+
+/// State of the regression test window. The currently loaded data is the session before the
+/// change, the session after the change is loaded from a directory.
+#[derive(Default)]
+pub struct RegressionTest {
+    pub after_dir: Option<PathBuf>,
+    pub after: Option<Arc<[LogStream]>>,
+    pub results: Option<Vec<CheckResult>>,
+    pub job: Option<Job<Vec<CheckResult>>>,
+    pub error: Option<String>,
+}
+
+pub enum RegressionAction {
+    PickAfter,
+    Run,
+}
+
+impl RegressionTest {
+    /// Evaluates the checks over both sessions on a worker thread.
+    pub fn start(&mut self, cfg: &Config, before: &Arc<[LogStream]>) {
+        let Some(after) = &self.after else {
+            return;
+        };
+        let checks = cfg.validation_checks.clone();
+        let library = cfg.expr_library.clone();
+        let timing = cfg.timing();
+        let (before, after) = (Arc::clone(before), Arc::clone(after));
+        self.job = Some(Job::spawn(move |progress| {
+            run(&checks, &library, &timing, &before, &after, progress)
+        }));
+    }
+}
+
+/// Stops early if the evaluation is cancelled.
+pub fn run(
+    checks: &[ValidationCheck],
+    library: &[UserExpr],
+    timing: &Timing,
+    before: &Arc<[LogStream]>,
+    after: &Arc<[LogStream]>,
+    progress: &Progress,
+) -> Vec<CheckResult> {
+    (checks.iter())
+        .take_while(|_| !progress.is_cancelled())
+        .map(|c| CheckResult {
+            name: c.name.clone(),
+            before: evaluate(c, library, timing, before, progress),
+            after: evaluate(c, library, timing, after, progress),
+            tolerance: c.tolerance,
+        })
+        .collect()
+}
+
+fn evaluate(
+    check: &ValidationCheck,
+    library: &[UserExpr],
+    timing: &Timing,
+    streams: &Arc<[LogStream]>,
+    progress: &Progress,
+) -> Result<f64, String> {
+    let expr = Expr::new("time", &check.expr);
+    match eval::eval(&expr, Arc::clone(streams), library, timing, progress) {
+        Ok(values) => {
+            let values = values.iter().map(|p| p.y);
+            (check.aggregate.apply(values)).ok_or_else(|| "no finite values".to_string())
+        }
+        Err(e) => {
+            let error = e.y.or(e.x).map(|e| e.to_string());
+            Err(error.unwrap_or_else(|| "invalid expression".to_string()))
+        }
+    }
+}
+
+/// Plain text report with one line per check and a summary line.
+pub fn format_report(results: &[CheckResult]) -> String {
+    let mut report = String::new();
+    for r in results.iter() {
+        let status = if r.passed() { "PASS" } else { "FAIL" };
+        let before = format_value(&r.before);
+        let after = format_value(&r.after);
+        _ = write!(report, "{status}  {}: {before} -> {after}", r.name);
+        if let Some(delta) = r.delta() {
+            _ = write!(report, " (Δ {delta:.3}, tolerance {})", r.tolerance);
+        }
+        report.push('\n');
+    }
+    let passed = results.iter().filter(|r| r.passed()).count();
+    _ = writeln!(report, "{passed}/{} checks passed", results.len());
+    report
+}
+
+fn format_value(value: &Result<f64, String>) -> String {
+    match value {
+        Ok(v) => format!("{v:.3}"),
+        Err(e) => format!("error: {e}"),
+    }
+}
+
+pub fn regression_dialog(
+    ui: &mut Ui,
+    test: &mut RegressionTest,
+    checks: &mut Vec<ValidationCheck>,
+) -> Option<RegressionAction> {
+    let mut action = None;
+
+    ui.heading("Checks");
+    let mut i = 0;
+    while i < checks.len() {
+        let c = &mut checks[i];
+        let removed = ui
+            .horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut c.name).desired_width(100.0));
+                ui.add(
+                    TextEdit::singleline(&mut c.expr)
+                        .code_editor()
+                        .desired_width(200.0),
+                );
+                ComboBox::from_id_source(("aggregate", i))
+                    .selected_text(c.aggregate.name())
+                    .show_ui(ui, |ui| {
+                        for a in Aggregate::ALL {
+                            ui.selectable_value(&mut c.aggregate, a, a.name());
+                        }
+                    });
+                ui.add(
+                    DragValue::new(&mut c.tolerance)
+                        .range(0.0..=f64::MAX)
+                        .speed(0.01)
+                        .prefix("± "),
+                );
+                ui.button("🗙").clicked()
+            })
+            .inner;
+        if removed {
+            checks.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    if ui.button(" + ").clicked() {
+        checks.push(ValidationCheck::new(format!("{}.", checks.len() + 1)));
+    }
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.label("After:");
+        match &test.after_dir {
+            Some(dir) => ui.monospace(dir.display().to_string()),
+            None => ui.weak("no session loaded"),
+        };
+        if ui.button("Open dir").clicked() {
+            action = Some(RegressionAction::PickAfter);
+        }
+    });
+    if let Some(e) = &test.error {
+        ui.colored_label(FAIL_RED, e);
+    }
+
+    let can_run = test.after.is_some() && !checks.is_empty() && test.job.is_none();
+    if ui.add_enabled(can_run, Button::new("Run")).clicked() {
+        action = Some(RegressionAction::Run);
+    }
+    if let Some(job) = &test.job {
+        app::job_progress(ui, job);
+    }
+
+    if let Some(results) = &test.results {
+        ui.separator();
+        results_table(ui, results);
+        if ui.button("Copy report").clicked() {
+            ui.output_mut(|o| o.copied_text = format_report(results));
+        }
+    }
+
+    action
+}
+
+fn results_table(ui: &mut Ui, results: &[CheckResult]) {
+    ScrollArea::vertical()
+        .max_height(400.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            Grid::new("regression_results")
+                .striped(true)
+                .num_columns(5)
+                .show(ui, |ui| {
+                    ui.strong("");
+                    ui.strong("Check");
+                    ui.strong("Before");
+                    ui.strong("After");
+                    ui.strong("Δ");
+                    ui.end_row();
+
+                    for r in results.iter() {
+                        if r.passed() {
+                            ui.label(RichText::new("PASS").color(PASS_GREEN).strong());
+                        } else {
+                            ui.label(RichText::new("FAIL").color(FAIL_RED).strong());
+                        }
+                        ui.label(&r.name);
+                        ui.monospace(format_value(&r.before));
+                        ui.monospace(format_value(&r.after));
+                        match r.delta() {
+                            Some(d) => ui.monospace(format!("{d:.3}")),
+                            None => ui.label("-"),
+                        };
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+const CLI_USAGE: &str = "usage: s3plot regress <config.ron> <before_dir> <after_dir>";
+
+/// Runs the validation checks of the config over two sessions and prints the report. Returns
+/// the exit code, `0` if all checks passed, `1` if some failed and `2` on usage or load errors.
+pub fn cli(args: &[String]) -> i32 {
+    let [config_path, before, after] = args else {
+        eprintln!("{CLI_USAGE}");
+        return 2;
+    };
+
+    let res = (|| -> anyhow::Result<Vec<CheckResult>> {
+        let config = std::fs::read_to_string(config_path)
+            .with_context(|| format!("reading {config_path}"))?;
+        let config: Config =
+            ron::from_str(&config).with_context(|| format!("parsing {config_path}"))?;
+        let before = fs::load_session(before.into())?;
+        let after = fs::load_session(after.into())?;
+        Ok(run(
+            &config.validation_checks,
+            &config.expr_library,
+            &config.timing(),
+            &before,
+            &after,
+            &Progress::default(),
+        ))
+    })();
+
+    match res {
+        Ok(results) => {
+            print!("{}", format_report(&results));
+            if results.iter().all(CheckResult::passed) {
+                0
+            } else {
+                1
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            2
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::io::{self, Write};
+
+use egui::{Checkbox, DragValue, Grid, TextEdit, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::data::{self, LogStream, SanityError};
+use crate::export;
+use crate::fs::SelectableFiles;
+use crate::util;
+
+/// Expected values of a channel, checked when files are opened in addition to the built-in
+/// sanity check. Files without the channel pass the rule.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SanityRule {
+    pub channel: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// largest allowed change between two consecutive samples
+    pub max_step: Option<f64>,
+}
+
+impl SanityRule {
+    pub fn describe(&self) -> String {
+        let mut text = self.channel.clone();
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => text += &format!(" in {min}..{max}"),
+            (Some(min), None) => text += &format!(" ≥ {min}"),
+            (None, Some(max)) => text += &format!(" ≤ {max}"),
+            (None, None) => (),
+        }
+        if let Some(step) = self.max_step {
+            text += &format!(", steps ≤ {step}");
+        }
+        text
+    }
+
+    /// Reports the first sample that violates the rule.
+    pub fn check(&self, stream: &LogStream) -> Result<(), SanityError> {
+        match self.first_violation(stream) {
+            Some((i, _, reason)) => {
+                let time = util::format_time(stream.time[i] as f64 / 1000.0);
+                Err(SanityError(format!(
+                    "'{}' {reason} at {time}",
+                    self.channel
+                )))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Index, value and reason of the first sample that violates the rule.
+    pub fn first_violation(&self, stream: &LogStream) -> Option<(usize, f64, String)> {
+        let e = stream.entries.iter().find(|e| e.name == self.channel)?;
+        for i in 0..stream.len() {
+            let v = e.get_f64(i);
+            // comparisons with the NaN step of the first sample are false
+            let step = match i {
+                0 => f64::NAN,
+                _ => (v - e.get_f64(i - 1)).abs(),
+            };
+            let reason = if let Some(min) = self.min.filter(|min| v < *min) {
+                format!("is {v} < {min}")
+            } else if let Some(max) = self.max.filter(|max| v > *max) {
+                format!("is {v} > {max}")
+            } else if let Some(max_step) = self.max_step.filter(|max| step > *max) {
+                format!("changes by {step} > {max_step}")
+            } else {
+                continue;
+            };
+            return Some((i, v, reason));
+        }
+        None
+    }
+}
+
+/// Checks the stream against every rule, together with the description of the rule.
+pub fn check_all(
+    rules: &[SanityRule],
+    stream: &LogStream,
+) -> Vec<(String, Result<(), SanityError>)> {
+    (rules.iter())
+        .map(|r| (r.describe(), r.check(stream)))
+        .collect()
+}
+
+/// Writes a csv report of the failed built-in sanity checks and rules of all files, with the
+/// first failing sample of each channel.
+pub fn write_report(
+    writer: &mut impl Write,
+    files: &SelectableFiles,
+    rules: &[SanityRule],
+) -> io::Result<()> {
+    writeln!(writer, "file,check,channel,index,time,value")?;
+    for f in files.by_header.iter().flatten() {
+        let name = f.file.strip_prefix(&files.dir).unwrap_or(&f.file);
+        let name = name.display().to_string();
+        let time = |i: usize| {
+            f.stream
+                .time
+                .get(i)
+                .map_or(f64::NAN, |t| *t as f64 / 1000.0)
+        };
+
+        for failure in data::sanity_failures(&f.stream.entries) {
+            export::write_field(writer, &name)?;
+            write!(writer, ",{},", failure.reason)?;
+            export::write_field(writer, &failure.channel)?;
+            let (i, v) = (failure.index, failure.value);
+            writeln!(writer, ",{i},{},{v}", time(i))?;
+        }
+        for r in rules {
+            if let Some((i, v, reason)) = r.first_violation(&f.stream) {
+                export::write_field(writer, &name)?;
+                write!(writer, ",")?;
+                export::write_field(writer, &format!("{}: {reason}", r.describe()))?;
+                write!(writer, ",")?;
+                export::write_field(writer, &r.channel)?;
+                writeln!(writer, ",{i},{},{v}", time(i))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if the rules were changed.
+pub fn sanity_rules_dialog(ui: &mut Ui, rules: &mut Vec<SanityRule>) -> bool {
+    let mut changed = false;
+    let mut removed = None;
+    Grid::new("sanity_rules").striped(true).show(ui, |ui| {
+        ui.strong("Channel");
+        ui.strong("Min");
+        ui.strong("Max");
+        ui.strong("Max step");
+        ui.end_row();
+
+        for (i, r) in rules.iter_mut().enumerate() {
+            let channel = TextEdit::singleline(&mut r.channel).desired_width(150.0);
+            changed |= ui.add(channel).changed();
+            changed |= optional_value(ui, &mut r.min);
+            changed |= optional_value(ui, &mut r.max);
+            changed |= optional_value(ui, &mut r.max_step);
+            if ui.button("🗙").clicked() {
+                removed = Some(i);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(i) = removed {
+        rules.remove(i);
+        changed = true;
+    }
+
+    if ui.button(" + ").clicked() {
+        rules.push(SanityRule::default());
+        changed = true;
+    }
+    changed
+}
+
+fn optional_value(ui: &mut Ui, value: &mut Option<f64>) -> bool {
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        let mut changed = ui.add(Checkbox::without_text(&mut enabled)).changed();
+        if changed {
+            *value = enabled.then_some(0.0);
+        }
+        if let Some(v) = value {
+            changed |= ui.add(DragValue::new(v).speed(0.1)).changed();
+        }
+        changed
+    })
+    .inner
+}
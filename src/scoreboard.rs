@@ -0,0 +1,322 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use egui::{Button, Color32, ComboBox, Grid, RichText, ScrollArea, TextEdit, Ui};
+use egui_plot::PlotPoint;
+use serde::{Deserialize, Serialize};
+
+use crate::aliases;
+use crate::app::{self, Job, PlotData};
+use crate::data::LogStream;
+use crate::eval::{self, Expr, Progress, Timing};
+use crate::laps::{self, Lap, LapTrigger};
+use crate::library::UserExpr;
+use crate::plot::{Config, ERROR_RED};
+use crate::regression::Aggregate;
+use crate::track;
+
+const BEST_GREEN: Color32 = Color32::from_rgb(0x56, 0xc0, 0x56);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Better {
+    #[default]
+    Higher,
+    Lower,
+}
+
+impl Better {
+    pub const ALL: [Self; 2] = [Self::Higher, Self::Lower];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Higher => "higher is better",
+            Self::Lower => "lower is better",
+        }
+    }
+}
+
+/// A key figure of a session, e.g. the peak power is the `max` of `P_total`. Per lap metrics use
+/// the change of the expression over each lap, so the best lap is the `min` of `time` per lap
+/// and the energy per lap is the `mean` of an energy counter per lap.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScoreMetric {
+    pub name: String,
+    pub expr: String,
+    pub aggregate: Aggregate,
+    pub per_lap: bool,
+    pub better: Better,
+}
+
+impl ScoreMetric {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            expr: String::new(),
+            aggregate: Aggregate::Max,
+            per_lap: false,
+            better: Better::Higher,
+        }
+    }
+}
+
+/// Indexed by metric and session, including the opened session.
+pub type Results = Vec<Vec<Result<f64, String>>>;
+
+/// A session that is compared with the opened one.
+pub struct ScoreSession {
+    pub dir: PathBuf,
+    pub streams: Arc<[LogStream]>,
+}
+
+/// State of the scoreboard window. The opened session is always the first column.
+#[derive(Default)]
+pub struct Scoreboard {
+    pub sessions: Vec<ScoreSession>,
+    pub results: Option<Results>,
+    pub job: Option<Job<Results>>,
+    pub error: Option<String>,
+}
+
+impl Scoreboard {
+    /// Evaluates the metrics over all sessions on a worker thread.
+    pub fn start(&mut self, cfg: &Config, data: &PlotData) {
+        let metrics = (cfg.score_metrics.iter())
+            .map(|m| ScoreMetric {
+                expr: aliases::resolve(cfg.expr_aliases(), &data.session.resolve(&m.expr)),
+                ..m.clone()
+            })
+            .collect::<Vec<_>>();
+        let library = cfg.expr_library.clone();
+        let trigger = cfg.lap_trigger.clone();
+        let timing = cfg.timing();
+        let current = (Arc::clone(&data.streams), data.laps.clone());
+        let sessions = (self.sessions.iter())
+            .map(|s| Arc::clone(&s.streams))
+            .collect::<Vec<_>>();
+        self.job = Some(Job::spawn(move |progress| {
+            let current = (&current.0, current.1.as_slice());
+            run(
+                &metrics, &library, &trigger, &timing, current, &sessions, progress,
+            )
+        }));
+    }
+
+    /// The results no longer match the metrics or sessions.
+    pub fn invalidate(&mut self) {
+        self.results = None;
+        if let Some(job) = self.job.take() {
+            job.cancel();
+        }
+    }
+}
+
+pub enum ScoreboardAction {
+    AddSession,
+    Run,
+}
+
+/// Evaluates all metrics for all sessions, the laps of the opened session are passed since they
+/// might be detected using markers that only apply to it. Stops early if the evaluation is
+/// cancelled.
+pub fn run(
+    metrics: &[ScoreMetric],
+    library: &[UserExpr],
+    trigger: &LapTrigger,
+    timing: &Timing,
+    current: (&Arc<[LogStream]>, &[Lap]),
+    sessions: &[Arc<[LogStream]>],
+    progress: &Progress,
+) -> Results {
+    let other_laps = (sessions.iter())
+        .map(|s| {
+            let track = track::reconstruct(s);
+            laps::detect(s, track.as_ref(), trigger)
+        })
+        .collect::<Vec<_>>();
+    let sessions = std::iter::once(current)
+        .chain((sessions.iter().zip(other_laps.iter())).map(|(s, l)| (s, l.as_slice())))
+        .collect::<Vec<_>>();
+
+    (metrics.iter())
+        .take_while(|_| !progress.is_cancelled())
+        .map(|m| {
+            (sessions.iter())
+                .map(|(streams, laps)| evaluate(m, library, timing, streams, laps, progress))
+                .collect()
+        })
+        .collect()
+}
+
+fn evaluate(
+    metric: &ScoreMetric,
+    library: &[UserExpr],
+    timing: &Timing,
+    streams: &Arc<[LogStream]>,
+    laps: &[Lap],
+    progress: &Progress,
+) -> Result<f64, String> {
+    let expr = Expr::new("time", &metric.expr);
+    let values = match eval::eval(&expr, Arc::clone(streams), library, timing, progress) {
+        Ok(v) => v,
+        Err(e) => {
+            let error = e.y.or(e.x).map(|e| e.to_string());
+            return Err(error.unwrap_or_else(|| "invalid expression".to_string()));
+        }
+    };
+
+    let value = if metric.per_lap {
+        if laps.is_empty() {
+            return Err("no laps".to_string());
+        }
+        let per_lap = laps.iter().filter_map(|l| lap_change(&values, l));
+        metric.aggregate.apply(per_lap)
+    } else {
+        metric.aggregate.apply(values.iter().map(|p| p.y))
+    };
+    value.ok_or_else(|| "no finite values".to_string())
+}
+
+/// Difference between the last and first finite value inside of the lap.
+fn lap_change(values: &[PlotPoint], lap: &Lap) -> Option<f64> {
+    let mut lap_values = (values.iter())
+        .filter(|p| p.x >= lap.start && p.x <= lap.end && p.y.is_finite())
+        .map(|p| p.y);
+    let first = lap_values.next()?;
+    Some(lap_values.next_back().unwrap_or(first) - first)
+}
+
+/// Index of the session with the best value, `None` if less than two sessions have a value.
+fn best_session(results: &[Result<f64, String>], better: Better) -> Option<usize> {
+    let values = (results.iter().enumerate()).filter_map(|(i, r)| Some((i, *r.as_ref().ok()?)));
+    if values.clone().count() < 2 {
+        return None;
+    }
+    let best = match better {
+        Better::Higher => values.max_by(|(_, a), (_, b)| a.total_cmp(b)),
+        Better::Lower => values.min_by(|(_, a), (_, b)| a.total_cmp(b)),
+    };
+    best.map(|(i, _)| i)
+}
+
+pub fn scoreboard_dialog(
+    ui: &mut Ui,
+    board: &mut Scoreboard,
+    metrics: &mut Vec<ScoreMetric>,
+    current_name: &str,
+) -> Option<ScoreboardAction> {
+    let mut action = None;
+
+    ui.collapsing("Metrics", |ui| {
+        let mut i = 0;
+        while i < metrics.len() {
+            let m = &mut metrics[i];
+            let removed = ui
+                .horizontal(|ui| {
+                    ui.add(TextEdit::singleline(&mut m.name).desired_width(100.0));
+                    ui.add(
+                        TextEdit::singleline(&mut m.expr)
+                            .code_editor()
+                            .desired_width(200.0),
+                    );
+                    ComboBox::from_id_source(("score_aggregate", i))
+                        .selected_text(m.aggregate.name())
+                        .show_ui(ui, |ui| {
+                            for a in Aggregate::ALL {
+                                ui.selectable_value(&mut m.aggregate, a, a.name());
+                            }
+                        });
+                    ui.checkbox(&mut m.per_lap, "per lap")
+                        .on_hover_text("Aggregate the change of the expression over each lap");
+                    ComboBox::from_id_source(("score_better", i))
+                        .selected_text(m.better.name())
+                        .show_ui(ui, |ui| {
+                            for b in Better::ALL {
+                                ui.selectable_value(&mut m.better, b, b.name());
+                            }
+                        });
+                    ui.button("🗙").clicked()
+                })
+                .inner;
+            if removed {
+                metrics.remove(i);
+                board.invalidate();
+            } else {
+                i += 1;
+            }
+        }
+        if ui.button(" + ").clicked() {
+            metrics.push(ScoreMetric::new(format!("{}.", metrics.len() + 1)));
+        }
+    });
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        if ui.button("Add session").clicked() {
+            action = Some(ScoreboardAction::AddSession);
+        }
+        let can_run = !metrics.is_empty() && board.job.is_none();
+        if ui.add_enabled(can_run, Button::new("Run")).clicked() {
+            action = Some(ScoreboardAction::Run);
+        }
+    });
+    if let Some(job) = &board.job {
+        app::job_progress(ui, job);
+    }
+    if let Some(e) = &board.error {
+        ui.colored_label(ERROR_RED, e);
+    }
+
+    ui.separator();
+
+    let mut removed = None;
+    ScrollArea::both()
+        .max_height(400.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            Grid::new("scoreboard")
+                .striped(true)
+                .num_columns(board.sessions.len() + 2)
+                .show(ui, |ui| {
+                    ui.strong("");
+                    ui.strong(current_name);
+                    for (i, s) in board.sessions.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let name = s.dir.file_name().unwrap_or(s.dir.as_os_str());
+                            ui.strong(name.to_string_lossy())
+                                .on_hover_text(s.dir.display().to_string());
+                            if ui.small_button("🗙").clicked() {
+                                removed = Some(i);
+                            }
+                        });
+                    }
+                    ui.end_row();
+
+                    let Some(results) = &board.results else {
+                        return;
+                    };
+                    for (m, results) in metrics.iter().zip(results.iter()) {
+                        ui.label(&m.name);
+                        let best = best_session(results, m.better);
+                        for (i, r) in results.iter().enumerate() {
+                            match r {
+                                Ok(v) if best == Some(i) => ui.label(
+                                    RichText::new(format!("{v:.3}"))
+                                        .monospace()
+                                        .color(BEST_GREEN),
+                                ),
+                                Ok(v) => ui.monospace(format!("{v:.3}")),
+                                Err(e) => ui.colored_label(ERROR_RED, "error").on_hover_text(e),
+                            };
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    if let Some(i) = removed {
+        board.sessions.remove(i);
+        board.invalidate();
+    }
+
+    action
+}
@@ -0,0 +1,247 @@
+use egui::{Color32, ComboBox, DragValue, Grid, RichText, ScrollArea, Ui};
+use egui_plot::{Bar, BarChart, HLine, LineStyle, Plot};
+use serde::{Deserialize, Serialize};
+
+use crate::laps::Lap;
+use crate::track::Track;
+use crate::util::format_time;
+
+/// the usual timing screen color of the best sector
+const BEST_SECTOR_COLOR: Color32 = Color32::from_rgb(0xb0, 0x60, 0xf0);
+const LAP_CHART_HEIGHT: f32 = 150.0;
+const LAP_BAR_WIDTH: f64 = 0.6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitMode {
+    /// driven distance since the start of the lap
+    #[default]
+    Distance,
+    /// time since the start of the lap
+    Time,
+}
+
+impl SplitMode {
+    pub const ALL: [Self; 2] = [Self::Distance, Self::Time];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Distance => "Distance",
+            Self::Time => "Time",
+        }
+    }
+
+    fn unit(&self) -> &'static str {
+        match self {
+            Self::Distance => "m",
+            Self::Time => "s",
+        }
+    }
+}
+
+/// Gates that split every lap into sectors.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sectors {
+    pub mode: SplitMode,
+    /// from the start of the lap, in m or s depending on the mode
+    pub splits: Vec<f64>,
+}
+
+impl Sectors {
+    /// Sector times of the lap in seconds, `None` if the lap doesn't pass every split.
+    pub fn times(&self, lap: &Lap, track: Option<&Track>) -> Option<Vec<f64>> {
+        let mut splits = self.splits.clone();
+        splits.sort_by(f64::total_cmp);
+
+        let mut bounds = vec![lap.start];
+        for s in splits {
+            let time = match self.mode {
+                SplitMode::Time => lap.start + s,
+                SplitMode::Distance => {
+                    let track = track?;
+                    let start = track.distance_at(lap.start)?;
+                    track.time_at_distance(start + s)?
+                }
+            };
+            let last = bounds.last().copied().unwrap_or(lap.start);
+            if time <= last || time >= lap.end {
+                return None;
+            }
+            bounds.push(time);
+        }
+        bounds.push(lap.end);
+        Some(bounds.windows(2).map(|w| w[1] - w[0]).collect())
+    }
+}
+
+/// Lap and sector times of all laps, with the best time of each sector.
+struct SectorTable {
+    /// lap number, lap time and sector times
+    laps: Vec<(usize, f64, Option<Vec<f64>>)>,
+    /// best time of each sector, over the laps that pass every split
+    best: Vec<f64>,
+}
+
+impl SectorTable {
+    fn new(sectors: &Sectors, laps: &[Lap], track: Option<&Track>) -> Self {
+        let laps = (laps.iter())
+            .map(|l| (l.number, l.duration(), sectors.times(l, track)))
+            .collect::<Vec<_>>();
+        let mut best = vec![f64::INFINITY; sectors.splits.len() + 1];
+        for times in laps.iter().filter_map(|(_, _, s)| s.as_ref()) {
+            for (b, t) in best.iter_mut().zip(times) {
+                *b = b.min(*t);
+            }
+        }
+        Self { laps, best }
+    }
+
+    /// Sum of the best sector times, if any lap passes every split.
+    fn ideal_lap(&self) -> Option<f64> {
+        let ideal = self.best.iter().sum::<f64>();
+        ideal.is_finite().then_some(ideal)
+    }
+
+    fn fastest_lap(&self) -> Option<(usize, f64)> {
+        (self.laps.iter())
+            .map(|(n, t, _)| (*n, *t))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+pub fn sectors_dialog(ui: &mut Ui, sectors: &mut Sectors, laps: &[Lap], track: Option<&Track>) {
+    ui.horizontal(|ui| {
+        ComboBox::from_id_source("split_mode")
+            .selected_text(sectors.mode.name())
+            .show_ui(ui, |ui| {
+                for m in SplitMode::ALL {
+                    ui.selectable_value(&mut sectors.mode, m, m.name());
+                }
+            });
+        ui.label("from the start of the lap");
+    });
+    if sectors.mode == SplitMode::Distance && track.is_none() {
+        ui.weak("Distance splits need GPS or speed and yaw rate channels");
+    }
+
+    let unit = sectors.mode.unit();
+    let mut i = 0;
+    while i < sectors.splits.len() {
+        let removed = ui
+            .horizontal(|ui| {
+                ui.label(format!("Split {}", i + 1));
+                let value = DragValue::new(&mut sectors.splits[i])
+                    .range(0.0..=f64::INFINITY)
+                    .suffix(format!(" {unit}"));
+                ui.add(value);
+                ui.button("🗙").clicked()
+            })
+            .inner;
+        if removed {
+            sectors.splits.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    if ui.button("Add split").clicked() {
+        let last = sectors.splits.iter().copied().fold(0.0, f64::max);
+        let step = match sectors.mode {
+            SplitMode::Distance => 100.0,
+            SplitMode::Time => 10.0,
+        };
+        sectors.splits.push(last + step);
+    }
+
+    ui.separator();
+
+    if laps.is_empty() {
+        ui.label("No laps detected, select a lap trigger in the sidebar");
+        return;
+    }
+
+    let table = SectorTable::new(sectors, laps, track);
+    let fastest = table.fastest_lap();
+    let num_sectors = table.best.len();
+    ScrollArea::vertical()
+        .max_height(300.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            Grid::new("sector_times")
+                .striped(true)
+                .num_columns(num_sectors + 2)
+                .show(ui, |ui| {
+                    ui.strong("Lap");
+                    for s in 0..num_sectors {
+                        ui.strong(format!("S{}", s + 1));
+                    }
+                    ui.strong("Time");
+                    ui.end_row();
+
+                    for (number, time, sector_times) in table.laps.iter() {
+                        ui.label(number.to_string());
+                        match sector_times {
+                            Some(times) => {
+                                for (t, best) in times.iter().zip(table.best.iter()) {
+                                    let mut text = RichText::new(format_time(*t)).monospace();
+                                    if t == best {
+                                        text = text.color(BEST_SECTOR_COLOR);
+                                    }
+                                    ui.label(text);
+                                }
+                            }
+                            None => {
+                                for _ in 0..num_sectors {
+                                    ui.weak("–");
+                                }
+                            }
+                        }
+                        let mut text = RichText::new(format_time(*time)).monospace();
+                        if fastest.is_some_and(|(n, _)| n == *number) {
+                            text = text.strong();
+                        }
+                        ui.label(text);
+                        ui.end_row();
+                    }
+
+                    if let Some(ideal) = table.ideal_lap() {
+                        ui.strong("Ideal");
+                        for best in table.best.iter() {
+                            let text = RichText::new(format_time(*best)).monospace();
+                            ui.label(text.color(BEST_SECTOR_COLOR));
+                        }
+                        ui.label(RichText::new(format_time(ideal)).monospace().strong());
+                        ui.end_row();
+                    }
+                });
+        });
+
+    if let (Some(ideal), Some((number, time))) = (table.ideal_lap(), fastest) {
+        ui.label(format!(
+            "The ideal lap is {:.3} s faster than the fastest lap {number}",
+            time - ideal
+        ));
+    }
+
+    let bars = (table.laps.iter())
+        .map(|(n, t, _)| {
+            let bar = Bar::new(*n as f64, *t).width(LAP_BAR_WIDTH);
+            match fastest {
+                Some((f, _)) if f == *n => bar.fill(BEST_SECTOR_COLOR),
+                _ => bar,
+            }
+        })
+        .collect::<Vec<_>>();
+    Plot::new("lap_times")
+        .height(LAP_CHART_HEIGHT)
+        .allow_scroll(false)
+        .label_formatter(|_, v| format!("lap {}\n{}", v.x.round(), format_time(v.y)))
+        .show(ui, |ui| {
+            ui.bar_chart(BarChart::new(bars).name("Lap time"));
+            if let Some(ideal) = table.ideal_lap() {
+                let line = HLine::new(ideal)
+                    .color(BEST_SECTOR_COLOR)
+                    .style(LineStyle::dashed_loose())
+                    .name("Ideal lap");
+                ui.hline(line);
+            }
+        });
+}
@@ -0,0 +1,236 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use egui_plot::PlotPoint;
+use serde::Serialize;
+
+use crate::eval::{Expr, Timing};
+use crate::library::UserExpr;
+use crate::APP_NAME;
+
+const CACHE_DIR: &str = "series_cache";
+/// has to be bumped when the evaluation or the file format changes, so stale entries are missed
+const CACHE_VERSION: u32 = 1;
+/// x and y as little endian `f64`s
+const POINT_SIZE: usize = 16;
+/// bytes of the series stored on disk, the least recently used ones are removed
+const MAX_CACHE_BYTES: u64 = 512 * 1024 * 1024;
+/// bytes of the series kept in memory by [`RecentSeries`], the least recently used ones are
+/// dropped
+const MAX_RECENT_BYTES: usize = 64 * 1024 * 1024;
+
+/// Evaluated series stored in the app's storage directory, keyed by the expression and the
+/// opened files. Files are identified by their path, size and modification time. The entries
+/// are named after a hash of the serialized key, which stays the same across builds.
+#[derive(Clone, Debug)]
+pub struct SeriesCache {
+    dir: PathBuf,
+    session: u128,
+}
+
+impl SeriesCache {
+    /// `None` if there is no storage directory on this platform.
+    pub fn new(files: &[PathBuf], derived: &[UserExpr]) -> Option<Self> {
+        let dir = cache_dir()?;
+        let files = (files.iter())
+            .map(|f| {
+                let meta = std::fs::metadata(f).ok();
+                let len = meta.as_ref().map(|m| m.len());
+                (f, len, meta.and_then(|m| m.modified().ok()))
+            })
+            .collect::<Vec<_>>();
+        let session = stable_hash(&(CACHE_VERSION, derived, files))?;
+        Some(Self { dir, session })
+    }
+
+    fn path(&self, expr: &Expr, library: &[UserExpr], timing: &Timing) -> Option<PathBuf> {
+        let key = stable_hash(&(self.session, expr, library, timing))?;
+        Some(self.dir.join(format!("{key:032x}.bin")))
+    }
+
+    pub fn load(
+        &self,
+        expr: &Expr,
+        library: &[UserExpr],
+        timing: &Timing,
+    ) -> Option<Vec<PlotPoint>> {
+        let path = self.path(expr, library, timing)?;
+        let values = read_points(&path).ok()?;
+        // the modification time orders the entries by their last use
+        _ = File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|f| f.set_modified(SystemTime::now()));
+        Some(values)
+    }
+
+    pub fn store(
+        &self,
+        expr: &Expr,
+        library: &[UserExpr],
+        timing: &Timing,
+        values: &[PlotPoint],
+    ) -> std::io::Result<()> {
+        let Some(path) = self.path(expr, library, timing) else {
+            return Ok(());
+        };
+        // write to a temporary file first, so a concurrent load never sees a partial entry
+        std::fs::create_dir_all(&self.dir)?;
+        let tmp_path = path.with_extension("tmp");
+        write_points(&tmp_path, values)?;
+        std::fs::rename(tmp_path, path)?;
+        evict(&self.dir, MAX_CACHE_BYTES)
+    }
+}
+
+/// Hash of the key serialized as json, `None` if it can't be serialized.
+fn stable_hash(key: &impl Serialize) -> Option<u128> {
+    Some(fnv1a(&serde_json::to_vec(key).ok()?))
+}
+
+/// 128 bit FNV-1a hash.
+fn fnv1a(bytes: &[u8]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    (bytes.iter()).fold(OFFSET, |h, b| (h ^ *b as u128).wrapping_mul(PRIME))
+}
+
+/// Removes the least recently used entries until the rest fit into `max` bytes.
+fn evict(dir: &Path, max: u64) -> std::io::Result<()> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "bin") {
+            let meta = std::fs::metadata(&path)?;
+            entries.push((meta.modified()?, meta.len(), path));
+        }
+    }
+
+    let mut size = entries.iter().map(|(_, len, _)| len).sum::<u64>();
+    entries.sort_unstable_by_key(|(modified, ..)| *modified);
+    for (_, len, path) in entries {
+        if size <= max {
+            break;
+        }
+        std::fs::remove_file(path)?;
+        size -= len;
+    }
+    Ok(())
+}
+
+/// Series evaluated recently, kept in memory so switching back to a previous formulation of an
+/// expression is instant. Keyed by the expression and the data revision, which changes whenever
+/// the streams are rebuilt. The series count towards the memory budget and are dropped before
+/// any series is spilled.
+#[derive(Clone, Default)]
+pub struct RecentSeries {
+    entries: Arc<Mutex<VecDeque<RecentEntry>>>,
+}
+
+struct RecentEntry {
+    key: u64,
+    values: Arc<[PlotPoint]>,
+}
+
+impl RecentSeries {
+    pub fn key(revision: u64, expr: &Expr, library: &[UserExpr], timing: &Timing) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        revision.hash(&mut hasher);
+        expr.x.hash(&mut hasher);
+        expr.y.hash(&mut hasher);
+        library.hash(&mut hasher);
+        timing.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn load(&self, key: u64) -> Option<Arc<[PlotPoint]>> {
+        let mut entries = self.entries.lock().unwrap();
+        let i = entries.iter().position(|e| e.key == key)?;
+        let entry = entries.remove(i)?;
+        let values = Arc::clone(&entry.values);
+        entries.push_back(entry);
+        Some(values)
+    }
+
+    pub fn store(&self, key: u64, values: &[PlotPoint]) {
+        if std::mem::size_of_val(values) > MAX_RECENT_BYTES {
+            return;
+        }
+        let values = Arc::from(values);
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|e| e.key != key);
+        entries.push_back(RecentEntry { key, values });
+        shrink(&mut entries, MAX_RECENT_BYTES);
+    }
+
+    /// Drops the least recently used series until the rest fit into `max` bytes.
+    pub fn shrink_to(&self, max: usize) {
+        shrink(&mut self.entries.lock().unwrap(), max);
+    }
+}
+
+fn shrink(entries: &mut VecDeque<RecentEntry>, max: usize) {
+    let mut size = (entries.iter())
+        .map(|e| std::mem::size_of_val(&*e.values))
+        .sum::<usize>();
+    while size > max {
+        let Some(e) = entries.pop_front() else {
+            break;
+        };
+        size -= std::mem::size_of_val(&*e.values);
+    }
+}
+
+pub fn write_points(path: &Path, values: &[PlotPoint]) -> std::io::Result<()> {
+    let mut bytes = Vec::with_capacity(values.len() * POINT_SIZE);
+    for p in values.iter() {
+        bytes.extend_from_slice(&p.x.to_le_bytes());
+        bytes.extend_from_slice(&p.y.to_le_bytes());
+    }
+    std::fs::write(path, bytes)
+}
+
+pub fn read_points(path: &Path) -> std::io::Result<Vec<PlotPoint>> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() % POINT_SIZE != 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "truncated series",
+        ));
+    }
+    let values = (bytes.chunks_exact(POINT_SIZE))
+        .map(|c| {
+            let x = f64::from_le_bytes(c[0..8].try_into().unwrap());
+            let y = f64::from_le_bytes(c[8..16].try_into().unwrap());
+            PlotPoint::new(x, y)
+        })
+        .collect();
+    Ok(values)
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    Some(eframe::storage_dir(APP_NAME)?.join(CACHE_DIR))
+}
+
+/// Removes all cached series.
+pub fn clear() -> std::io::Result<()> {
+    match cache_dir() {
+        Some(dir) if dir.exists() => std::fs::remove_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_test_vectors() {
+        assert_eq!(fnv1a(b""), 0x6c62272e07bb014262b821756295c58d);
+        assert_eq!(fnv1a(b"a"), 0xd228cb696f1a8caf78912b704e4a8964);
+    }
+}
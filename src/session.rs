@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::data::LogStream;
+
+const SESSION_FILE: &str = "session.txt";
+
+/// Metadata of the opened session, e.g. the driver and the track, that is referenced by
+/// `${key}` placeholders in expressions and tab names. Read from a `session.txt` in the session
+/// directory with one `key = value` pair per line. The directory name and the start date are
+/// always available as `dir` and `date`.
+#[derive(Clone, Debug, Default)]
+pub struct SessionMeta {
+    pub values: BTreeMap<String, String>,
+}
+
+impl SessionMeta {
+    pub fn load(dir: &Path, streams: &[LogStream]) -> Self {
+        let mut values = BTreeMap::new();
+        if let Some(name) = dir.file_name() {
+            values.insert("dir".into(), name.to_string_lossy().into_owned());
+        }
+        if let Some(start) = streams.iter().filter_map(|s| s.start).min() {
+            values.insert("date".into(), start.format("%Y-%m-%d").to_string());
+        }
+
+        if let Ok(text) = std::fs::read_to_string(dir.join(SESSION_FILE)) {
+            let lines = text.lines().map(str::trim);
+            for line in lines.filter(|l| !l.is_empty() && !l.starts_with('#')) {
+                if let Some((key, value)) = line.split_once('=') {
+                    values.insert(key.trim().into(), value.trim().into());
+                }
+            }
+        }
+
+        Self { values }
+    }
+
+    /// Replaces all `${key}` placeholders, unknown keys are left untouched.
+    pub fn resolve(&self, text: &str) -> String {
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("${") {
+            output.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let value =
+                (rest.find('}')).and_then(|end| Some((end, self.values.get(&rest[2..end])?)));
+            match value {
+                Some((end, value)) => {
+                    output.push_str(value);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    output.push_str("${");
+                    rest = &rest[2..];
+                }
+            }
+        }
+        output.push_str(rest);
+        output
+    }
+}
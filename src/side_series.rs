@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+
+use egui_plot::PlotPoint;
+
+use crate::app::{PlotData, PlotValues};
+use crate::color_by;
+use crate::eval::Expr;
+use crate::plot::{Config, TabConfig, XyMode};
+
+/// Series line plots are drawn with besides their own values, e.g. the values they are colored
+/// by, evaluated at the x values of the plot.
+#[derive(Default)]
+pub struct SideSeries {
+    /// hash of the streams and settings the values were evaluated with
+    inputs: u64,
+    /// by the x and y expression
+    values: HashMap<(String, String), PlotValues>,
+    /// finite minimum and maximum of the finished values
+    ranges: HashMap<(String, String), (f64, f64)>,
+}
+
+impl SideSeries {
+    /// The values and their range, once they are evaluated and some are finite.
+    pub fn get(&self, expr: &Expr) -> Option<(&[PlotPoint], (f64, f64))> {
+        let key = (expr.x.clone(), expr.y.clone());
+        match (self.values.get(&key), self.ranges.get(&key)) {
+            (Some(PlotValues::Result(Ok(d))), Some(&(min, max))) if min <= max => {
+                Some((d, (min, max)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Collects the results of finished jobs, returns true if some are still running.
+    fn poll_jobs(&mut self) -> bool {
+        let mut pending = false;
+        for (key, values) in self.values.iter_mut() {
+            if let PlotValues::Job(j) = values {
+                if j.is_done() {
+                    let job = std::mem::replace(values, PlotValues::empty());
+                    *values = PlotValues::Result(job.into_job().unwrap().join());
+                } else {
+                    pending = true;
+                }
+            }
+            if let PlotValues::Result(Ok(d)) = values {
+                if !self.ranges.contains_key(key) {
+                    self.ranges.insert(key.clone(), finite_range(d));
+                }
+            }
+        }
+        pending
+    }
+}
+
+/// Starts the evaluation of the side series of the tab that weren't requested before. All
+/// values are dropped once the streams, library or evaluation settings change. Returns true if
+/// some are still being evaluated.
+pub fn request(data: &mut PlotData, cfg: &Config, tab: usize) -> bool {
+    let mut hasher = DefaultHasher::new();
+    (Arc::as_ptr(&data.streams) as *const () as usize).hash(&mut hasher);
+    cfg.timing().hash(&mut hasher);
+    cfg.expr_library.hash(&mut hasher);
+    for a in cfg.expr_aliases() {
+        (&a.name, &a.alias).hash(&mut hasher);
+    }
+    let inputs = hasher.finish();
+    if data.side_series.inputs != inputs {
+        data.side_series = SideSeries {
+            inputs,
+            ..Default::default()
+        };
+    }
+
+    let tab_cfg = &cfg.tabs[tab];
+    let exprs = (0..tab_cfg.plots.len())
+        .flat_map(|i| [color_by::color_expr(tab_cfg, i), fill_expr(tab_cfg, i)]);
+    for expr in exprs.flatten() {
+        let key = (expr.x.clone(), expr.y.clone());
+        if !data.side_series.values.contains_key(&key) {
+            let job = data.start_job(&expr, cfg);
+            data.side_series.values.insert(key, job);
+        }
+    }
+    data.side_series.poll_jobs()
+}
+
+/// The expression the area between the line plot and its values is filled.
+pub fn fill_expr(tab_cfg: &TabConfig, plot: usize) -> Option<Expr> {
+    let p = &tab_cfg.plots[plot];
+    if p.fill_to.trim().is_empty() || p.xy_mode != XyMode::Off {
+        return None;
+    }
+    Some(Expr::new(&tab_cfg.plot_expr(p).x, &p.fill_to))
+}
+
+fn finite_range(values: &[PlotPoint]) -> (f64, f64) {
+    let finite = values.iter().map(|p| p.y).filter(|v| v.is_finite());
+    let min = finite.clone().fold(f64::INFINITY, f64::min);
+    let max = finite.fold(f64::NEG_INFINITY, f64::max);
+    (min, max)
+}
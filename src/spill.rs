@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use egui_plot::PlotPoint;
+
+use crate::series_cache::{read_points, write_points};
+
+/// Temporary directory that evaluated series are moved to, when they exceed the memory budget.
+/// The directory is removed when the store is dropped.
+#[derive(Debug)]
+pub struct SpillStore {
+    dir: PathBuf,
+    next_id: usize,
+    /// when each tab was last shown, by tab id
+    last_used: HashMap<u64, Instant>,
+}
+
+/// A series that was moved to disk, the file is removed when it's dropped.
+#[derive(Debug)]
+pub struct SpilledSeries {
+    path: PathBuf,
+}
+
+impl SpillStore {
+    pub fn new() -> Self {
+        // the store of the previously opened session might still be alive
+        static NEXT_STORE: AtomicUsize = AtomicUsize::new(0);
+        let store = NEXT_STORE.fetch_add(1, Ordering::Relaxed);
+        let dir_name = format!("s3plot-spill-{}-{store}", std::process::id());
+        let dir = std::env::temp_dir().join(dir_name);
+        Self {
+            dir,
+            next_id: 0,
+            last_used: HashMap::new(),
+        }
+    }
+
+    pub fn spill(&mut self, values: &[PlotPoint]) -> std::io::Result<SpilledSeries> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("{}.bin", self.next_id));
+        self.next_id += 1;
+        write_points(&path, values)?;
+        Ok(SpilledSeries { path })
+    }
+
+    pub fn touch(&mut self, tab_id: u64) {
+        self.last_used.insert(tab_id, Instant::now());
+    }
+
+    /// `None` if the tab wasn't shown yet.
+    pub fn last_used(&self, tab_id: u64) -> Option<Instant> {
+        self.last_used.get(&tab_id).copied()
+    }
+}
+
+impl Drop for SpillStore {
+    fn drop(&mut self) {
+        _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+impl SpilledSeries {
+    pub fn load(&self) -> std::io::Result<Vec<PlotPoint>> {
+        read_points(&self.path)
+    }
+}
+
+impl Drop for SpilledSeries {
+    fn drop(&mut self) {
+        _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Approximate heap size of a series.
+pub fn series_size(values: &[PlotPoint]) -> usize {
+    std::mem::size_of_val(values)
+}
@@ -0,0 +1,97 @@
+use egui::epaint::Hsva;
+use egui::{Color32, Context, Id, Stroke, Visuals};
+use serde::{Deserialize, Serialize};
+
+const THEME_ID: &str = "theme";
+
+/// Saturated colors that stay distinguishable on a white background in direct sunlight.
+const HIGH_CONTRAST_PALETTE: [Color32; 8] = [
+    Color32::from_rgb(0x00, 0x00, 0xe0),
+    Color32::from_rgb(0xd0, 0x00, 0x00),
+    Color32::from_rgb(0x00, 0x80, 0x00),
+    Color32::from_rgb(0xc0, 0x00, 0xc0),
+    Color32::from_rgb(0xe0, 0x70, 0x00),
+    Color32::from_rgb(0x00, 0x80, 0x90),
+    Color32::from_rgb(0x70, 0x00, 0xc0),
+    Color32::from_rgb(0x60, 0x40, 0x00),
+];
+const HIGH_CONTRAST_STROKE_WIDTH: f32 = 1.5;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    /// dark or light, following the OS
+    #[default]
+    System,
+    Dark,
+    Light,
+    /// for sunlight at the track
+    HighContrast,
+}
+
+impl Theme {
+    pub const ALL: [Self; 4] = [Self::System, Self::Dark, Self::Light, Self::HighContrast];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::System => "System",
+            Self::Dark => "Dark",
+            Self::Light => "Light",
+            Self::HighContrast => "High contrast",
+        }
+    }
+
+    /// Sets the visuals of the theme, which also determine the plot background and grid. The
+    /// resolved theme is stored for [`Theme::current`].
+    pub fn apply(self, ctx: &Context, system: Option<eframe::Theme>) {
+        let theme = match (self, system) {
+            (Self::System, Some(eframe::Theme::Light)) => Self::Light,
+            (Self::System, _) => Self::Dark,
+            (theme, _) => theme,
+        };
+        let visuals = theme.visuals();
+        if ctx.style().visuals != visuals {
+            ctx.set_visuals(visuals);
+        }
+        ctx.data_mut(|d| d.insert_temp(Id::new(THEME_ID), theme));
+    }
+
+    /// The theme applied in this frame, never [`Theme::System`].
+    pub fn current(ctx: &Context) -> Self {
+        ctx.data(|d| d.get_temp(Id::new(THEME_ID)))
+            .unwrap_or(Self::Dark)
+    }
+
+    fn visuals(&self) -> Visuals {
+        match self {
+            Self::System | Self::Dark => Visuals::dark(),
+            Self::Light => Visuals::light(),
+            Self::HighContrast => {
+                let mut visuals = Visuals::light();
+                visuals.override_text_color = Some(Color32::BLACK);
+                visuals.extreme_bg_color = Color32::WHITE;
+                visuals.panel_fill = Color32::WHITE;
+                visuals.window_fill = Color32::WHITE;
+                visuals.faint_bg_color = Color32::from_gray(0xe8);
+                let stroke = Stroke::new(HIGH_CONTRAST_STROKE_WIDTH, Color32::BLACK);
+                visuals.window_stroke = stroke;
+                visuals.widgets.noninteractive.bg_stroke = stroke;
+                visuals.widgets.noninteractive.fg_stroke = stroke;
+                visuals.widgets.inactive.fg_stroke = stroke;
+                visuals.widgets.inactive.bg_stroke = stroke;
+                visuals
+            }
+        }
+    }
+
+    /// Default color of the series at `index`, for series without a configured color.
+    pub fn line_color(&self, index: usize) -> Color32 {
+        let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0;
+        let hue = (index as f32 * golden_ratio).fract();
+        match self {
+            // the colors egui_plot assigns automatically
+            Self::System | Self::Dark => Hsva::new(hue, 0.85, 0.5, 1.0).into(),
+            Self::Light => Hsva::new(hue, 0.9, 0.4, 1.0).into(),
+            Self::HighContrast => HIGH_CONTRAST_PALETTE[index % HIGH_CONTRAST_PALETTE.len()],
+        }
+    }
+}
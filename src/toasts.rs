@@ -0,0 +1,124 @@
+use std::time::{Duration, Instant};
+
+use egui::{
+    Align, Align2, Area, Context, Frame, Id, Layout, Order, RichText, ScrollArea, Vec2, Window,
+};
+
+use crate::plot::ERROR_RED;
+
+const TOAST_DURATION: Duration = Duration::from_secs(8);
+const TOAST_WIDTH: f32 = 320.0;
+const TOAST_MARGIN: f32 = 12.0;
+/// older toasts are dropped
+const MAX_TOASTS: usize = 5;
+/// characters of the details shown in the toast itself
+const MAX_PREVIEW_LEN: usize = 120;
+
+#[derive(Clone)]
+struct Toast {
+    summary: String,
+    /// underlying error, e.g. of ron or io, might be empty
+    details: String,
+    shown: Instant,
+}
+
+/// Errors shown as toasts in the bottom right corner, which disappear after a while unless they
+/// are hovered. The details of a toast can be opened in a dialog to read or copy them.
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    details: Option<Toast>,
+}
+
+impl Toasts {
+    pub fn error(&mut self, summary: impl Into<String>, details: impl Into<String>) {
+        let toast = Toast {
+            summary: summary.into(),
+            details: details.into(),
+            shown: Instant::now(),
+        };
+        self.toasts.push(toast);
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        self.toasts.retain(|t| t.shown.elapsed() < TOAST_DURATION);
+        if let Some(next) = self.toasts.iter().map(|t| t.shown).min() {
+            ctx.request_repaint_after(TOAST_DURATION.saturating_sub(next.elapsed()));
+        }
+
+        let mut closed = None;
+        let mut opened = None;
+        Area::new(Id::new("toasts"))
+            .anchor(Align2::RIGHT_BOTTOM, Vec2::splat(-TOAST_MARGIN))
+            .order(Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_width(TOAST_WIDTH);
+                for (i, t) in self.toasts.iter_mut().enumerate() {
+                    let frame = Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(&t.summary).strong().color(ERROR_RED));
+                            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                if ui.small_button("🗙").clicked() {
+                                    closed = Some(i);
+                                }
+                                if !t.details.is_empty() && ui.small_button("Details").clicked() {
+                                    opened = Some(i);
+                                }
+                            });
+                        });
+                        if !t.details.is_empty() {
+                            ui.label(preview(&t.details));
+                        }
+                    });
+                    // keep the toast while the user is reading it
+                    if frame.response.contains_pointer() {
+                        t.shown = Instant::now();
+                    }
+                }
+            });
+
+        if let Some(i) = opened {
+            self.details = Some(self.toasts.remove(i));
+        } else if let Some(i) = closed {
+            self.toasts.remove(i);
+        }
+
+        let Some(details) = &self.details else {
+            return;
+        };
+        let mut open = true;
+        let r = Window::new(&details.summary)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    ui.label(RichText::new(&details.details).monospace());
+                });
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = details.details.clone());
+                    }
+                    ui.button("Ok").clicked()
+                })
+                .inner
+            });
+        if !open || r.and_then(|r| r.inner) == Some(true) {
+            self.details = None;
+        }
+    }
+}
+
+/// First line of the details, shortened to fit into the toast.
+fn preview(details: &str) -> String {
+    let line = details.lines().next().unwrap_or_default();
+    match line.char_indices().nth(MAX_PREVIEW_LEN) {
+        Some((i, _)) => format!("{}…", &line[..i]),
+        None if details.lines().nth(1).is_some() => format!("{line}…"),
+        None => line.to_string(),
+    }
+}
@@ -0,0 +1,161 @@
+use egui_plot::PlotPoint;
+
+use crate::data::LogStream;
+
+/// mean earth radius in meters
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+const LATITUDE_NAMES: [&str; 3] = ["gps_lat", "latitude", "lat"];
+const LONGITUDE_NAMES: [&str; 4] = ["gps_lon", "longitude", "lon", "lng"];
+const SPEED_NAMES: [&str; 4] = ["speed", "velocity", "vehicle_speed", "gps_speed"];
+const YAW_RATE_NAMES: [&str; 3] = ["yaw_rate", "gyro_z", "yaw"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackSource {
+    Gps,
+    DeadReckoning,
+}
+
+impl TrackSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gps => "GPS",
+            Self::DeadReckoning => "dead reckoning",
+        }
+    }
+}
+
+/// The driven path in meters, relative to the first position.
+pub struct Track {
+    pub source: TrackSource,
+    /// time in seconds
+    pub time: Vec<f64>,
+    pub points: Vec<PlotPoint>,
+    /// cumulative driven distance in meters
+    pub distance: Vec<f64>,
+}
+
+impl Track {
+    fn new(source: TrackSource, time: Vec<f64>, points: Vec<PlotPoint>) -> Self {
+        let mut distance = Vec::with_capacity(points.len());
+        let mut sum = 0.0;
+        for (i, p) in points.iter().enumerate() {
+            if let Some(prev) = i.checked_sub(1).map(|i| points[i]) {
+                sum += (p.x - prev.x).hypot(p.y - prev.y);
+            }
+            distance.push(sum);
+        }
+
+        Self {
+            source,
+            time,
+            points,
+            distance,
+        }
+    }
+
+    /// Index of the point closest to `pos`.
+    pub fn nearest(&self, pos: PlotPoint) -> Option<usize> {
+        let dist = |p: &PlotPoint| (p.x - pos.x).powi(2) + (p.y - pos.y).powi(2);
+        (self.points.iter().enumerate())
+            .min_by(|(_, a), (_, b)| dist(a).total_cmp(&dist(b)))
+            .map(|(i, _)| i)
+    }
+
+    /// Position at the last sample before or at `time`.
+    pub fn position_at(&self, time: f64) -> Option<PlotPoint> {
+        let i = self.time.partition_point(|&t| t <= time);
+        self.points.get(i.saturating_sub(1)).copied()
+    }
+
+    /// Driven distance at the last sample before or at `time`.
+    pub fn distance_at(&self, time: f64) -> Option<f64> {
+        let i = self.time.partition_point(|&t| t <= time);
+        self.distance.get(i.saturating_sub(1)).copied()
+    }
+
+    /// Time of the first sample at which the driven distance reaches `distance`.
+    pub fn time_at_distance(&self, distance: f64) -> Option<f64> {
+        let i = self.distance.partition_point(|&d| d < distance);
+        self.time.get(i).copied()
+    }
+}
+
+/// Reconstructs the driven path from GPS channels if present, otherwise it is dead-reckoned by
+/// integrating the speed (m/s) and the yaw rate (deg/s).
+pub fn reconstruct(streams: &[LogStream]) -> Option<Track> {
+    gps_track(streams).or_else(|| dead_reckoning_track(streams))
+}
+
+fn gps_track(streams: &[LogStream]) -> Option<Track> {
+    let (s, lat) = find_channel(streams, &LATITUDE_NAMES)?;
+    let lon = find_entry(&streams[s], &LONGITUDE_NAMES)?;
+    let stream = &streams[s];
+    let lat = &stream.entries[lat];
+    let lon = &stream.entries[lon];
+
+    let mut origin = None;
+    let mut time = Vec::new();
+    let mut points = Vec::new();
+    for (i, &t) in stream.time.iter().enumerate() {
+        let (lat, lon) = (lat.get_f64(i), lon.get_f64(i));
+        // receivers without a fix commonly report zero
+        let valid = lat.is_finite() && lon.is_finite() && (lat != 0.0 || lon != 0.0);
+        if !valid {
+            continue;
+        }
+
+        let (lat0, lon0) = *origin.get_or_insert((lat, lon));
+        let x = (lon - lon0).to_radians() * lat0.to_radians().cos() * EARTH_RADIUS;
+        let y = (lat - lat0).to_radians() * EARTH_RADIUS;
+        time.push(t as f64 / 1000.0);
+        points.push(PlotPoint::new(x, y));
+    }
+
+    (!points.is_empty()).then(|| Track::new(TrackSource::Gps, time, points))
+}
+
+fn dead_reckoning_track(streams: &[LogStream]) -> Option<Track> {
+    let (s, speed) = find_channel(streams, &SPEED_NAMES)?;
+    let (y, yaw_rate) = find_channel(streams, &YAW_RATE_NAMES)?;
+    let stream = &streams[s];
+    let speed = &stream.entries[speed];
+
+    let mut heading: f64 = 0.0;
+    let mut pos = PlotPoint::new(0.0, 0.0);
+    let mut time = Vec::with_capacity(stream.len());
+    let mut points = Vec::with_capacity(stream.len());
+    for (i, &t) in stream.time.iter().enumerate() {
+        if let Some(&prev) = i.checked_sub(1).and_then(|p| stream.time.get(p)) {
+            let dt = t.saturating_sub(prev) as f64 / 1000.0;
+            let v = finite_or_zero(speed.get_f64(i));
+            let rate = finite_or_zero(streams[y].value_at(yaw_rate, t));
+            heading += rate.to_radians() * dt;
+            pos.x += v * heading.cos() * dt;
+            pos.y += v * heading.sin() * dt;
+        }
+        time.push(t as f64 / 1000.0);
+        points.push(pos);
+    }
+
+    (!points.is_empty()).then(|| Track::new(TrackSource::DeadReckoning, time, points))
+}
+
+fn finite_or_zero(v: f64) -> f64 {
+    if v.is_finite() {
+        v
+    } else {
+        0.0
+    }
+}
+
+/// Finds the first channel matching one of the names, ignoring case.
+fn find_channel(streams: &[LogStream], names: &[&str]) -> Option<(usize, usize)> {
+    (streams.iter().enumerate()).find_map(|(i, s)| Some((i, find_entry(s, names)?)))
+}
+
+fn find_entry(stream: &LogStream, names: &[&str]) -> Option<usize> {
+    names
+        .iter()
+        .find_map(|n| (stream.entries.iter()).position(|e| e.name.eq_ignore_ascii_case(n)))
+}
@@ -0,0 +1,323 @@
+use cods::{BuiltinConst, UserFacing};
+use egui::{Button, TextEdit, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::data::{DataEntry, LogStream};
+use crate::eval;
+
+/// Functions whose result has the unit of their arguments.
+const UNIT_PRESERVING_FUNS: [&str; 8] = [
+    "abs", "min", "max", "floor", "ceil", "round", "lowpass", "movavg",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Unit {
+    /// number literals and comparison results, compatible with every unit
+    Scalar,
+    /// channels without unit metadata and results that can't be tracked
+    Unknown,
+    Known(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Num,
+    Op(&'a str),
+    Open,
+    Close,
+    Comma,
+}
+
+/// User defined unit of a channel, for files that don't contain unit metadata.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChannelUnit {
+    pub name: String,
+    pub unit: String,
+}
+
+/// Unit of a channel, `time` is always in seconds and the builtin `distance` in meters. Units in
+/// the metadata table take precedence over the ones stored in the files.
+pub fn channel_unit(streams: &[LogStream], table: &[ChannelUnit], name: &str) -> Option<String> {
+    if name == "time" {
+        return Some("s".into());
+    }
+    match (streams.iter().flat_map(|s| s.entries.iter())).find(|e| e.name == name) {
+        Some(e) => entry_unit(e, table).map(str::to_string),
+        None if name == eval::DISTANCE => Some("m".into()),
+        None => table_unit(table, name).map(str::to_string),
+    }
+}
+
+pub fn entry_unit<'a>(entry: &'a DataEntry, table: &'a [ChannelUnit]) -> Option<&'a str> {
+    table_unit(table, &entry.name).or_else(|| entry.unit())
+}
+
+fn table_unit<'a>(table: &'a [ChannelUnit], name: &str) -> Option<&'a str> {
+    (table.iter())
+        .find(|c| c.name == name && !c.unit.is_empty())
+        .map(|c| c.unit.as_str())
+}
+
+pub fn channel_units_sidebar(ui: &mut Ui, table: &mut Vec<ChannelUnit>, new: &mut ChannelUnit) {
+    let mut i = 0;
+    while i < table.len() {
+        let c = &mut table[i];
+        let removed = ui
+            .horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut c.name).desired_width(150.0));
+                ui.add(TextEdit::singleline(&mut c.unit).desired_width(60.0));
+                ui.button("🗙").clicked()
+            })
+            .inner;
+        if removed {
+            table.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut new.name)
+                .hint_text("channel")
+                .desired_width(150.0),
+        );
+        ui.add(
+            TextEdit::singleline(&mut new.unit)
+                .hint_text("unit")
+                .desired_width(60.0),
+        );
+
+        let valid = !new.name.trim().is_empty() && !new.unit.trim().is_empty();
+        if ui.add_enabled(valid, Button::new("Add")).clicked() {
+            let mut c = std::mem::take(new);
+            c.name = c.name.trim().to_string();
+            c.unit = c.unit.trim().to_string();
+            table.push(c);
+        }
+    });
+}
+
+/// Carries the units of the channels through the expression and returns a warning for every
+/// addition, subtraction or comparison of incompatible units, e.g. `Nm + rpm`. Expressions that
+/// use syntax which isn't understood here are not checked at all.
+pub fn check(input: &str, unit_of: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let Some(tokens) = tokenize(input) else {
+        return Vec::new();
+    };
+    let mut checker = Checker {
+        tokens,
+        pos: 0,
+        unit_of,
+        warnings: Vec::new(),
+    };
+    match checker.logic() {
+        Some(_) if checker.pos == checker.tokens.len() => checker.warnings,
+        _ => Vec::new(),
+    }
+}
+
+fn tokenize(input: &str) -> Option<Vec<Token<'_>>> {
+    const OPS: [&str; 15] = [
+        "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "^", "!",
+    ];
+
+    let mut tokens = Vec::new();
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_whitespace() {
+            c.len_utf8()
+        } else if c.is_ascii_digit() || c == '.' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Num);
+            len
+        } else if c.is_alphanumeric() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(&rest[..len]));
+            len
+        } else if c == '(' {
+            tokens.push(Token::Open);
+            1
+        } else if c == ')' {
+            tokens.push(Token::Close);
+            1
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            1
+        } else {
+            let op = OPS.iter().find(|op| rest.starts_with(*op))?;
+            tokens.push(Token::Op(op));
+            op.len()
+        };
+        rest = &rest[len..];
+    }
+    Some(tokens)
+}
+
+struct Checker<'a, F> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    unit_of: F,
+    warnings: Vec<String>,
+}
+
+impl<'a, F: Fn(&str) -> Option<String>> Checker<'a, F> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let t = self.peek();
+        self.pos += 1;
+        t
+    }
+
+    fn eat_op(&mut self, ops: &[&str]) -> Option<&'a str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(&op) => {
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn logic(&mut self) -> Option<Unit> {
+        let mut unit = self.comparison()?;
+        while self.eat_op(&["&&", "||"]).is_some() {
+            self.comparison()?;
+            unit = Unit::Scalar;
+        }
+        Some(unit)
+    }
+
+    fn comparison(&mut self) -> Option<Unit> {
+        let lhs = self.sum()?;
+        match self.eat_op(&["==", "!=", "<=", ">=", "<", ">"]) {
+            Some(op) => {
+                let rhs = self.sum()?;
+                self.combine(lhs, rhs, |a, b| format!("{a} {op} {b}"));
+                Some(Unit::Scalar)
+            }
+            None => Some(lhs),
+        }
+    }
+
+    fn sum(&mut self) -> Option<Unit> {
+        let mut unit = self.product()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            let rhs = self.product()?;
+            unit = self.combine(unit, rhs, |a, b| format!("{a} {op} {b}"));
+        }
+        Some(unit)
+    }
+
+    fn product(&mut self) -> Option<Unit> {
+        let mut unit = self.unary()?;
+        while let Some(op) = self.eat_op(&["*", "/", "%"]) {
+            let rhs = self.unary()?;
+            unit = match (op, unit, rhs) {
+                (_, Unit::Scalar, Unit::Scalar) => Unit::Scalar,
+                ("*", Unit::Scalar, u) | (_, u, Unit::Scalar) => u,
+                ("/", Unit::Known(a), Unit::Known(b)) if a == b => Unit::Scalar,
+                _ => Unit::Unknown,
+            };
+        }
+        Some(unit)
+    }
+
+    fn unary(&mut self) -> Option<Unit> {
+        if self.eat_op(&["-"]).is_some() {
+            return self.unary();
+        }
+        if self.eat_op(&["!"]).is_some() {
+            self.unary()?;
+            return Some(Unit::Scalar);
+        }
+        let base = self.primary()?;
+        if self.eat_op(&["^"]).is_some() {
+            let exp = self.unary()?;
+            return Some(match (base, exp) {
+                (Unit::Scalar, Unit::Scalar) => Unit::Scalar,
+                _ => Unit::Unknown,
+            });
+        }
+        Some(base)
+    }
+
+    fn primary(&mut self) -> Option<Unit> {
+        match self.next()? {
+            Token::Num => Some(Unit::Scalar),
+            Token::Open => {
+                let unit = self.logic()?;
+                (self.next()? == Token::Close).then_some(unit)
+            }
+            Token::Ident(name) if self.peek() == Some(Token::Open) => {
+                self.pos += 1;
+                let mut args = Vec::new();
+                if self.peek() == Some(Token::Close) {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        args.push(self.logic()?);
+                        match self.next()? {
+                            Token::Comma => (),
+                            Token::Close => break,
+                            _ => return None,
+                        }
+                    }
+                }
+                Some(self.call(name, args))
+            }
+            Token::Ident(name) => match (self.unit_of)(name) {
+                Some(unit) => Some(Unit::Known(unit)),
+                None if BuiltinConst::members()
+                    .iter()
+                    .any(|c| c.to_string() == name) =>
+                {
+                    Some(Unit::Scalar)
+                }
+                None => Some(Unit::Unknown),
+            },
+            _ => None,
+        }
+    }
+
+    fn call(&mut self, name: &str, args: Vec<Unit>) -> Unit {
+        if !UNIT_PRESERVING_FUNS.contains(&name) {
+            return Unit::Unknown;
+        }
+        match name {
+            // the second parameter is the cutoff frequency or window size
+            "lowpass" | "movavg" => args.into_iter().next().unwrap_or(Unit::Unknown),
+            _ => {
+                let mut args = args.into_iter();
+                let first = args.next().unwrap_or(Unit::Unknown);
+                args.fold(first, |a, b| {
+                    self.combine(a, b, |a, b| format!("{name}({a}, {b})"))
+                })
+            }
+        }
+    }
+
+    /// Unit of two values that have to be compatible, records a warning if they aren't.
+    fn combine(&mut self, lhs: Unit, rhs: Unit, describe: impl Fn(&str, &str) -> String) -> Unit {
+        match (lhs, rhs) {
+            (Unit::Scalar, u) | (u, Unit::Scalar) => u,
+            (Unit::Known(a), Unit::Known(b)) if a == b => Unit::Known(a),
+            (Unit::Known(a), Unit::Known(b)) => {
+                let warning = format!("Incompatible units: {}", describe(&a, &b));
+                self.warnings.push(warning);
+                Unit::Unknown
+            }
+            _ => Unit::Unknown,
+        }
+    }
+}
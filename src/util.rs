@@ -26,6 +26,41 @@ pub fn format_time(seconds: f64) -> String {
     }
 }
 
+/// Parses a time in seconds, either plain seconds or `[hh:]mm:ss[.fraction]`.
+pub fn parse_time(text: &str) -> Option<f64> {
+    let mut parts = text.trim().rsplit(':');
+    let seconds = parts.next()?.parse::<f64>().ok()?;
+    if !seconds.is_finite() || seconds < 0.0 {
+        return None;
+    }
+
+    let mut total = seconds;
+    for (i, part) in parts.enumerate() {
+        // hours are the largest unit
+        if i >= 2 {
+            return None;
+        }
+        total += part.parse::<u32>().ok()? as f64 * 60f64.powi(i as i32 + 1);
+    }
+    Some(total)
+}
+
+/// Rounds up to 1, 2 or 5 times a power of ten.
+pub fn nice_step(value: f64) -> f64 {
+    let magnitude = 10f64.powf(value.log10().floor());
+    let normalized = value / magnitude;
+    let nice = if normalized <= 1.0 {
+        1.0
+    } else if normalized <= 2.0 {
+        2.0
+    } else if normalized <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
 pub fn common_parent_dir<'a>(mut files: impl Iterator<Item = &'a PathBuf>) -> Option<&'a Path> {
     let first = files.next()?;
     let parent = first.parent()?;
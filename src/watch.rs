@@ -0,0 +1,92 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use egui::Ui;
+
+use crate::fs::{self, Files};
+
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Polls the opened directory for log files that aren't opened yet. Files are only reported once
+/// their size didn't change between two polls, so files that are still being written are
+/// skipped.
+#[derive(Default)]
+pub struct DirWatch {
+    dir: PathBuf,
+    last_poll: Option<Instant>,
+    /// size of the unreported files at the last poll
+    pending: HashMap<PathBuf, u64>,
+    /// files that weren't appended or ignored yet
+    pub new_files: Vec<PathBuf>,
+    ignored: HashSet<PathBuf>,
+}
+
+impl DirWatch {
+    pub fn poll(&mut self, files: &Files) {
+        if self.dir != files.dir {
+            *self = Self {
+                dir: files.dir.clone(),
+                ..Default::default()
+            };
+        }
+        if (self.last_poll).is_some_and(|t| t.elapsed() < WATCH_INTERVAL) {
+            return;
+        }
+        self.last_poll = Some(Instant::now());
+
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            let known = files.items.contains(&path)
+                || self.ignored.contains(&path)
+                || self.new_files.contains(&path);
+            if known || !path.is_file() || !fs::is_log_file(&path) {
+                continue;
+            }
+            let Ok(size) = std::fs::metadata(&path).map(|m| m.len()) else {
+                continue;
+            };
+            match self.pending.insert(path.clone(), size) {
+                Some(prev) if prev == size && size > 0 => {
+                    self.pending.remove(&path);
+                    self.new_files.push(path);
+                }
+                _ => (),
+            }
+        }
+        self.new_files.sort();
+    }
+
+    pub fn ignore(&mut self) {
+        self.ignored.extend(self.new_files.drain(..));
+    }
+}
+
+/// Returns `Some(true)` if the new files should be appended and `Some(false)` if they should be
+/// ignored.
+pub fn new_files_dialog(ui: &mut Ui, watch: &DirWatch) -> Option<bool> {
+    ui.label(format!(
+        "{} new log files were written to {}",
+        watch.new_files.len(),
+        watch.dir.display()
+    ));
+    for f in watch.new_files.iter() {
+        if let Some(name) = f.file_name() {
+            ui.monospace(name.to_string_lossy());
+        }
+    }
+    ui.add_space(10.0);
+
+    let mut action = None;
+    ui.horizontal(|ui| {
+        if ui.button("Append").clicked() {
+            action = Some(true);
+        }
+        if ui.button("Ignore").clicked() {
+            action = Some(false);
+        }
+    });
+    action
+}